@@ -2,7 +2,8 @@ use crate::city::{City, CityToCity};
 
 use array_init::array_init;
 use rand::seq::SliceRandom;
-use rand::thread_rng;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
 use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
 use std::collections::VecDeque;
@@ -31,6 +32,7 @@ pub const NUM_DRAWN_INITIAL_TRAIN_CARDS: usize = 4;
 #[derive(Clone, Copy, Debug, Deserialize, Display, EnumIter, Eq, Hash, PartialEq, Serialize)]
 #[serde(rename_all = "lowercase")]
 #[strum(serialize_all = "lowercase")]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub enum TrainColor {
     /// Also known as the *Hopper train*.
     Black,
@@ -90,7 +92,8 @@ impl TrainColor {
 }
 
 /// Encapsulates information about a destination card.
-#[derive(Clone, Debug, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct DestinationCard {
     /// The two cities that must be connected to fulfill the destination card.
     pub destination: CityToCity,
@@ -116,6 +119,7 @@ macro_rules! destination_card {
 
 #[derive(Serialize)]
 /// State of the [`CardDealer`], which is shared uniformly to all players.
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct CardDealerState<'a> {
     /// All train cards in the open train card deck.
     pub open_train_card_deck: &'a [Option<TrainColor>],
@@ -128,12 +132,17 @@ pub struct CardDealerState<'a> {
 }
 
 /// Entity in charge of dealing as well as shuffling destination and train cards.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct CardDealer {
     open_train_card_deck: SmallVec<[Option<TrainColor>; NUM_OPEN_TRAIN_CARDS]>,
     close_train_card_deck: Vec<TrainColor>,
     discarded_train_card_deck: Vec<TrainColor>,
     destination_card_deck: VecDeque<DestinationCard>,
+    /// How many times the open train card deck was re-shuffled, since the dealer was created.
+    num_open_train_card_deck_reshuffles: usize,
+    /// Source of randomness for all shuffles, such that a dealer created with the same seed deals the same cards.
+    /// Unlike [`rand::rngs::StdRng`], its output is portable across releases, as games are replayed from their seed.
+    rng: ChaCha8Rng,
 }
 
 impl CardDealer {
@@ -147,6 +156,26 @@ impl CardDealer {
     /// let card_dealer = CardDealer::new();
     /// ```
     pub fn new() -> Self {
+        Self::with_rng(ChaCha8Rng::from_entropy())
+    }
+
+    /// Creates a new `CardDealer`, like [`CardDealer::new`], whose shuffles are all derived from the given seed.
+    ///
+    /// # Example
+    /// ```
+    /// use ticket_to_ride::card::CardDealer;
+    ///
+    /// let card_dealer = CardDealer::with_seed(42);
+    /// assert_eq!(
+    ///     card_dealer.get_state().open_train_card_deck,
+    ///     CardDealer::with_seed(42).get_state().open_train_card_deck
+    /// );
+    /// ```
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_rng(ChaCha8Rng::seed_from_u64(seed))
+    }
+
+    fn with_rng(mut rng: ChaCha8Rng) -> Self {
         let mut all_train_cards = Vec::with_capacity(TOTAL_NUM_TRAIN_CARDS);
 
         for color in TrainColor::iter() {
@@ -158,7 +187,7 @@ impl CardDealer {
             all_train_cards.extend(repeat(color).take(num_of_train_cards_per_color));
         }
 
-        all_train_cards.shuffle(&mut thread_rng());
+        all_train_cards.shuffle(&mut rng);
 
         let open_train_card_deck: SmallVec<_> = all_train_cards
             .iter_mut()
@@ -174,7 +203,9 @@ impl CardDealer {
             open_train_card_deck,
             close_train_card_deck,
             discarded_train_card_deck: Vec::new(),
-            destination_card_deck: Self::generate_destination_cards(),
+            destination_card_deck: Self::generate_destination_cards(&mut rng),
+            num_open_train_card_deck_reshuffles: 0,
+            rng,
         };
 
         new_card_dealer.maybe_reshuffle_open_train_card_deck();
@@ -182,7 +213,7 @@ impl CardDealer {
         new_card_dealer
     }
 
-    fn generate_destination_cards() -> VecDeque<DestinationCard> {
+    fn generate_destination_cards(rng: &mut ChaCha8Rng) -> VecDeque<DestinationCard> {
         let mut destination_cards = [
             destination_card! {City::Boston, City::Miami, 12},
             destination_card! {City::Calgary, City::Phoenix, 13},
@@ -216,7 +247,7 @@ impl CardDealer {
             destination_card! {City::Winnipeg, City::LittleRock, 11},
         ];
 
-        destination_cards.shuffle(&mut thread_rng());
+        destination_cards.shuffle(rng);
         VecDeque::from(destination_cards)
     }
 
@@ -266,6 +297,7 @@ impl CardDealer {
         }
 
         // We should re-shuffle. Let's move cards from the open deck to the discarded deck.
+        self.num_open_train_card_deck_reshuffles += 1;
         self.discarded_train_card_deck.extend(
             self.open_train_card_deck
                 .drain(..)
@@ -470,12 +502,11 @@ impl CardDealer {
     /// the discarded destination cards.
     /// # Example
     /// ```
-    /// use smallvec::smallvec;
     /// use ticket_to_ride::card::{CardDealer, DestinationCard};
     /// use ticket_to_ride::city::City;
     ///
     /// let mut card_dealer = CardDealer::new();
-    /// let destination_cards_to_discard = smallvec![
+    /// let destination_cards_to_discard = vec![
     ///     DestinationCard {
     ///         destination: (City::Chicago, City::SantaFe),
     ///         points: 9,
@@ -487,7 +518,7 @@ impl CardDealer {
     /// ```
     pub fn discard_destination_cards(
         &mut self,
-        destination_cards: SmallVec<[DestinationCard; NUM_DRAWN_DESTINATION_CARDS]>,
+        destination_cards: impl IntoIterator<Item = DestinationCard>,
     ) {
         for destination_card in destination_cards {
             self.destination_card_deck.push_front(destination_card);
@@ -500,7 +531,7 @@ impl CardDealer {
             return;
         }
 
-        self.discarded_train_card_deck.shuffle(&mut thread_rng());
+        self.discarded_train_card_deck.shuffle(&mut self.rng);
 
         std::mem::swap(
             &mut self.close_train_card_deck,
@@ -542,6 +573,26 @@ impl CardDealer {
                 .any(|card| card.is_some() && card.unwrap().is_not_wild())
     }
 
+    /// Predicate that determines whether a player could draw a train card at the beginning of their turn,
+    /// from either deck.
+    #[inline]
+    pub fn can_player_draw_train_card(&self) -> bool {
+        !self.close_train_card_deck.is_empty()
+            || self.open_train_card_deck.iter().any(|card| card.is_some())
+    }
+
+    /// Predicate that determines whether a player could draw destination cards.
+    #[inline]
+    pub fn can_player_draw_destination_cards(&self) -> bool {
+        !self.destination_card_deck.is_empty()
+    }
+
+    /// How many times the open train card deck was re-shuffled (revealing new cards), since the dealer was created.
+    #[inline]
+    pub fn num_open_train_card_deck_reshuffles(&self) -> usize {
+        self.num_open_train_card_deck_reshuffles
+    }
+
     #[cfg(test)]
     pub fn get_mut_open_train_card_deck(
         &mut self,
@@ -718,6 +769,70 @@ mod tests {
         );
     }
 
+    #[test]
+    fn card_dealer_with_seed_deals_the_same_cards() {
+        let mut first_card_dealer = CardDealer::with_seed(7);
+        let mut second_card_dealer = CardDealer::with_seed(7);
+
+        assert_eq!(
+            first_card_dealer.open_train_card_deck,
+            second_card_dealer.open_train_card_deck
+        );
+        assert_eq!(
+            first_card_dealer.destination_card_deck,
+            second_card_dealer.destination_card_deck
+        );
+
+        // Re-shuffling the discarded deck is seeded as well.
+        for card_dealer in [&mut first_card_dealer, &mut second_card_dealer] {
+            let close_train_card_deck = std::mem::take(&mut card_dealer.close_train_card_deck);
+            card_dealer.discard_train_cards(close_train_card_deck);
+            card_dealer.maybe_reshuffle_and_swap_discarded_deck();
+        }
+        assert_eq!(
+            first_card_dealer.close_train_card_deck,
+            second_card_dealer.close_train_card_deck
+        );
+    }
+
+    #[test]
+    fn card_dealer_with_seed_is_portable() {
+        // Games are replayed from their seed: a given seed must deal the same cards, whatever the version of `rand`.
+        let mut card_dealer = CardDealer::with_seed(42);
+
+        assert_eq!(
+            card_dealer.open_train_card_deck.as_slice(),
+            [
+                Some(TrainColor::Yellow),
+                Some(TrainColor::Black),
+                Some(TrainColor::Blue),
+                Some(TrainColor::Wild),
+                Some(TrainColor::Green),
+            ]
+        );
+        assert_eq!(
+            card_dealer.draw_from_close_train_card_deck(),
+            Ok(TrainColor::Green)
+        );
+        assert_eq!(
+            card_dealer.draw_from_close_train_card_deck(),
+            Ok(TrainColor::White)
+        );
+        assert_eq!(
+            card_dealer
+                .destination_card_deck
+                .iter()
+                .take(3)
+                .map(|destination_card| destination_card.destination)
+                .collect::<Vec<_>>(),
+            [
+                (City::Calgary, City::SaltLakeCity),
+                (City::Denver, City::Pittsburgh),
+                (City::Montreal, City::Atlanta),
+            ]
+        );
+    }
+
     #[test]
     fn card_dealer_should_reshuffle() {
         let mut card_dealer = CardDealer::new();
@@ -827,6 +942,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn card_dealer_can_player_draw() {
+        let mut card_dealer = CardDealer::new();
+        assert!(card_dealer.can_player_draw_train_card());
+        assert!(card_dealer.can_player_draw_destination_cards());
+
+        // Wild cards can be drawn at the beginning of the turn.
+        card_dealer.close_train_card_deck.clear();
+        card_dealer.open_train_card_deck = [None, None, Some(TrainColor::Wild), None, None].into();
+        assert!(card_dealer.can_player_draw_train_card());
+        assert!(!card_dealer.can_player_draw_again_this_turn());
+
+        card_dealer.open_train_card_deck = [None; NUM_OPEN_TRAIN_CARDS].into();
+        assert!(!card_dealer.can_player_draw_train_card());
+
+        card_dealer.destination_card_deck.clear();
+        assert!(!card_dealer.can_player_draw_destination_cards());
+    }
+
     #[test]
     fn card_dealer_draw_from_close_deck() {
         let mut card_dealer = CardDealer::new();
@@ -997,7 +1131,7 @@ mod tests {
         let only_destination_card = destination_card! {City::Boston, City::Montreal, 5};
         card_dealer.destination_card_deck = VecDeque::from([only_destination_card.clone()]);
 
-        let discarded_destination_cards = smallvec![
+        let discarded_destination_cards: SmallVec<[DestinationCard; 2]> = smallvec![
             destination_card! {City::Duluth, City::Vancouver, 15},
             destination_card! {City::LosAngeles, City::ElPaso, 6},
         ];
@@ -1,7 +1,7 @@
 use serde_repr::{Deserialize_repr, Serialize_repr};
 #[allow(unused_imports)]
 use strum::EnumCount;
-use strum_macros::{Display, EnumCount as EnumCountMacro};
+use strum_macros::{Display, EnumCount as EnumCountMacro, EnumIter};
 
 /// All the different cities on the map.
 ///
@@ -14,6 +14,7 @@ use strum_macros::{Display, EnumCount as EnumCountMacro};
     Deserialize_repr,
     Display,
     EnumCountMacro,
+    EnumIter,
     Eq,
     Hash,
     PartialEq,
@@ -22,6 +23,7 @@ use strum_macros::{Display, EnumCount as EnumCountMacro};
     Serialize_repr,
 )]
 #[repr(u8)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema_repr))]
 pub enum City {
     Atlanta = 0,
     Boston = 1,
@@ -87,6 +89,15 @@ mod tests {
         assert_eq!(City::COUNT, 36);
     }
 
+    #[test]
+    fn city_iter() {
+        use strum::IntoEnumIterator;
+
+        assert_eq!(City::iter().count(), City::COUNT);
+        assert_eq!(City::iter().next(), Some(City::Atlanta));
+        assert_eq!(City::iter().next_back(), Some(City::Winnipeg));
+    }
+
     #[test]
     fn simple_city_to_string() {
         assert_eq!(City::Atlanta.to_string(), "Atlanta");
@@ -0,0 +1,420 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// What happens on behalf of a player who runs out of time during their turn.
+///
+/// In both cases, destination cards the player drew but did not select yet are put back in the deck.
+///
+/// # JSON
+/// Policies are serialized in snake_case.
+#[derive(Clone, Copy, Deserialize, Serialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum TimeoutPolicy {
+    /// The player draws from the close deck until their turn is over (i.e. two train cards,
+    /// or one if they had already drawn one). This is the default.
+    #[default]
+    DrawTrainCards,
+    /// The player's turn is over, without anything else happening.
+    Pass,
+}
+
+/// Time limits of the turn-based game, configured before the game starts.
+///
+/// Without any limit (the default), players can take as long as they want.
+#[derive(Clone, Copy, Deserialize, Serialize, Debug, Default, PartialEq)]
+pub struct TimeControls {
+    /// How long a single turn can last.
+    pub turn_time_limit: Option<Duration>,
+    /// How much time each player has for all their turns combined, like a chess clock.
+    pub time_bank: Option<Duration>,
+    /// What happens once a player runs out of time.
+    pub timeout_policy: TimeoutPolicy,
+}
+
+impl TimeControls {
+    /// Whether the game has any time limit at all.
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.turn_time_limit.is_some() || self.time_bank.is_some()
+    }
+}
+
+/// Time left on the clock, returned as part of [`crate::manager::GameState`] in games with [`TimeControls`].
+#[derive(Debug, PartialEq, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct ClockState {
+    /// How long the current player has left to play their turn, in milliseconds, before the
+    /// [`TimeoutPolicy`] kicks in.
+    ///
+    /// This is `None` outside of the turn-based game.
+    pub turn_time_left_ms: Option<u64>,
+    /// How much time each player has left in their time bank, in milliseconds, by player ID.
+    ///
+    /// This is empty without a time bank.
+    pub time_banks_left_ms: HashMap<usize, u64>,
+}
+
+/// What a [`Clock`] has counted so far, to restore it once its game is replayed (see [`Clock::restore`]).
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ClockSnapshot {
+    /// The player whose turn was running, and for how long it had been.
+    running_turn: Option<(usize, Duration)>,
+    /// Time left in each player's time bank (by player ID), not counting the running turn.
+    time_banks: HashMap<usize, Duration>,
+}
+
+/// The player whose turn is being timed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct RunningTurn {
+    player_id: usize,
+    started_at: Instant,
+}
+
+/// Times players' turns, and keeps track of their time banks.
+///
+/// The [`crate::manager::Manager`] owns one once the game has started (if it has [`TimeControls`]), and
+/// tells it whenever a new turn starts, and when the game is over.
+#[derive(Clone)]
+pub struct Clock {
+    time_controls: TimeControls,
+    /// Only set during the turn-based game.
+    running_turn: Option<RunningTurn>,
+    /// Time left in each player's time bank (by player ID), not counting the running turn.
+    time_banks: HashMap<usize, Duration>,
+}
+
+impl Clock {
+    /// Creates a stopped clock, with a full time bank for every given player (if there is a time bank).
+    pub fn new(time_controls: TimeControls, player_ids: impl IntoIterator<Item = usize>) -> Self {
+        let time_banks = match time_controls.time_bank {
+            Some(time_bank) => player_ids
+                .into_iter()
+                .map(|player_id| (player_id, time_bank))
+                .collect(),
+            None => HashMap::new(),
+        };
+
+        Self {
+            time_controls,
+            running_turn: None,
+            time_banks,
+        }
+    }
+
+    #[inline]
+    pub fn timeout_policy(&self) -> TimeoutPolicy {
+        self.time_controls.timeout_policy
+    }
+
+    /// Starts timing the given player's turn.
+    ///
+    /// The time spent on the previous turn, if any, is charged to the previous player's time bank.
+    pub fn start_turn(&mut self, player_id: usize, now: Instant) {
+        self.stop(now);
+        self.running_turn = Some(RunningTurn {
+            player_id,
+            started_at: now,
+        });
+    }
+
+    /// Stops timing the running turn, if any, and charges the time spent on it to the player's time bank.
+    pub fn stop(&mut self, now: Instant) {
+        let running_turn = match self.running_turn.take() {
+            Some(running_turn) => running_turn,
+            None => return,
+        };
+
+        if let Some(time_bank) = self.time_banks.get_mut(&running_turn.player_id) {
+            *time_bank =
+                time_bank.saturating_sub(now.saturating_duration_since(running_turn.started_at));
+        }
+    }
+
+    /// Postpones the running turn, if any, such that the given time is charged to no one.
+    pub fn postpone(&mut self, by: Duration) {
+        if let Some(running_turn) = self.running_turn.as_mut() {
+            running_turn.started_at += by;
+        }
+    }
+
+    /// Returns what the clock counted so far, as of `now`.
+    pub fn snapshot(&self, now: Instant) -> ClockSnapshot {
+        ClockSnapshot {
+            running_turn: self.running_turn.map(|running_turn| {
+                (
+                    running_turn.player_id,
+                    now.saturating_duration_since(running_turn.started_at),
+                )
+            }),
+            time_banks: self.time_banks.clone(),
+        }
+    }
+
+    /// Picks up counting where the snapshot left off, as of `now`: the time in between is charged to no one.
+    ///
+    /// Only the players the clock knows about are restored, and the running turn only if it is the same player's.
+    pub fn restore(&mut self, snapshot: &ClockSnapshot, now: Instant) {
+        for (player_id, time_bank) in self.time_banks.iter_mut() {
+            if let Some(saved_time_bank) = snapshot.time_banks.get(player_id) {
+                *time_bank = *saved_time_bank;
+            }
+        }
+
+        if let (Some(running_turn), Some((player_id, elapsed))) =
+            (self.running_turn.as_mut(), snapshot.running_turn)
+        {
+            if running_turn.player_id == player_id {
+                running_turn.started_at = now.checked_sub(elapsed).unwrap_or(now);
+            }
+        }
+    }
+
+    /// Forgets about a player leaving the game.
+    ///
+    /// If their turn was running, the clock stops (without charging anyone).
+    pub fn remove_player(&mut self, player_id: usize) {
+        self.time_banks.remove(&player_id);
+
+        if self
+            .running_turn
+            .is_some_and(|running_turn| running_turn.player_id == player_id)
+        {
+            self.running_turn = None;
+        }
+    }
+
+    /// Returns how long the current player has left to play their turn: the least of the turn time limit, and of
+    /// what is left in their time bank.
+    ///
+    /// Returns `None` if no turn is running.
+    pub fn time_left_in_turn(&self, now: Instant) -> Option<Duration> {
+        let running_turn = self.running_turn?;
+        let elapsed = now.saturating_duration_since(running_turn.started_at);
+
+        let time_limit = match (
+            self.time_controls.turn_time_limit,
+            self.time_banks.get(&running_turn.player_id),
+        ) {
+            (Some(turn_time_limit), Some(time_bank)) => turn_time_limit.min(*time_bank),
+            (Some(turn_time_limit), None) => turn_time_limit,
+            (None, Some(time_bank)) => *time_bank,
+            (None, None) => return None,
+        };
+
+        Some(time_limit.saturating_sub(elapsed))
+    }
+
+    /// Whether the current player ran out of time.
+    #[inline]
+    pub fn has_timed_out(&self, now: Instant) -> bool {
+        self.time_left_in_turn(now) == Some(Duration::ZERO)
+    }
+
+    /// Returns the time left on the clock, as of `now`.
+    pub fn get_state(&self, now: Instant) -> ClockState {
+        let time_banks_left_ms = self
+            .time_banks
+            .iter()
+            .map(|(player_id, time_bank)| {
+                let time_bank_left = match self.running_turn {
+                    Some(running_turn) if running_turn.player_id == *player_id => time_bank
+                        .saturating_sub(now.saturating_duration_since(running_turn.started_at)),
+                    _ => *time_bank,
+                };
+
+                (*player_id, time_bank_left.as_millis() as u64)
+            })
+            .collect();
+
+        ClockState {
+            turn_time_left_ms: self
+                .time_left_in_turn(now)
+                .map(|time_left| time_left.as_millis() as u64),
+            time_banks_left_ms,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    fn new_clock(turn_time_limit: Option<u64>, time_bank: Option<u64>) -> Clock {
+        Clock::new(
+            TimeControls {
+                turn_time_limit: turn_time_limit.map(Duration::from_secs),
+                time_bank: time_bank.map(Duration::from_secs),
+                ..Default::default()
+            },
+            [0, 1],
+        )
+    }
+
+    #[test]
+    fn time_controls_is_enabled() {
+        assert!(!TimeControls::default().is_enabled());
+        assert!(TimeControls {
+            turn_time_limit: Some(SECOND),
+            ..Default::default()
+        }
+        .is_enabled());
+        assert!(TimeControls {
+            time_bank: Some(SECOND),
+            ..Default::default()
+        }
+        .is_enabled());
+    }
+
+    #[test]
+    fn timeout_policy_json() -> serde_json::Result<()> {
+        assert_eq!(
+            serde_json::to_string(&TimeoutPolicy::DrawTrainCards)?,
+            r#""draw_train_cards""#
+        );
+        assert_eq!(
+            serde_json::from_str::<TimeoutPolicy>(r#""pass""#)?,
+            TimeoutPolicy::Pass
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn clock_turn_time_limit() {
+        let mut clock = new_clock(Some(30), None);
+        let now = Instant::now();
+
+        assert!(clock.time_left_in_turn(now).is_none());
+        assert!(!clock.has_timed_out(now));
+
+        clock.start_turn(0, now);
+        assert_eq!(clock.time_left_in_turn(now), Some(30 * SECOND));
+        assert_eq!(
+            clock.time_left_in_turn(now + 10 * SECOND),
+            Some(20 * SECOND)
+        );
+        assert!(!clock.has_timed_out(now + 29 * SECOND));
+        assert!(clock.has_timed_out(now + 30 * SECOND));
+        assert!(clock.has_timed_out(now + 60 * SECOND));
+
+        // Every turn gets the same time limit.
+        clock.start_turn(1, now + 10 * SECOND);
+        assert_eq!(
+            clock.time_left_in_turn(now + 10 * SECOND),
+            Some(30 * SECOND)
+        );
+
+        let clock_state = clock.get_state(now + 10 * SECOND);
+        assert_eq!(clock_state.turn_time_left_ms, Some(30_000));
+        assert!(clock_state.time_banks_left_ms.is_empty());
+
+        clock.stop(now + 20 * SECOND);
+        assert!(clock.time_left_in_turn(now + 20 * SECOND).is_none());
+    }
+
+    #[test]
+    fn clock_time_bank() {
+        let mut clock = new_clock(Some(30), Some(50));
+        let now = Instant::now();
+
+        clock.start_turn(0, now);
+        assert_eq!(clock.time_left_in_turn(now), Some(30 * SECOND));
+
+        // Player 0 spends 20 seconds.
+        clock.start_turn(1, now + 20 * SECOND);
+        // Player 1 spends 5 seconds.
+        clock.start_turn(0, now + 25 * SECOND);
+
+        let clock_state = clock.get_state(now + 35 * SECOND);
+        assert_eq!(clock_state.turn_time_left_ms, Some(20_000));
+        assert_eq!(clock_state.time_banks_left_ms[&0], 20_000);
+        assert_eq!(clock_state.time_banks_left_ms[&1], 45_000);
+
+        // Player 0 only has 30 seconds left in their time bank, which is less than the turn time limit.
+        assert!(!clock.has_timed_out(now + 54 * SECOND));
+        assert!(clock.has_timed_out(now + 55 * SECOND));
+
+        // Once the bank is empty, every turn times out right away.
+        clock.start_turn(1, now + 60 * SECOND);
+        clock.start_turn(0, now + 60 * SECOND);
+        assert!(clock.has_timed_out(now + 60 * SECOND));
+        assert_eq!(clock.get_state(now + 60 * SECOND).time_banks_left_ms[&0], 0);
+    }
+
+    #[test]
+    fn clock_snapshot_and_restore() {
+        let mut clock = new_clock(Some(30), Some(50));
+        let now = Instant::now();
+
+        // Player 0 spends 20 seconds, and player 1 10 seconds of their turn.
+        clock.start_turn(0, now);
+        clock.start_turn(1, now + 20 * SECOND);
+        let snapshot = clock.snapshot(now + 30 * SECOND);
+
+        // The restored clock picks up where it left off, however late it is restored.
+        let mut restored_clock = new_clock(Some(30), Some(50));
+        restored_clock.start_turn(1, now + 100 * SECOND);
+        restored_clock.restore(&snapshot, now + 100 * SECOND);
+        assert_eq!(
+            restored_clock.get_state(now + 100 * SECOND),
+            clock.get_state(now + 30 * SECOND)
+        );
+
+        // Running turns of other players are left alone.
+        let mut restored_clock = new_clock(Some(30), Some(50));
+        restored_clock.start_turn(0, now + 100 * SECOND);
+        restored_clock.restore(&snapshot, now + 100 * SECOND);
+        let clock_state = restored_clock.get_state(now + 100 * SECOND);
+        assert_eq!(clock_state.turn_time_left_ms, Some(30_000));
+        assert_eq!(clock_state.time_banks_left_ms[&0], 30_000);
+        assert_eq!(clock_state.time_banks_left_ms[&1], 50_000);
+
+        // Snapshots are serializable, to survive restarts.
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert_eq!(
+            serde_json::from_str::<ClockSnapshot>(&json).unwrap(),
+            snapshot
+        );
+    }
+
+    #[test]
+    fn clock_postpone() {
+        let mut clock = new_clock(Some(30), Some(50));
+        let now = Instant::now();
+
+        clock.postpone(10 * SECOND);
+        assert!(clock.time_left_in_turn(now).is_none());
+
+        clock.start_turn(0, now);
+        clock.postpone(10 * SECOND);
+        assert_eq!(
+            clock.time_left_in_turn(now + 10 * SECOND),
+            Some(30 * SECOND)
+        );
+
+        // Time postponed is not charged to the time bank either.
+        clock.start_turn(1, now + 20 * SECOND);
+        assert_eq!(
+            clock.get_state(now + 20 * SECOND).time_banks_left_ms[&0],
+            40_000
+        );
+    }
+
+    #[test]
+    fn clock_remove_player() {
+        let mut clock = new_clock(None, Some(50));
+        let now = Instant::now();
+
+        clock.start_turn(0, now);
+        clock.remove_player(0);
+        assert!(clock.time_left_in_turn(now).is_none());
+        assert!(!clock.get_state(now).time_banks_left_ms.contains_key(&0));
+
+        clock.start_turn(1, now);
+        assert_eq!(clock.time_left_in_turn(now), Some(50 * SECOND));
+    }
+}
@@ -0,0 +1,189 @@
+use crate::card::{TrainColor, NUM_DRAWN_DESTINATION_CARDS};
+use crate::city::CityToCity;
+use crate::clock::TimeControls;
+use crate::manager::{AbandonedRoutes, GamePhase, SeatingOrder};
+use crate::player::PlayerColor;
+
+use serde::{Deserialize, Serialize};
+use smallvec::SmallVec;
+
+/// Everything that happened in a game, in order, as recorded by the [`crate::manager::Manager`].
+///
+/// There are two kinds of events:
+///   * Commands, which the manager accepted (e.g. [`GameEvent::RouteClaimed`]). The state of the game is derived
+///     from applying them, one after the other: replaying them rebuilds the exact same game (see
+///     [`crate::manager::Manager::replay`]).
+///   * Outcomes, which follow the command that led to them (e.g. [`GameEvent::PhaseChanged`]). They are derived from
+///     commands, and only recorded for those following the game (e.g. to notify players).
+///
+/// The log is thus a log of commands, rather than of every change to the state: the game is rebuilt by applying its
+/// commands again to a game created with the same seed, not by applying its outcomes.
+///
+/// Events hold no private information that commands did not already carry: the cards dealt to players, drawn from
+/// a face-down deck, or revealed to refill the open deck are not part of the log, as observers and archives must not
+/// learn players' hands. The seed of the game is, such that replaying it deals the same cards: auditing which cards
+/// were dealt takes replaying the game (see [`crate::manager::Manager::replay`]).
+///
+/// # JSON
+/// Events are serialized as objects, with their variant in snake_case under `type`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GameEvent {
+    /// # Command
+    /// The game was created, with a seed from which all randomness is derived (seating order, shuffled decks).
+    ///
+    /// This is always the first event.
+    GameCreated { seed: u64 },
+    /// # Command
+    /// See [`crate::manager::Manager::add_player`].
+    PlayerJoined { player_id: usize },
+    /// # Command
+    /// See [`crate::manager::Manager::change_player_name`].
+    PlayerRenamed { player_id: usize, name: String },
+    /// # Command
+    /// See [`crate::manager::Manager::change_player_color`].
+    PlayerColorChanged {
+        player_id: usize,
+        color: PlayerColor,
+    },
+    /// # Command
+    /// See [`crate::manager::Manager::set_ready`].
+    PlayerReadyChanged { player_id: usize, is_ready: bool },
+    /// # Command
+    /// See [`crate::manager::Manager::remove_player`].
+    PlayerRemoved { player_id: usize },
+    /// # Command
+    /// See [`crate::manager::Manager::set_abandoned_routes`].
+    AbandonedRoutesChanged { abandoned_routes: AbandonedRoutes },
+    /// # Command
+    /// See [`crate::manager::Manager::set_seating_order`].
+    SeatingOrderChanged { seating_order: SeatingOrder },
+    /// # Command
+    /// See [`crate::manager::Manager::set_time_controls`].
+    TimeControlsChanged { time_controls: TimeControls },
+    /// # Command
+    /// See [`crate::manager::Manager::force_start`].
+    GameForceStarted,
+    /// # Command
+    /// See [`crate::manager::Manager::force_end`].
+    GameForceEnded,
+    /// # Command
+    /// See [`crate::manager::Manager::select_destination_cards`].
+    DestinationCardsSelected {
+        player_id: usize,
+        destination_cards_decisions: SmallVec<[bool; NUM_DRAWN_DESTINATION_CARDS]>,
+    },
+    /// # Command
+    /// See [`crate::manager::Manager::draw_destination_cards`].
+    DestinationCardsDrawn { player_id: usize },
+    /// # Command
+    /// See [`crate::manager::Manager::draw_open_train_card`].
+    OpenTrainCardDrawn { player_id: usize, card_index: usize },
+    /// # Command
+    /// See [`crate::manager::Manager::draw_close_train_card`].
+    CloseTrainCardDrawn { player_id: usize },
+    /// # Command
+    /// See [`crate::manager::Manager::claim_route`].
+    RouteClaimed {
+        player_id: usize,
+        route: CityToCity,
+        parallel_route_index: usize,
+        cards: Vec<TrainColor>,
+    },
+    /// # Command
+    /// The current player ran out of time. See [`crate::manager::Manager::enforce_time_controls`].
+    TurnTimedOut { player_id: usize },
+    /// # Command
+    /// See [`crate::manager::Manager::request_undo`].
+    UndoRequested { player_id: usize },
+    /// # Command
+    /// See [`crate::manager::Manager::answer_undo_request`].
+    UndoAnswered { player_id: usize, approve: bool },
+    /// # Outcome
+    /// The game transitioned to another phase.
+    PhaseChanged { phase: GamePhase },
+    /// # Outcome
+    /// A player started their turn.
+    TurnStarted { turn: usize, player_id: usize },
+    /// # Outcome
+    /// A player passed their turn, having no legal move.
+    TurnPassed { player_id: usize },
+    /// # Outcome
+    /// The open train card deck was re-shuffled, having too many wild cards.
+    OpenTrainCardDeckReshuffled,
+    /// # Outcome
+    /// The last action was undone, as every player agreed to.
+    ActionUndone,
+}
+
+impl GameEvent {
+    /// Whether this event is the outcome of a command, rather than a command itself. See [`GameEvent`].
+    ///
+    /// # Example
+    /// ```
+    /// use ticket_to_ride::event::GameEvent;
+    ///
+    /// assert!(!GameEvent::CloseTrainCardDrawn { player_id: 0 }.is_outcome());
+    /// assert!(GameEvent::TurnPassed { player_id: 0 }.is_outcome());
+    /// ```
+    pub fn is_outcome(&self) -> bool {
+        matches!(
+            self,
+            Self::PhaseChanged { .. }
+                | Self::TurnStarted { .. }
+                | Self::TurnPassed { .. }
+                | Self::OpenTrainCardDeckReshuffled
+                | Self::ActionUndone
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::city::City;
+
+    #[test]
+    fn game_event_to_json() -> serde_json::Result<()> {
+        assert_eq!(
+            serde_json::to_string(&GameEvent::GameCreated { seed: 42 })?,
+            r#"{"type":"game_created","seed":42}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&GameEvent::GameForceStarted)?,
+            r#"{"type":"game_force_started"}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&GameEvent::RouteClaimed {
+                player_id: 1,
+                route: (City::Atlanta, City::Charleston),
+                parallel_route_index: 0,
+                cards: vec![TrainColor::Wild; 2],
+            })?,
+            r#"{"type":"route_claimed","player_id":1,"route":[0,3],"parallel_route_index":0,"cards":["wild","wild"]}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&GameEvent::PhaseChanged {
+                phase: GamePhase::LastTurn
+            })?,
+            r#"{"type":"phase_changed","phase":"last_turn"}"#
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn game_event_from_json() -> serde_json::Result<()> {
+        assert_eq!(
+            serde_json::from_str::<GameEvent>(
+                r#"{"type": "destination_cards_selected", "player_id": 2, "destination_cards_decisions": [true, false, true]}"#
+            )?,
+            GameEvent::DestinationCardsSelected {
+                player_id: 2,
+                destination_cards_decisions: smallvec![true, false, true],
+            }
+        );
+
+        Ok(())
+    }
+}
@@ -20,6 +20,13 @@ pub mod card;
 /// as [`city::CityToCity`] tuples.
 pub mod city;
 
+/// Module that defines the [`event::GameEvent`]s recorded by the [`manager::Manager`], from which the state of a game
+/// is derived.
+pub mod event;
+
+/// Module that times players' turns, via the [`clock::Clock`], when the game has [`clock::TimeControls`].
+pub mod clock;
+
 /// Module in charge of managing the whole game, via its [`manager::Manager`].
 /// This is the main entrypoint for clients interacting with the game logic.
 pub mod manager;
@@ -27,6 +34,10 @@ pub mod manager;
 /// Module that mostly pertains to the [`map::Map`], its routes -- and who claims them.
 pub mod map;
 
+/// Module that defines the [`observer::GameObserver`], which applications embedding the library implement to be
+/// notified of what happens in a game.
+pub mod observer;
+
 /// Modules that defines what a [`player::Player`] is, what state it holds,
 /// what actions they can take, and whether they are allowed to fulfill them.
 pub mod player;
@@ -1,33 +1,41 @@
 use crate::{
-    card::{CardDealer, CardDealerState, TrainColor, NUM_DRAWN_DESTINATION_CARDS},
+    card::{CardDealer, CardDealerState, DestinationCard, TrainColor, NUM_DRAWN_DESTINATION_CARDS},
     city::CityToCity,
+    clock::{Clock, ClockSnapshot, ClockState, TimeControls, TimeoutPolicy},
+    event::GameEvent,
     map::Map,
-    player::{Player, PlayerColor, PlayerState},
+    observer::GameObserver,
+    player::{Player, PlayerAction, PlayerColor, PlayerState},
 };
 
 use rand::seq::SliceRandom;
-use rand::thread_rng;
-use serde::Serialize;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
 use std::collections::{HashMap, HashSet};
+use std::time::Instant;
 use strum::IntoEnumIterator;
 
-const MIN_PLAYERS: usize = 2;
-const MAX_PLAYERS: usize = 5;
+/// Number of players needed for the game to start.
+pub const MIN_PLAYERS: usize = 2;
+/// Number of players a game can seat at most.
+pub const MAX_PLAYERS: usize = 5;
 const CARS_THRESHOLD_TO_TRIGGER_LAST_TURN_TRANSITION: u8 = 3;
 
-#[derive(Clone, Copy, Serialize, Debug, PartialEq)]
+#[derive(Clone, Copy, Deserialize, Serialize, Debug, PartialEq)]
 #[serde(rename_all = "snake_case")]
 /// Phases of the games, which act as states in the game's finite-state machine.
 ///
 /// # JSON
 /// Phases are serialized in snake_case.
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub enum GamePhase {
     /// Initial phase of the game: when players are joining the lobby, before the game has started.
     ///
     /// Players can join the game, change their name and color, and mark themselves as ready.
     ///
-    /// Once all players are ready, we move to the next phase.
+    /// Once all players are ready (or the start is forced, see [`Manager::force_start`]), we move to the next phase.
     InLobby,
     /// When the game starts, players concurrently select their initial set of
     /// [`crate::card::DestinationCard`]s.
@@ -42,7 +50,8 @@ pub enum GamePhase {
     ///
     /// This last turn is denoted by this special phase.
     LastTurn,
-    /// When each player has played their last turn.
+    /// When each player has played their last turn, or when nobody can play anymore (i.e. every player passed
+    /// during a full round, having no legal move).
     ///
     /// No actions can be taken at this point.
     Done,
@@ -50,6 +59,7 @@ pub enum GamePhase {
 
 #[derive(Serialize)]
 /// All the information about a game's current state, returned by [`Manager::get_state`].
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct GameState<'a> {
     /// The phase of the game.
     pub phase: GamePhase,
@@ -69,6 +79,128 @@ pub struct GameState<'a> {
     /// This only contains public information about them, except for requests coming from player _A_,
     /// which also holds private information about _A_ (and only _A_).
     pub players_state: SmallVec<[PlayerState<'a>; MAX_PLAYERS]>,
+    /// Time left on the clock, in games with [`TimeControls`] (see [`Manager::set_time_controls`]).
+    ///
+    /// Until the game has started, or if the game has no time controls, this is `None`.
+    ///
+    /// ## Serde
+    /// When serializing to JSON, this field is skipped if it is `None`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clock: Option<ClockState>,
+    /// When the game ends, once a player triggered the final round (see [`GamePhase::LastTurn`]).
+    ///
+    /// Until then, this is `None`.
+    ///
+    /// ## Serde
+    /// When serializing to JSON, this field is skipped if it is `None`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_game: Option<EndGame>,
+    /// How many turns are left to play before the game ends, including the current one, once a player triggered
+    /// the final round (see [`GamePhase::LastTurn`]).
+    ///
+    /// Until then, this is `None`.
+    ///
+    /// ## Serde
+    /// When serializing to JSON, this field is skipped if it is `None`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_turns_left: Option<usize>,
+    /// The last action, if it can be undone (see [`Manager::request_undo`]).
+    ///
+    /// ## Serde
+    /// When serializing to JSON, this field is skipped if it is `None`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub undo: Option<&'a UndoState>,
+}
+
+/// Schedules the end of the game, once a player triggered the final round (see [`GamePhase::LastTurn`]).
+///
+/// Every player, starting with the one after the triggering player, has exactly one turn left:
+/// the game is over once the final turn is.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct EndGame {
+    /// The ID of the player who triggered the final round, by running low on cars.
+    pub triggered_by: usize,
+    /// The last turn of the game.
+    pub final_turn: usize,
+}
+
+impl EndGame {
+    /// How many turns are left to play at the given turn, including that turn.
+    #[inline]
+    pub fn num_turns_left(&self, turn: usize) -> usize {
+        (self.final_turn + 1).saturating_sub(turn)
+    }
+}
+
+/// The last action taken in the game, which can be undone if every other player consents.
+///
+/// Only actions that reveal no hidden information can be undone: claiming a route, and drawing an open train card
+/// when the close deck is empty. Otherwise, the card replacing it in the open deck, and the open deck if it was then
+/// re-shuffled, would be revealed.
+///
+/// Undoing an action also restores the clock, such that the player picks up their turn with the time they had left.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct UndoState {
+    /// The ID of the player who took the action.
+    pub player_id: usize,
+    /// The action that would be undone.
+    pub action: PlayerAction,
+    /// The ID of the player who asked to undo the action, if anyone did.
+    pub requested_by: Option<usize>,
+    /// The IDs of the players who agreed to undo the action so far, besides the one who asked.
+    pub approved_by: Vec<usize>,
+}
+
+/// The state of the game before an action, to restore if the action is undone.
+struct Snapshot {
+    phase: GamePhase,
+    turn: Option<usize>,
+    map: Map,
+    card_dealer: CardDealer,
+    players: SmallVec<[Player; MAX_PLAYERS]>,
+    end_game: Option<EndGame>,
+    clock: Option<Clock>,
+    /// When the snapshot was taken, such that the time spent until the action is undone is charged to no one.
+    taken_at: Instant,
+}
+
+/// The last action taken in the game, if it can be undone.
+struct Undo {
+    state: UndoState,
+    snapshot: Snapshot,
+}
+
+/// How the order in which players play is decided, when the game starts.
+///
+/// # JSON
+/// Seating orders are serialized in snake_case: either `"random"`, or `{"manual": [<player IDs>]}`.
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum SeatingOrder {
+    /// Players are shuffled when the game starts. This is the default.
+    Random,
+    /// Players play in the given order of player IDs, which must list every player exactly once.
+    ///
+    /// Players joining afterwards play last, in the order they joined.
+    Manual(Vec<usize>),
+}
+
+/// What happens to the routes of a player who leaves the game before it is over.
+///
+/// # JSON
+/// Rules are serialized in snake_case.
+#[derive(Clone, Copy, Deserialize, Serialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum AbandonedRoutes {
+    /// The routes are freed, and can be claimed again by the remaining players. This is the default.
+    #[default]
+    Freed,
+    /// The routes stay claimed, and keep blocking the remaining players.
+    Kept,
 }
 
 /// All actions taken by a manager have the same `Result`:
@@ -95,13 +227,27 @@ pub struct Manager {
     /// Holds the [`CardDealer`].
     /// Only populated once the game is started!
     card_dealer: Option<CardDealer>,
+    /// Time limits of the turn-based game.
+    time_controls: TimeControls,
+    /// Holds the [`Clock`].
+    /// Only populated once the game is started, and if it has time controls!
+    clock: Option<Clock>,
     /// List of all players.
     ///
-    /// In the lobby, the player ID simply matches their index in this array.
-    /// However, once we start the game, we shuffle this list, such that the
-    /// order in which players play is random. To help keep track of specific
-    /// players after shuffling, we map their positions in `players_position`.
+    /// In the lobby, players are listed in the order they joined (unless a manual [`SeatingOrder`]
+    /// rearranged them), and removed players leave no gap: a player's index does not necessarily match
+    /// their ID. Once we start the game, we shuffle this list (unless the seating order is manual), such
+    /// that the order in which players play is random. To help keep track of specific players after
+    /// shuffling, we map their positions in `players_position`.
     players: SmallVec<[Player; MAX_PLAYERS]>,
+    /// ID given to the next player joining the game.
+    ///
+    /// IDs are never reused, such that a removed player's ID never ends up designating someone else.
+    next_player_id: usize,
+    /// Whether players get shuffled when the game starts. See [`SeatingOrder`].
+    random_seating_order: bool,
+    /// What happens to the routes of players leaving mid-game.
+    abandoned_routes: AbandonedRoutes,
     /// Maps a player ID to their position in the `players` array.
     /// Only populated once the game is started!
     players_position: HashMap<usize, usize>,
@@ -113,27 +259,272 @@ pub struct Manager {
     /// Once that number equals the number of players, we are ready to start the turn-based
     /// game -- and transition to the [`GamePhase::Playing`].
     num_players_selected_initial_destination_cards: usize,
-    /// Only relevant in the [`GamePhase::LastTurn`].
-    ///
-    /// Keeps track of the number of players that have finished playing.
+    /// Only populated from the [`GamePhase::LastTurn`].
     ///
-    /// Once that number equals the number of players, the game is over -- and transition
-    /// to the [`GamePhase::Done`].
-    num_players_done_playing: usize,
+    /// Once the final turn is over, the game is over -- and we transition to the [`GamePhase::Done`].
+    end_game: Option<EndGame>,
+    /// The last action, if it can be undone. See [`UndoState`].
+    undo: Option<Undo>,
+    /// Seed from which all randomness of the game is derived. See [`GameEvent::GameCreated`].
+    seed: u64,
+    /// Every event recorded so far, from which the state of the game is derived. See [`GameEvent`].
+    events: Vec<GameEvent>,
+    /// Notified of every command applied. See [`GameObserver`].
+    observers: Vec<Box<dyn GameObserver>>,
 }
 
 impl Manager {
     /// Creates a new [`Manager`] in the [`GamePhase::InLobby`].
     pub fn new() -> Self {
+        Self::with_seed(rand::random())
+    }
+
+    /// Creates a new [`Manager`], like [`Manager::new`], whose randomness (seating order, shuffled decks)
+    /// is all derived from the given seed.
+    pub fn with_seed(seed: u64) -> Self {
         Self {
             phase: GamePhase::InLobby,
             turn: None,
             map: None,
             card_dealer: None,
+            time_controls: TimeControls::default(),
+            clock: None,
             players: SmallVec::new(),
+            next_player_id: 0,
+            random_seating_order: true,
+            abandoned_routes: AbandonedRoutes::default(),
             players_position: HashMap::new(),
             num_players_selected_initial_destination_cards: 0,
-            num_players_done_playing: 0,
+            end_game: None,
+            undo: None,
+            seed,
+            events: vec![GameEvent::GameCreated { seed }],
+            observers: Vec::new(),
+        }
+    }
+
+    /// Rebuilds a game from the events it recorded (see [`Manager::events`]), by applying its commands one after
+    /// the other. Outcomes are not applied, but recorded again as commands lead to them.
+    ///
+    /// Returns an `Err` if the events do not start with [`GameEvent::GameCreated`], or if a command cannot be
+    /// applied.
+    ///
+    /// Otherwise, returns the game in the exact state it was in after the last event -- except for the clock,
+    /// which restarts from the current turn, unless restored as well (see [`Manager::restore_clock`]).
+    pub fn replay(events: &[GameEvent]) -> Result<Self, String> {
+        let mut manager = match events.first() {
+            Some(GameEvent::GameCreated { seed }) => Self::with_seed(*seed),
+            _ => {
+                return Err(String::from(
+                    "A game can only be replayed from its creation.",
+                ))
+            }
+        };
+
+        for event in events[1..].iter().filter(|event| !event.is_outcome()) {
+            manager.record(event.clone())?;
+        }
+
+        Ok(manager)
+    }
+
+    /// Returns what the clock counted so far, in games with [`TimeControls`] which have started.
+    pub fn clock_snapshot(&self) -> Option<ClockSnapshot> {
+        self.clock
+            .as_ref()
+            .map(|clock| clock.snapshot(Instant::now()))
+    }
+
+    /// Restores the clock of a replayed game (see [`Manager::replay`]), from what it counted before (see
+    /// [`Manager::clock_snapshot`]).
+    pub fn restore_clock(&mut self, snapshot: &ClockSnapshot) {
+        if let Some(clock) = self.clock.as_mut() {
+            clock.restore(snapshot, Instant::now());
+        }
+    }
+
+    /// Returns every event recorded so far, in order. See [`GameEvent`].
+    #[inline]
+    pub fn events(&self) -> &[GameEvent] {
+        &self.events
+    }
+
+    /// Registers an observer, notified of every command applied from now on. See [`GameObserver`].
+    pub fn add_observer(&mut self, observer: Box<dyn GameObserver>) {
+        self.observers.push(observer);
+    }
+
+    /// Applies the given command, and records it if it was accepted -- before the outcomes it led to.
+    fn record(&mut self, event: GameEvent) -> ManagerActionResult {
+        let num_events = self.events.len();
+        let num_reshuffles = self.num_open_train_card_deck_reshuffles();
+        let fulfilled_destination_cards = self.fulfilled_destination_cards();
+
+        if let Err(e) = self.apply(&event) {
+            self.events.truncate(num_events);
+            return Err(e);
+        }
+
+        self.events.insert(num_events, event);
+        if self.num_open_train_card_deck_reshuffles() > num_reshuffles {
+            self.events.push(GameEvent::OpenTrainCardDeckReshuffled);
+        }
+
+        self.notify_observers(num_events, fulfilled_destination_cards);
+        Ok(())
+    }
+
+    /// Returns the destination cards fulfilled so far, by player ID -- if anyone observes the game.
+    fn fulfilled_destination_cards(&self) -> Vec<(usize, DestinationCard)> {
+        if self.observers.is_empty() {
+            return Vec::new();
+        }
+
+        self.players
+            .iter()
+            .flat_map(|player| {
+                player
+                    .fulfilled_destination_cards()
+                    .map(|destination_card| (player.id(), destination_card.clone()))
+            })
+            .collect()
+    }
+
+    /// Notifies observers of the events recorded from `first_event`, and of the destination cards fulfilled since
+    /// `fulfilled_destination_cards` were.
+    fn notify_observers(
+        &mut self,
+        first_event: usize,
+        fulfilled_destination_cards: Vec<(usize, DestinationCard)>,
+    ) {
+        if self.observers.is_empty() {
+            return;
+        }
+
+        let mut observers = std::mem::take(&mut self.observers);
+        let state = self.get_public_state();
+
+        for event in &self.events[first_event..] {
+            for observer in observers.iter_mut() {
+                match event {
+                    GameEvent::TurnStarted { turn, player_id } => {
+                        observer.on_turn_started(*turn, *player_id)
+                    }
+                    GameEvent::PhaseChanged { phase } => {
+                        observer.on_phase_changed(*phase);
+                        if *phase == GamePhase::Done {
+                            observer.on_game_over(&state);
+                        }
+                    }
+                    GameEvent::OpenTrainCardDeckReshuffled => observer
+                        .on_open_train_card_deck_reshuffled(
+                            state
+                                .card_dealer_state
+                                .as_ref()
+                                .unwrap()
+                                .open_train_card_deck,
+                        ),
+                    _ => observer.on_action_applied(event, &state),
+                }
+            }
+        }
+
+        for player in &self.players {
+            for destination_card in player.fulfilled_destination_cards() {
+                let fulfilled = (player.id(), destination_card.clone());
+                if fulfilled_destination_cards.contains(&fulfilled) {
+                    continue;
+                }
+
+                for observer in observers.iter_mut() {
+                    observer.on_destination_card_fulfilled(player.id(), destination_card);
+                }
+            }
+
+            let player_state = self.get_state(player.id());
+            for observer in observers.iter_mut() {
+                observer.on_player_state_changed(player.id(), &player_state);
+            }
+        }
+
+        drop(state);
+        self.observers = observers;
+    }
+
+    fn apply(&mut self, event: &GameEvent) -> ManagerActionResult {
+        match event.clone() {
+            GameEvent::PlayerJoined { player_id } => self.apply_add_player(player_id),
+            GameEvent::PlayerRenamed { player_id, name } => {
+                self.apply_change_player_name(player_id, name)
+            }
+            GameEvent::PlayerColorChanged { player_id, color } => {
+                self.apply_change_player_color(player_id, color)
+            }
+            GameEvent::PlayerReadyChanged {
+                player_id,
+                is_ready,
+            } => self.apply_set_ready(player_id, is_ready),
+            GameEvent::PlayerRemoved { player_id } => self.apply_remove_player(player_id),
+            GameEvent::AbandonedRoutesChanged { abandoned_routes } => {
+                self.apply_set_abandoned_routes(abandoned_routes)
+            }
+            GameEvent::SeatingOrderChanged { seating_order } => {
+                self.apply_set_seating_order(seating_order)
+            }
+            GameEvent::TimeControlsChanged { time_controls } => {
+                self.apply_set_time_controls(time_controls)
+            }
+            GameEvent::GameForceStarted => self.apply_force_start(),
+            GameEvent::GameForceEnded => self.apply_force_end(),
+            GameEvent::DestinationCardsSelected {
+                player_id,
+                destination_cards_decisions,
+            } => self.apply_select_destination_cards(player_id, destination_cards_decisions),
+            GameEvent::DestinationCardsDrawn { player_id } => {
+                self.apply_draw_destination_cards(player_id)
+            }
+            GameEvent::OpenTrainCardDrawn {
+                player_id,
+                card_index,
+            } => self.apply_draw_open_train_card(player_id, card_index),
+            GameEvent::CloseTrainCardDrawn { player_id } => {
+                self.apply_draw_close_train_card(player_id)
+            }
+            GameEvent::RouteClaimed {
+                player_id,
+                route,
+                parallel_route_index,
+                cards,
+            } => self.apply_claim_route(player_id, route, parallel_route_index, cards),
+            GameEvent::TurnTimedOut { player_id } => self.apply_run_out_of_time(player_id),
+            GameEvent::UndoRequested { player_id } => self.apply_request_undo(player_id),
+            GameEvent::UndoAnswered { player_id, approve } => {
+                self.apply_answer_undo_request(player_id, approve)
+            }
+            GameEvent::GameCreated { .. } => Err(String::from("The game was already created.")),
+            _ => Err(String::from(
+                "Outcomes cannot be applied, only the commands leading to them.",
+            )),
+        }
+    }
+
+    #[inline]
+    fn emit(&mut self, event: GameEvent) {
+        self.events.push(event);
+    }
+
+    #[inline]
+    fn num_open_train_card_deck_reshuffles(&self) -> usize {
+        self.card_dealer.as_ref().map_or(0, |card_dealer| {
+            card_dealer.num_open_train_card_deck_reshuffles()
+        })
+    }
+
+    /// Transitions to the given phase.
+    fn set_phase(&mut self, phase: GamePhase) {
+        if self.phase != phase {
+            self.phase = phase;
+            self.emit(GameEvent::PhaseChanged { phase });
         }
     }
 
@@ -145,6 +536,28 @@ impl Manager {
     /// The one exception is when the game is done: in that case, every player should
     /// be able to see each other's public and private state.
     pub fn get_state(&self, player_id: usize) -> GameState {
+        self.get_state_with_private_state_of(|id| id == player_id)
+    }
+
+    /// Returns the game's state, without any player's private state.
+    ///
+    /// This is what someone who is not seated at the table would see. As with [`Manager::get_state`],
+    /// once the game is done, every player's private state is revealed.
+    pub fn get_public_state(&self) -> GameState<'_> {
+        self.get_state_with_private_state_of(|_| false)
+    }
+
+    /// Returns the game's state, with every player's private state.
+    ///
+    /// Nobody at the table should ever see this before the game is done: this is meant for those running the game.
+    pub fn get_full_state(&self) -> GameState<'_> {
+        self.get_state_with_private_state_of(|_| true)
+    }
+
+    fn get_state_with_private_state_of(
+        &self,
+        is_private_state_included: impl Fn(usize) -> bool,
+    ) -> GameState<'_> {
         GameState {
             phase: self.phase,
             turn: self.turn,
@@ -157,10 +570,20 @@ impl Manager {
                 .iter()
                 .map(|player| {
                     let include_private_state =
-                        player.id() == player_id || self.phase == GamePhase::Done;
+                        is_private_state_included(player.id()) || self.phase == GamePhase::Done;
                     player.get_player_state(include_private_state)
                 })
                 .collect(),
+            clock: self
+                .clock
+                .as_ref()
+                .map(|clock| clock.get_state(Instant::now())),
+            end_game: self.end_game,
+            num_turns_left: self
+                .end_game
+                .zip(self.turn)
+                .map(|(end_game, turn)| end_game.num_turns_left(turn)),
+            undo: self.undo.as_ref().map(|undo| &undo.state),
         }
     }
 
@@ -169,6 +592,35 @@ impl Manager {
         self.players.len()
     }
 
+    /// Whether a player with the given ID is part of the current game.
+    pub fn has_player(&self, player_id: usize) -> bool {
+        self.players.iter().any(|player| player.id() == player_id)
+    }
+
+    /// Returns the current phase of the game.
+    #[inline]
+    pub fn phase(&self) -> GamePhase {
+        self.phase
+    }
+
+    /// Returns the current turn, if the turn-based game has started.
+    #[inline]
+    pub fn turn(&self) -> Option<usize> {
+        self.turn
+    }
+
+    /// Returns what happens to the routes of players leaving the game. See [`Manager::set_abandoned_routes`].
+    #[inline]
+    pub fn abandoned_routes(&self) -> AbandonedRoutes {
+        self.abandoned_routes
+    }
+
+    /// Returns the time limits of the game. See [`Manager::set_time_controls`].
+    #[inline]
+    pub fn time_controls(&self) -> TimeControls {
+        self.time_controls
+    }
+
     #[inline]
     fn get_player_index(&self, player_id: usize) -> Option<usize> {
         self.players_position
@@ -176,6 +628,14 @@ impl Manager {
             .map(|player_id| *player_id)
     }
 
+    /// Returns the index of the given player in the lobby, where `players_position` is not populated yet.
+    fn get_lobby_player_index(&self, player_id: usize) -> Result<usize, String> {
+        self.players
+            .iter()
+            .position(|player| player.id() == player_id)
+            .ok_or_else(|| format!("There is no player with ID {}.", player_id))
+    }
+
     /// Creates a new [`Player`] (with a unique name and color),
     /// and adds it to the list of players for the current game.
     ///
@@ -184,11 +644,24 @@ impl Manager {
     ///
     /// Otherwise, returns the ID of the new player.
     pub fn add_player(&mut self) -> Option<usize> {
+        let player_id = self.next_player_id;
+        self.record(GameEvent::PlayerJoined { player_id }).ok()?;
+
+        Some(player_id)
+    }
+
+    fn apply_add_player(&mut self, player_id: usize) -> ManagerActionResult {
         if self.phase != GamePhase::InLobby || self.num_players() == MAX_PLAYERS {
-            return None;
+            return Err(String::from("Cannot join the game."));
         }
 
-        let player_id = self.num_players();
+        if player_id != self.next_player_id {
+            return Err(format!(
+                "The next player to join should have ID {}.",
+                self.next_player_id
+            ));
+        }
+        self.next_player_id += 1;
 
         self.players.push(Player::new(
             player_id,
@@ -196,7 +669,7 @@ impl Manager {
             self.generate_default_player_name(player_id),
         ));
 
-        Some(player_id)
+        Ok(())
     }
 
     fn generate_default_player_color(&self) -> PlayerColor {
@@ -240,6 +713,17 @@ impl Manager {
         &mut self,
         player_id: usize,
         new_name: String,
+    ) -> ManagerActionResult {
+        self.record(GameEvent::PlayerRenamed {
+            player_id,
+            name: new_name,
+        })
+    }
+
+    fn apply_change_player_name(
+        &mut self,
+        player_id: usize,
+        new_name: String,
     ) -> ManagerActionResult {
         if self.phase != GamePhase::InLobby {
             return Err(String::from(
@@ -256,7 +740,8 @@ impl Manager {
             }
         }
 
-        self.players[player_id].change_name(new_name);
+        let player_index = self.get_lobby_player_index(player_id)?;
+        self.players[player_index].change_name(new_name);
         Ok(())
     }
 
@@ -271,6 +756,17 @@ impl Manager {
         &mut self,
         player_id: usize,
         new_color: PlayerColor,
+    ) -> ManagerActionResult {
+        self.record(GameEvent::PlayerColorChanged {
+            player_id,
+            color: new_color,
+        })
+    }
+
+    fn apply_change_player_color(
+        &mut self,
+        player_id: usize,
+        new_color: PlayerColor,
     ) -> ManagerActionResult {
         if self.phase != GamePhase::InLobby {
             return Err(String::from(
@@ -287,7 +783,8 @@ impl Manager {
             }
         }
 
-        self.players[player_id].change_color(new_color);
+        let player_index = self.get_lobby_player_index(player_id)?;
+        self.players[player_index].change_color(new_color);
         Ok(())
     }
 
@@ -303,104 +800,454 @@ impl Manager {
     ///   * Drawing the initial set of train and destination cards for each player.
     ///   * Shuffling the order of players.
     pub fn set_ready(&mut self, player_id: usize, is_ready: bool) -> ManagerActionResult {
+        self.record(GameEvent::PlayerReadyChanged {
+            player_id,
+            is_ready,
+        })
+    }
+
+    fn apply_set_ready(&mut self, player_id: usize, is_ready: bool) -> ManagerActionResult {
         if self.phase != GamePhase::InLobby {
             return Err(String::from(
                 "Cannot change ready status outside of the lobby phase.",
             ));
         }
 
-        self.players[player_id].set_ready(is_ready);
+        let player_index = self.get_lobby_player_index(player_id)?;
+        self.players[player_index].set_ready(is_ready);
 
-        if self.num_players() >= MIN_PLAYERS && self.players.iter().all(|player| player.ready()) {
-            self.start_game()?;
-        }
+        self.maybe_start_game()
+    }
 
-        Ok(())
+    /// Removes the given player from the game, whether they leave or are removed by someone else.
+    ///
+    /// Returns an `Err` if either:
+    ///   * We are in [`GamePhase::Done`].
+    ///   * There is no such player.
+    ///
+    /// Otherwise, returns `Ok(())`.
+    ///
+    /// In the lobby, this frees the player's seat and color. If all remaining players are ready, the game starts
+    /// (see [`Manager::set_ready`]).
+    ///
+    /// Once the game has started, the player's cards are handed back to the [`CardDealer`], and their routes are
+    /// either freed or kept (see [`AbandonedRoutes`]). Whoever was playing keeps playing: if it was the removed
+    /// player, the next player starts their turn. If less than two players remain, the game is over.
+    pub fn remove_player(&mut self, player_id: usize) -> ManagerActionResult {
+        self.record(GameEvent::PlayerRemoved { player_id })
     }
 
-    fn start_game(&mut self) -> ManagerActionResult {
-        let map = Map::new(self.num_players())?;
-        let mut card_dealer = CardDealer::new();
+    fn apply_remove_player(&mut self, player_id: usize) -> ManagerActionResult {
+        match self.phase {
+            GamePhase::InLobby => {
+                let player_index = self.get_lobby_player_index(player_id)?;
+                self.players.remove(player_index);
 
-        self.phase = GamePhase::Starting;
-        self.players.shuffle(&mut thread_rng());
+                self.maybe_start_game()
+            }
+            GamePhase::Done => Err(String::from(
+                "Cannot remove a player once the game is over.",
+            )),
+            _ => {
+                let player_index = self
+                    .get_player_index(player_id)
+                    .ok_or_else(|| format!("There is no player with ID {}.", player_id))?;
+                self.remove_player_mid_game(player_index);
+
+                Ok(())
+            }
+        }
+    }
 
-        for (index, player) in self.players.iter_mut().enumerate() {
-            self.players_position.insert(player.id(), index);
-            player.initialize_when_game_starts(&mut card_dealer);
+    fn remove_player_mid_game(&mut self, player_index: usize) {
+        let player = self.players.remove(player_index);
+        self.undo = None;
+        let num_players = self.num_players();
+
+        match self.phase {
+            GamePhase::Starting if !player.has_pending_destination_cards() => {
+                self.num_players_selected_initial_destination_cards -= 1;
+            }
+            _ => {}
         }
 
-        self.map = Some(map);
-        self.card_dealer = Some(card_dealer);
-        Ok(())
-    }
+        if let Some(clock) = self.clock.as_mut() {
+            clock.remove_player(player.id());
+        }
 
-    #[inline]
-    fn has_game_started(&self) -> ManagerActionResult {
-        if self.phase == GamePhase::Starting || self.has_turn_based_game_started().is_ok() {
-            Ok(())
-        } else {
-            Err(String::from(
-                "Cannot play if the game has not started, or if it has ended.",
-            ))
+        let claimed_routes = player.leave_game(self.card_dealer.as_mut().unwrap());
+        if self.abandoned_routes == AbandonedRoutes::Freed {
+            let map = self.map.as_mut().unwrap();
+            for claimed_route in claimed_routes {
+                map.release_route(claimed_route.route, claimed_route.parallel_route_index);
+            }
         }
-    }
 
-    #[inline]
-    fn has_turn_based_game_started(&self) -> ManagerActionResult {
-        if self.phase == GamePhase::Playing || self.phase == GamePhase::LastTurn {
-            Ok(())
-        } else {
-            Err(String::from(
-                "Cannot play if the turn-based game has not started, or if it has ended.",
-            ))
+        self.players_position = self
+            .players
+            .iter()
+            .enumerate()
+            .map(|(index, player)| (player.id(), index))
+            .collect();
+
+        if num_players < MIN_PLAYERS {
+            self.finalize_game();
+            return;
         }
-    }
 
-    /// # Panic!
-    /// This should only be called once the turn-based game has started,
-    /// as it assumes that `self.turn` is defined, and that the number of players is >0.
-    #[inline]
-    fn is_player_turn(&self, player_index: usize) -> ManagerActionResult {
-        if self.turn.unwrap() % self.num_players() == player_index {
-            Ok(())
-        } else {
-            Err(String::from("This is not your turn!"))
+        match self.phase {
+            GamePhase::Starting => {
+                if self.num_players_selected_initial_destination_cards == num_players {
+                    self.start_turn_based_game();
+                }
+            }
+            GamePhase::Playing | GamePhase::LastTurn => {
+                self.realign_turn(player_index);
+
+                if self.is_final_turn_over() {
+                    self.finalize_game();
+                }
+            }
+            _ => unreachable!("Players are only removed mid-game before the game is over."),
         }
-    }
 
-    /// # Panic!
-    /// This should only be called once the turn-based game has started,
-    /// as it assumes that `self.turn` is defined.
-    #[inline]
-    fn increment_turn(&mut self) {
-        *self.turn.as_mut().unwrap() += 1;
+        self.pass_stalled_turns();
     }
 
-    /// Updates players' and game's state, depending on whether a given player is done playing.
-    ///
-    /// If we are in [`GamePhase::LastTurn`], the player is marked as done.
+    /// Moves the turn forward, such that turns keep rotating in the same order once the player at
+    /// `removed_player_index` has left.
     ///
-    /// Furthermore, if all players are done, then we transition to [`GamePhase::Done`].
-    /// When we do so, we update the points of each player, based on whether they have fulfilled
-    /// or not their destination cards. Finally, we compute the game's longest route, and grant
-    /// points to those having built said longest route.
-    fn maybe_player_and_game_done(&mut self, player_index: usize) {
-        if self.phase != GamePhase::LastTurn {
-            return;
+    /// Whoever was playing keeps playing. If it was the removed player, the next player starts their turn.
+    fn realign_turn(&mut self, removed_player_index: usize) {
+        let turn = self.turn.unwrap();
+        let num_players = self.num_players();
+        let playing_index = turn % (num_players + 1);
+
+        let new_playing_index = if playing_index > removed_player_index {
+            playing_index - 1
+        } else {
+            playing_index % num_players
+        };
+        let new_turn = turn + (new_playing_index + num_players - turn % num_players) % num_players;
+
+        self.turn = Some(new_turn);
+        if let Some(end_game) = self.end_game.as_mut() {
+            // The removed player no longer plays their final turn, if they had one left.
+            let num_turns_left = end_game.num_turns_left(turn);
+            let removed_player_offset =
+                (removed_player_index + num_players + 1 - playing_index) % (num_players + 1);
+            let num_turns_left = if removed_player_offset < num_turns_left {
+                num_turns_left - 1
+            } else {
+                num_turns_left
+            };
+
+            end_game.final_turn = (new_turn + num_turns_left).saturating_sub(1);
         }
-        self.num_players_done_playing += 1;
-        self.players[player_index].set_done_playing();
 
-        if self.num_players_done_playing != self.num_players() {
-            return;
+        if playing_index != removed_player_index {
+            self.players[new_playing_index].carry_over_turn_actions(turn, new_turn);
+        } else {
+            self.start_turn();
         }
+    }
 
-        self.phase = GamePhase::Done;
-        let all_longest_routes: SmallVec<[u16; MAX_PLAYERS]> = self
-            .players
-            .iter_mut()
-            .map(|player| player.finalize_game())
+    /// Changes what happens to the routes of players leaving mid-game. See [`AbandonedRoutes`].
+    ///
+    /// Returns an `Err` if we are not in [`GamePhase::InLobby`].
+    ///
+    /// Otherwise, returns `Ok(())`.
+    pub fn set_abandoned_routes(
+        &mut self,
+        abandoned_routes: AbandonedRoutes,
+    ) -> ManagerActionResult {
+        self.record(GameEvent::AbandonedRoutesChanged { abandoned_routes })
+    }
+
+    fn apply_set_abandoned_routes(
+        &mut self,
+        abandoned_routes: AbandonedRoutes,
+    ) -> ManagerActionResult {
+        if self.phase != GamePhase::InLobby {
+            return Err(String::from(
+                "Cannot change what happens to abandoned routes outside of the lobby phase.",
+            ));
+        }
+
+        self.abandoned_routes = abandoned_routes;
+        Ok(())
+    }
+
+    /// Changes how the order in which players play is decided. See [`SeatingOrder`].
+    ///
+    /// Returns an `Err` if either:
+    ///   * We are not in [`GamePhase::InLobby`].
+    ///   * A manual seating order does not list every player exactly once.
+    ///
+    /// Otherwise, returns `Ok(())`. With a manual seating order, players are rearranged right away.
+    pub fn set_seating_order(&mut self, seating_order: SeatingOrder) -> ManagerActionResult {
+        self.record(GameEvent::SeatingOrderChanged { seating_order })
+    }
+
+    fn apply_set_seating_order(&mut self, seating_order: SeatingOrder) -> ManagerActionResult {
+        if self.phase != GamePhase::InLobby {
+            return Err(String::from(
+                "Cannot change the seating order outside of the lobby phase.",
+            ));
+        }
+
+        let player_ids = match seating_order {
+            SeatingOrder::Random => {
+                self.random_seating_order = true;
+                return Ok(());
+            }
+            SeatingOrder::Manual(player_ids) => player_ids,
+        };
+
+        for (index, player_id) in player_ids.iter().enumerate() {
+            self.get_lobby_player_index(*player_id)?;
+            if player_ids[..index].contains(player_id) {
+                return Err(format!(
+                    "Player {} is listed more than once in the seating order.",
+                    player_id
+                ));
+            }
+        }
+
+        if player_ids.len() != self.num_players() {
+            return Err(String::from(
+                "The seating order must list every player in the game.",
+            ));
+        }
+
+        self.players.sort_by_key(|player| {
+            player_ids
+                .iter()
+                .position(|player_id| *player_id == player.id())
+        });
+        self.random_seating_order = false;
+        Ok(())
+    }
+
+    /// Starts the game, whether or not all players are ready.
+    ///
+    /// Returns an `Err` if either:
+    ///   * We are not in [`GamePhase::InLobby`].
+    ///   * There are not enough players to start.
+    ///
+    /// Otherwise, starts the game (see [`Manager::set_ready`]), and returns `Ok(())`.
+    pub fn force_start(&mut self) -> ManagerActionResult {
+        self.record(GameEvent::GameForceStarted)
+    }
+
+    fn apply_force_start(&mut self) -> ManagerActionResult {
+        if self.phase != GamePhase::InLobby {
+            return Err(String::from(
+                "Cannot start the game outside of the lobby phase.",
+            ));
+        }
+
+        if self.num_players() < MIN_PLAYERS {
+            return Err(format!(
+                "Cannot start the game with less than {} players.",
+                MIN_PLAYERS
+            ));
+        }
+
+        self.start_game()
+    }
+
+    /// Ends the game, wherever it is at.
+    ///
+    /// Returns an `Err` if we are in [`GamePhase::InLobby`], or in [`GamePhase::Done`].
+    ///
+    /// Otherwise, transitions to [`GamePhase::Done`], and returns `Ok(())`. As when the final turn is over, the
+    /// end-of-game points are granted (see `Manager::maybe_player_and_game_done`).
+    pub fn force_end(&mut self) -> ManagerActionResult {
+        self.record(GameEvent::GameForceEnded)
+    }
+
+    fn apply_force_end(&mut self) -> ManagerActionResult {
+        if self.has_game_started().is_err() {
+            return Err(String::from(
+                "Cannot end a game which has not started, or which has ended.",
+            ));
+        }
+
+        self.undo = None;
+        self.finalize_game();
+        Ok(())
+    }
+
+    /// Changes the time limits of the turn-based game. See [`TimeControls`].
+    ///
+    /// Returns an `Err` if we are not in [`GamePhase::InLobby`].
+    ///
+    /// Otherwise, returns `Ok(())`.
+    pub fn set_time_controls(&mut self, time_controls: TimeControls) -> ManagerActionResult {
+        self.record(GameEvent::TimeControlsChanged { time_controls })
+    }
+
+    fn apply_set_time_controls(&mut self, time_controls: TimeControls) -> ManagerActionResult {
+        if self.phase != GamePhase::InLobby {
+            return Err(String::from(
+                "Cannot change time controls outside of the lobby phase.",
+            ));
+        }
+
+        self.time_controls = time_controls;
+        Ok(())
+    }
+
+    fn maybe_start_game(&mut self) -> ManagerActionResult {
+        if self.num_players() >= MIN_PLAYERS && self.players.iter().all(|player| player.ready()) {
+            self.start_game()?;
+        }
+
+        Ok(())
+    }
+
+    fn start_game(&mut self) -> ManagerActionResult {
+        let map = Map::new(self.num_players())?;
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        let mut card_dealer = CardDealer::with_seed(rng.gen());
+
+        self.set_phase(GamePhase::Starting);
+        if self.random_seating_order {
+            self.players.shuffle(&mut rng);
+        }
+
+        for (index, player) in self.players.iter_mut().enumerate() {
+            self.players_position.insert(player.id(), index);
+            player.initialize_when_game_starts(&mut card_dealer);
+        }
+
+        self.map = Some(map);
+        self.card_dealer = Some(card_dealer);
+        if self.time_controls.is_enabled() {
+            self.clock = Some(Clock::new(
+                self.time_controls,
+                self.players.iter().map(|player| player.id()),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Transitions to [`GamePhase::Playing`], where the first player starts their turn.
+    fn start_turn_based_game(&mut self) {
+        self.set_phase(GamePhase::Playing);
+        self.turn = Some(0);
+        self.start_turn();
+    }
+
+    /// Starts the current player's turn, timing it if the game has time controls.
+    fn start_turn(&mut self) {
+        let turn = self.turn.unwrap();
+        let player_id = self.players[turn % self.players.len()].id();
+
+        if let Some(clock) = self.clock.as_mut() {
+            clock.start_turn(player_id, Instant::now());
+        }
+        self.emit(GameEvent::TurnStarted { turn, player_id });
+    }
+
+    #[inline]
+    fn has_game_started(&self) -> ManagerActionResult {
+        if self.phase == GamePhase::Starting || self.has_turn_based_game_started().is_ok() {
+            Ok(())
+        } else {
+            Err(String::from(
+                "Cannot play if the game has not started, or if it has ended.",
+            ))
+        }
+    }
+
+    #[inline]
+    fn has_turn_based_game_started(&self) -> ManagerActionResult {
+        if self.phase == GamePhase::Playing || self.phase == GamePhase::LastTurn {
+            Ok(())
+        } else {
+            Err(String::from(
+                "Cannot play if the turn-based game has not started, or if it has ended.",
+            ))
+        }
+    }
+
+    /// # Panic!
+    /// This should only be called once the turn-based game has started,
+    /// as it assumes that `self.turn` is defined, and that the number of players is >0.
+    #[inline]
+    fn is_player_turn(&self, player_index: usize) -> ManagerActionResult {
+        if self.turn.unwrap() % self.num_players() == player_index {
+            Ok(())
+        } else {
+            Err(String::from("This is not your turn!"))
+        }
+    }
+
+    /// # Panic!
+    /// This should only be called once the turn-based game has started,
+    /// as it assumes that `self.turn` is defined.
+    #[inline]
+    fn increment_turn(&mut self) {
+        *self.turn.as_mut().unwrap() += 1;
+        self.start_turn();
+    }
+
+    /// Transitions to [`GamePhase::LastTurn`], once the player at the given index triggered the final round.
+    ///
+    /// This must be called once the turn was incremented: every player, starting with the current one,
+    /// has one turn left. See [`EndGame`].
+    fn schedule_end_game(&mut self, player_index: usize) {
+        self.set_phase(GamePhase::LastTurn);
+        self.end_game = Some(EndGame {
+            triggered_by: self.players[player_index].id(),
+            final_turn: self.turn.unwrap() + self.num_players() - 1,
+        });
+    }
+
+    /// Whether the final turn of the game was played. See [`EndGame`].
+    #[inline]
+    fn is_final_turn_over(&self) -> bool {
+        self.phase == GamePhase::LastTurn
+            && self
+                .end_game
+                .zip(self.turn)
+                .is_some_and(|(end_game, turn)| turn > end_game.final_turn)
+    }
+
+    /// Updates players' and game's state, depending on whether a given player is done playing.
+    ///
+    /// This must be called once the turn was incremented.
+    ///
+    /// If we are in [`GamePhase::LastTurn`], the player is marked as done.
+    ///
+    /// Furthermore, if the final turn is over (see [`EndGame`]), then we transition to [`GamePhase::Done`].
+    /// When we do so, we update the points of each player, based on whether they have fulfilled
+    /// or not their destination cards. Finally, we compute the game's longest route, and grant
+    /// points to those having built said longest route.
+    fn maybe_player_and_game_done(&mut self, player_index: usize) {
+        if self.phase != GamePhase::LastTurn {
+            return;
+        }
+        self.players[player_index].set_done_playing();
+
+        if self.is_final_turn_over() {
+            self.finalize_game();
+        }
+    }
+
+    /// Transitions to [`GamePhase::Done`], and grants the end-of-game points (see `Manager::maybe_player_and_game_done`).
+    fn finalize_game(&mut self) {
+        self.set_phase(GamePhase::Done);
+        if let Some(clock) = self.clock.as_mut() {
+            clock.stop(Instant::now());
+        }
+
+        let all_longest_routes: SmallVec<[u16; MAX_PLAYERS]> = self
+            .players
+            .iter_mut()
+            .map(|player| player.finalize_game())
             .collect();
         let max_longest_route = *all_longest_routes.iter().max().unwrap();
 
@@ -413,6 +1260,46 @@ impl Manager {
             });
     }
 
+    /// Whether the player at the given index could take any action, were it the beginning of their turn: drawing
+    /// a train card, drawing destination cards, or claiming a route.
+    ///
+    /// # Panic!
+    /// This should only be called once the game has started, as it assumes that the map and card dealer exist.
+    fn has_legal_move(&self, player_index: usize) -> bool {
+        let card_dealer = self.card_dealer.as_ref().unwrap();
+
+        card_dealer.can_player_draw_train_card()
+            || card_dealer.can_player_draw_destination_cards()
+            || self.players[player_index].can_claim_any_route(self.map.as_ref().unwrap())
+    }
+
+    /// Passes the turn on behalf of the current player if they have no legal move, and so on with the next players,
+    /// until someone can play. As all actions that mark the end of the turn, we subsequently verify whether the
+    /// player is done playing. More details in `Manager::maybe_player_and_game_done`.
+    ///
+    /// If every player passes during a full round, nobody will ever be able to play again: the game is over.
+    ///
+    /// This must be called whenever a new turn starts.
+    fn pass_stalled_turns(&mut self) {
+        while self.has_turn_based_game_started().is_ok() {
+            let turn = self.turn.unwrap();
+            let player_index = turn % self.num_players();
+            if self.has_legal_move(player_index) {
+                return;
+            }
+
+            self.players[player_index].pass(turn);
+            let player_id = self.players[player_index].id();
+            self.emit(GameEvent::TurnPassed { player_id });
+            self.increment_turn();
+            self.maybe_player_and_game_done(player_index);
+
+            if self.phase != GamePhase::Done && self.players.iter().all(|player| player.passed()) {
+                self.finalize_game();
+            }
+        }
+    }
+
     /// Allows a given player to select from the set of destination cards --
     /// which they will try to fulfill.
     ///
@@ -433,10 +1320,23 @@ impl Manager {
         &mut self,
         player_id: usize,
         destination_cards_decisions: SmallVec<[bool; NUM_DRAWN_DESTINATION_CARDS]>,
+    ) -> ManagerActionResult {
+        self.record(GameEvent::DestinationCardsSelected {
+            player_id,
+            destination_cards_decisions,
+        })
+    }
+
+    fn apply_select_destination_cards(
+        &mut self,
+        player_id: usize,
+        destination_cards_decisions: SmallVec<[bool; NUM_DRAWN_DESTINATION_CARDS]>,
     ) -> ManagerActionResult {
         self.has_game_started()?;
 
-        let player_index = self.get_player_index(player_id).unwrap();
+        let player_index = self
+            .get_player_index(player_id)
+            .ok_or_else(|| format!("Player {} is not in the game", player_id))?;
         if self.has_turn_based_game_started().is_ok() {
             self.is_player_turn(player_index)?;
         }
@@ -446,19 +1346,20 @@ impl Manager {
             self.turn,
             self.card_dealer.as_mut().unwrap(),
         )?;
+        self.undo = None;
 
         if self.phase == GamePhase::Starting {
             self.num_players_selected_initial_destination_cards += 1;
 
             if self.num_players_selected_initial_destination_cards == self.num_players() {
-                self.phase = GamePhase::Playing;
-                self.turn = Some(0);
+                self.start_turn_based_game();
             }
         } else {
             self.increment_turn();
             self.maybe_player_and_game_done(player_index);
         }
 
+        self.pass_stalled_turns();
         Ok(())
     }
 
@@ -471,13 +1372,20 @@ impl Manager {
     ///
     /// Otherwise, returns `Ok(())`.
     pub fn draw_destination_cards(&mut self, player_id: usize) -> ManagerActionResult {
+        self.record(GameEvent::DestinationCardsDrawn { player_id })
+    }
+
+    fn apply_draw_destination_cards(&mut self, player_id: usize) -> ManagerActionResult {
         self.has_turn_based_game_started()?;
 
-        let player_index = self.get_player_index(player_id).unwrap();
+        let player_index = self
+            .get_player_index(player_id)
+            .ok_or_else(|| format!("Player {} is not in the game", player_id))?;
         self.is_player_turn(player_index)?;
 
         self.players[player_index]
             .draw_destination_cards(self.turn.unwrap(), self.card_dealer.as_mut().unwrap())?;
+        self.undo = None;
 
         Ok(())
     }
@@ -496,23 +1404,51 @@ impl Manager {
         &mut self,
         player_id: usize,
         card_index: usize,
+    ) -> ManagerActionResult {
+        self.record(GameEvent::OpenTrainCardDrawn {
+            player_id,
+            card_index,
+        })
+    }
+
+    fn apply_draw_open_train_card(
+        &mut self,
+        player_id: usize,
+        card_index: usize,
     ) -> ManagerActionResult {
         self.has_turn_based_game_started()?;
 
-        let player_index = self.get_player_index(player_id).unwrap();
+        let player_index = self
+            .get_player_index(player_id)
+            .ok_or_else(|| format!("Player {} is not in the game", player_id))?;
         self.is_player_turn(player_index)?;
 
+        let snapshot = self.take_snapshot();
         let is_turn_over = self.players[player_index].draw_open_train_card(
             card_index,
             self.turn.unwrap(),
             self.card_dealer.as_mut().unwrap(),
         )?;
+        // The card replacing the one drawn came from the top of the close deck, for everyone to see.
+        let revealed_card = self
+            .card_dealer
+            .as_ref()
+            .unwrap()
+            .get_state()
+            .open_train_card_deck[card_index]
+            .is_some();
 
         if is_turn_over {
             self.increment_turn();
             self.maybe_player_and_game_done(player_index);
+            self.pass_stalled_turns();
         }
 
+        if revealed_card {
+            self.undo = None;
+        } else {
+            self.allow_undo(snapshot, player_index);
+        }
         Ok(())
     }
 
@@ -527,17 +1463,25 @@ impl Manager {
     /// As all actions that mark the end of the turn, we subsequently verify whether the
     /// player is done playing. More details in `Manager::maybe_player_and_game_done`.
     pub fn draw_close_train_card(&mut self, player_id: usize) -> ManagerActionResult {
+        self.record(GameEvent::CloseTrainCardDrawn { player_id })
+    }
+
+    fn apply_draw_close_train_card(&mut self, player_id: usize) -> ManagerActionResult {
         self.has_turn_based_game_started()?;
 
-        let player_index = self.get_player_index(player_id).unwrap();
+        let player_index = self
+            .get_player_index(player_id)
+            .ok_or_else(|| format!("Player {} is not in the game", player_id))?;
         self.is_player_turn(player_index)?;
 
         let is_turn_over = self.players[player_index]
             .draw_close_train_card(self.turn.unwrap(), self.card_dealer.as_mut().unwrap())?;
+        self.undo = None;
 
         if is_turn_over {
             self.increment_turn();
             self.maybe_player_and_game_done(player_index);
+            self.pass_stalled_turns();
         }
 
         Ok(())
@@ -559,12 +1503,30 @@ impl Manager {
         route: CityToCity,
         parallel_route_index: usize,
         cards: Vec<TrainColor>,
+    ) -> ManagerActionResult {
+        self.record(GameEvent::RouteClaimed {
+            player_id,
+            route,
+            parallel_route_index,
+            cards,
+        })
+    }
+
+    fn apply_claim_route(
+        &mut self,
+        player_id: usize,
+        route: CityToCity,
+        parallel_route_index: usize,
+        cards: Vec<TrainColor>,
     ) -> ManagerActionResult {
         self.has_turn_based_game_started()?;
 
-        let player_index = self.get_player_index(player_id).unwrap();
+        let player_index = self
+            .get_player_index(player_id)
+            .ok_or_else(|| format!("Player {} is not in the game", player_id))?;
         self.is_player_turn(player_index)?;
 
+        let snapshot = self.take_snapshot();
         self.players[player_index].claim_route(
             route,
             parallel_route_index,
@@ -581,28 +1543,253 @@ impl Manager {
         if self.phase == GamePhase::Playing
             && self.players[player_index].cars() < CARS_THRESHOLD_TO_TRIGGER_LAST_TURN_TRANSITION
         {
-            self.phase = GamePhase::LastTurn;
+            self.schedule_end_game(player_index);
         } else {
             self.maybe_player_and_game_done(player_index);
         }
 
+        self.pass_stalled_turns();
+        self.allow_undo(snapshot, player_index);
         Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::{card::TrainColor, city::City, map::ClaimedRoute};
+    /// Applies the [`TimeoutPolicy`] on behalf of the current player, if they ran out of time.
+    ///
+    /// This must be called regularly during the turn-based game, as nothing else ends turns automatically.
+    ///
+    /// Returns whether the current player ran out of time. If so, their pending destination cards (if any)
+    /// go back to the deck, the [`TimeoutPolicy`] is applied (unless they had drawn destination cards),
+    /// and their turn is over. As all actions that mark the end of the turn, we subsequently verify whether the
+    /// player is done playing. More details in `Manager::maybe_player_and_game_done`.
+    pub fn enforce_time_controls(&mut self) -> bool {
+        self.enforce_time_controls_at(Instant::now())
+    }
 
-    // Tests for `GamePhase`.
+    fn enforce_time_controls_at(&mut self, now: Instant) -> bool {
+        if !self.has_timed_out_at(now) {
+            return false;
+        }
 
-    #[test]
-    fn game_phase_to_json() -> serde_json::Result<()> {
-        assert_eq!(serde_json::to_string(&GamePhase::InLobby)?, r#""in_lobby""#);
-        assert_eq!(
-            serde_json::to_string(&GamePhase::Starting)?,
-            r#""starting""#
+        let turn = self.turn.unwrap();
+        let player_id = self.players[turn % self.num_players()].id();
+
+        // The clock is left alone until the event is recorded: ending the turn then charges the whole turn to the
+        // player, whatever the time it took to time out, and starts timing the next one.
+        self.record(GameEvent::TurnTimedOut { player_id }).is_ok()
+    }
+
+    /// Whether the current player ran out of time, in which case [`Manager::enforce_time_controls`] ends their turn.
+    pub fn has_timed_out(&self) -> bool {
+        self.has_timed_out_at(Instant::now())
+    }
+
+    fn has_timed_out_at(&self, now: Instant) -> bool {
+        self.has_turn_based_game_started().is_ok()
+            && self
+                .clock
+                .as_ref()
+                .is_some_and(|clock| clock.has_timed_out(now))
+    }
+
+    fn apply_run_out_of_time(&mut self, player_id: usize) -> ManagerActionResult {
+        self.has_turn_based_game_started()?;
+
+        let turn = self.turn.unwrap();
+        let player_index = self
+            .get_player_index(player_id)
+            .ok_or_else(|| format!("Player {} is not in the game", player_id))?;
+        self.is_player_turn(player_index)?;
+
+        if self.time_controls.timeout_policy == TimeoutPolicy::DrawTrainCards
+            && !self.players[player_index].has_pending_destination_cards()
+        {
+            while self.turn == Some(turn) && self.apply_draw_close_train_card(player_id).is_ok() {}
+        }
+
+        self.players[player_index].run_out_of_time(turn, self.card_dealer.as_mut().unwrap());
+        self.undo = None;
+
+        if self.turn == Some(turn) {
+            self.increment_turn();
+            self.maybe_player_and_game_done(player_index);
+            self.pass_stalled_turns();
+        }
+
+        Ok(())
+    }
+
+    fn take_snapshot(&self) -> Snapshot {
+        Snapshot {
+            phase: self.phase,
+            turn: self.turn,
+            map: self.map.clone().unwrap(),
+            card_dealer: self.card_dealer.clone().unwrap(),
+            players: self.players.clone(),
+            end_game: self.end_game,
+            clock: self.clock.clone(),
+            taken_at: Instant::now(),
+        }
+    }
+
+    /// Keeps the snapshot taken before the given player's last action, such that the action can be undone.
+    ///
+    /// Actions that revealed hidden information (i.e. re-shuffling the open deck), or that ended the game,
+    /// cannot be undone.
+    fn allow_undo(&mut self, snapshot: Snapshot, player_index: usize) {
+        let reshuffled = self
+            .card_dealer
+            .as_ref()
+            .unwrap()
+            .num_open_train_card_deck_reshuffles()
+            != snapshot.card_dealer.num_open_train_card_deck_reshuffles();
+        if reshuffled || self.phase == GamePhase::Done {
+            self.undo = None;
+            return;
+        }
+
+        let player = &self.players[player_index];
+        self.undo = Some(Undo {
+            state: UndoState {
+                player_id: player.id(),
+                action: player.last_action().unwrap(),
+                requested_by: None,
+                approved_by: Vec::new(),
+            },
+            snapshot,
+        });
+    }
+
+    /// Asks for the last action to be undone. Any player can ask, and every other player must then agree,
+    /// via [`Manager::answer_undo_request`].
+    ///
+    /// Returns an `Err` if either:
+    ///   * We are not in [`GamePhase::Playing`], nor [`GamePhase::LastTurn`].
+    ///   * The player is not in the game.
+    ///   * The last action cannot be undone (see [`UndoState`]).
+    ///   * Someone already asked to undo it.
+    ///
+    /// Otherwise, returns `Ok(())`. Taking any other action in the meantime makes the request moot.
+    pub fn request_undo(&mut self, player_id: usize) -> ManagerActionResult {
+        self.record(GameEvent::UndoRequested { player_id })
+    }
+
+    fn apply_request_undo(&mut self, player_id: usize) -> ManagerActionResult {
+        self.has_turn_based_game_started()?;
+        self.get_player_index(player_id)
+            .ok_or_else(|| format!("Player {} is not in the game", player_id))?;
+
+        let undo_state = match self.undo.as_mut() {
+            Some(undo) => &mut undo.state,
+            None => return Err(String::from("There is no action that can be undone.")),
+        };
+
+        if undo_state.requested_by.is_some() {
+            return Err(String::from(
+                "Someone already asked to undo the last action.",
+            ));
+        }
+
+        undo_state.requested_by = Some(player_id);
+        self.maybe_undo();
+        Ok(())
+    }
+
+    /// Agrees to undo the last action (or refuses to), once someone asked to via [`Manager::request_undo`].
+    ///
+    /// Returns an `Err` if either:
+    ///   * The player is not in the game.
+    ///   * Nobody asked to undo the last action.
+    ///   * The player already agreed to undo it.
+    ///
+    /// Otherwise, returns `Ok(())`. If the player refuses, the request is dropped. Once every player agreed,
+    /// the game goes back to the exact state it was in before the action.
+    pub fn answer_undo_request(&mut self, player_id: usize, approve: bool) -> ManagerActionResult {
+        self.record(GameEvent::UndoAnswered { player_id, approve })
+    }
+
+    fn apply_answer_undo_request(
+        &mut self,
+        player_id: usize,
+        approve: bool,
+    ) -> ManagerActionResult {
+        self.get_player_index(player_id)
+            .ok_or_else(|| format!("Player {} is not in the game", player_id))?;
+
+        let undo_state = match self.undo.as_mut() {
+            Some(undo) if undo.state.requested_by.is_some() => &mut undo.state,
+            _ => return Err(String::from("Nobody asked to undo the last action.")),
+        };
+
+        if undo_state.requested_by == Some(player_id) || undo_state.approved_by.contains(&player_id)
+        {
+            return Err(String::from("You already agreed to undo the last action."));
+        }
+
+        if approve {
+            undo_state.approved_by.push(player_id);
+            self.maybe_undo();
+        } else {
+            undo_state.requested_by = None;
+            undo_state.approved_by.clear();
+        }
+
+        Ok(())
+    }
+
+    /// Restores the state of the game before the last action, once every player agreed to undo it.
+    fn maybe_undo(&mut self) {
+        let undo_state = &self.undo.as_ref().unwrap().state;
+        let everyone_agreed = self.players.iter().all(|player| {
+            undo_state.requested_by == Some(player.id())
+                || undo_state.approved_by.contains(&player.id())
+        });
+        if !everyone_agreed {
+            return;
+        }
+
+        let snapshot = self.undo.take().unwrap().snapshot;
+        let turn = self.turn;
+        self.emit(GameEvent::ActionUndone);
+
+        self.set_phase(snapshot.phase);
+        self.turn = snapshot.turn;
+        self.map = Some(snapshot.map);
+        self.card_dealer = Some(snapshot.card_dealer);
+        self.players = snapshot.players;
+        self.end_game = snapshot.end_game;
+
+        if self.turn != turn {
+            self.start_turn();
+        }
+        // The player's turn resumes, rather than restarting.
+        self.clock = snapshot.clock.map(|mut clock| {
+            clock.postpone(snapshot.taken_at.elapsed());
+            clock
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        card::{TrainColor, TOTAL_NUM_TRAIN_CARDS},
+        city::City,
+        map::ClaimedRoute,
+        player::PlayerAction,
+    };
+    use smallvec::smallvec;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    // Tests for `GamePhase`.
+
+    #[test]
+    fn game_phase_to_json() -> serde_json::Result<()> {
+        assert_eq!(serde_json::to_string(&GamePhase::InLobby)?, r#""in_lobby""#);
+        assert_eq!(
+            serde_json::to_string(&GamePhase::Starting)?,
+            r#""starting""#
         );
         assert_eq!(serde_json::to_string(&GamePhase::Playing)?, r#""playing""#);
         assert_eq!(
@@ -611,199 +1798,1318 @@ mod tests {
         );
         assert_eq!(serde_json::to_string(&GamePhase::Done)?, r#""done""#);
 
-        Ok(())
-    }
+        Ok(())
+    }
+
+    // Tests for `Manager`.
+
+    #[test]
+    fn manager_new() {
+        let m = Manager::new();
+
+        assert_eq!(m.phase, GamePhase::InLobby);
+        assert!(m.turn.is_none());
+        assert!(m.map.is_none());
+        assert!(m.card_dealer.is_none());
+        assert!(m.players.is_empty());
+        assert!(m.players_position.is_empty());
+        assert_eq!(m.num_players_selected_initial_destination_cards, 0);
+    }
+
+    #[test]
+    fn manager_add_player_outside_of_in_lobby_phase() {
+        let mut m = Manager::new();
+
+        m.phase = GamePhase::Starting;
+        assert!(m.add_player().is_none());
+
+        m.phase = GamePhase::Playing;
+        assert!(m.add_player().is_none());
+
+        assert!(m.players.is_empty());
+
+        m.phase = GamePhase::InLobby;
+        let player_id = m.add_player();
+        assert!(player_id.is_some());
+        let player_id = player_id.unwrap();
+
+        let game_state = m.get_state(player_id);
+        assert_eq!(game_state.phase, GamePhase::InLobby);
+        assert!(game_state.turn.is_none());
+        assert!(game_state.card_dealer_state.is_none());
+        assert_eq!(game_state.players_state.len(), 1);
+    }
+
+    #[test]
+    fn manager_add_player_unique() {
+        let mut m = Manager::new();
+
+        assert_eq!(m.add_player(), Some(0));
+        assert_eq!(m.add_player(), Some(1));
+        assert_eq!(m.add_player(), Some(2));
+        assert_eq!(m.add_player(), Some(3));
+        assert_eq!(m.add_player(), Some(4));
+        assert!(m.add_player().is_none());
+        assert_eq!(m.num_players(), 5);
+
+        for (i, player) in m.players.iter().enumerate() {
+            for (j, other_player) in m.players.iter().enumerate() {
+                if i == j {
+                    continue;
+                }
+
+                assert_ne!(player.color(), other_player.color());
+                assert_ne!(player.name(), other_player.name());
+                assert_ne!(player.id(), other_player.id());
+            }
+        }
+
+        let game_state = m.get_state(0);
+        assert_eq!(game_state.players_state.len(), MAX_PLAYERS);
+    }
+
+    #[test]
+    fn manager_add_player_name_collision() {
+        let mut m = Manager::new();
+
+        for i in 1..=4 {
+            assert_eq!(m.add_player(), Some(i - 1));
+            m.players[i - 1].change_name(format!("Player {:01$}", 4, i));
+        }
+
+        assert_eq!(m.add_player(), Some(4));
+        assert_eq!(m.num_players(), 5);
+        assert_eq!(m.players[4].name(), "Player 00004");
+    }
+
+    #[test]
+    fn manager_change_player_name() {
+        let mut m = Manager::new();
+
+        let player_id = m.add_player().unwrap();
+        let other_player_id = m.add_player().unwrap();
+
+        let new_name = String::from("Bob");
+        assert!(m.change_player_name(player_id, new_name.clone()).is_ok());
+        assert_eq!(m.players[0].name(), new_name.clone());
+
+        assert!(m.change_player_name(player_id, new_name.clone()).is_err());
+        assert!(m
+            .change_player_name(other_player_id, new_name.clone())
+            .is_err());
+        assert_ne!(m.players[1].name(), new_name);
+    }
+
+    #[test]
+    fn manager_change_player_name_wrong_phase() {
+        let mut m = Manager::new();
+
+        let player_id = m.add_player().unwrap();
+        let other_player_id = m.add_player().unwrap();
+
+        m.phase = GamePhase::Playing;
+
+        let new_name = String::from("Bob");
+        assert!(m.change_player_name(player_id, new_name.clone()).is_err());
+        assert!(m.change_player_name(other_player_id, new_name).is_err());
+    }
+
+    #[test]
+    fn manager_change_player_color() {
+        let mut m = Manager::new();
+
+        let player_id = m.add_player().unwrap();
+        let other_player_id = m.add_player().unwrap();
+
+        let new_color = PlayerColor::Yellow;
+        assert!(m.change_player_color(player_id, new_color).is_ok());
+        assert_eq!(m.players[0].color(), new_color);
+
+        assert!(m.change_player_color(player_id, new_color).is_err());
+        assert!(m.change_player_color(other_player_id, new_color).is_err());
+        assert_ne!(m.players[1].color(), new_color);
+    }
+
+    #[test]
+    fn manager_change_player_color_wrong_phase() {
+        let mut m = Manager::new();
+
+        let player_id = m.add_player().unwrap();
+        let other_player_id = m.add_player().unwrap();
+
+        m.phase = GamePhase::Playing;
+
+        let new_color = PlayerColor::Yellow;
+        assert!(m.change_player_color(player_id, new_color).is_err());
+        assert!(m.change_player_color(other_player_id, new_color).is_err());
+    }
+
+    #[test]
+    fn manager_set_ready() {
+        let mut m = Manager::new();
+
+        let player_id = m.add_player().unwrap();
+        let other_player_id = m.add_player().unwrap();
+
+        assert!(m.set_ready(player_id, true).is_ok());
+        assert_eq!(m.phase, GamePhase::InLobby);
+        assert!(m.players[0].ready());
+
+        assert!(m.set_ready(player_id, false).is_ok());
+        assert_eq!(m.phase, GamePhase::InLobby);
+        assert_eq!(m.players[0].ready(), false);
+
+        assert!(m.set_ready(other_player_id, true).is_ok());
+        assert_eq!(m.phase, GamePhase::InLobby);
+        assert!(m.players[1].ready());
+
+        assert!(m.set_ready(player_id, true).is_ok());
+        assert_eq!(m.phase, GamePhase::Starting);
+        assert!(m.players[0].ready());
+
+        assert!(m.turn.is_none());
+        assert!(m.map.is_some());
+        assert!(m.card_dealer.is_some());
+
+        let game_state = m.get_state(player_id);
+        assert_eq!(game_state.phase, GamePhase::Starting);
+        assert!(game_state.turn.is_none());
+        assert!(game_state.card_dealer_state.is_some());
+        assert_eq!(game_state.players_state.len(), 2);
+        assert_ne!(game_state.players_state[0], game_state.players_state[1]);
+    }
+
+    #[test]
+    fn manager_set_ready_wrong_phase() {
+        let mut m = Manager::new();
+
+        let player_id = m.add_player().unwrap();
+        let other_player_id = m.add_player().unwrap();
+
+        m.phase = GamePhase::Playing;
+
+        let is_ready = false;
+        assert!(m.set_ready(player_id, is_ready).is_err());
+        assert!(m.set_ready(other_player_id, is_ready).is_err());
+    }
+
+    #[test]
+    fn manager_remove_player() {
+        let mut m = Manager::new();
+
+        let player_id = m.add_player().unwrap();
+        let other_player_id = m.add_player().unwrap();
+        let color = m.players[1].color();
+
+        assert!(m.remove_player(other_player_id).is_ok());
+        assert_eq!(m.num_players(), 1);
+        assert!(!m.has_player(other_player_id));
+        assert!(m.remove_player(other_player_id).is_err());
+        assert!(m.set_ready(other_player_id, true).is_err());
+
+        // The seat and color are freed, but the ID is not reused.
+        assert_eq!(m.add_player(), Some(2));
+        assert_eq!(m.players[1].color(), color);
+
+        // Removing the only player who is not ready starts the game.
+        let third_player_id = m.add_player().unwrap();
+        assert!(m.set_ready(player_id, true).is_ok());
+        assert!(m.set_ready(2, true).is_ok());
+        assert_eq!(m.phase, GamePhase::InLobby);
+        assert!(m.remove_player(third_player_id).is_ok());
+        assert_eq!(m.phase, GamePhase::Starting);
+
+        // Once the game has started, the remaining player is left alone: the game is over.
+        assert!(m.remove_player(player_id).is_ok());
+        assert_eq!(m.phase, GamePhase::Done);
+    }
+
+    #[test]
+    fn manager_set_seating_order() {
+        let mut m = Manager::new();
+
+        for _ in 0..3 {
+            m.add_player();
+        }
+
+        assert!(m
+            .set_seating_order(SeatingOrder::Manual(vec![2, 0]))
+            .is_err());
+        assert!(m
+            .set_seating_order(SeatingOrder::Manual(vec![2, 0, 0]))
+            .is_err());
+        assert!(m
+            .set_seating_order(SeatingOrder::Manual(vec![2, 0, 3]))
+            .is_err());
+        assert!(m.random_seating_order);
+
+        assert!(m
+            .set_seating_order(SeatingOrder::Manual(vec![2, 0, 1]))
+            .is_ok());
+        assert!(!m.random_seating_order);
+
+        for player_id in 0..3 {
+            assert!(m.set_ready(player_id, true).is_ok());
+        }
+        assert_eq!(m.phase, GamePhase::Starting);
+
+        let player_ids: Vec<usize> = m.players.iter().map(|player| player.id()).collect();
+        assert_eq!(player_ids, vec![2, 0, 1]);
+        assert_eq!(m.get_player_index(2), Some(0));
+
+        assert!(m.set_seating_order(SeatingOrder::Random).is_err());
+    }
+
+    #[test]
+    fn manager_force_start() {
+        let mut m = Manager::new();
+
+        m.add_player();
+        assert!(m.force_start().is_err());
+        assert_eq!(m.phase, GamePhase::InLobby);
+
+        m.add_player();
+        assert!(m.set_ready(1, true).is_ok());
+        assert!(m.force_start().is_ok());
+        assert_eq!(m.phase, GamePhase::Starting);
+        assert!(m.map.is_some());
+        assert!(m.card_dealer.is_some());
+
+        assert!(m.force_start().is_err());
+    }
+
+    #[test]
+    fn manager_force_end() {
+        let mut m = Manager::new();
+
+        m.add_player();
+        m.add_player();
+        assert!(m.force_end().is_err());
+        assert_eq!(m.phase, GamePhase::InLobby);
+
+        assert!(m.force_start().is_ok());
+        assert!(m.force_end().is_ok());
+        assert_eq!(m.phase, GamePhase::Done);
+        assert_eq!(
+            m.events()[m.events().len() - 2..],
+            [
+                GameEvent::GameForceEnded,
+                GameEvent::PhaseChanged {
+                    phase: GamePhase::Done
+                },
+            ]
+        );
+
+        assert!(m.force_end().is_err());
+        assert!(Manager::replay(m.events()).is_ok());
+    }
+
+    #[test]
+    fn manager_get_full_state() {
+        let m = new_manager_playing(3);
+
+        let state = m.get_full_state();
+        assert!(state
+            .players_state
+            .iter()
+            .all(|player_state| player_state.private_player_state.is_some()));
+        assert!(m.get_public_state().players_state[0]
+            .private_player_state
+            .is_none());
+    }
+
+    /// Starts a game with the given number of players, seated by increasing ID, up to the turn-based game.
+    fn new_manager_playing(num_players: usize) -> Manager {
+        let mut m = Manager::new();
+
+        for _ in 0..num_players {
+            m.add_player();
+        }
+        assert!(m
+            .set_seating_order(SeatingOrder::Manual((0..num_players).collect()))
+            .is_ok());
+        assert!(m.force_start().is_ok());
+
+        for player_id in 0..num_players {
+            assert!(m
+                .select_destination_cards(player_id, smallvec![true, true, true])
+                .is_ok());
+        }
+        assert_eq!(m.phase, GamePhase::Playing);
+        assert_eq!(m.turn, Some(0));
+
+        m
+    }
+
+    fn num_train_cards_in_game(m: &Manager) -> usize {
+        let card_dealer = m.card_dealer.as_ref().unwrap();
+        let card_dealer_state = card_dealer.get_state();
+
+        card_dealer_state
+            .open_train_card_deck
+            .iter()
+            .filter(|train_card| train_card.is_some())
+            .count()
+            + card_dealer.get_close_train_card_deck().len()
+            + card_dealer.get_discarded_train_card_deck().len()
+            + m.players
+                .iter()
+                .map(|player| player.get_public_state().num_train_cards as usize)
+                .sum::<usize>()
+    }
+
+    #[test]
+    fn manager_remove_player_starting_phase() {
+        let mut m = Manager::new();
+
+        for _ in 0..4 {
+            m.add_player();
+        }
+        assert!(m.force_start().is_ok());
+        let num_destination_cards = m
+            .card_dealer
+            .as_ref()
+            .unwrap()
+            .get_destination_card_deck()
+            .len();
+
+        assert!(m
+            .select_destination_cards(0, smallvec![true, true, true])
+            .is_ok());
+        assert!(m.remove_player(0).is_ok());
+        assert_eq!(m.num_players_selected_initial_destination_cards, 0);
+        assert_eq!(m.num_players(), 3);
+        assert!(m.get_player_index(0).is_none());
+
+        // The player's cards are back in the decks.
+        assert_eq!(num_train_cards_in_game(&m), TOTAL_NUM_TRAIN_CARDS);
+        assert_eq!(
+            m.card_dealer
+                .as_ref()
+                .unwrap()
+                .get_destination_card_deck()
+                .len(),
+            num_destination_cards + NUM_DRAWN_DESTINATION_CARDS
+        );
+
+        for player_id in 1..3 {
+            assert!(m
+                .select_destination_cards(player_id, smallvec![true, true, true])
+                .is_ok());
+        }
+        assert_eq!(m.phase, GamePhase::Starting);
+
+        // Once the last player who had not selected their destination cards leaves, the turn-based game starts.
+        assert!(m.remove_player(3).is_ok());
+        assert_eq!(m.phase, GamePhase::Playing);
+        assert_eq!(m.turn, Some(0));
+    }
+
+    #[test]
+    fn manager_remove_player_playing_phase() {
+        let mut m = new_manager_playing(4);
+        let turn_of = |m: &Manager| m.players[m.turn.unwrap() % m.num_players()].id();
+
+        // Player 0 draws their first train card, and player 3 leaves in the meantime.
+        assert!(m.draw_close_train_card(0).is_ok());
+        assert!(m.remove_player(3).is_ok());
+        assert_eq!(turn_of(&m), 0);
+        assert_eq!(num_train_cards_in_game(&m), TOTAL_NUM_TRAIN_CARDS);
+
+        // Player 0 keeps playing their turn, and only draws one more card.
+        assert!(m.draw_close_train_card(0).is_ok());
+        assert_eq!(turn_of(&m), 1);
+        assert!(m.draw_close_train_card(0).is_err());
+
+        // The player whose turn it is leaves: the next player starts their turn.
+        assert!(m.remove_player(1).is_ok());
+        assert_eq!(turn_of(&m), 2);
+        assert!(m.draw_close_train_card(2).is_ok());
+        assert!(m.draw_close_train_card(2).is_ok());
+        assert_eq!(turn_of(&m), 0);
+        assert_eq!(m.get_player_index(2), Some(1));
+
+        // With a single player left, the game is over.
+        assert!(m.remove_player(0).is_ok());
+        assert_eq!(m.phase, GamePhase::Done);
+        assert!(m.remove_player(2).is_err());
+    }
+
+    #[test]
+    fn manager_remove_player_last_turn_phase() {
+        let mut m = new_manager_playing(3);
+
+        // Players 0 and 1 are done playing, and player 2 leaves before playing their last turn.
+        m.turn = Some(2);
+        for player_index in 0..2 {
+            m.players[player_index].set_done_playing();
+        }
+        m.phase = GamePhase::LastTurn;
+        m.end_game = Some(EndGame {
+            triggered_by: 2,
+            final_turn: 2,
+        });
+
+        assert!(m.remove_player(2).is_ok());
+        assert_eq!(m.phase, GamePhase::Done);
+        assert!(m.players[0].get_public_state().has_longest_route.is_some());
+    }
+
+    fn claim_route_with_new_cards(m: &mut Manager, player_id: usize, route: CityToCity) {
+        let player_index = m.get_player_index(player_id).unwrap();
+        m.players[player_index]
+            .get_mut_private_state()
+            .train_cards
+            .entry(TrainColor::Wild)
+            .and_modify(|count| *count += 2);
+        m.players[player_index]
+            .get_mut_public_state()
+            .num_train_cards += 2;
+
+        assert!(m
+            .claim_route(player_id, route, 0, vec![TrainColor::Wild; 2])
+            .is_ok());
+    }
+
+    #[test]
+    fn manager_end_game() {
+        let mut m = new_manager_playing(3);
+        assert!(m.get_public_state().end_game.is_none());
+        assert!(m.get_public_state().num_turns_left.is_none());
+
+        // The first player runs low on cars: every player, including them, has one turn left.
+        m.players[0].get_mut_public_state().cars = 4;
+        claim_route_with_new_cards(&mut m, 0, (City::Atlanta, City::Charleston));
+        assert_eq!(m.phase, GamePhase::LastTurn);
+
+        {
+            let game_state = m.get_public_state();
+            assert_eq!(
+                game_state.end_game,
+                Some(EndGame {
+                    triggered_by: 0,
+                    final_turn: 3,
+                })
+            );
+            assert_eq!(game_state.num_turns_left, Some(3));
+        }
+
+        claim_route_with_new_cards(&mut m, 1, (City::Boston, City::Montreal));
+        claim_route_with_new_cards(&mut m, 2, (City::Chicago, City::SaintLouis));
+        assert_eq!(m.get_public_state().num_turns_left, Some(1));
+        assert_eq!(m.phase, GamePhase::LastTurn);
+
+        // The first player falling below the threshold again does not reschedule the end of the game.
+        claim_route_with_new_cards(&mut m, 0, (City::Dallas, City::LittleRock));
+        assert_eq!(m.phase, GamePhase::Done);
+        assert_eq!(m.get_public_state().num_turns_left, Some(0));
+        assert!(m.players.iter().all(|player| player.done_playing()));
+    }
+
+    #[test]
+    fn manager_end_game_remove_player() {
+        // The player leaving still had their final turn to play.
+        let mut m = new_manager_playing(4);
+        m.players[1].get_mut_public_state().cars = 4;
+        assert!(m.draw_close_train_card(0).is_ok());
+        assert!(m.draw_close_train_card(0).is_ok());
+        claim_route_with_new_cards(&mut m, 1, (City::Atlanta, City::Charleston));
+        assert_eq!(m.end_game.unwrap().final_turn, 5);
+
+        assert!(m.remove_player(3).is_ok());
+        assert_eq!(m.turn, Some(2));
+        assert_eq!(m.end_game.unwrap().final_turn, 4);
+        assert_eq!(m.get_public_state().num_turns_left, Some(3));
+
+        // The player leaving had played their final turn already.
+        claim_route_with_new_cards(&mut m, 2, (City::Boston, City::Montreal));
+        assert!(m.remove_player(2).is_ok());
+        assert_eq!(m.get_public_state().num_turns_left, Some(2));
+
+        // The player leaving is the one playing the final turn.
+        claim_route_with_new_cards(&mut m, 0, (City::Chicago, City::SaintLouis));
+        assert_eq!(m.phase, GamePhase::LastTurn);
+        assert!(m.remove_player(1).is_ok());
+        assert_eq!(m.phase, GamePhase::Done);
+    }
+
+    #[test]
+    fn manager_remove_player_abandoned_routes() {
+        let route = (City::Raleigh, City::Washington);
+
+        for (abandoned_routes, is_freed) in [
+            (AbandonedRoutes::Freed, true),
+            (AbandonedRoutes::Kept, false),
+        ] {
+            let mut m = Manager::new();
+
+            for _ in 0..3 {
+                m.add_player();
+            }
+            assert!(m.set_abandoned_routes(abandoned_routes).is_ok());
+            assert_eq!(m.abandoned_routes(), abandoned_routes);
+            assert!(m
+                .set_seating_order(SeatingOrder::Manual(vec![0, 1, 2]))
+                .is_ok());
+            assert!(m.force_start().is_ok());
+            assert!(m.set_abandoned_routes(abandoned_routes).is_err());
+            m.phase = GamePhase::Playing;
+            m.turn = Some(0);
+
+            claim_route_with_new_cards(&mut m, 0, route);
+            assert!(m.remove_player(0).is_ok());
+
+            let player_index = m.get_player_index(1).unwrap();
+            m.players[player_index]
+                .get_mut_private_state()
+                .train_cards
+                .entry(TrainColor::Wild)
+                .and_modify(|count| *count += 2);
+            m.players[player_index]
+                .get_mut_public_state()
+                .num_train_cards += 2;
+            assert_eq!(
+                m.claim_route(
+                    1,
+                    (City::Washington, City::Raleigh),
+                    0,
+                    vec![TrainColor::Wild; 2]
+                )
+                .is_ok(),
+                is_freed
+            );
+        }
+    }
+
+    fn new_manager_with_time_controls(timeout_policy: TimeoutPolicy) -> Manager {
+        let mut m = Manager::new();
+
+        m.add_player();
+        m.add_player();
+        assert!(m
+            .set_time_controls(TimeControls {
+                turn_time_limit: Some(Duration::from_secs(60)),
+                time_bank: None,
+                timeout_policy,
+            })
+            .is_ok());
+        assert!(m
+            .set_seating_order(SeatingOrder::Manual(vec![0, 1]))
+            .is_ok());
+        assert!(m.force_start().is_ok());
+        assert!(m.set_time_controls(TimeControls::default()).is_err());
+        assert_eq!(m.time_controls().timeout_policy, timeout_policy);
+
+        // The clock only starts with the turn-based game.
+        assert!(!m.enforce_time_controls_at(Instant::now() + Duration::from_secs(3600)));
+        assert_eq!(m.get_public_state().clock.unwrap().turn_time_left_ms, None);
+
+        for player_id in 0..2 {
+            assert!(m
+                .select_destination_cards(player_id, smallvec![true, true, true])
+                .is_ok());
+        }
+
+        m
+    }
+
+    #[test]
+    fn manager_enforce_time_controls() {
+        for (timeout_policy, num_drawn_train_cards) in
+            [(TimeoutPolicy::DrawTrainCards, 2), (TimeoutPolicy::Pass, 0)]
+        {
+            let mut m = new_manager_with_time_controls(timeout_policy);
+            let num_train_cards = m.players[0].get_public_state().num_train_cards;
+
+            let turn_time_left_ms = m.get_state(0).clock.unwrap().turn_time_left_ms.unwrap();
+            assert!(turn_time_left_ms > 59_000 && turn_time_left_ms <= 60_000);
+            assert!(!m.enforce_time_controls());
+
+            let timed_out_at = Instant::now() + Duration::from_secs(60);
+            assert!(!m.has_timed_out());
+            assert!(m.has_timed_out_at(timed_out_at));
+            assert!(m.enforce_time_controls_at(timed_out_at));
+            assert_eq!(m.turn, Some(1));
+            assert_eq!(
+                m.players[0].get_public_state().num_train_cards,
+                num_train_cards + num_drawn_train_cards
+            );
+            assert_eq!(
+                m.players[0].get_public_state().turn_actions.actions.last(),
+                Some(&PlayerAction::RanOutOfTime)
+            );
+
+            // The timeout is recorded, and the next player just started their turn.
+            assert_eq!(
+                m.events().iter().rev().find(|event| !event.is_outcome()),
+                Some(&GameEvent::TurnTimedOut { player_id: 0 })
+            );
+            let turn_time_left_ms = m.get_state(1).clock.unwrap().turn_time_left_ms.unwrap();
+            assert!(turn_time_left_ms > 59_000 && turn_time_left_ms <= 60_000);
+            assert!(!m.enforce_time_controls());
+        }
+    }
+
+    #[test]
+    fn manager_enforce_time_controls_pending_destination_cards() {
+        let mut m = new_manager_with_time_controls(TimeoutPolicy::DrawTrainCards);
+        let num_destination_cards = m
+            .card_dealer
+            .as_ref()
+            .unwrap()
+            .get_destination_card_deck()
+            .len();
+        let num_train_cards = m.players[0].get_public_state().num_train_cards;
+
+        assert!(m.draw_destination_cards(0).is_ok());
+        assert!(m.enforce_time_controls_at(Instant::now() + Duration::from_secs(60)));
+
+        assert_eq!(m.turn, Some(1));
+        assert!(!m.players[0].has_pending_destination_cards());
+        assert_eq!(
+            m.players[0].get_public_state().num_train_cards,
+            num_train_cards
+        );
+        assert_eq!(
+            m.card_dealer
+                .as_ref()
+                .unwrap()
+                .get_destination_card_deck()
+                .len(),
+            num_destination_cards
+        );
+    }
+
+    #[test]
+    fn manager_without_time_controls() {
+        let mut m = new_manager_playing(2);
+
+        assert!(m.clock.is_none());
+        assert!(m.get_state(0).clock.is_none());
+        assert!(!m.enforce_time_controls_at(Instant::now() + Duration::from_secs(3600)));
+        assert_eq!(m.turn, Some(0));
+    }
+
+    /// Leaves nothing to draw, and no train cards in the players' hands.
+    fn empty_decks_and_hands(m: &mut Manager) {
+        let card_dealer = m.card_dealer.as_mut().unwrap();
+        card_dealer.get_mut_close_train_card_deck().clear();
+        card_dealer.get_mut_open_train_card_deck().fill(None);
+        card_dealer.get_mut_destination_card_deck().clear();
+
+        for player in m.players.iter_mut() {
+            player
+                .get_mut_private_state()
+                .train_cards
+                .values_mut()
+                .for_each(|num| *num = 0);
+            player.get_mut_public_state().num_train_cards = 0;
+        }
+    }
+
+    #[test]
+    fn manager_pass_without_legal_move() {
+        let mut m = new_manager_playing(2);
+        empty_decks_and_hands(&mut m);
+        m.card_dealer
+            .as_mut()
+            .unwrap()
+            .get_mut_close_train_card_deck()
+            .push(TrainColor::Wild);
+
+        // Drawing the last train card ends the turn, and the next player has nothing left to do.
+        assert!(m.draw_close_train_card(0).is_ok());
+        assert!(!m.players[0].passed());
+        assert!(m.players[1].passed());
+        assert_eq!(
+            m.players[1]
+                .get_public_state()
+                .turn_actions
+                .actions
+                .as_slice(),
+            &[PlayerAction::Passed]
+        );
+
+        // The first player can still claim a route with their only card.
+        assert_eq!(m.turn, Some(2));
+        assert_eq!(m.phase, GamePhase::Playing);
+        assert!(m
+            .claim_route(
+                0,
+                (City::Portland, City::Seattle),
+                0,
+                vec![TrainColor::Wild]
+            )
+            .is_ok());
+
+        // The claimed card went back to the close deck: the other player can draw it.
+        assert_eq!(m.turn, Some(3));
+        assert_eq!(m.phase, GamePhase::Playing);
+    }
+
+    #[test]
+    fn manager_pass_full_round_ends_game() {
+        let mut m = new_manager_playing(3);
+        empty_decks_and_hands(&mut m);
+
+        m.pass_stalled_turns();
+        assert_eq!(m.phase, GamePhase::Done);
+        assert_eq!(m.turn, Some(3));
+        assert!(m.players.iter().all(|player| player.passed()));
+        assert!(m
+            .players
+            .iter()
+            .all(|player| player.get_public_state().has_longest_route.is_some()));
+    }
+
+    #[test]
+    fn manager_pass_last_turn() {
+        let mut m = new_manager_playing(2);
+        empty_decks_and_hands(&mut m);
+        m.schedule_end_game(1);
+
+        // Passing counts as the player's last turn.
+        m.pass_stalled_turns();
+        assert_eq!(m.phase, GamePhase::Done);
+        assert!(m.players.iter().all(|player| player.done_playing()));
+    }
+
+    #[test]
+    fn manager_undo_claim_route() -> serde_json::Result<()> {
+        let mut m = new_manager_playing(3);
+        let route = (City::Atlanta, City::Charleston);
+        assert!(m.request_undo(0).is_err());
+
+        m.players[0]
+            .get_mut_private_state()
+            .train_cards
+            .entry(TrainColor::Wild)
+            .and_modify(|count| *count += 2);
+        m.players[0].get_mut_public_state().num_train_cards += 2;
+        m.players[0].get_mut_public_state().cars = 4;
+        let state_before = serde_json::to_string(&m.get_state(0))?;
+
+        assert!(m
+            .claim_route(0, route, 0, vec![TrainColor::Wild; 2])
+            .is_ok());
+        assert_eq!(m.phase, GamePhase::LastTurn);
+        assert_eq!(
+            m.get_public_state().undo,
+            Some(&UndoState {
+                player_id: 0,
+                action: PlayerAction::ClaimedRoute,
+                requested_by: None,
+                approved_by: Vec::new(),
+            })
+        );
+
+        // Every other player must agree.
+        assert!(m.answer_undo_request(1, true).is_err());
+        assert!(m.request_undo(0).is_ok());
+        assert!(m.request_undo(1).is_err());
+        assert!(m.answer_undo_request(0, true).is_err());
+        assert!(m.answer_undo_request(1, true).is_ok());
+        assert!(m.answer_undo_request(1, true).is_err());
+        assert_eq!(m.turn, Some(1));
+
+        assert!(m.answer_undo_request(2, true).is_ok());
+        assert_eq!(serde_json::to_string(&m.get_state(0))?, state_before);
+        assert_eq!(m.phase, GamePhase::Playing);
+        assert_eq!(m.turn, Some(0));
+        assert!(m.end_game.is_none());
+
+        // The route is free again.
+        assert!(m
+            .claim_route(0, route, 0, vec![TrainColor::Wild; 2])
+            .is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn manager_undo_draw_open_train_card() -> serde_json::Result<()> {
+        let mut m = new_manager_playing(2);
+        substitute_open_train_card(&mut m, 0, TrainColor::Red);
+        // Nothing replaces the card drawn.
+        m.card_dealer
+            .as_mut()
+            .unwrap()
+            .get_mut_close_train_card_deck()
+            .clear();
+        let state_before = serde_json::to_string(&m.get_state(0))?;
+
+        assert!(m.draw_open_train_card(0, 0).is_ok());
+        assert_eq!(m.turn, Some(0));
 
-    // Tests for `Manager`.
+        // A refusal drops the request, but someone can ask again.
+        assert!(m.request_undo(1).is_ok());
+        assert!(m.answer_undo_request(0, false).is_ok());
+        assert!(m.get_public_state().undo.unwrap().requested_by.is_none());
+        assert!(m.answer_undo_request(0, true).is_err());
 
-    #[test]
-    fn manager_new() {
-        let m = Manager::new();
+        assert!(m.request_undo(0).is_ok());
+        assert!(m.answer_undo_request(1, true).is_ok());
+        assert_eq!(serde_json::to_string(&m.get_state(0))?, state_before);
+        assert!(m.get_public_state().undo.is_none());
 
-        assert_eq!(m.phase, GamePhase::InLobby);
-        assert!(m.turn.is_none());
-        assert!(m.map.is_none());
-        assert!(m.card_dealer.is_none());
-        assert!(m.players.is_empty());
-        assert!(m.players_position.is_empty());
-        assert_eq!(m.num_players_selected_initial_destination_cards, 0);
+        Ok(())
     }
 
     #[test]
-    fn manager_add_player_outside_of_in_lobby_phase() {
-        let mut m = Manager::new();
+    fn manager_undo_restores_clock() {
+        let mut m = new_manager_with_time_controls(TimeoutPolicy::Pass);
+        // Player 0 took 30 seconds to claim a route.
+        m.clock
+            .as_mut()
+            .unwrap()
+            .start_turn(0, Instant::now() - Duration::from_secs(30));
+        claim_route_with_new_cards(&mut m, 0, (City::Atlanta, City::Charleston));
+        assert_eq!(m.turn, Some(1));
 
-        m.phase = GamePhase::Starting;
-        assert!(m.add_player().is_none());
+        assert!(m.request_undo(1).is_ok());
+        assert!(m.answer_undo_request(0, true).is_ok());
+        assert_eq!(m.turn, Some(0));
 
-        m.phase = GamePhase::Playing;
-        assert!(m.add_player().is_none());
+        // Player 0 picks up their turn with the time they had left, rather than with a new turn.
+        let turn_time_left_ms = m.get_state(0).clock.unwrap().turn_time_left_ms.unwrap();
+        assert!(turn_time_left_ms > 29_000 && turn_time_left_ms <= 30_000);
+        assert!(m.enforce_time_controls_at(Instant::now() + Duration::from_secs(30)));
+        assert_eq!(m.turn, Some(1));
+        assert_eq!(
+            m.players[0].get_public_state().turn_actions.actions.last(),
+            Some(&PlayerAction::RanOutOfTime)
+        );
+    }
 
-        assert!(m.players.is_empty());
+    #[test]
+    fn manager_undo_unknown_player() {
+        let mut m = new_manager_playing(2);
+        claim_route_with_new_cards(&mut m, 0, (City::Atlanta, City::Charleston));
+        assert_eq!(m.turn, Some(1));
 
-        m.phase = GamePhase::InLobby;
-        let player_id = m.add_player();
-        assert!(player_id.is_some());
-        let player_id = player_id.unwrap();
+        // Players who are not in the game can neither ask to undo, nor agree to.
+        assert!(m.request_undo(9).is_err());
+        assert!(m.request_undo(1).is_ok());
+        assert!(m.answer_undo_request(9, true).is_err());
+        assert!(m.answer_undo_request(9, false).is_err());
+        assert_eq!(m.turn, Some(1));
+        assert_eq!(m.get_public_state().undo.unwrap().requested_by, Some(1));
 
-        let game_state = m.get_state(player_id);
-        assert_eq!(game_state.phase, GamePhase::InLobby);
-        assert!(game_state.turn.is_none());
-        assert!(game_state.card_dealer_state.is_none());
-        assert_eq!(game_state.players_state.len(), 1);
+        assert!(m.answer_undo_request(0, true).is_ok());
+        assert_eq!(m.turn, Some(0));
     }
 
     #[test]
-    fn manager_add_player_unique() {
-        let mut m = Manager::new();
+    fn manager_undo_not_allowed() {
+        let mut m = new_manager_playing(2);
+
+        // Drawing from the close deck reveals a card.
+        assert!(m.draw_close_train_card(0).is_ok());
+        assert!(m.draw_close_train_card(0).is_ok());
+        assert!(m.get_public_state().undo.is_none());
+        assert!(m.request_undo(0).is_err());
+
+        // Drawing from the open deck reveals the card replacing it, from the top of the close deck.
+        substitute_open_train_card(&mut m, 0, TrainColor::Red);
+        assert!(m.draw_open_train_card(1, 0).is_ok());
+        assert!(m.get_public_state().undo.is_none());
+        assert!(m.request_undo(0).is_err());
+
+        // Any other action makes a pending request moot.
+        assert!(m.draw_close_train_card(1).is_ok());
+        claim_route_with_new_cards(&mut m, 0, (City::Atlanta, City::Charleston));
+        assert!(m.request_undo(1).is_ok());
+        assert!(m.draw_close_train_card(1).is_ok());
+        assert!(m.get_public_state().undo.is_none());
+        assert!(m.answer_undo_request(0, true).is_err());
+        assert!(m.draw_close_train_card(1).is_ok());
+
+        // Re-shuffling the open deck reveals new cards.
+        substitute_open_train_card(&mut m, 0, TrainColor::Wild);
+        substitute_open_train_card(&mut m, 1, TrainColor::Wild);
+        substitute_open_train_card(&mut m, 2, TrainColor::Red);
+        substitute_close_train_card(&mut m, TrainColor::Wild);
+        assert!(m.draw_open_train_card(0, 2).is_ok());
+        assert!(m.get_public_state().undo.is_none());
+    }
 
-        assert_eq!(m.add_player(), Some(0));
-        assert_eq!(m.add_player(), Some(1));
-        assert_eq!(m.add_player(), Some(2));
-        assert_eq!(m.add_player(), Some(3));
-        assert_eq!(m.add_player(), Some(4));
-        assert!(m.add_player().is_none());
-        assert_eq!(m.num_players(), 5);
+    #[test]
+    fn manager_events() {
+        let mut m = Manager::with_seed(42);
+        assert_eq!(m.events(), &[GameEvent::GameCreated { seed: 42 }]);
 
-        for (i, player) in m.players.iter().enumerate() {
-            for (j, other_player) in m.players.iter().enumerate() {
-                if i == j {
-                    continue;
-                }
+        m.add_player();
+        m.add_player();
+        assert!(m.change_player_name(1, String::from("Player 0")).is_err());
+        assert!(m.force_start().is_ok());
+        assert!(m.change_player_name(1, String::from("joe")).is_err());
 
-                assert_ne!(player.color(), other_player.color());
-                assert_ne!(player.name(), other_player.name());
-                assert_ne!(player.id(), other_player.id());
-            }
+        // Rejected commands are not recorded.
+        assert_eq!(
+            m.events()[1..],
+            [
+                GameEvent::PlayerJoined { player_id: 0 },
+                GameEvent::PlayerJoined { player_id: 1 },
+                GameEvent::GameForceStarted,
+                GameEvent::PhaseChanged {
+                    phase: GamePhase::Starting
+                },
+            ]
+        );
+
+        let num_events = m.events().len();
+        for player_id in 0..2 {
+            assert!(m
+                .select_destination_cards(player_id, smallvec![true, true, true])
+                .is_ok());
         }
 
-        let game_state = m.get_state(0);
-        assert_eq!(game_state.players_state.len(), MAX_PLAYERS);
+        // Outcomes follow the command that led to them.
+        let first_player_id = m.players[0].id();
+        assert_eq!(
+            m.events()[num_events + 1..],
+            [
+                GameEvent::DestinationCardsSelected {
+                    player_id: 1,
+                    destination_cards_decisions: smallvec![true, true, true],
+                },
+                GameEvent::PhaseChanged {
+                    phase: GamePhase::Playing
+                },
+                GameEvent::TurnStarted {
+                    turn: 0,
+                    player_id: first_player_id,
+                },
+            ]
+        );
     }
 
     #[test]
-    fn manager_add_player_name_collision() {
-        let mut m = Manager::new();
-
-        for i in 1..=4 {
-            assert_eq!(m.add_player(), Some(i - 1));
-            m.players[i - 1].change_name(format!("Player {:01$}", 4, i));
-        }
+    fn manager_events_reshuffle_and_pass() {
+        let mut m = new_manager_playing(2);
+        substitute_open_train_card(&mut m, 0, TrainColor::Wild);
+        substitute_open_train_card(&mut m, 1, TrainColor::Wild);
+        substitute_open_train_card(&mut m, 2, TrainColor::Red);
+        substitute_close_train_card(&mut m, TrainColor::Wild);
+        assert!(m.draw_open_train_card(0, 2).is_ok());
+        assert_eq!(
+            m.events().last(),
+            Some(&GameEvent::OpenTrainCardDeckReshuffled)
+        );
 
-        assert_eq!(m.add_player(), Some(4));
-        assert_eq!(m.num_players(), 5);
-        assert_eq!(m.players[4].name(), "Player 00004");
+        empty_decks_and_hands(&mut m);
+        assert!(m.draw_open_train_card(0, 0).is_err());
+        m.pass_stalled_turns();
+        assert!(m.events().contains(&GameEvent::TurnPassed { player_id: 0 }));
+        assert_eq!(
+            m.events().last(),
+            Some(&GameEvent::PhaseChanged {
+                phase: GamePhase::Done
+            })
+        );
     }
 
     #[test]
-    fn manager_change_player_name() {
-        let mut m = Manager::new();
-
-        let player_id = m.add_player().unwrap();
-        let other_player_id = m.add_player().unwrap();
+    fn manager_replay() -> serde_json::Result<()> {
+        let mut m = Manager::with_seed(7);
+        for _ in 0..3 {
+            m.add_player();
+        }
+        assert!(m.change_player_color(2, PlayerColor::Orange).is_ok());
+        assert!(m.force_start().is_ok());
+        for player_id in 0..3 {
+            assert!(m
+                .select_destination_cards(player_id, smallvec![true, true, false])
+                .is_ok());
+        }
 
-        let new_name = String::from("Bob");
-        assert!(m.change_player_name(player_id, new_name.clone()).is_ok());
-        assert_eq!(m.players[0].name(), new_name.clone());
+        for _ in 0..12 {
+            let player_id = m.players[m.turn.unwrap() % m.num_players()].id();
+            while m.draw_close_train_card(player_id).is_ok() {}
+        }
 
-        assert!(m.change_player_name(player_id, new_name.clone()).is_err());
+        let player_id = m.players[m.turn.unwrap() % m.num_players()].id();
+        assert!(m.draw_destination_cards(player_id).is_ok());
         assert!(m
-            .change_player_name(other_player_id, new_name.clone())
-            .is_err());
-        assert_ne!(m.players[1].name(), new_name);
-    }
+            .select_destination_cards(player_id, smallvec![false, true, false])
+            .is_ok());
+        assert!(m.remove_player(player_id).is_ok());
 
-    #[test]
-    fn manager_change_player_name_wrong_phase() {
-        let mut m = Manager::new();
+        // Until the discarded deck is re-shuffled.
+        for _ in 0..40 {
+            let player_id = m.players[m.turn.unwrap() % m.num_players()].id();
+            while m.draw_close_train_card(player_id).is_ok() {}
+        }
 
-        let player_id = m.add_player().unwrap();
-        let other_player_id = m.add_player().unwrap();
+        let replayed_m = Manager::replay(m.events()).unwrap();
+        assert_eq!(replayed_m.events(), m.events());
+        for player in &m.players {
+            assert_eq!(
+                serde_json::to_value(replayed_m.get_state(player.id()))?,
+                serde_json::to_value(m.get_state(player.id()))?
+            );
+        }
 
-        m.phase = GamePhase::Playing;
+        // The events must tell the whole story, from the beginning.
+        assert!(Manager::replay(&m.events()[1..]).is_err());
+        let mut events = m.events().to_vec();
+        events.insert(1, GameEvent::GameCreated { seed: 7 });
+        assert!(Manager::replay(&events).is_err());
 
-        let new_name = String::from("Bob");
-        assert!(m.change_player_name(player_id, new_name.clone()).is_err());
-        assert!(m.change_player_name(other_player_id, new_name).is_err());
+        Ok(())
     }
 
     #[test]
-    fn manager_change_player_color() {
-        let mut m = Manager::new();
-
-        let player_id = m.add_player().unwrap();
-        let other_player_id = m.add_player().unwrap();
-
-        let new_color = PlayerColor::Yellow;
-        assert!(m.change_player_color(player_id, new_color).is_ok());
-        assert_eq!(m.players[0].color(), new_color);
+    fn manager_replay_restores_clock() {
+        let mut m = new_manager_with_time_controls(TimeoutPolicy::Pass);
+        // Player 0 took 40 seconds of their turn so far.
+        m.clock
+            .as_mut()
+            .unwrap()
+            .start_turn(0, Instant::now() - Duration::from_secs(40));
+        let clock_snapshot = m.clock_snapshot().unwrap();
+
+        // Replayed games restart the clock from the current turn...
+        let mut replayed_m = Manager::replay(m.events()).unwrap();
+        let turn_time_left_ms = replayed_m
+            .get_state(0)
+            .clock
+            .unwrap()
+            .turn_time_left_ms
+            .unwrap();
+        assert!(turn_time_left_ms > 59_000);
+
+        // ... unless it is restored as well.
+        replayed_m.restore_clock(&clock_snapshot);
+        let turn_time_left_ms = replayed_m
+            .get_state(0)
+            .clock
+            .unwrap()
+            .turn_time_left_ms
+            .unwrap();
+        assert!(turn_time_left_ms > 19_000 && turn_time_left_ms <= 20_000);
 
-        assert!(m.change_player_color(player_id, new_color).is_err());
-        assert!(m.change_player_color(other_player_id, new_color).is_err());
-        assert_ne!(m.players[1].color(), new_color);
+        // Games without time controls have no clock.
+        let m = new_manager_playing(2);
+        assert!(m.clock_snapshot().is_none());
     }
 
     #[test]
-    fn manager_change_player_color_wrong_phase() {
-        let mut m = Manager::new();
+    fn manager_replay_unknown_player() {
+        let m = new_manager_playing(2);
+        assert!(Manager::replay(m.events()).is_ok());
+
+        // Events may come from anywhere (e.g. a file), and name players who are not in the game.
+        let player_id = 9;
+        for event in [
+            GameEvent::DestinationCardsSelected {
+                player_id,
+                destination_cards_decisions: smallvec![true, true, true],
+            },
+            GameEvent::DestinationCardsDrawn { player_id },
+            GameEvent::OpenTrainCardDrawn {
+                player_id,
+                card_index: 0,
+            },
+            GameEvent::CloseTrainCardDrawn { player_id },
+            GameEvent::RouteClaimed {
+                player_id,
+                route: (City::Atlanta, City::Charleston),
+                parallel_route_index: 0,
+                cards: vec![TrainColor::Wild; 2],
+            },
+            GameEvent::TurnTimedOut { player_id },
+        ] {
+            let mut events = m.events().to_vec();
+            events.push(event);
+            assert_eq!(
+                Manager::replay(&events).err(),
+                Some(String::from("Player 9 is not in the game"))
+            );
+        }
+    }
 
-        let player_id = m.add_player().unwrap();
-        let other_player_id = m.add_player().unwrap();
+    #[derive(Clone, Default)]
+    struct RecordingObserver {
+        notifications: Arc<Mutex<Vec<String>>>,
+    }
 
-        m.phase = GamePhase::Playing;
+    impl RecordingObserver {
+        fn take(&self) -> Vec<String> {
+            std::mem::take(&mut *self.notifications.lock().unwrap())
+        }
 
-        let new_color = PlayerColor::Yellow;
-        assert!(m.change_player_color(player_id, new_color).is_err());
-        assert!(m.change_player_color(other_player_id, new_color).is_err());
+        fn push(&self, notification: String) {
+            self.notifications.lock().unwrap().push(notification);
+        }
     }
 
-    #[test]
-    fn manager_set_ready() {
-        let mut m = Manager::new();
+    impl GameObserver for RecordingObserver {
+        fn on_action_applied(&mut self, event: &GameEvent, _state: &GameState) {
+            self.push(format!("action {:?}", event));
+        }
 
-        let player_id = m.add_player().unwrap();
-        let other_player_id = m.add_player().unwrap();
+        fn on_turn_started(&mut self, turn: usize, player_id: usize) {
+            self.push(format!("turn {} of {}", turn, player_id));
+        }
 
-        assert!(m.set_ready(player_id, true).is_ok());
-        assert_eq!(m.phase, GamePhase::InLobby);
-        assert!(m.players[0].ready());
+        fn on_phase_changed(&mut self, phase: GamePhase) {
+            self.push(format!("phase {:?}", phase));
+        }
 
-        assert!(m.set_ready(player_id, false).is_ok());
-        assert_eq!(m.phase, GamePhase::InLobby);
-        assert_eq!(m.players[0].ready(), false);
+        fn on_open_train_card_deck_reshuffled(
+            &mut self,
+            open_train_card_deck: &[Option<TrainColor>],
+        ) {
+            self.push(format!("reshuffle {}", open_train_card_deck.len()));
+        }
 
-        assert!(m.set_ready(other_player_id, true).is_ok());
-        assert_eq!(m.phase, GamePhase::InLobby);
-        assert!(m.players[1].ready());
+        fn on_destination_card_fulfilled(
+            &mut self,
+            player_id: usize,
+            destination_card: &DestinationCard,
+        ) {
+            self.push(format!(
+                "fulfilled {:?} by {}",
+                destination_card.destination, player_id
+            ));
+        }
 
-        assert!(m.set_ready(player_id, true).is_ok());
-        assert_eq!(m.phase, GamePhase::Starting);
-        assert!(m.players[0].ready());
+        fn on_player_state_changed(&mut self, player_id: usize, state: &GameState) {
+            let has_private_state = state
+                .players_state
+                .iter()
+                .filter(|player_state| player_state.private_player_state.is_some())
+                .map(|player_state| player_state.public_player_state.id)
+                .collect::<Vec<_>>();
+            if state.phase != GamePhase::Done {
+                assert_eq!(has_private_state, vec![player_id]);
+            }
+            self.push(format!("state of {}", player_id));
+        }
 
-        assert!(m.turn.is_none());
-        assert!(m.map.is_some());
-        assert!(m.card_dealer.is_some());
+        fn on_game_over(&mut self, state: &GameState) {
+            assert!(state
+                .players_state
+                .iter()
+                .all(|player_state| player_state.private_player_state.is_some()));
+            self.push(String::from("game over"));
+        }
+    }
 
-        let game_state = m.get_state(player_id);
-        assert_eq!(game_state.phase, GamePhase::Starting);
-        assert!(game_state.turn.is_none());
-        assert!(game_state.card_dealer_state.is_some());
-        assert_eq!(game_state.players_state.len(), 2);
-        assert_ne!(game_state.players_state[0], game_state.players_state[1]);
+    #[test]
+    fn manager_observer() {
+        let mut m = new_manager_playing(2);
+        let observer = RecordingObserver::default();
+        m.add_observer(Box::new(observer.clone()));
+
+        assert!(m.draw_close_train_card(1).is_err());
+        assert!(observer.take().is_empty());
+
+        assert!(m.draw_close_train_card(0).is_ok());
+        assert_eq!(
+            observer.take(),
+            vec![
+                "action CloseTrainCardDrawn { player_id: 0 }",
+                "state of 0",
+                "state of 1"
+            ]
+        );
+
+        assert!(m.draw_close_train_card(0).is_ok());
+        assert_eq!(
+            observer.take(),
+            vec![
+                "action CloseTrainCardDrawn { player_id: 0 }",
+                "turn 1 of 1",
+                "state of 0",
+                "state of 1"
+            ]
+        );
+
+        substitute_open_train_card(&mut m, 0, TrainColor::Wild);
+        substitute_open_train_card(&mut m, 1, TrainColor::Wild);
+        substitute_open_train_card(&mut m, 2, TrainColor::Red);
+        substitute_close_train_card(&mut m, TrainColor::Wild);
+        assert!(m.draw_open_train_card(1, 2).is_ok());
+        assert_eq!(
+            observer.take(),
+            vec![
+                "action OpenTrainCardDrawn { player_id: 1, card_index: 2 }",
+                "reshuffle 5",
+                "state of 0",
+                "state of 1"
+            ]
+        );
     }
 
     #[test]
-    fn manager_set_ready_wrong_phase() {
-        let mut m = Manager::new();
+    fn manager_observer_destination_card_fulfilled_and_game_over() {
+        let mut m = new_manager_playing(2);
+        m.players[0]
+            .get_mut_private_state()
+            .selected_destination_cards
+            .push(DestinationCard {
+                destination: (City::Atlanta, City::Charleston),
+                points: 2,
+                fulfilled: false,
+            });
+        m.players[0]
+            .get_mut_private_state()
+            .train_cards
+            .entry(TrainColor::Wild)
+            .and_modify(|count| *count += 2);
+        m.players[0].get_mut_public_state().num_train_cards += 2;
+        m.players[0].get_mut_public_state().cars = 4;
+        m.players[1].get_mut_public_state().cars = 4;
+
+        let observer = RecordingObserver::default();
+        m.add_observer(Box::new(observer.clone()));
+        assert!(m
+            .claim_route(
+                0,
+                (City::Atlanta, City::Charleston),
+                0,
+                vec![TrainColor::Wild; 2]
+            )
+            .is_ok());
+        assert_eq!(
+            observer.take()[1..],
+            [
+                "turn 1 of 1",
+                "phase LastTurn",
+                "fulfilled (Atlanta, Charleston) by 0",
+                "state of 0",
+                "state of 1"
+            ]
+        );
 
-        let player_id = m.add_player().unwrap();
-        let other_player_id = m.add_player().unwrap();
+        while m.phase != GamePhase::Done {
+            let player_id = m.players[m.turn.unwrap() % m.num_players()].id();
+            assert!(m.draw_destination_cards(player_id).is_ok());
+            assert!(m
+                .select_destination_cards(player_id, smallvec![true, false, false])
+                .is_ok());
+        }
+        let notifications = observer.take();
+        assert_eq!(
+            notifications[notifications.len() - 4..],
+            ["phase Done", "game over", "state of 0", "state of 1"]
+        );
+    }
 
-        m.phase = GamePhase::Playing;
+    #[test]
+    fn seating_order_json() -> serde_json::Result<()> {
+        assert_eq!(
+            serde_json::from_str::<SeatingOrder>(r#""random""#)?,
+            SeatingOrder::Random
+        );
+        assert_eq!(
+            serde_json::from_str::<SeatingOrder>(r#"{"manual": [1, 0]}"#)?,
+            SeatingOrder::Manual(vec![1, 0])
+        );
 
-        let is_ready = false;
-        assert!(m.set_ready(player_id, is_ready).is_err());
-        assert!(m.set_ready(other_player_id, is_ready).is_err());
+        Ok(())
     }
 
     #[test]
@@ -974,6 +3280,41 @@ mod tests {
             .all(|player_state| player_state.private_player_state.is_some()));
     }
 
+    #[test]
+    fn manager_get_public_state() {
+        let mut m = Manager::new();
+
+        let player_id = m.add_player().unwrap();
+        let other_player_id = m.add_player().unwrap();
+
+        assert!(m.has_player(player_id));
+        assert!(m.has_player(other_player_id));
+        assert!(!m.has_player(other_player_id + 1));
+
+        assert!(m.set_ready(player_id, true).is_ok());
+        assert!(m.set_ready(other_player_id, true).is_ok());
+        assert_eq!(m.phase(), GamePhase::Starting);
+        assert!(m.turn().is_none());
+
+        {
+            let game_state = m.get_public_state();
+            assert_eq!(game_state.players_state.len(), 2);
+            assert!(game_state
+                .players_state
+                .iter()
+                .all(|player_state| player_state.private_player_state.is_none()));
+        }
+
+        // Once the game is over, the public state reveals everything.
+        m.phase = GamePhase::Done;
+
+        let game_state = m.get_public_state();
+        assert!(game_state
+            .players_state
+            .iter()
+            .all(|player_state| player_state.private_player_state.is_some()));
+    }
+
     #[test]
     fn manager_select_destination_cards_last_turn() {
         let mut m = Manager::new();
@@ -984,8 +3325,8 @@ mod tests {
         assert!(m.set_ready(player_id, true).is_ok());
         assert!(m.set_ready(other_player_id, true).is_ok());
 
-        m.phase = GamePhase::LastTurn;
         m.turn = Some(40);
+        m.schedule_end_game(1);
 
         let (player_id_first, player_id_second) = if m.get_player_index(player_id) == Some(0) {
             (player_id, other_player_id)
@@ -1258,8 +3599,8 @@ mod tests {
         assert!(m.set_ready(player_id, true).is_ok());
         assert!(m.set_ready(other_player_id, true).is_ok());
 
-        m.phase = GamePhase::LastTurn;
         m.turn = Some(0);
+        m.schedule_end_game(1);
 
         let (player_id_first, player_id_second) = if m.get_player_index(player_id) == Some(0) {
             (player_id, other_player_id)
@@ -1473,8 +3814,8 @@ mod tests {
         assert!(m.set_ready(player_id, true).is_ok());
         assert!(m.set_ready(other_player_id, true).is_ok());
 
-        m.phase = GamePhase::LastTurn;
         m.turn = Some(0);
+        m.schedule_end_game(1);
 
         let (player_id_first, player_id_second) = if m.get_player_index(player_id) == Some(0) {
             (player_id, other_player_id)
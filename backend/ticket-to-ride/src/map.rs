@@ -4,12 +4,14 @@ use crate::city::{City, CityToCity};
 
 use array_init::array_init;
 use atom::AtomSetOnce;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
 use std::cmp::max;
-use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::ops::RangeInclusive;
-use std::sync::{atomic::Ordering, mpsc, Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
 use strum::EnumCount;
 use threadpool::ThreadPool;
 
@@ -17,6 +19,11 @@ lazy_static! {
     static ref THREAD_POOL: Mutex<ThreadPool> = Mutex::new(ThreadPool::default());
 }
 
+/// How many longest routes were computed since the process started (see [`Map::get_longest_route`]).
+static LONGEST_ROUTE_COMPUTATIONS: AtomicU64 = AtomicU64::new(0);
+/// How long was spent computing longest routes since the process started, in nanoseconds.
+static LONGEST_ROUTE_NANOS: AtomicU64 = AtomicU64::new(0);
+
 // Helena has the highest number of neighbors, which is 7 adjacent cities.
 const MAX_ROUTES_PER_CITY: usize = 7;
 
@@ -90,7 +97,8 @@ macro_rules! parallel_routes {
 }
 
 /// Holds the information about a route successfully claimed by a player.
-#[derive(Debug, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct ClaimedRoute {
     /// Which two adjacent cities are part of the claimed route.
     pub route: CityToCity,
@@ -100,6 +108,33 @@ pub struct ClaimedRoute {
     pub length: u8,
 }
 
+/// Static information about one of the routes on the map, whether it is claimed or not.
+///
+/// Clients rendering the whole board can list them via [`Map::routes`].
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct RouteDescription {
+    /// Which two adjacent cities are connected by the route.
+    pub route: CityToCity,
+    /// There can be up to two routes between two cities: this index distinguishes them.
+    pub parallel_route_index: usize,
+    /// The color of the route. The `Wild` color means that any color matches.
+    pub train_color: TrainColor,
+    /// How many cards must be used to claim that route.
+    pub length: u8,
+}
+
+/// Time spent computing longest routes (see [`Map::get_longest_route`]) across all games, since the process started.
+///
+/// Applications embedding the library can monitor it via [`Map::longest_route_timings`], as computing longest routes
+/// is the most expensive part of the game logic.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LongestRouteTimings {
+    /// How many longest routes were computed.
+    pub computations: u64,
+    /// How long was spent computing them, in total.
+    pub total: Duration,
+}
+
 /// The authoritative state of the map, per game.
 /// This can be mutated as players claim routes throughout the game.
 pub struct Map {
@@ -110,6 +145,47 @@ pub struct Map {
     parallel_routes_allowed: bool,
 }
 
+impl Clone for Map {
+    /// Clones the map, including who claimed which route.
+    ///
+    /// Claiming a route on the clone does not claim it on the original map, and vice versa.
+    fn clone(&self) -> Self {
+        let mut all_parallel_routes: BTreeMap<CityToCity, ParallelRoutes> = BTreeMap::new();
+
+        for (&(start, end), parallel_routes) in &self.all_parallel_routes {
+            // Both directions of a route share the same claimer, which we create when cloning the first one.
+            let cloned_parallel_routes = match all_parallel_routes.get(&(end, start)) {
+                Some(reverse_parallel_routes) => parallel_routes
+                    .iter()
+                    .zip(reverse_parallel_routes)
+                    .map(|(route, reverse_route)| Route {
+                        claimer: Arc::clone(&reverse_route.claimer),
+                        ..route.clone()
+                    })
+                    .collect(),
+                None => parallel_routes
+                    .iter()
+                    .map(|route| {
+                        let mut cloned_route = Route::new(route.train_color, route.length);
+                        if let Some(claimer) = route.claimer() {
+                            cloned_route.set_claimer(claimer);
+                        }
+
+                        cloned_route
+                    })
+                    .collect(),
+            };
+
+            all_parallel_routes.insert((start, end), cloned_parallel_routes);
+        }
+
+        Self {
+            all_parallel_routes,
+            parallel_routes_allowed: self.parallel_routes_allowed,
+        }
+    }
+}
+
 impl Map {
     fn get_range_of_routes_starting_at_city(city: City) -> RangeInclusive<CityToCity> {
         // This assumes that the smallest city, as ordered in `City`, is Atlanta,
@@ -501,6 +577,36 @@ impl Map {
         }
     }
 
+    /// Lists every route of the map, in a single direction (as opposed to the internal
+    /// bidirectional mapping), ordered by the first city of each route.
+    ///
+    /// # Example
+    /// ```
+    /// use ticket_to_ride::city::City;
+    /// use ticket_to_ride::map::Map;
+    ///
+    /// let routes = Map::routes();
+    /// assert_eq!(routes.len(), 100);
+    /// assert!(routes
+    ///     .iter()
+    ///     .any(|route| route.route == (City::Raleigh, City::Washington)));
+    /// ```
+    pub fn routes() -> Vec<RouteDescription> {
+        Self::build_us_map()
+            .into_iter()
+            .flat_map(|[(route, parallel_routes), _]| {
+                parallel_routes.into_iter().enumerate().map(
+                    move |(parallel_route_index, parallel_route)| RouteDescription {
+                        route,
+                        parallel_route_index,
+                        train_color: parallel_route.train_color,
+                        length: parallel_route.length,
+                    },
+                )
+            })
+            .collect()
+    }
+
     /// Request from a player `player_id` to claim a specific route between two cities.
     ///
     /// As there can be many routes connecting two cities, the request must specify which of the _parallel_ routes they want to claim.
@@ -560,6 +666,42 @@ impl Map {
         })
     }
 
+    /// Frees a route claimed by a player, such that anyone can claim it again.
+    ///
+    /// This is only used when a player leaves the game before it is over. Freeing a route that does not
+    /// exist, or that is not claimed, is a no-op.
+    ///
+    /// # Example
+    /// ```
+    /// use ticket_to_ride::city::City;
+    /// use ticket_to_ride::map::Map;
+    /// use ticket_to_ride::card::TrainColor;
+    ///
+    /// let mut map = Map::new(2).unwrap();
+    ///
+    /// let route = (City::Raleigh, City::Washington);
+    /// let cards = vec![TrainColor::White, TrainColor::White];
+    /// assert!(map.claim_route_for_player(route, 0, &cards, 0).is_ok());
+    /// assert!(map.claim_route_for_player(route, 0, &cards, 1).is_err());
+    ///
+    /// map.release_route(route, 0);
+    /// assert!(map.claim_route_for_player(route, 0, &cards, 1).is_ok());
+    /// ```
+    pub fn release_route(&mut self, (start, end): CityToCity, parallel_route_index: usize) {
+        // Claimers cannot be unset: instead, both directions of the route get a fresh (shared) claimer.
+        let claimer = Arc::new(AtomSetOnce::empty());
+
+        for route in [(start, end), (end, start)] {
+            if let Some(parallel_route) = self
+                .all_parallel_routes
+                .get_mut(&route)
+                .and_then(|parallel_routes| parallel_routes.get_mut(parallel_route_index))
+            {
+                parallel_route.claimer = Arc::clone(&claimer);
+            }
+        }
+    }
+
     fn can_route_be_claimed_by_player(
         &mut self,
         (start, end): CityToCity,
@@ -659,6 +801,74 @@ impl Map {
         Ok(claimed_route)
     }
 
+    /// Predicate that assesses whether a given player could claim any route on the map, with the train cards
+    /// and cars they have left.
+    ///
+    /// # Example
+    /// ```
+    /// use std::collections::HashMap;
+    /// use ticket_to_ride::card::TrainColor;
+    /// use ticket_to_ride::map::Map;
+    ///
+    /// let map = Map::new(2).unwrap();
+    /// let player_id = 0;
+    ///
+    /// // Claiming a route takes at least one train card.
+    /// let train_cards = HashMap::from([(TrainColor::Wild, 0)]);
+    /// assert!(!map.can_player_claim_any_route(player_id, &train_cards, 45));
+    ///
+    /// // Most routes are gray: any color works.
+    /// let train_cards = HashMap::from([(TrainColor::Pink, 1), (TrainColor::Wild, 1)]);
+    /// assert!(map.can_player_claim_any_route(player_id, &train_cards, 45));
+    ///
+    /// // But not without any car left.
+    /// assert!(!map.can_player_claim_any_route(player_id, &train_cards, 0));
+    /// ```
+    pub fn can_player_claim_any_route(
+        &self,
+        player_id: usize,
+        train_cards: &HashMap<TrainColor, u8>,
+        cars: u8,
+    ) -> bool {
+        let num_cards_of = |color: TrainColor| train_cards.get(&color).copied().unwrap_or(0);
+        let num_wild_cards = num_cards_of(Wild);
+        let max_num_cards_of_same_color = train_cards
+            .iter()
+            .filter(|(color, _)| color.is_not_wild())
+            .map(|(_, num)| *num)
+            .max()
+            .unwrap_or(0);
+
+        self.all_parallel_routes.values().any(|parallel_routes| {
+            parallel_routes
+                .iter()
+                .enumerate()
+                .any(|(parallel_route_index, route)| {
+                    let num_cards = if route.train_color.is_wild() {
+                        max_num_cards_of_same_color
+                    } else {
+                        num_cards_of(route.train_color)
+                    };
+
+                    // Same rules for parallel routes as in `Map::can_route_be_claimed_by_player`.
+                    let other_claimer = match parallel_routes.len() {
+                        1 => None,
+                        num => parallel_routes[(parallel_route_index + 1) % num].claimer(),
+                    };
+                    let is_parallel_route_free = match other_claimer {
+                        Some(claimer) if claimer == player_id => false,
+                        Some(_) => self.parallel_routes_allowed,
+                        None => true,
+                    };
+
+                    route.claimer().is_none()
+                        && is_parallel_route_free
+                        && route.length <= cars
+                        && route.length <= num_cards.saturating_add(num_wild_cards)
+                })
+        })
+    }
+
     /// Predicate that assess whether a given player has connected two cities on the map, based on their claimed routes.
     ///
     /// Returns true if that is the case, false otherwise.
@@ -776,7 +986,25 @@ impl Map {
     /// // Route Phoenix -> Denver is of length 5.
     /// assert_eq!(Map::get_longest_route(&claimed_routes), 8);
     /// ```
-    pub fn get_longest_route(claimed_routes: &Vec<ClaimedRoute>) -> u16 {
+    pub fn get_longest_route(claimed_routes: &[ClaimedRoute]) -> u16 {
+        let started_at = Instant::now();
+        let longest_route = Self::compute_longest_route(claimed_routes);
+
+        LONGEST_ROUTE_NANOS.fetch_add(started_at.elapsed().as_nanos() as u64, Ordering::Relaxed);
+        LONGEST_ROUTE_COMPUTATIONS.fetch_add(1, Ordering::Relaxed);
+
+        longest_route
+    }
+
+    /// Returns the time spent in [`Map::get_longest_route`] across all games, since the process started.
+    pub fn longest_route_timings() -> LongestRouteTimings {
+        LongestRouteTimings {
+            computations: LONGEST_ROUTE_COMPUTATIONS.load(Ordering::Relaxed),
+            total: Duration::from_nanos(LONGEST_ROUTE_NANOS.load(Ordering::Relaxed)),
+        }
+    }
+
+    fn compute_longest_route(claimed_routes: &[ClaimedRoute]) -> u16 {
         let mut cities_to_visit = HashSet::new();
         let mut longest_route = 0;
 
@@ -988,6 +1216,31 @@ mod tests {
         }
     }
 
+    #[test]
+    fn clone_map() {
+        let mut map = Map::new(2).unwrap();
+        let cards = vec![Wild, Wild];
+        assert!(map
+            .claim_route_for_player((City::Raleigh, City::Washington), 0, &cards, 0)
+            .is_ok());
+
+        let mut cloned_map = map.clone();
+        assert_eq!(cloned_map.all_parallel_routes, map.all_parallel_routes);
+        assert!(cloned_map.has_player_fulfilled_destination((City::Washington, City::Raleigh), 0));
+
+        // Claiming a route on the clone does not affect the original, in either direction.
+        assert!(cloned_map
+            .claim_route_for_player((City::Washington, City::NewYork), 0, &cards, 1)
+            .is_ok());
+        assert!(cloned_map
+            .claim_route_for_player((City::NewYork, City::Washington), 1, &cards, 1)
+            .is_err());
+        assert!(map
+            .claim_route_for_player((City::NewYork, City::Washington), 0, &cards, 0)
+            .is_ok());
+        assert_ne!(cloned_map.all_parallel_routes, map.all_parallel_routes);
+    }
+
     // Tests for `Map::claim_route_for_player`.
 
     struct ClaimRouteArgs {
@@ -1557,7 +1810,19 @@ mod tests {
 
     #[test]
     fn longest_route_zero_length() {
-        assert_eq!(Map::get_longest_route(&vec![]), 0);
+        assert_eq!(Map::get_longest_route(&[]), 0);
+    }
+
+    #[test]
+    fn longest_route_timings() {
+        let timings = Map::longest_route_timings();
+
+        Map::get_longest_route(&[]);
+
+        // Other tests may compute longest routes concurrently.
+        let new_timings = Map::longest_route_timings();
+        assert!(new_timings.computations > timings.computations);
+        assert!(new_timings.total >= timings.total);
     }
 
     #[test]
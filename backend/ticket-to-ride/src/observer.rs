@@ -0,0 +1,43 @@
+use crate::card::{DestinationCard, TrainColor};
+use crate::event::GameEvent;
+use crate::manager::{GamePhase, GameState};
+
+/// Notified by the [`crate::manager::Manager`] of everything that happens in a game, such that applications
+/// embedding the library do not have to compare successive [`GameState`]s to find out.
+///
+/// Observers are registered via [`crate::manager::Manager::add_observer`]. They are notified once a command was
+/// applied, following the events it led to (see [`GameEvent`]). Every method does nothing by default.
+///
+/// Payloads are public, unless stated otherwise: those that are private to a player must only be shared with them.
+pub trait GameObserver: Send + Sync {
+    /// An action was applied, leading to the given state: any command, a player passing their turn, or the last
+    /// action being undone.
+    fn on_action_applied(&mut self, _event: &GameEvent, _state: &GameState) {}
+
+    /// A player started their turn.
+    fn on_turn_started(&mut self, _turn: usize, _player_id: usize) {}
+
+    /// The game transitioned to another phase.
+    fn on_phase_changed(&mut self, _phase: GamePhase) {}
+
+    /// The open train card deck was re-shuffled, revealing new cards.
+    fn on_open_train_card_deck_reshuffled(&mut self, _open_train_card_deck: &[Option<TrainColor>]) {
+    }
+
+    /// # Private
+    /// A player fulfilled one of their destination cards.
+    fn on_destination_card_fulfilled(
+        &mut self,
+        _player_id: usize,
+        _destination_card: &DestinationCard,
+    ) {
+    }
+
+    /// # Private
+    /// The state of the game after a command was applied, from the perspective of a given player. This is called
+    /// for every player, after every command.
+    fn on_player_state_changed(&mut self, _player_id: usize, _state: &GameState) {}
+
+    /// The game is over. Every player's private state is revealed in the final state.
+    fn on_game_over(&mut self, _state: &GameState) {}
+}
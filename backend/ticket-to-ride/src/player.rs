@@ -26,6 +26,7 @@ pub type ActionResult = Result<bool, String>;
 #[derive(Clone, Copy, Debug, Deserialize, Display, EnumIter, Eq, Hash, PartialEq, Serialize)]
 #[serde(rename_all = "lowercase")]
 #[strum(serialize_all = "lowercase")]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub enum PlayerColor {
     Black,
     Blue,
@@ -43,8 +44,9 @@ pub enum PlayerColor {
 ///
 /// # JSON
 /// Player actions are serialized in SCREAMING_SNAKE_CASE.
-#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub enum PlayerAction {
     /// # Cardinality
     /// The first and only player action per turn.
@@ -73,10 +75,19 @@ pub enum PlayerAction {
     /// One exception: the initial selection of destination cards,
     /// which happens before turns have started.
     SelectedDestinationCards,
+    /// # Cardinality
+    /// The last player action of a turn, when the player ran out of time (see [`crate::clock::TimeControls`]).
+    /// May be preceded by the actions taken on their behalf (see [`crate::clock::TimeoutPolicy`]).
+    RanOutOfTime,
+    /// # Cardinality
+    /// The only player action of a turn, when the player had no legal move to make: no train card nor destination
+    /// card to draw, and no route they could claim.
+    Passed,
 }
 
-#[derive(Debug, PartialEq, Serialize)]
+#[derive(Clone, Debug, PartialEq, Serialize)]
 /// Keeps track of actions taken at a given turn.
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct TurnActions {
     /// Initially, `turn` is None. This denotes the initial draw that happens concurrently for all players,
     /// before turns have started.
@@ -102,6 +113,7 @@ impl TurnActions {
 
 /// All the information about a player's current state, returned by [`Player::get_player_state`].
 #[derive(Debug, PartialEq, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct PlayerState<'a> {
     /// Encapsulates information that is visible to all players. Always populated!
     pub public_player_state: &'a PublicPlayerState,
@@ -111,7 +123,8 @@ pub struct PlayerState<'a> {
 }
 
 /// Information about a player's state that is visible to all players.
-#[derive(Debug, PartialEq, Serialize)]
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct PublicPlayerState {
     /// Unique to each player in the game.
     /// Requests from the web client are authenticated using this id.
@@ -170,8 +183,9 @@ impl PublicPlayerState {
     }
 }
 
-#[derive(Debug, PartialEq, Serialize)]
+#[derive(Clone, Debug, PartialEq, Serialize)]
 /// Information about a player's state that is only visible to that player.
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct PrivatePlayerState {
     /// Maps how many of a train color a player has.
     /// It is guaranteed that the map has at all times key-value pairs for all train colors.
@@ -208,6 +222,7 @@ impl PrivatePlayerState {
 /// A [`Player`] is not aware of other players in this game: thus, management of inter-player
 /// state (e.g. ensuring unique names, verifying whether we should transition to the _end game_) are
 /// to be taken care of by the [`crate::manager::Manager`].
+#[derive(Clone)]
 pub struct Player {
     public: PublicPlayerState,
     private: PrivatePlayerState,
@@ -298,6 +313,28 @@ impl Player {
         self.public.is_done_playing = true;
     }
 
+    /// Access whether a player has taken their last turn of the game.
+    #[inline]
+    pub fn done_playing(&self) -> bool {
+        self.public.is_done_playing
+    }
+
+    /// Access whether a player has drawn destination cards they have not selected from yet.
+    #[inline]
+    pub fn has_pending_destination_cards(&self) -> bool {
+        !self.private.pending_destination_cards.is_empty()
+    }
+
+    /// If the player took actions during `previous_turn`, they are now considered to have been taken during `turn`.
+    ///
+    /// The [`crate::manager::Manager`] calls this when the turn counter moves in the middle of a player's turn,
+    /// which happens when another player leaves the game.
+    pub fn carry_over_turn_actions(&mut self, previous_turn: usize, turn: usize) {
+        if self.public.turn_actions.turn == Some(previous_turn) {
+            self.public.turn_actions.turn = Some(turn);
+        }
+    }
+
     // Set whether or not a player has completed the overall longest route of the game.
     #[inline]
     pub fn set_has_longest_route(&mut self, has_longest_route: bool) {
@@ -372,6 +409,34 @@ impl Player {
       )
     }
 
+    /// Predicate that assesses whether the player could claim any route on the map, with the train cards and cars
+    /// they have left. See [`Map::can_player_claim_any_route`].
+    #[inline]
+    pub fn can_claim_any_route(&self, map: &Map) -> bool {
+        map.can_player_claim_any_route(self.public.id, &self.private.train_cards, self.public.cars)
+    }
+
+    /// Passes the given turn, when the player has no legal move to make.
+    pub fn pass(&mut self, turn: usize) {
+        self.replace_turn_action(
+            turn,
+            PlayerAction::Passed,
+            format!("{} passed, having no legal move.", self.public.name),
+        );
+    }
+
+    /// The last action the player took, during the last turn they played.
+    #[inline]
+    pub fn last_action(&self) -> Option<PlayerAction> {
+        self.public.turn_actions.actions.last().copied()
+    }
+
+    /// Whether the player passed on their last turn.
+    #[inline]
+    pub fn passed(&self) -> bool {
+        self.last_action() == Some(PlayerAction::Passed)
+    }
+
     /// Try to claim a route for a player.
     ///
     /// Returns an `Err` if either:
@@ -756,7 +821,9 @@ impl Player {
 
         // Note that we iterate backwards, because `remove` shifts all elements after the removed item.
         // Going forward would thus break the mapping we implicitly have using indices.
-        let mut discarded_destination_cards = SmallVec::new();
+        let mut discarded_destination_cards: SmallVec<
+            [DestinationCard; NUM_DRAWN_DESTINATION_CARDS],
+        > = SmallVec::new();
         for i in (0..destination_cards_decisions.len()).rev() {
             let destination_card = self.private.pending_destination_cards.remove(i);
 
@@ -795,6 +862,14 @@ impl Player {
         &mut self.private
     }
 
+    /// Returns the selected destination cards that the player has fulfilled so far.
+    pub fn fulfilled_destination_cards(&self) -> impl Iterator<Item = &DestinationCard> {
+        self.private
+            .selected_destination_cards
+            .iter()
+            .filter(|destination_card| destination_card.fulfilled)
+    }
+
     /// Retrieve the player's state, which encapsulates both [`PublicPlayerState`] and [`PrivatePlayerState`].
     ///
     /// If the given `player_id` is not the same as the current player, only the public state will be populated --
@@ -813,6 +888,48 @@ impl Player {
         }
     }
 
+    /// Records that the player ran out of time during the given turn, after any action taken on their behalf.
+    ///
+    /// Destination cards they drew but did not select yet are added to the bottom of the destination card deck.
+    pub fn run_out_of_time(&mut self, turn: usize, card_dealer: &mut CardDealer) {
+        card_dealer.discard_destination_cards(self.private.pending_destination_cards.drain(..));
+
+        let description = format!("{} ran out of time.", self.public.name);
+        if self.public.turn_actions.turn == Some(turn) {
+            self.append_turn_action(PlayerAction::RanOutOfTime, description);
+        } else {
+            self.replace_turn_action(turn, PlayerAction::RanOutOfTime, description);
+        }
+    }
+
+    /// Hands back all of the player's cards to the [`CardDealer`], when they leave the game before it is over.
+    ///
+    /// Train cards are discarded, and destination cards (pending or selected) are added to the bottom of the
+    /// destination card deck. Returns the routes the player had claimed, which the caller may free.
+    pub fn leave_game(self, card_dealer: &mut CardDealer) -> Vec<ClaimedRoute> {
+        // Discarded in a fixed order, such that re-shuffling them is as deterministic as the dealer.
+        let train_cards = TrainColor::iter()
+            .flat_map(|train_color| {
+                let count = self.private.train_cards.get(&train_color).copied();
+                std::iter::repeat_n(train_color, count.unwrap_or(0) as usize)
+            })
+            .collect();
+        card_dealer.discard_train_cards(train_cards);
+
+        card_dealer.discard_destination_cards(
+            self.private
+                .pending_destination_cards
+                .into_iter()
+                .chain(self.private.selected_destination_cards)
+                .map(|destination_card| DestinationCard {
+                    fulfilled: false,
+                    ..destination_card
+                }),
+        );
+
+        self.public.claimed_routes
+    }
+
     /// Final bookkeeping for a player, only once the game is over.
     ///
     /// Goes over all the selected destination cards, and updates points based on whether the player
@@ -1790,6 +1907,41 @@ mod tests {
         assert_eq!(player.public.has_longest_route, Some(false));
     }
 
+    #[test]
+    fn player_leave_game() {
+        let mut card_dealer = CardDealer::new();
+        let num_train_cards_in_decks = card_dealer.get_close_train_card_deck().len()
+            + card_dealer.get_discarded_train_card_deck().len();
+        let num_destination_cards = card_dealer.get_destination_card_deck().len();
+
+        let mut player = Player::new(PLAYER_ID, PLAYER_COLOR, format!("Player {}", PLAYER_ID));
+        player.initialize_when_game_starts(&mut card_dealer);
+        player.private.selected_destination_cards = vec![DestinationCard {
+            destination: (City::Toronto, City::Chicago),
+            points: 3,
+            fulfilled: true,
+        }];
+        player.public.claimed_routes = vec![ClaimedRoute {
+            route: (City::Chicago, City::Pittsburgh),
+            parallel_route_index: 0,
+            length: 3,
+        }];
+
+        let claimed_routes = player.leave_game(&mut card_dealer);
+        assert_eq!(claimed_routes.len(), 1);
+
+        assert_eq!(
+            card_dealer.get_close_train_card_deck().len()
+                + card_dealer.get_discarded_train_card_deck().len(),
+            num_train_cards_in_decks
+        );
+        let destination_card_deck = card_dealer.get_destination_card_deck();
+        assert_eq!(destination_card_deck.len(), num_destination_cards + 1);
+        assert!(destination_card_deck
+            .iter()
+            .all(|destination_card| !destination_card.fulfilled));
+    }
+
     #[test]
     fn player_finalize_game() {
         let route = (City::Chicago, City::Pittsburgh);
@@ -0,0 +1,307 @@
+//! The interactive loop: renders the game, reads commands from the keyboard, and
+//! forwards the resulting [`Action`]s to the [`GameBackend`].
+
+use crate::backend::{Action, GameBackend};
+use crate::command::{Command, HELP};
+use crate::render::{player_color, render_game};
+use crate::view::GameView;
+
+use crossterm::{
+    cursor::MoveTo,
+    execute,
+    style::Stylize,
+    terminal::{Clear, ClearType},
+};
+use std::io::{self, BufRead, Write};
+use std::iter::repeat_n;
+use ticket_to_ride::{
+    card::TrainColor,
+    map::{Map, RouteDescription},
+};
+
+/// Whether the interactive loop should keep going after a command.
+#[derive(Debug, PartialEq)]
+enum Flow {
+    Continue,
+    Quit,
+}
+
+/// The terminal client, generic over where the game runs.
+pub struct App<B: GameBackend> {
+    backend: B,
+    /// All routes of the board, numbered by their index (displayed 1-based).
+    routes: Vec<RouteDescription>,
+    /// Whether the board is displayed, and if so with which city filter (empty for all routes).
+    board: Option<String>,
+    /// Feedback from the last command, displayed below the game.
+    message: Option<String>,
+    /// In hot-seat games, the last seat the game was rendered for. When it changes, we hide the
+    /// screen until the next player confirms they have the keyboard.
+    last_seat: Option<usize>,
+}
+
+impl<B: GameBackend> App<B> {
+    /// Creates the client, on top of the given backend.
+    pub fn new(backend: B) -> Self {
+        Self {
+            backend,
+            routes: Map::routes(),
+            board: None,
+            message: None,
+            last_seat: None,
+        }
+    }
+
+    /// Runs the interactive loop until the player quits, or the input is closed.
+    pub fn run(&mut self) -> io::Result<()> {
+        let stdin = io::stdin();
+        let mut lines = stdin.lock().lines();
+        let mut stdout = io::stdout();
+
+        loop {
+            execute!(stdout, Clear(ClearType::All), MoveTo(0, 0))?;
+
+            let view = match self.backend.state() {
+                Ok(view) => view,
+                Err(e) => {
+                    writeln!(stdout, "{}", e.red())?;
+                    return Ok(());
+                }
+            };
+
+            if self.should_pass_keyboard(&view) {
+                let me = &view.me().unwrap().public_player_state;
+                write!(
+                    stdout,
+                    "Pass the keyboard to {}, then press Enter.",
+                    me.name.as_str().with(player_color(me.color)).bold()
+                )?;
+                stdout.flush()?;
+                if lines.next().transpose()?.is_none() {
+                    return Ok(());
+                }
+                self.last_seat = Some(me.id);
+                continue;
+            }
+
+            write!(
+                stdout,
+                "{}",
+                render_game(&view, &self.routes, self.board.as_deref())
+            )?;
+            if let Some(message) = self.message.take() {
+                writeln!(stdout, "{}\n", message)?;
+            }
+            write!(stdout, "> ")?;
+            stdout.flush()?;
+
+            let line = match lines.next().transpose()? {
+                Some(line) => line,
+                None => return Ok(()),
+            };
+
+            match line.parse::<Command>() {
+                Ok(command) => match self.handle(command, &view) {
+                    Ok(Flow::Quit) => return Ok(()),
+                    Ok(Flow::Continue) => {}
+                    Err(e) => self.message = Some(e.red().to_string()),
+                },
+                Err(e) => self.message = Some(e.red().to_string()),
+            }
+        }
+    }
+
+    /// In hot-seat games, the private state of a seat must not be shown to the next seat.
+    fn should_pass_keyboard(&self, view: &GameView) -> bool {
+        self.backend.is_hot_seat()
+            && view
+                .me()
+                .is_some_and(|me| Some(me.public_player_state.id) != self.last_seat)
+    }
+
+    fn handle(&mut self, command: Command, view: &GameView) -> Result<Flow, String> {
+        let action = match command {
+            Command::Help => {
+                self.message = Some(String::from(HELP));
+                return Ok(Flow::Continue);
+            }
+            Command::Refresh => return Ok(Flow::Continue),
+            Command::Quit => return Ok(Flow::Quit),
+            Command::Board(filter) => {
+                self.board = match (&self.board, filter) {
+                    (_, Some(filter)) => Some(filter),
+                    (Some(_), None) => None,
+                    (None, None) => Some(String::new()),
+                };
+                return Ok(Flow::Continue);
+            }
+            Command::Seat(seat) => {
+                self.backend.switch_seat(seat)?;
+                return Ok(Flow::Continue);
+            }
+            Command::Name(new_name) => Action::ChangeName(new_name),
+            Command::Color(new_color) => Action::ChangeColor(new_color),
+            Command::Ready(is_ready) => Action::SetReady(is_ready),
+            Command::DrawOpen(card_index) => Action::DrawOpenTrainCard(card_index),
+            Command::DrawClose => Action::DrawCloseTrainCard,
+            Command::DrawDestinations => Action::DrawDestinationCards,
+            Command::Keep(indices) => Self::select_destination_cards(view, indices)?,
+            Command::Claim {
+                route_number,
+                color,
+                num_wild_cards,
+            } => self.claim_route(view, route_number, color, num_wild_cards)?,
+        };
+
+        self.backend.act(action)?;
+        Ok(Flow::Continue)
+    }
+
+    fn select_destination_cards(view: &GameView, indices: Vec<usize>) -> Result<Action, String> {
+        let num_pending = view
+            .me()
+            .and_then(|me| me.private_player_state.as_ref())
+            .map_or(0, |private| private.pending_destination_cards.len());
+
+        if let Some(index) = indices.iter().find(|index| **index >= num_pending) {
+            return Err(format!(
+                "There is no pending destination card ({}).",
+                index + 1
+            ));
+        }
+
+        Ok(Action::SelectDestinationCards(
+            (0..num_pending)
+                .map(|index| indices.contains(&index))
+                .collect(),
+        ))
+    }
+
+    /// Builds the cards used to claim a route.
+    ///
+    /// Unless the number of wild cards is specified, we use as many cards of the given color as
+    /// the player has, and complete with wild cards.
+    fn claim_route(
+        &self,
+        view: &GameView,
+        route_number: usize,
+        color: TrainColor,
+        num_wild_cards: Option<u8>,
+    ) -> Result<Action, String> {
+        let route_description = self
+            .routes
+            .get(route_number)
+            .ok_or_else(|| format!("There is no route {} on the board.", route_number + 1))?;
+        let length = route_description.length;
+
+        let num_wild_cards = match num_wild_cards {
+            _ if color.is_wild() => length,
+            Some(num_wild_cards) if num_wild_cards > length => {
+                return Err(format!(
+                    "This route only needs {} cards, not {} wild cards.",
+                    length, num_wild_cards
+                ))
+            }
+            Some(num_wild_cards) => num_wild_cards,
+            None => {
+                let num_color_cards = view
+                    .me()
+                    .and_then(|me| me.private_player_state.as_ref())
+                    .and_then(|private| private.train_cards.get(&color).copied())
+                    .unwrap_or(0);
+
+                length - num_color_cards.min(length)
+            }
+        };
+
+        Ok(Action::ClaimRoute {
+            route: route_description.route,
+            parallel_route_index: route_description.parallel_route_index,
+            cards: repeat_n(color, (length - num_wild_cards) as usize)
+                .chain(repeat_n(TrainColor::Wild, num_wild_cards as usize))
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::LocalBackend;
+    use ticket_to_ride::{city::City, manager::GamePhase};
+
+    fn started_app() -> App<LocalBackend> {
+        let mut app = App::new(LocalBackend::new(2).unwrap());
+
+        for seat in 0..2 {
+            let view = app.backend.state().unwrap();
+            assert_eq!(app.handle(Command::Seat(seat), &view), Ok(Flow::Continue));
+            assert_eq!(app.handle(Command::Ready(true), &view), Ok(Flow::Continue));
+        }
+
+        app
+    }
+
+    #[test]
+    fn app_handles_ui_commands() {
+        let mut app = App::new(LocalBackend::new(2).unwrap());
+        let view = app.backend.state().unwrap();
+
+        assert_eq!(app.handle(Command::Help, &view), Ok(Flow::Continue));
+        assert!(app.message.is_some());
+        assert_eq!(app.handle(Command::Board(None), &view), Ok(Flow::Continue));
+        assert_eq!(app.board, Some(String::new()));
+        assert_eq!(app.handle(Command::Board(None), &view), Ok(Flow::Continue));
+        assert!(app.board.is_none());
+        assert_eq!(app.handle(Command::Quit, &view), Ok(Flow::Quit));
+        assert!(app.handle(Command::Seat(4), &view).is_err());
+    }
+
+    #[test]
+    fn app_keeps_destination_cards() {
+        let mut app = started_app();
+        let view = app.backend.state().unwrap();
+        assert_eq!(view.phase, GamePhase::Starting);
+
+        assert!(app.handle(Command::Keep(vec![3]), &view).is_err());
+        assert!(app.handle(Command::Keep(vec![0]), &view).is_err());
+        assert_eq!(
+            app.handle(Command::Keep(vec![0, 2]), &view),
+            Ok(Flow::Continue)
+        );
+    }
+
+    #[test]
+    fn app_claim_route_cards() {
+        let mut app = started_app();
+        let view = app.backend.state().unwrap();
+        let route_number = app
+            .routes
+            .iter()
+            .position(|route| route.route == (City::Raleigh, City::Washington))
+            .unwrap();
+
+        assert_eq!(
+            app.claim_route(&view, route_number, TrainColor::Red, Some(1)),
+            Ok(Action::ClaimRoute {
+                route: (City::Raleigh, City::Washington),
+                parallel_route_index: 0,
+                cards: vec![TrainColor::Red, TrainColor::Wild],
+            })
+        );
+        assert_eq!(
+            app.claim_route(&view, route_number, TrainColor::Wild, None),
+            Ok(Action::ClaimRoute {
+                route: (City::Raleigh, City::Washington),
+                parallel_route_index: 0,
+                cards: vec![TrainColor::Wild, TrainColor::Wild],
+            })
+        );
+        assert!(app
+            .claim_route(&view, route_number, TrainColor::Red, Some(3))
+            .is_err());
+        assert!(app
+            .claim_route(&view, app.routes.len(), TrainColor::Red, None)
+            .is_err());
+    }
+}
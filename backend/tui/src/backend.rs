@@ -0,0 +1,478 @@
+//! Where the game actually runs: either locally, on a [`Manager`] shared by all the seats
+//! around the keyboard, or remotely, on the web server.
+
+use crate::view::GameView;
+
+use reqwest::blocking::{Client, RequestBuilder};
+use reqwest::header::LOCATION;
+use reqwest::redirect::Policy;
+use serde::Deserialize;
+use serde_json::json;
+use smallvec::SmallVec;
+use ticket_to_ride::{
+    card::{TrainColor, NUM_DRAWN_DESTINATION_CARDS},
+    city::CityToCity,
+    manager::{GamePhase, Manager},
+    player::PlayerColor,
+};
+
+/// All the actions a seat can take on the game.
+///
+/// These map 1:1 to the [`Manager`] methods, and to the web server's JSON routes.
+#[derive(Debug, PartialEq)]
+pub enum Action {
+    ChangeName(String),
+    ChangeColor(PlayerColor),
+    SetReady(bool),
+    DrawOpenTrainCard(usize),
+    DrawCloseTrainCard,
+    DrawDestinationCards,
+    SelectDestinationCards(SmallVec<[bool; NUM_DRAWN_DESTINATION_CARDS]>),
+    ClaimRoute {
+        route: CityToCity,
+        parallel_route_index: usize,
+        cards: Vec<TrainColor>,
+    },
+}
+
+/// Common interface of the local and remote games.
+pub trait GameBackend {
+    /// Retrieves the game's state, from the perspective of the seat at the keyboard.
+    fn state(&mut self) -> Result<GameView, String>;
+
+    /// Takes an action on behalf of the seat at the keyboard.
+    fn act(&mut self, action: Action) -> Result<(), String>;
+
+    /// Hands the keyboard to another seat.
+    ///
+    /// Only hot-seat games, where all seats share the same keyboard, support this.
+    fn switch_seat(&mut self, seat: usize) -> Result<(), String>;
+
+    /// Whether all seats share the keyboard, in which case the client must hide
+    /// the private state of a seat before handing the keyboard to the next one.
+    fn is_hot_seat(&self) -> bool;
+}
+
+/// A hot-seat game, running directly on a [`Manager`].
+pub struct LocalBackend {
+    manager: Manager,
+    /// The seat (i.e. player ID) at the keyboard.
+    seat: usize,
+}
+
+impl LocalBackend {
+    /// Creates a game with the given number of seats, all controlled from this keyboard.
+    pub fn new(num_players: usize) -> Result<Self, String> {
+        let mut manager = Manager::new();
+
+        for _ in 0..num_players {
+            if manager.add_player().is_none() {
+                return Err(format!(
+                    "Cannot create a game with {} players.",
+                    num_players
+                ));
+            }
+        }
+
+        Ok(Self { manager, seat: 0 })
+    }
+
+    fn view(&self, seat: usize) -> Result<GameView, String> {
+        serde_json::to_value(self.manager.get_state(seat))
+            .and_then(serde_json::from_value)
+            .map_err(|e| format!("Could not read the game state: {}", e))
+    }
+
+    /// Once an action went through, hands the keyboard to whoever has to play next.
+    ///
+    /// During the initial selection of destination cards, this is the first seat that has
+    /// not selected them yet. During the turn-based game, this is the player whose turn it is.
+    fn follow_next_seat(&mut self) -> Result<(), String> {
+        let view = self.view(self.seat)?;
+
+        let next_seat = match view.phase {
+            GamePhase::Starting => (0..self.manager.num_players()).find(|seat| {
+                self.view(*seat)
+                    .ok()
+                    .and_then(|view| {
+                        view.players_state
+                            .into_iter()
+                            .find(|player| player.public_player_state.id == *seat)
+                    })
+                    .and_then(|player| player.private_player_state)
+                    .is_some_and(|private_player_state| {
+                        !private_player_state.pending_destination_cards.is_empty()
+                    })
+            }),
+            _ => view
+                .current_player()
+                .map(|player| player.public_player_state.id),
+        };
+
+        if let Some(next_seat) = next_seat {
+            self.seat = next_seat;
+        }
+
+        Ok(())
+    }
+}
+
+impl GameBackend for LocalBackend {
+    fn state(&mut self) -> Result<GameView, String> {
+        self.view(self.seat)
+    }
+
+    fn act(&mut self, action: Action) -> Result<(), String> {
+        let seat = self.seat;
+
+        match action {
+            Action::ChangeName(new_name) => self.manager.change_player_name(seat, new_name),
+            Action::ChangeColor(new_color) => self.manager.change_player_color(seat, new_color),
+            Action::SetReady(is_ready) => self.manager.set_ready(seat, is_ready),
+            Action::DrawOpenTrainCard(card_index) => {
+                self.manager.draw_open_train_card(seat, card_index)
+            }
+            Action::DrawCloseTrainCard => self.manager.draw_close_train_card(seat),
+            Action::DrawDestinationCards => self.manager.draw_destination_cards(seat),
+            Action::SelectDestinationCards(destination_cards_decisions) => self
+                .manager
+                .select_destination_cards(seat, destination_cards_decisions),
+            Action::ClaimRoute {
+                route,
+                parallel_route_index,
+                cards,
+            } => self
+                .manager
+                .claim_route(seat, route, parallel_route_index, cards),
+        }?;
+
+        self.follow_next_seat()
+    }
+
+    fn switch_seat(&mut self, seat: usize) -> Result<(), String> {
+        if seat >= self.manager.num_players() {
+            return Err(format!("There is no seat {}.", seat));
+        }
+
+        self.seat = seat;
+        Ok(())
+    }
+
+    fn is_hot_seat(&self) -> bool {
+        true
+    }
+}
+
+/// Mirror of the web server's response to player actions.
+#[derive(Deserialize)]
+struct ActionResponse {
+    success: bool,
+    error_message: Option<String>,
+}
+
+/// Mirror of the web server's response to players joining a game.
+#[derive(Deserialize)]
+struct BearerTokenResponse {
+    token: String,
+}
+
+/// Base path of the web server's JSON API.
+const API_BASE: &str = "/api/v1";
+
+/// A game running on the web server, which this client joined as one of its players.
+///
+/// The server authenticates players via the bearer token it returns when they join,
+/// which is sent along with every request to its JSON API.
+pub struct RemoteBackend {
+    client: Client,
+    /// URL of the game's page, e.g. `http://localhost:8000/game/<game_id>`.
+    game_url: String,
+    /// URL of the game in the JSON API, e.g. `http://localhost:8000/api/v1/game/<game_id>`.
+    api_game_url: String,
+    bearer_token: String,
+}
+
+impl RemoteBackend {
+    /// Creates a new game on the server at `server_url`, and joins it.
+    pub fn create(server_url: &str) -> Result<Self, String> {
+        let client = Self::new_client()?;
+        let server_url = server_url.trim_end_matches('/');
+
+        // The server redirects to the newly created game, whose ID is all we need:
+        // the client joins it through the JSON API instead.
+        let res = client
+            .post(format!("{}/create", server_url))
+            .send()
+            .map_err(|e| format!("Could not create a game: {}", e))?;
+        let game_id = res
+            .headers()
+            .get(LOCATION)
+            .and_then(|location| location.to_str().ok())
+            .and_then(|location| location.strip_prefix("/game/"))
+            .and_then(|game_path| game_path.split('?').next())
+            .ok_or_else(|| String::from("The server did not create a game."))?;
+
+        Self::joined(client, server_url, game_id, None)
+    }
+
+    /// Joins an existing game on the server at `server_url`.
+    ///
+    /// Private games only seat players providing their invite code or password, as `invitation`.
+    pub fn join(server_url: &str, game_id: &str, invitation: Option<&str>) -> Result<Self, String> {
+        let client = Self::new_client()?;
+
+        Self::joined(
+            client,
+            server_url.trim_end_matches('/'),
+            game_id,
+            invitation,
+        )
+    }
+
+    fn new_client() -> Result<Client, String> {
+        Client::builder()
+            .redirect(Policy::none())
+            .build()
+            .map_err(|e| format!("Could not create an HTTP client: {}", e))
+    }
+
+    /// Asks the server to seat a new player in the game, which it refuses with an error status.
+    /// Otherwise, it returns the bearer token authenticating the player.
+    fn joined(
+        client: Client,
+        server_url: &str,
+        game_id: &str,
+        invitation: Option<&str>,
+    ) -> Result<Self, String> {
+        let api_game_url = format!("{}{}/game/{}", server_url, API_BASE, game_id);
+        let res = Self::join_request(&client, &api_game_url, invitation)
+            .send()
+            .map_err(|e| format!("Could not join the game: {}", e))?;
+
+        if !res.status().is_success() {
+            return Err(String::from(
+                "The server refused to seat a new player in this game.",
+            ));
+        }
+        let res: BearerTokenResponse = res
+            .json()
+            .map_err(|e| format!("Could not join the game: {}", e))?;
+
+        Ok(Self {
+            client,
+            game_url: format!("{}/game/{}", server_url, game_id),
+            api_game_url,
+            bearer_token: res.token,
+        })
+    }
+
+    /// The request seating a new player in the game, with the invite code or password, if any, as `?code=`.
+    fn join_request(
+        client: &Client,
+        api_game_url: &str,
+        invitation: Option<&str>,
+    ) -> RequestBuilder {
+        let request = client.post(format!("{}/join", api_game_url));
+
+        match invitation {
+            Some(invitation) => request.query(&[("code", invitation)]),
+            None => request,
+        }
+    }
+
+    /// The game's URL, to share with the other players.
+    pub fn game_url(&self) -> &str {
+        &self.game_url
+    }
+
+    fn send_action(&self, request: RequestBuilder) -> Result<(), String> {
+        let res: ActionResponse = request
+            .bearer_auth(&self.bearer_token)
+            .send()
+            .and_then(|res| res.error_for_status())
+            .and_then(|res| res.json())
+            .map_err(|e| format!("The server did not accept the action: {}", e))?;
+
+        if res.success {
+            Ok(())
+        } else {
+            Err(res
+                .error_message
+                .unwrap_or_else(|| String::from("The action failed.")))
+        }
+    }
+}
+
+impl GameBackend for RemoteBackend {
+    fn state(&mut self) -> Result<GameView, String> {
+        self.client
+            .get(format!("{}/state", self.api_game_url))
+            .bearer_auth(&self.bearer_token)
+            .send()
+            .and_then(|res| res.error_for_status())
+            .and_then(|res| res.json())
+            .map_err(|e| format!("Could not fetch the game state: {}", e))
+    }
+
+    fn act(&mut self, action: Action) -> Result<(), String> {
+        let player_url = format!("{}/player", self.api_game_url);
+
+        let request = match action {
+            Action::ChangeName(new_name) => self
+                .client
+                .put(format!("{}/name", player_url))
+                .json(&json!({ "new_name": new_name })),
+            Action::ChangeColor(new_color) => self
+                .client
+                .put(format!("{}/color", player_url))
+                .json(&json!({ "new_color": new_color })),
+            Action::SetReady(is_ready) => self
+                .client
+                .put(format!("{}/is_ready", player_url))
+                .json(&json!({ "is_ready": is_ready })),
+            Action::DrawOpenTrainCard(card_index) => self
+                .client
+                .post(format!("{}/draw_open_train_card", player_url))
+                .json(&json!({ "card_index": card_index })),
+            Action::DrawCloseTrainCard => self
+                .client
+                .post(format!("{}/draw_close_train_card", player_url)),
+            Action::DrawDestinationCards => self
+                .client
+                .post(format!("{}/draw_destination_cards", player_url)),
+            Action::SelectDestinationCards(destination_cards_decisions) => self
+                .client
+                .put(format!("{}/select_destination_cards", player_url))
+                .json(&json!({ "destination_cards_decisions": destination_cards_decisions })),
+            Action::ClaimRoute {
+                route,
+                parallel_route_index,
+                cards,
+            } => self
+                .client
+                .post(format!("{}/claim_route", player_url))
+                .json(&json!({
+                    "route": route,
+                    "parallel_route_index": parallel_route_index,
+                    "cards": cards,
+                })),
+        };
+
+        self.send_action(request)
+    }
+
+    fn switch_seat(&mut self, _seat: usize) -> Result<(), String> {
+        Err(String::from(
+            "Only local games let you switch seats: each remote player has their own client.",
+        ))
+    }
+
+    fn is_hot_seat(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remote_backend_join_request() {
+        let client = RemoteBackend::new_client().unwrap();
+        let api_game_url = "http://localhost:8000/api/v1/game/ABCDEF";
+
+        let request = RemoteBackend::join_request(&client, api_game_url, None)
+            .build()
+            .unwrap();
+        assert_eq!(
+            request.url().as_str(),
+            "http://localhost:8000/api/v1/game/ABCDEF/join"
+        );
+
+        // Invite codes and passwords are sent in the query, encoded.
+        let request = RemoteBackend::join_request(&client, api_game_url, Some("open sesame&co"))
+            .build()
+            .unwrap();
+        assert_eq!(
+            request.url().as_str(),
+            "http://localhost:8000/api/v1/game/ABCDEF/join?code=open+sesame%26co"
+        );
+    }
+
+    #[test]
+    fn local_backend_invalid_number_of_players() {
+        assert!(LocalBackend::new(6).is_err());
+        assert!(LocalBackend::new(2).is_ok());
+    }
+
+    #[test]
+    fn local_backend_lobby() {
+        let mut backend = LocalBackend::new(2).unwrap();
+
+        assert!(backend
+            .act(Action::ChangeName(String::from("Alice")))
+            .is_ok());
+        assert!(backend.act(Action::SetReady(true)).is_ok());
+        assert!(backend.switch_seat(2).is_err());
+        assert!(backend.switch_seat(1).is_ok());
+        // Name collision.
+        assert!(backend
+            .act(Action::ChangeName(String::from("Alice")))
+            .is_err());
+
+        let view = backend.state().unwrap();
+        assert_eq!(view.phase, GamePhase::InLobby);
+        assert_eq!(view.me().unwrap().public_player_state.id, 1);
+
+        assert!(backend.act(Action::SetReady(true)).is_ok());
+        assert_eq!(backend.state().unwrap().phase, GamePhase::Starting);
+    }
+
+    #[test]
+    fn local_backend_follows_next_seat() {
+        let mut backend = LocalBackend::new(2).unwrap();
+        assert!(backend.act(Action::SetReady(true)).is_ok());
+        backend.switch_seat(1).unwrap();
+        assert!(backend.act(Action::SetReady(true)).is_ok());
+
+        // The keyboard goes to the first seat that has to select destination cards.
+        assert_eq!(
+            backend
+                .state()
+                .unwrap()
+                .me()
+                .unwrap()
+                .public_player_state
+                .id,
+            0
+        );
+        assert!(backend
+            .act(Action::SelectDestinationCards(smallvec::smallvec![
+                true, true, false
+            ]))
+            .is_ok());
+        assert_eq!(
+            backend
+                .state()
+                .unwrap()
+                .me()
+                .unwrap()
+                .public_player_state
+                .id,
+            1
+        );
+        assert!(backend
+            .act(Action::SelectDestinationCards(smallvec::smallvec![
+                true, true, true
+            ]))
+            .is_ok());
+
+        // Then, the keyboard goes to whoever's turn it is.
+        let view = backend.state().unwrap();
+        assert_eq!(view.phase, GamePhase::Playing);
+        assert_eq!(
+            view.me().unwrap().public_player_state.id,
+            view.current_player().unwrap().public_player_state.id
+        );
+    }
+}
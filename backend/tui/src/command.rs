@@ -0,0 +1,210 @@
+//! Parsing of the keyboard input into [`Command`]s.
+//!
+//! Every line typed by the player is parsed into a single [`Command`]. Commands that need
+//! information about the game (e.g. which route is behind a route number) are only resolved
+//! into a [`crate::backend::Action`] later on, by the [`crate::app::App`].
+
+use std::str::FromStr;
+use ticket_to_ride::{card::TrainColor, player::PlayerColor};
+
+/// Everything the player can ask for from the keyboard.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    /// Displays the list of commands.
+    Help,
+    /// Fetches the latest state. This is what an empty line does.
+    Refresh,
+    /// Exits the client.
+    Quit,
+    /// Toggles the board, optionally only showing routes that touch a city matching the filter.
+    Board(Option<String>),
+    /// Local hot-seat games only: hands the keyboard to another seat.
+    Seat(usize),
+    /// Changes the player's name.
+    Name(String),
+    /// Changes the player's color.
+    Color(PlayerColor),
+    /// Marks the player as ready, or not.
+    Ready(bool),
+    /// Draws the open train card at the given index (0-based, displayed 1-based).
+    DrawOpen(usize),
+    /// Draws a train card from the close-faced deck.
+    DrawClose,
+    /// Draws destination cards.
+    DrawDestinations,
+    /// Keeps the pending destination cards at the given indices (0-based, displayed 1-based).
+    Keep(Vec<usize>),
+    /// Claims the route with the given number (0-based, displayed 1-based) on the board,
+    /// using cards of the given color, and optionally a specific number of wild cards.
+    Claim {
+        route_number: usize,
+        color: TrainColor,
+        num_wild_cards: Option<u8>,
+    },
+}
+
+/// Human-readable summary of all commands, shown by [`Command::Help`].
+pub const HELP: &str = "\
+Commands:
+  <enter> | r              refresh the game state
+  b [city]                 toggle the board (optionally, routes touching a city)
+  seat <id>                [local] hand the keyboard to another seat
+  name <new name>          change your name (lobby)
+  color <color>            change your color (lobby)
+  ready | unready          mark yourself as (not) ready (lobby)
+  o <n>                    draw the n-th open train card
+  c                        draw a train card from the close deck
+  d                        draw destination cards
+  keep <n> [<n>...]        keep the given pending destination cards
+  claim <route> <color> [<wilds>]
+                           claim a board route, paying with <color> cards,
+                           and optionally exactly <wilds> wild cards
+  h | help                 show this help
+  q | quit                 exit";
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let mut words = line.split_whitespace();
+        let keyword = match words.next() {
+            Some(keyword) => keyword.to_lowercase(),
+            None => return Ok(Command::Refresh),
+        };
+        let arguments: Vec<&str> = words.collect();
+
+        match (keyword.as_str(), arguments.as_slice()) {
+            ("h" | "help", []) => Ok(Command::Help),
+            ("r" | "refresh", []) => Ok(Command::Refresh),
+            ("q" | "quit" | "exit", []) => Ok(Command::Quit),
+            ("b" | "board", []) => Ok(Command::Board(None)),
+            ("b" | "board", filter) => Ok(Command::Board(Some(filter.join(" ")))),
+            ("seat", [seat]) => parse_number(seat).map(Command::Seat),
+            ("name", name) if !name.is_empty() => Ok(Command::Name(name.join(" "))),
+            ("color", [color]) => parse_color(color).map(Command::Color),
+            ("ready", []) => Ok(Command::Ready(true)),
+            ("unready", []) => Ok(Command::Ready(false)),
+            ("o" | "open", [index]) => parse_index(index).map(Command::DrawOpen),
+            ("c" | "close", []) => Ok(Command::DrawClose),
+            ("d" | "destinations", []) => Ok(Command::DrawDestinations),
+            ("keep", indices) if !indices.is_empty() => indices
+                .iter()
+                .map(|index| parse_index(index))
+                .collect::<Result<_, _>>()
+                .map(Command::Keep),
+            ("claim", [route_number, color]) => Ok(Command::Claim {
+                route_number: parse_index(route_number)?,
+                color: parse_color(color)?,
+                num_wild_cards: None,
+            }),
+            ("claim", [route_number, color, num_wild_cards]) => Ok(Command::Claim {
+                route_number: parse_index(route_number)?,
+                color: parse_color(color)?,
+                num_wild_cards: Some(
+                    num_wild_cards
+                        .parse()
+                        .map_err(|_| format!("`{}` is not a number of cards.", num_wild_cards))?,
+                ),
+            }),
+            _ => Err(format!(
+                "Unknown command `{}`. Type `help` for the list of commands.",
+                line.trim()
+            )),
+        }
+    }
+}
+
+fn parse_number(word: &str) -> Result<usize, String> {
+    word.parse()
+        .map_err(|_| format!("`{}` is not a valid number.", word))
+}
+
+/// Parses a 1-based index, as displayed to the player, into a 0-based index.
+fn parse_index(word: &str) -> Result<usize, String> {
+    match parse_number(word)? {
+        0 => Err(String::from("Numbers displayed on screen start at 1.")),
+        index => Ok(index - 1),
+    }
+}
+
+/// Parses a color, via its JSON representation (which is lowercase for both
+/// [`PlayerColor`] and [`TrainColor`]).
+fn parse_color<T: serde::de::DeserializeOwned>(word: &str) -> Result<T, String> {
+    serde_json::from_value(serde_json::Value::String(word.to_lowercase()))
+        .map_err(|_| format!("`{}` is not a valid color.", word))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_empty_line_refreshes() {
+        assert_eq!("".parse(), Ok(Command::Refresh));
+        assert_eq!("   ".parse(), Ok(Command::Refresh));
+        assert_eq!("r".parse(), Ok(Command::Refresh));
+    }
+
+    #[test]
+    fn parse_lobby_commands() {
+        assert_eq!(
+            "name Bobby Tables".parse(),
+            Ok(Command::Name(String::from("Bobby Tables")))
+        );
+        assert_eq!("color Pink".parse(), Ok(Command::Color(PlayerColor::Pink)));
+        assert!("color purple".parse::<Command>().is_err());
+        assert_eq!("ready".parse(), Ok(Command::Ready(true)));
+        assert_eq!("unready".parse(), Ok(Command::Ready(false)));
+        assert_eq!("seat 3".parse(), Ok(Command::Seat(3)));
+    }
+
+    #[test]
+    fn parse_draw_commands() {
+        assert_eq!("o 1".parse(), Ok(Command::DrawOpen(0)));
+        assert_eq!("open 5".parse(), Ok(Command::DrawOpen(4)));
+        assert!("o 0".parse::<Command>().is_err());
+        assert!("o".parse::<Command>().is_err());
+        assert_eq!("c".parse(), Ok(Command::DrawClose));
+        assert_eq!("d".parse(), Ok(Command::DrawDestinations));
+        assert_eq!("keep 1 3".parse(), Ok(Command::Keep(vec![0, 2])));
+        assert!("keep".parse::<Command>().is_err());
+        assert!("keep 1 x".parse::<Command>().is_err());
+    }
+
+    #[test]
+    fn parse_claim_commands() {
+        assert_eq!(
+            "claim 42 red".parse(),
+            Ok(Command::Claim {
+                route_number: 41,
+                color: TrainColor::Red,
+                num_wild_cards: None,
+            })
+        );
+        assert_eq!(
+            "claim 1 wild 2".parse(),
+            Ok(Command::Claim {
+                route_number: 0,
+                color: TrainColor::Wild,
+                num_wild_cards: Some(2),
+            })
+        );
+        assert!("claim 1".parse::<Command>().is_err());
+        assert!("claim 1 red many".parse::<Command>().is_err());
+    }
+
+    #[test]
+    fn parse_board_commands() {
+        assert_eq!("b".parse(), Ok(Command::Board(None)));
+        assert_eq!(
+            "board new york".parse(),
+            Ok(Command::Board(Some(String::from("new york"))))
+        );
+    }
+
+    #[test]
+    fn parse_unknown_command() {
+        assert!("fly to the moon".parse::<Command>().is_err());
+        assert!("ready now".parse::<Command>().is_err());
+    }
+}
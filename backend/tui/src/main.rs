@@ -0,0 +1,61 @@
+//! Launches the terminal client, either on a local hot-seat game, or on a game hosted by the web server.
+
+mod app;
+mod backend;
+mod command;
+mod render;
+mod view;
+
+use crate::app::App;
+use crate::backend::{LocalBackend, RemoteBackend};
+
+use std::process::ExitCode;
+
+/// How to launch the client, as shown when the arguments are invalid.
+const USAGE: &str = "\
+Usage:
+  ticket-to-ride-tui local [<number of players>]                 hot-seat game on this terminal (default: 2 players)
+  ticket-to-ride-tui create <server url>                         create a game on the server, and join it
+  ticket-to-ride-tui join <server url> <game id> [<invitation>]  join a game on the server, private ones with their
+                                                                 invite code or password";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    let result = match args.as_slice() {
+        ["local"] => LocalBackend::new(2).map(|backend| App::new(backend).run()),
+        ["local", num_players] => num_players
+            .parse()
+            .map_err(|_| format!("`{}` is not a number of players.", num_players))
+            .and_then(LocalBackend::new)
+            .map(|backend| App::new(backend).run()),
+        ["create", server_url] => RemoteBackend::create(server_url).map(|backend| {
+            println!(
+                "Share this link with the other players: {}",
+                backend.game_url()
+            );
+            App::new(backend).run()
+        }),
+        ["join", server_url, game_id] => {
+            RemoteBackend::join(server_url, game_id, None).map(|backend| App::new(backend).run())
+        }
+        ["join", server_url, game_id, invitation] => {
+            RemoteBackend::join(server_url, game_id, Some(invitation))
+                .map(|backend| App::new(backend).run())
+        }
+        _ => Err(String::from(USAGE)),
+    };
+
+    match result {
+        Ok(Ok(())) => ExitCode::SUCCESS,
+        Ok(Err(e)) => {
+            eprintln!("Terminal error: {}", e);
+            ExitCode::FAILURE
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
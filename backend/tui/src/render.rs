@@ -0,0 +1,360 @@
+//! Renders a [`GameView`] as text, with claimed routes and players painted in their [`PlayerColor`].
+
+use crate::view::{GameView, PlayerView};
+
+use crossterm::style::{Color, Stylize};
+use std::fmt::Write;
+use strum::IntoEnumIterator;
+use ticket_to_ride::{
+    card::TrainColor, city::City, manager::GamePhase, map::RouteDescription, player::PlayerColor,
+};
+
+/// Terminal color used to paint a player.
+pub fn player_color(color: PlayerColor) -> Color {
+    match color {
+        PlayerColor::Black => Color::DarkGrey,
+        PlayerColor::Blue => Color::Blue,
+        PlayerColor::Green => Color::Green,
+        PlayerColor::Orange => Color::DarkYellow,
+        PlayerColor::Pink => Color::Magenta,
+        PlayerColor::Red => Color::Red,
+        PlayerColor::Yellow => Color::Yellow,
+        PlayerColor::White => Color::White,
+    }
+}
+
+/// Terminal color used to paint a train card, or a route.
+pub fn train_color(color: TrainColor) -> Color {
+    match color {
+        TrainColor::Black => Color::DarkGrey,
+        TrainColor::Blue => Color::Blue,
+        TrainColor::Green => Color::Green,
+        TrainColor::Orange => Color::DarkYellow,
+        TrainColor::Pink => Color::Magenta,
+        TrainColor::Red => Color::Red,
+        TrainColor::White => Color::White,
+        TrainColor::Wild => Color::Cyan,
+        TrainColor::Yellow => Color::Yellow,
+    }
+}
+
+/// Normalizes a city name, so that players can type `montreal` or `saultstmarie`.
+fn normalize_city_name(name: &str) -> String {
+    name.to_lowercase()
+        .replace('é', "e")
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect()
+}
+
+/// Whether the city's name contains the given filter, ignoring case, spaces and punctuation.
+pub fn city_matches(city: City, filter: &str) -> bool {
+    normalize_city_name(&city.to_string()).contains(&normalize_city_name(filter))
+}
+
+/// Renders the whole screen: game status, players, decks, the seat's hand, and optionally the board.
+pub fn render_game(view: &GameView, routes: &[RouteDescription], board: Option<&str>) -> String {
+    let mut out = String::new();
+
+    render_status(&mut out, view);
+    render_players(&mut out, view);
+    render_decks(&mut out, view);
+    if let Some(me) = view.me() {
+        render_hand(&mut out, me);
+    }
+    if let Some(filter) = board {
+        render_board(&mut out, view, routes, filter);
+    }
+
+    out
+}
+
+fn render_status(out: &mut String, view: &GameView) {
+    let phase = match view.phase {
+        GamePhase::InLobby => "In the lobby: waiting for every player to be ready",
+        GamePhase::Starting => "Starting: every player selects their initial destination cards",
+        GamePhase::Playing => "Playing",
+        GamePhase::LastTurn => "Last turn: every player has one turn left",
+        GamePhase::Done => "Game over",
+    };
+    let _ = writeln!(out, "=== Ticket To Ride === {}", phase.bold());
+
+    if let (GamePhase::LastTurn, Some(num_turns_left)) = (view.phase, view.num_turns_left) {
+        let _ = writeln!(out, "{} turn(s) remaining", num_turns_left);
+    }
+
+    if let (Some(turn), Some(player)) = (view.turn, view.current_player()) {
+        let player = &player.public_player_state;
+        let _ = writeln!(
+            out,
+            "Turn {}: {} to play",
+            turn + 1,
+            player.name.as_str().with(player_color(player.color)).bold()
+        );
+    }
+    let _ = writeln!(out);
+}
+
+fn render_players(out: &mut String, view: &GameView) {
+    let _ = writeln!(out, "Players:");
+
+    for player in &view.players_state {
+        let public = &player.public_player_state;
+        let mut flags = Vec::new();
+
+        match view.phase {
+            GamePhase::InLobby if public.is_ready => flags.push("ready"),
+            GamePhase::InLobby => flags.push("not ready"),
+            _ => {}
+        }
+        if public.is_done_playing {
+            flags.push("done");
+        }
+        if public.has_longest_route == Some(true) {
+            flags.push("longest route");
+        }
+        if player.private_player_state.is_some() && view.me().is_some() {
+            flags.push("you");
+        }
+
+        let _ = writeln!(
+            out,
+            "  #{} {} {:>3} pts  {:>2} cars  {:>3} cards  {}",
+            public.id,
+            // Styled content ignores the formatting width, so we pad before styling.
+            format!("{:<20}", public.name).with(player_color(public.color)),
+            public.points,
+            public.cars,
+            public.num_train_cards,
+            flags.join(", ")
+        );
+        for description in &public.turn_actions.description {
+            let _ = writeln!(out, "      {}", description.as_str().italic());
+        }
+    }
+    let _ = writeln!(out);
+}
+
+fn render_decks(out: &mut String, view: &GameView) {
+    let card_dealer_state = match &view.card_dealer_state {
+        Some(card_dealer_state) => card_dealer_state,
+        None => return,
+    };
+
+    let _ = write!(out, "Open deck:");
+    for (index, card) in card_dealer_state.open_train_card_deck.iter().enumerate() {
+        match card {
+            Some(card) => {
+                let _ = write!(
+                    out,
+                    "  [{}] {}",
+                    index + 1,
+                    card.to_string().with(train_color(*card))
+                );
+            }
+            None => {
+                let _ = write!(out, "  [{}] -", index + 1);
+            }
+        }
+    }
+    let _ = writeln!(
+        out,
+        "\nClose deck: {} cards    Discarded: {} cards    Destination deck: {} cards\n",
+        card_dealer_state.close_train_card_deck_size,
+        card_dealer_state.discarded_train_card_deck_size,
+        card_dealer_state.destination_card_deck_size
+    );
+}
+
+fn render_hand(out: &mut String, me: &PlayerView) {
+    let private = match &me.private_player_state {
+        Some(private) => private,
+        None => return,
+    };
+
+    let _ = write!(out, "Your train cards:");
+    for color in TrainColor::iter() {
+        let count = private.train_cards.get(&color).copied().unwrap_or(0);
+        if count > 0 {
+            let _ = write!(
+                out,
+                "  {} x{}",
+                color.to_string().with(train_color(color)),
+                count
+            );
+        }
+    }
+    let _ = writeln!(out);
+
+    if !private.selected_destination_cards.is_empty() {
+        let _ = writeln!(out, "Your destination cards:");
+        for card in &private.selected_destination_cards {
+            let (start, end) = card.destination;
+            let _ = writeln!(
+                out,
+                "  {} {} - {} ({} pts)",
+                if card.fulfilled { "[x]" } else { "[ ]" },
+                start,
+                end,
+                card.points
+            );
+        }
+    }
+
+    if !private.pending_destination_cards.is_empty() {
+        let _ = writeln!(out, "Pending destination cards (use `keep`):");
+        for (index, card) in private.pending_destination_cards.iter().enumerate() {
+            let (start, end) = card.destination;
+            let _ = writeln!(
+                out,
+                "  ({}) {} - {} ({} pts)",
+                index + 1,
+                start,
+                end,
+                card.points
+            );
+        }
+    }
+    let _ = writeln!(out);
+}
+
+fn render_board(out: &mut String, view: &GameView, routes: &[RouteDescription], filter: &str) {
+    let _ = writeln!(out, "Board:");
+
+    for (route_number, route_description) in routes.iter().enumerate() {
+        let (start, end) = route_description.route;
+        if !filter.is_empty() && !city_matches(start, filter) && !city_matches(end, filter) {
+            continue;
+        }
+
+        let claimer = match view.claimer(
+            route_description.route,
+            route_description.parallel_route_index,
+        ) {
+            Some(claimer) => claimer
+                .name
+                .as_str()
+                .with(player_color(claimer.color))
+                .to_string(),
+            None => String::from("-"),
+        };
+
+        let _ = writeln!(
+            out,
+            "  {:>3}. {:<35} {} {} {}",
+            route_number + 1,
+            format!("{} - {}", start, end),
+            route_description.length,
+            format!("{:<6}", route_description.train_color.to_string())
+                .with(train_color(route_description.train_color)),
+            claimer
+        );
+    }
+    let _ = writeln!(out);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ticket_to_ride::map::Map;
+
+    const PLAYING_STATE: &str = r#"{
+        "phase": "playing",
+        "turn": 4,
+        "card_dealer_state": {
+            "open_train_card_deck": ["red", null, "wild", "blue", "blue"],
+            "close_train_card_deck_size": 80,
+            "discarded_train_card_deck_size": 2,
+            "destination_card_deck_size": 24
+        },
+        "players_state": [
+            {
+                "public_player_state": {
+                    "id": 1, "name": "Alice", "color": "pink", "is_ready": true,
+                    "is_done_playing": false, "cars": 43, "points": 2,
+                    "turn_actions": {"turn": 3, "actions": ["CLAIMED_ROUTE"], "description": ["Alice has claimed a route."]},
+                    "claimed_routes": [{"route": [34, 25], "parallel_route_index": 0, "length": 2}],
+                    "num_train_cards": 3
+                },
+                "private_player_state": {
+                    "train_cards": {"red": 2, "wild": 1, "blue": 0},
+                    "pending_destination_cards": [
+                        {"destination": [4, 29], "points": 9, "fulfilled": false}
+                    ],
+                    "selected_destination_cards": [
+                        {"destination": [25, 19], "points": 6, "fulfilled": true}
+                    ]
+                }
+            },
+            {
+                "public_player_state": {
+                    "id": 0, "name": "Bob", "color": "blue", "is_ready": true,
+                    "is_done_playing": false, "cars": 45, "points": 0,
+                    "turn_actions": {"turn": 2, "actions": [], "description": []},
+                    "claimed_routes": [], "num_train_cards": 6
+                },
+                "private_player_state": null
+            }
+        ]
+    }"#;
+
+    fn playing_view() -> GameView {
+        serde_json::from_str(PLAYING_STATE).unwrap()
+    }
+
+    #[test]
+    fn city_matches_ignores_case_spaces_and_accents() {
+        assert!(city_matches(City::NewYork, "new york"));
+        assert!(city_matches(City::NewYork, "NEWYORK"));
+        assert!(city_matches(City::Montreal, "montreal"));
+        assert!(city_matches(City::SaultStMarie, "sault st marie"));
+        assert!(!city_matches(City::Boston, "miami"));
+    }
+
+    #[test]
+    fn render_game_without_board() {
+        let out = render_game(&playing_view(), &Map::routes(), None);
+
+        assert!(out.contains("Turn 5"));
+        assert!(out.contains("Alice"));
+        assert!(out.contains("Bob"));
+        assert!(out.contains("Alice has claimed a route."));
+        assert!(out.contains("[2] -"));
+        assert!(out.contains("Close deck: 80 cards"));
+        assert!(out.contains("Your train cards:"));
+        assert!(out.contains("[x] Raleigh - New York (6 pts)"));
+        assert!(out.contains("(1) Chicago - Santa Fe (9 pts)"));
+        assert!(!out.contains("Board:"));
+    }
+
+    #[test]
+    fn render_game_last_turn() {
+        let mut view = playing_view();
+        view.phase = GamePhase::LastTurn;
+        view.num_turns_left = Some(2);
+
+        let out = render_game(&view, &Map::routes(), None);
+        assert!(out.contains("2 turn(s) remaining"));
+    }
+
+    #[test]
+    fn render_game_with_filtered_board() {
+        let routes = Map::routes();
+        let out = render_game(&playing_view(), &routes, Some("raleigh"));
+
+        assert!(out.contains("Board:"));
+        assert!(out.contains("Raleigh - Washington"));
+        assert!(!out.contains("Boston - Montréal"));
+
+        let out = render_game(&playing_view(), &routes, Some(""));
+        assert!(out.contains("Boston - Montréal"));
+        assert_eq!(
+            out.lines()
+                .skip_while(|line| *line != "Board:")
+                .skip(1)
+                .filter(|line| !line.is_empty())
+                .count(),
+            routes.len()
+        );
+    }
+}
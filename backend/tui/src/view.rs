@@ -0,0 +1,229 @@
+//! Owned mirror of [`ticket_to_ride::manager::GameState`], which can be deserialized.
+//!
+//! The library's [`ticket_to_ride::manager::GameState`] borrows from its [`ticket_to_ride::manager::Manager`],
+//! and is only meant to be serialized. Both backends therefore go through JSON: the local backend
+//! serializes the state returned by its manager, and the remote backend receives the very same JSON
+//! from the web server.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use ticket_to_ride::{
+    card::{DestinationCard, TrainColor},
+    city::CityToCity,
+    manager::GamePhase,
+    map::ClaimedRoute,
+    player::PlayerColor,
+};
+
+/// What the client knows about the game, from the perspective of one seat.
+#[derive(Debug, Deserialize)]
+pub struct GameView {
+    /// The phase of the game.
+    pub phase: GamePhase,
+    /// Which player's turn it is, modulo the number of players.
+    pub turn: Option<usize>,
+    /// Public information about the decks. `None` until the game has started.
+    pub card_dealer_state: Option<CardDealerView>,
+    /// All players, in the order in which they play.
+    pub players_state: Vec<PlayerView>,
+    /// How many turns are left, including the current one, once the final round was triggered.
+    #[serde(default)]
+    pub num_turns_left: Option<usize>,
+}
+
+/// Public information about the decks of cards.
+#[derive(Debug, Deserialize)]
+pub struct CardDealerView {
+    /// All train cards in the open-faced deck.
+    pub open_train_card_deck: Vec<Option<TrainColor>>,
+    /// How many cards are left in the close-faced deck.
+    pub close_train_card_deck_size: usize,
+    /// How many train cards are discarded.
+    pub discarded_train_card_deck_size: usize,
+    /// How many cards are left in the destination card deck.
+    pub destination_card_deck_size: usize,
+}
+
+/// A player's state, with its private part only populated for the seat at the keyboard.
+#[derive(Debug, Deserialize)]
+pub struct PlayerView {
+    /// Information visible to all players.
+    pub public_player_state: PublicPlayerView,
+    /// Information only visible to the player themselves.
+    pub private_player_state: Option<PrivatePlayerView>,
+}
+
+/// Information about a player that is visible to all players.
+#[derive(Debug, Deserialize)]
+pub struct PublicPlayerView {
+    pub id: usize,
+    pub name: String,
+    pub color: PlayerColor,
+    pub is_ready: bool,
+    pub is_done_playing: bool,
+    pub cars: u8,
+    pub points: i16,
+    pub turn_actions: TurnActionsView,
+    pub claimed_routes: Vec<ClaimedRoute>,
+    pub num_train_cards: u8,
+    #[serde(default)]
+    pub has_longest_route: Option<bool>,
+}
+
+/// Human-readable description of the actions taken by a player during the last turn
+/// they have participated in.
+#[derive(Debug, Deserialize)]
+pub struct TurnActionsView {
+    pub description: Vec<String>,
+}
+
+/// Information about a player that is only visible to that player.
+#[derive(Debug, Deserialize)]
+pub struct PrivatePlayerView {
+    pub train_cards: HashMap<TrainColor, u8>,
+    pub pending_destination_cards: Vec<DestinationCard>,
+    pub selected_destination_cards: Vec<DestinationCard>,
+}
+
+impl GameView {
+    /// Whether the turn-based part of the game is underway.
+    pub fn is_turn_based(&self) -> bool {
+        matches!(self.phase, GamePhase::Playing | GamePhase::LastTurn)
+    }
+
+    /// The player whose turn it is, if the turn-based game has started.
+    pub fn current_player(&self) -> Option<&PlayerView> {
+        match self.turn {
+            Some(turn) if self.is_turn_based() && !self.players_state.is_empty() => {
+                Some(&self.players_state[turn % self.players_state.len()])
+            }
+            _ => None,
+        }
+    }
+
+    /// The player at the keyboard, i.e. the only one with a populated private state.
+    ///
+    /// Once the game is done, every private state is populated, so this is `None`.
+    pub fn me(&self) -> Option<&PlayerView> {
+        let mut players_with_private_state = self
+            .players_state
+            .iter()
+            .filter(|player| player.private_player_state.is_some());
+
+        match (
+            players_with_private_state.next(),
+            players_with_private_state.next(),
+        ) {
+            (Some(player), None) => Some(player),
+            _ => None,
+        }
+    }
+
+    /// Finds which player, if any, has claimed the given route.
+    pub fn claimer(
+        &self,
+        (start, end): CityToCity,
+        parallel_route_index: usize,
+    ) -> Option<&PublicPlayerView> {
+        self.players_state
+            .iter()
+            .map(|player| &player.public_player_state)
+            .find(|player| {
+                player.claimed_routes.iter().any(|claimed_route| {
+                    claimed_route.parallel_route_index == parallel_route_index
+                        && (claimed_route.route == (start, end)
+                            || claimed_route.route == (end, start))
+                })
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ticket_to_ride::city::City;
+
+    const LOBBY_STATE: &str = r#"{
+        "phase": "in_lobby",
+        "turn": null,
+        "card_dealer_state": null,
+        "players_state": [
+            {
+                "public_player_state": {
+                    "id": 0, "name": "Player 0", "color": "black", "is_ready": false,
+                    "is_done_playing": false, "cars": 45, "points": 0,
+                    "turn_actions": {"turn": null, "actions": [], "description": []},
+                    "claimed_routes": [], "num_train_cards": 0
+                },
+                "private_player_state": {
+                    "train_cards": {"red": 0, "wild": 0},
+                    "pending_destination_cards": [],
+                    "selected_destination_cards": []
+                }
+            },
+            {
+                "public_player_state": {
+                    "id": 1, "name": "Player 1", "color": "blue", "is_ready": true,
+                    "is_done_playing": false, "cars": 43, "points": 2,
+                    "turn_actions": {"turn": null, "actions": [], "description": []},
+                    "claimed_routes": [{"route": [34, 25], "parallel_route_index": 1, "length": 2}],
+                    "num_train_cards": 0
+                },
+                "private_player_state": null
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn game_view_from_json() -> serde_json::Result<()> {
+        let view: GameView = serde_json::from_str(LOBBY_STATE)?;
+
+        assert_eq!(view.phase, GamePhase::InLobby);
+        assert!(view.turn.is_none());
+        assert!(view.card_dealer_state.is_none());
+        assert_eq!(view.players_state.len(), 2);
+        assert!(view.players_state[1]
+            .public_player_state
+            .has_longest_route
+            .is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn game_view_me_and_current_player() -> serde_json::Result<()> {
+        let mut view: GameView = serde_json::from_str(LOBBY_STATE)?;
+
+        assert_eq!(view.me().map(|me| me.public_player_state.id), Some(0));
+        assert!(view.current_player().is_none());
+
+        view.phase = GamePhase::Playing;
+        view.turn = Some(3);
+        assert_eq!(
+            view.current_player()
+                .map(|player| player.public_player_state.id),
+            Some(1)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn game_view_claimer_in_both_directions() -> serde_json::Result<()> {
+        let view: GameView = serde_json::from_str(LOBBY_STATE)?;
+
+        assert_eq!(
+            view.claimer((City::Raleigh, City::Washington), 1)
+                .map(|player| player.id),
+            Some(1)
+        );
+        assert_eq!(
+            view.claimer((City::Washington, City::Raleigh), 1)
+                .map(|player| player.id),
+            Some(1)
+        );
+        assert!(view.claimer((City::Raleigh, City::Washington), 0).is_none());
+
+        Ok(())
+    }
+}
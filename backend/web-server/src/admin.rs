@@ -0,0 +1,262 @@
+//! Running the server: probes telling whether it is up, and an admin API to oversee the games it hosts.
+//!
+//! The admin API is guarded by a secret, read from Rocket's configuration (see [`AdminSecret::from_figment`]), which
+//! admins send in the `Authorization: Bearer <secret>` header (see [`AdminAuthenticator`]). Without a secret, the
+//! admin API is disabled: its routes respond with 404, as if they did not exist.
+//!
+//! Admins can also post a maintenance notice (see [`MaintenanceNotice`]), which anyone can read, for clients to show.
+
+use crate::bearer_token::AUTHORIZATION_BEARER_PREFIX;
+use crate::game::Game;
+use crate::response_types::{AdminGameResponse, NoticeResponse};
+use crate::signing;
+use crate::unix_time::unix_now;
+
+use dashmap::DashMap;
+use rocket::figment::Figment;
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::Shutdown;
+use std::cmp::Reverse;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+use ticket_to_ride::manager::ManagerActionResult;
+use tracing::info;
+use uuid::Uuid;
+
+/// The secret guarding the admin API.
+///
+/// Only an HMAC of the secret is kept, with a random key: secrets sent by clients are hashed the same way, and
+/// compared in constant time.
+pub struct AdminSecret {
+    key: Vec<u8>,
+    /// The HMAC of the secret, if the admin API is enabled.
+    digest: Option<Vec<u8>>,
+}
+
+impl AdminSecret {
+    /// Creates the secret guarding the admin API, which is disabled without one.
+    pub fn new(secret: Option<&str>) -> Self {
+        let key = signing::random_key();
+        let digest = secret
+            .filter(|secret| !secret.is_empty())
+            .map(|secret| signing::sign(&key, secret.as_bytes()));
+
+        Self { key, digest }
+    }
+
+    /// Reads the secret from Rocket's configuration: `admin_secret`. If not configured (or empty), the admin API is
+    /// disabled.
+    pub fn from_figment(figment: &Figment) -> Self {
+        Self::new(
+            figment
+                .extract_inner::<String>("admin_secret")
+                .ok()
+                .as_deref(),
+        )
+    }
+
+    /// Whether the admin API is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.digest.is_some()
+    }
+
+    /// Whether the given secret is the admin secret. Always `false` if the admin API is disabled.
+    pub fn verify(&self, secret: &str) -> bool {
+        match &self.digest {
+            Some(digest) => signing::verify(&self.key, secret.as_bytes(), digest),
+            None => false,
+        }
+    }
+}
+
+/// Types of error when authenticating an admin.
+#[derive(Debug)]
+pub enum AdminGuardError {
+    /// No admin secret is configured.
+    Disabled,
+    /// The `Authorization` header is missing, or does not hold the admin secret.
+    Unauthorized,
+}
+
+/// Request guard of the admin API: the request must carry the admin secret, in the `Authorization: Bearer <secret>`
+/// header.
+///
+/// Responds with 404 if the admin API is disabled, and with 401 if the secret is missing or wrong.
+pub struct AdminAuthenticator;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AdminAuthenticator {
+    type Error = AdminGuardError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let admin_secret = match req.rocket().state::<AdminSecret>() {
+            Some(admin_secret) if admin_secret.is_enabled() => admin_secret,
+            _ => return Outcome::Failure((Status::NotFound, AdminGuardError::Disabled)),
+        };
+
+        let secret = req
+            .headers()
+            .get_one("Authorization")
+            .and_then(|authorization| authorization.strip_prefix(AUTHORIZATION_BEARER_PREFIX));
+
+        match secret {
+            Some(secret) if admin_secret.verify(secret) => Outcome::Success(Self),
+            _ => Outcome::Failure((Status::Unauthorized, AdminGuardError::Unauthorized)),
+        }
+    }
+}
+
+/// Whether the server lifted off, and is not shutting down. See [`is_ready`].
+///
+/// Managed by Rocket, and set once the server lifted off (see [`Readiness::track`]).
+#[derive(Clone, Default)]
+pub struct Readiness(Arc<AtomicBool>);
+
+impl Readiness {
+    /// Marks the server as ready, until it shuts down.
+    pub(crate) fn track(&self, shutdown: Shutdown) {
+        self.0.store(true, Ordering::Relaxed);
+
+        let readiness = self.clone();
+        rocket::tokio::spawn(async move {
+            shutdown.await;
+            readiness.0.store(false, Ordering::Relaxed);
+        });
+    }
+}
+
+/// Whether the server is ready to serve requests: it lifted off, and it is not shutting down.
+///
+/// Hosting as many games as it can does not make the server unready, as the games it hosts have to stay reachable:
+/// creating more games fails instead (see [`crate::limits::GameLimits`]).
+///
+/// Returns an `Err` explaining why the server is not ready, otherwise `Ok(())`.
+pub(crate) fn is_ready(readiness: &Readiness) -> Result<(), String> {
+    if !readiness.0.load(Ordering::Relaxed) {
+        return Err(String::from("The server is starting, or shutting down."));
+    }
+
+    Ok(())
+}
+
+/// A notice for everyone using the server, e.g. ahead of maintenance. Set by admins, and read by clients (see
+/// [`crate::router::get_notice`]).
+///
+/// Managed by Rocket.
+#[derive(Default)]
+pub struct MaintenanceNotice(RwLock<Option<NoticeResponse>>);
+
+impl MaintenanceNotice {
+    /// Returns the current notice, if any.
+    pub fn get(&self) -> Option<NoticeResponse> {
+        self.0.read().unwrap().clone()
+    }
+
+    /// Replaces the current notice, if any.
+    pub fn set(&self, message: String) {
+        info!(%message, "Maintenance notice posted");
+
+        *self.0.write().unwrap() = Some(NoticeResponse {
+            message,
+            posted_at: unix_now(),
+        });
+    }
+
+    /// Removes the current notice, if any.
+    pub fn clear(&self) {
+        if self.0.write().unwrap().take().is_some() {
+            info!("Maintenance notice cleared");
+        }
+    }
+}
+
+/// Lists every game hosted by the server, from newest to oldest, whatever their phase.
+pub(crate) fn list_games(games: &DashMap<Uuid, Game>) -> Vec<AdminGameResponse> {
+    let now = Instant::now();
+    let mut games: Vec<AdminGameResponse> = games
+        .iter()
+        .map(|game| AdminGameResponse::new(*game.key(), game.value(), now))
+        .collect();
+
+    games.sort_by_key(|game| Reverse(game.created_at));
+    games
+}
+
+/// Ends the game, wherever it is at (see [`Game::force_end`]). Returns `None` if there is no such game.
+pub(crate) fn end_game(games: &DashMap<Uuid, Game>, game_id: Uuid) -> Option<ManagerActionResult> {
+    let mut game = games.get_mut(&game_id)?;
+    let result = game.force_end();
+
+    if result.is_ok() {
+        info!(%game_id, "Game ended by an admin");
+    }
+    Some(result)
+}
+
+/// Removes the game, along with its code. Returns whether the game existed.
+pub(crate) fn delete_game(
+    games: &DashMap<Uuid, Game>,
+    game_codes: &DashMap<String, Uuid>,
+    game_id: Uuid,
+) -> bool {
+    match games.remove(&game_id) {
+        Some((_, game)) => {
+            if let Some(game_code) = game.code() {
+                game_codes.remove(game_code);
+            }
+            info!(%game_id, "Game deleted by an admin");
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request_types::CreateGameOptions;
+
+    #[test]
+    fn admin_secret_verify() {
+        let admin_secret = AdminSecret::new(Some("s3cret"));
+        assert!(admin_secret.is_enabled());
+        assert!(admin_secret.verify("s3cret"));
+        assert!(!admin_secret.verify("s3cre"));
+        assert!(!admin_secret.verify(""));
+
+        for admin_secret in [AdminSecret::new(None), AdminSecret::new(Some(""))] {
+            assert!(!admin_secret.is_enabled());
+            assert!(!admin_secret.verify(""));
+        }
+
+        let figment = Figment::from(rocket::Config::default()).merge(("admin_secret", "s3cret"));
+        assert!(AdminSecret::from_figment(&figment).verify("s3cret"));
+    }
+
+    #[test]
+    fn is_ready_once_lifted_off() {
+        let readiness = Readiness::default();
+        assert!(is_ready(&readiness).is_err());
+
+        readiness.0.store(true, Ordering::Relaxed);
+        assert!(is_ready(&readiness).is_ok());
+    }
+
+    #[test]
+    fn delete_game_and_code() {
+        let games = DashMap::new();
+        let game_codes = DashMap::new();
+        let game_id = Uuid::new_v4();
+        let mut game = Game::new(&CreateGameOptions::default());
+        game.set_code(String::from("ABCDEF"));
+        games.insert(game_id, game);
+        game_codes.insert(String::from("ABCDEF"), game_id);
+
+        assert!(delete_game(&games, &game_codes, game_id));
+        assert!(games.is_empty());
+        assert!(game_codes.is_empty());
+        assert!(!delete_game(&games, &game_codes, game_id));
+    }
+}
@@ -1,13 +1,20 @@
 //! All things related to authenticating incoming HTTP requests.
 
+use crate::bearer_token::{BearerTokenError, BearerTokenKey, AUTHORIZATION_BEARER_PREFIX};
+use crate::game_code::{GameCodeMapping, GameKey};
+
 use parse_display::{Display, FromStr};
 use rocket::http::{uri::Path, Cookie, CookieJar, Status};
 use rocket::request::{FromRequest, Outcome, Request};
+use tracing::error;
 use uuid::Uuid;
 
 /// The cookie's name for the [`Identifier`], which we authenticate against.
 pub(crate) const COOKIE_IDENTIFIER_NAME: &str = "identifier";
 
+/// The cookie's name for the [`SpectatorIdentifier`], which we authenticate spectators against.
+pub(crate) const COOKIE_SPECTATOR_IDENTIFIER_NAME: &str = "spectator_identifier";
+
 /// Identifier of the request, which is contained in a private cookie sent by the players.
 ///
 /// We make it contain both the `game_id` and the `player_id`, even though only the `player_id`
@@ -16,26 +23,68 @@ pub(crate) const COOKIE_IDENTIFIER_NAME: &str = "identifier";
 ///
 /// As the cookie hashes and encrypts the identifier, the mangling of the `game_id` results in
 /// different cookies across games.
+///
+/// The `session` is the player's session generation when the cookie was issued: revoking a player's
+/// sessions bumps their generation, which invalidates all previously issued cookies.
 #[derive(Clone, Copy, Debug, Display, FromStr, PartialEq)]
-#[display("{game_id}/{player_id}")]
+#[display("{game_id}/{player_id}/{session}")]
 pub(crate) struct Identifier {
     /// The game ID attached to the request.
     game_id: Uuid,
     /// The player ID attached to the request.
     player_id: usize,
+    /// The player's session generation attached to the request.
+    session: u32,
 }
 
 impl Identifier {
     /// Constructs an [`Identifier`].
-    pub fn new(game_id: Uuid, player_id: usize) -> Self {
-        Identifier { game_id, player_id }
+    pub fn new(game_id: Uuid, player_id: usize, session: u32) -> Self {
+        Identifier {
+            game_id,
+            player_id,
+            session,
+        }
+    }
+
+    /// Returns the player ID.
+    pub fn player_id(&self) -> usize {
+        self.player_id
+    }
+}
+
+/// Identifier of a spectator, which is contained in a private cookie sent by spectators.
+///
+/// Spectators are not seated in the game: this identifier is kept in a different cookie than the
+/// [`Identifier`], so that it can never be mistaken for a player's.
+#[derive(Clone, Copy, Debug, Display, FromStr, PartialEq)]
+#[display("{game_id}/{spectator_id}")]
+pub(crate) struct SpectatorIdentifier {
+    /// The game ID attached to the request.
+    game_id: Uuid,
+    /// The spectator ID attached to the request.
+    spectator_id: usize,
+}
+
+impl SpectatorIdentifier {
+    /// Constructs a [`SpectatorIdentifier`].
+    pub fn new(game_id: Uuid, spectator_id: usize) -> Self {
+        SpectatorIdentifier {
+            game_id,
+            spectator_id,
+        }
     }
 }
 
 /// Types of error when authenticating a request.
 #[derive(Debug, PartialEq)]
 pub enum AuthenticatorError {
+    /// Should never occur!
+    ///
+    /// This only happens if the [`BearerTokenKey`] is not managed by Rocket.
+    BearerTokenKeyNotFound,
     GameIdMismatch,
+    InvalidBearerToken(BearerTokenError),
     InvalidUrl,
     Unauthenticated,
     UnparsableCookie,
@@ -43,6 +92,10 @@ pub enum AuthenticatorError {
 
 /// Authenticates incoming HTTP requests, encapsulating the corresponding player and game IDs.
 ///
+/// Requests are authenticated either with the private cookie set when joining the game, or with a
+/// bearer token (see [`crate::bearer_token`]) in the `Authorization` header, which is more convenient
+/// for scripts and bots. When both are provided, the bearer token takes precedence.
+///
 /// Implements [`rocket::request::FromRequest`], so it can be used as a request guard.
 /// In fact, as there is no public constructor, an [`Authenticator`] can only be
 /// instantiated via a request guard.
@@ -54,12 +107,12 @@ pub(crate) struct Authenticator {
 impl Authenticator {
     /// Validates the given request.
     ///
-    /// If the request is authenticated, it returns the player's ID.
+    /// If the request is authenticated, it returns the corresponding [`Authenticator`].
     ///
     /// Otherwise, it returns `None`.
-    pub(crate) fn validate_and_get_player_id(cookies: &CookieJar, game_id: Uuid) -> Option<usize> {
+    pub(crate) fn validate(cookies: &CookieJar, game_id: Uuid) -> Option<Self> {
         match Self::authentication_outcome(cookies, game_id) {
-            Outcome::Success(authenticator) => Some(authenticator.player_id()),
+            Outcome::Success(authenticator) => Some(authenticator),
             _ => None,
         }
     }
@@ -87,6 +140,11 @@ impl Authenticator {
         &self.identifier.game_id
     }
 
+    /// Returns the session generation of the authenticated player, when the cookie was issued.
+    pub(crate) fn session(&self) -> u32 {
+        self.identifier.session
+    }
+
     fn authentication_outcome(
         cookies: &CookieJar,
         game_id: Uuid,
@@ -105,6 +163,38 @@ impl Authenticator {
             Outcome::Failure((Status::Unauthorized, AuthenticatorError::Unauthenticated))
         }
     }
+
+    fn bearer_authentication_outcome(
+        bearer_token_key: Option<&BearerTokenKey>,
+        authorization: &str,
+        game_id: Uuid,
+    ) -> Outcome<Self, AuthenticatorError> {
+        let bearer_token_key = match bearer_token_key {
+            Some(bearer_token_key) => bearer_token_key,
+            None => {
+                return Outcome::Failure((
+                    Status::InternalServerError,
+                    AuthenticatorError::BearerTokenKeyNotFound,
+                ))
+            }
+        };
+
+        let verified_identifier = authorization
+            .strip_prefix(AUTHORIZATION_BEARER_PREFIX)
+            .ok_or(BearerTokenError::Malformed)
+            .and_then(|token| bearer_token_key.verify(token.trim()));
+
+        match verified_identifier {
+            Ok(identifier) if identifier.game_id == game_id => {
+                Outcome::Success(Authenticator { identifier })
+            }
+            Ok(_) => Outcome::Failure((Status::Unauthorized, AuthenticatorError::GameIdMismatch)),
+            Err(e) => Outcome::Failure((
+                Status::Unauthorized,
+                AuthenticatorError::InvalidBearerToken(e),
+            )),
+        }
+    }
 }
 
 #[rocket::async_trait]
@@ -112,27 +202,128 @@ impl<'r> FromRequest<'r> for Authenticator {
     type Error = AuthenticatorError;
 
     async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
-        // The path should be `/game/<game_id>`.
-        if !req.uri().path().starts_with("/game/") {
-            eprintln!(
-                "The `Authenticator` is used as a request guard for a path `{}`,
-                which does not start with `/game/`.",
-                req.uri().path()
-            );
-            return Outcome::Failure((Status::InternalServerError, AuthenticatorError::InvalidUrl));
+        let game_id = match game_id_from_request(req) {
+            Ok(game_id) => game_id,
+            Err(e) => return Outcome::Failure(e),
+        };
+
+        match req.headers().get_one("Authorization") {
+            Some(authorization) => Self::bearer_authentication_outcome(
+                req.rocket().state::<BearerTokenKey>(),
+                authorization,
+                game_id,
+            ),
+            None => Self::authentication_outcome(req.cookies(), game_id),
+        }
+    }
+}
+
+/// Authenticates incoming HTTP requests from spectators, encapsulating the corresponding spectator and game IDs.
+///
+/// Implements [`rocket::request::FromRequest`], so it can be used as a request guard.
+/// In fact, as there is no public constructor, a [`SpectatorAuthenticator`] can only be
+/// instantiated via a request guard.
+#[derive(Debug, PartialEq)]
+pub(crate) struct SpectatorAuthenticator {
+    identifier: SpectatorIdentifier,
+}
+
+impl SpectatorAuthenticator {
+    /// Validates the given request.
+    ///
+    /// If the request comes from a spectator of the game, it returns the spectator's ID.
+    ///
+    /// Otherwise, it returns `None`.
+    pub(crate) fn validate_and_get_spectator_id(
+        cookies: &CookieJar,
+        game_id: Uuid,
+    ) -> Option<usize> {
+        match Self::authentication_outcome(cookies, game_id) {
+            Outcome::Success(authenticator) => Some(authenticator.identifier.spectator_id),
+            _ => None,
+        }
+    }
+
+    /// Given a [`SpectatorIdentifier`] and the game path, writes a new private cookie to authenticate
+    /// subsequent requests coming from this browser as a spectator.
+    pub(crate) fn authenticate(
+        cookies: &CookieJar,
+        game_path: &Path,
+        identifier: SpectatorIdentifier,
+    ) {
+        cookies.add_private(
+            Cookie::build(COOKIE_SPECTATOR_IDENTIFIER_NAME, identifier.to_string())
+                .path(game_path.to_string())
+                .finish(),
+        );
+    }
+
+    /// Returns the authenticated game ID.
+    pub(crate) fn game_id(&self) -> &Uuid {
+        &self.identifier.game_id
+    }
+
+    fn authentication_outcome(
+        cookies: &CookieJar,
+        game_id: Uuid,
+    ) -> Outcome<Self, AuthenticatorError> {
+        if let Some(identifier_cookie) = cookies.get_private(COOKIE_SPECTATOR_IDENTIFIER_NAME) {
+            match identifier_cookie.value().parse::<SpectatorIdentifier>() {
+                Ok(identifier) if identifier.game_id == game_id => {
+                    Outcome::Success(SpectatorAuthenticator { identifier })
+                }
+                Ok(_) => {
+                    Outcome::Failure((Status::Unauthorized, AuthenticatorError::GameIdMismatch))
+                }
+                _ => Outcome::Failure((Status::Unauthorized, AuthenticatorError::UnparsableCookie)),
+            }
+        } else {
+            Outcome::Failure((Status::Unauthorized, AuthenticatorError::Unauthenticated))
         }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for SpectatorAuthenticator {
+    type Error = AuthenticatorError;
 
-        match req.param::<Uuid>(1) {
-            Some(Ok(game_id)) => Self::authentication_outcome(req.cookies(), game_id),
-            _ => Outcome::Failure((Status::NotFound, AuthenticatorError::InvalidUrl)),
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        match game_id_from_request(req) {
+            Ok(game_id) => Self::authentication_outcome(req.cookies(), game_id),
+            Err(e) => Outcome::Failure(e),
         }
     }
 }
 
+/// Extracts the game ID from the request's path, which should be `/game/<game_id>/...`, after the mount point of the
+/// route (e.g. [`crate::router::API_BASE`]).
+///
+/// The path may hold the game's code instead of its ID (see [`crate::game_code`]).
+fn game_id_from_request(req: &Request<'_>) -> Result<Uuid, (Status, AuthenticatorError)> {
+    if req.routed_segment(0) != Some("game") {
+        error!(
+            path = %req.uri().path(),
+            "An authenticator is used as a request guard for a path which does not start with `/game/`."
+        );
+        return Err((Status::InternalServerError, AuthenticatorError::InvalidUrl));
+    }
+
+    let game_codes = req.rocket().state::<GameCodeMapping>();
+    match req.param::<GameKey>(1) {
+        Some(Ok(GameKey::Id(game_id))) => Ok(game_id),
+        Some(Ok(game_key)) => game_codes
+            .and_then(|game_codes| game_key.resolve(game_codes))
+            .ok_or((Status::NotFound, AuthenticatorError::InvalidUrl)),
+        _ => Err((Status::NotFound, AuthenticatorError::InvalidUrl)),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rocket::http::Header;
     use rocket::local::blocking::Client;
+    use std::time::Duration;
 
     type AsyncClient = rocket::local::asynchronous::Client;
 
@@ -146,6 +337,7 @@ mod tests {
         Identifier {
             player_id: 6,
             game_id: Uuid::new_v4(),
+            session: 2,
         }
     }
 
@@ -157,7 +349,10 @@ mod tests {
 
         assert_eq!(
             identifier.to_string(),
-            format!("{}/{}", identifier.game_id, identifier.player_id)
+            format!(
+                "{}/{}/{}",
+                identifier.game_id, identifier.player_id, identifier.session
+            )
         );
     }
 
@@ -171,6 +366,7 @@ mod tests {
             authenticator.player_id(),
             authenticator.identifier.player_id
         );
+        assert_eq!(authenticator.session(), authenticator.identifier.session);
     }
 
     #[test]
@@ -182,7 +378,7 @@ mod tests {
             Authenticator::authentication_outcome(&client.cookies(), game_id),
             Outcome::Failure((Status::Unauthorized, AuthenticatorError::Unauthenticated))
         );
-        assert!(Authenticator::validate_and_get_player_id(&client.cookies(), game_id).is_none());
+        assert!(Authenticator::validate(&client.cookies(), game_id).is_none());
     }
 
     #[test]
@@ -199,7 +395,7 @@ mod tests {
             outcome.failed(),
             Some((Status::Unauthorized, AuthenticatorError::UnparsableCookie))
         );
-        assert!(Authenticator::validate_and_get_player_id(&client.cookies(), game_id).is_none());
+        assert!(Authenticator::validate(&client.cookies(), game_id).is_none());
     }
 
     #[test]
@@ -217,7 +413,7 @@ mod tests {
             outcome.failed(),
             Some((Status::Unauthorized, AuthenticatorError::GameIdMismatch))
         );
-        assert!(Authenticator::validate_and_get_player_id(cookies, wrong_game_id).is_none());
+        assert!(Authenticator::validate(cookies, wrong_game_id).is_none());
     }
 
     #[test]
@@ -232,7 +428,7 @@ mod tests {
         let outcome = Authenticator::authentication_outcome(cookies, identifier.game_id);
         assert_eq!(outcome.succeeded(), Some(Authenticator { identifier }));
         assert_eq!(
-            Authenticator::validate_and_get_player_id(cookies, identifier.game_id),
+            Authenticator::validate(cookies, identifier.game_id).map(|auth| auth.player_id()),
             Some(identifier.player_id)
         );
     }
@@ -261,6 +457,47 @@ mod tests {
         assert_eq!(authenticated_cookie.path(), Some(path.as_str()));
     }
 
+    // Tests for `SpectatorAuthenticator`.
+
+    #[test]
+    fn spectator_authenticator_authentication_outcome() {
+        let client = Client::tracked(rocket::build()).expect("valid rocket");
+        let req = client.get("/");
+        let Identifier {
+            game_id, player_id, ..
+        } = new_identifier();
+        let identifier = SpectatorIdentifier::new(game_id, player_id);
+
+        // A player's cookie does not authenticate a spectator.
+        let req = req.private_cookie(Cookie::new(
+            COOKIE_IDENTIFIER_NAME,
+            Identifier::new(game_id, player_id, 0).to_string(),
+        ));
+        assert_eq!(
+            SpectatorAuthenticator::authentication_outcome(req.inner().cookies(), game_id).failed(),
+            Some((Status::Unauthorized, AuthenticatorError::Unauthenticated))
+        );
+
+        let req = req.private_cookie(Cookie::new(
+            COOKIE_SPECTATOR_IDENTIFIER_NAME,
+            identifier.to_string(),
+        ));
+        let cookies = req.inner().cookies();
+
+        assert_eq!(
+            SpectatorAuthenticator::authentication_outcome(cookies, Uuid::new_v4()).failed(),
+            Some((Status::Unauthorized, AuthenticatorError::GameIdMismatch))
+        );
+        assert_eq!(
+            SpectatorAuthenticator::authentication_outcome(cookies, game_id).succeeded(),
+            Some(SpectatorAuthenticator { identifier })
+        );
+        assert_eq!(
+            SpectatorAuthenticator::validate_and_get_spectator_id(cookies, game_id),
+            Some(player_id)
+        );
+    }
+
     #[rocket::async_test]
     async fn authenticator_from_request_invalid_path() {
         let client = AsyncClient::tracked(rocket::build())
@@ -322,4 +559,70 @@ mod tests {
         let outcome = Authenticator::from_request(req.inner()).await;
         assert_eq!(outcome.succeeded(), Some(Authenticator { identifier }));
     }
+
+    #[rocket::async_test]
+    async fn authenticator_from_request_bearer_token() {
+        let bearer_token_key = BearerTokenKey::new(b"secret", Duration::from_secs(60));
+        let identifier = new_identifier();
+        let bearer_token = bearer_token_key.issue(identifier);
+
+        let client = AsyncClient::tracked(rocket::build().manage(bearer_token_key))
+            .await
+            .expect("valid rocket");
+        let path = format!("/game/{}", identifier.game_id);
+
+        let req = client.get(&path).header(Header::new(
+            "Authorization",
+            format!("Bearer {}", bearer_token.token),
+        ));
+        let outcome = Authenticator::from_request(req.inner()).await;
+        assert_eq!(outcome.succeeded(), Some(Authenticator { identifier }));
+
+        // The token is only valid for its game.
+        let req = client
+            .get(format!("/game/{}", Uuid::new_v4()))
+            .header(Header::new(
+                "Authorization",
+                format!("Bearer {}", bearer_token.token),
+            ));
+        let outcome = Authenticator::from_request(req.inner()).await;
+        assert_eq!(
+            outcome.failed(),
+            Some((Status::Unauthorized, AuthenticatorError::GameIdMismatch))
+        );
+
+        // The bearer token takes precedence over the cookie.
+        let req = client
+            .get(&path)
+            .header(Header::new("Authorization", "Basic abc"))
+            .private_cookie(Cookie::new(COOKIE_IDENTIFIER_NAME, identifier.to_string()));
+        let outcome = Authenticator::from_request(req.inner()).await;
+        assert_eq!(
+            outcome.failed(),
+            Some((
+                Status::Unauthorized,
+                AuthenticatorError::InvalidBearerToken(BearerTokenError::Malformed)
+            ))
+        );
+    }
+
+    #[rocket::async_test]
+    async fn authenticator_from_request_bearer_token_key_not_found() {
+        let client = AsyncClient::tracked(rocket::build())
+            .await
+            .expect("valid rocket");
+        let identifier = new_identifier();
+        let req = client
+            .get(format!("/game/{}", identifier.game_id))
+            .header(Header::new("Authorization", "Bearer abc.def"));
+
+        let outcome = Authenticator::from_request(req.inner()).await;
+        assert_eq!(
+            outcome.failed(),
+            Some((
+                Status::InternalServerError,
+                AuthenticatorError::BearerTokenKeyNotFound
+            ))
+        );
+    }
 }
@@ -0,0 +1,229 @@
+//! Bearer tokens: an alternative to the private cookie, for scripts and bots.
+//!
+//! A token is made of two base64url-encoded parts, separated by a dot: the claims, and their signature.
+//! The claims are the [`Identifier`] the token authenticates, along with when the token expires
+//! (`<identifier>/<expires_at>`, in seconds since the UNIX epoch). The signature is an HMAC-SHA256 of the
+//! claims, with a secret key only known to the server.
+
+use crate::authenticator::Identifier;
+use crate::signing;
+use crate::unix_time::unix_now;
+
+use rocket::figment::Figment;
+use std::time::Duration;
+
+/// Prefix of the `Authorization` header carrying a bearer token.
+pub(crate) const AUTHORIZATION_BEARER_PREFIX: &str = "Bearer ";
+
+/// How long a bearer token is valid for, unless configured otherwise (see [`BearerTokenKey::from_figment`]).
+pub(crate) const DEFAULT_BEARER_TOKEN_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Types of error when verifying a bearer token.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BearerTokenError {
+    Expired,
+    InvalidSignature,
+    Malformed,
+}
+
+/// A freshly issued bearer token.
+#[derive(Debug, PartialEq)]
+pub(crate) struct BearerToken {
+    /// The token, to send in the `Authorization: Bearer <token>` header.
+    pub token: String,
+    /// When the token expires, in seconds since the UNIX epoch.
+    pub expires_at: u64,
+}
+
+/// Issues and verifies bearer tokens.
+///
+/// It is managed by Rocket, so that the [`crate::authenticator::Authenticator`] request guard can verify tokens.
+pub struct BearerTokenKey {
+    secret: Vec<u8>,
+    ttl: Duration,
+}
+
+impl BearerTokenKey {
+    /// Creates a key from the given secret, issuing tokens valid for `ttl`.
+    pub fn new(secret: &[u8], ttl: Duration) -> Self {
+        Self {
+            secret: secret.to_vec(),
+            ttl,
+        }
+    }
+
+    /// Creates a key from Rocket's configuration:
+    ///   * `bearer_token_secret`: the secret used to sign tokens. If not configured, a random secret is
+    ///     generated, meaning that tokens do not survive a restart of the server.
+    ///   * `bearer_token_ttl`: how long tokens are valid for, in seconds (see [`DEFAULT_BEARER_TOKEN_TTL`]).
+    pub fn from_figment(figment: &Figment) -> Self {
+        let secret = match figment.extract_inner::<String>("bearer_token_secret") {
+            Ok(secret) if !secret.is_empty() => secret.into_bytes(),
+            _ => signing::random_key(),
+        };
+        let ttl = figment
+            .extract_inner::<u64>("bearer_token_ttl")
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_BEARER_TOKEN_TTL);
+
+        Self::new(&secret, ttl)
+    }
+
+    /// Issues a token authenticating the given [`Identifier`].
+    pub(crate) fn issue(&self, identifier: Identifier) -> BearerToken {
+        self.issue_at(identifier, unix_now())
+    }
+
+    /// Verifies the token, and returns the [`Identifier`] it authenticates.
+    pub(crate) fn verify(&self, token: &str) -> Result<Identifier, BearerTokenError> {
+        self.verify_at(token, unix_now())
+    }
+
+    fn issue_at(&self, identifier: Identifier, now: u64) -> BearerToken {
+        let expires_at = now + self.ttl.as_secs();
+        let claims = format!("{}/{}", identifier, expires_at);
+
+        let signature = signing::sign(&self.secret, claims.as_bytes());
+
+        BearerToken {
+            token: format!(
+                "{}.{}",
+                base64::encode_config(&claims, base64::URL_SAFE_NO_PAD),
+                base64::encode_config(signature, base64::URL_SAFE_NO_PAD)
+            ),
+            expires_at,
+        }
+    }
+
+    fn verify_at(&self, token: &str, now: u64) -> Result<Identifier, BearerTokenError> {
+        let (claims, signature) = token.split_once('.').ok_or(BearerTokenError::Malformed)?;
+        let claims = base64::decode_config(claims, base64::URL_SAFE_NO_PAD)
+            .map_err(|_| BearerTokenError::Malformed)?;
+        let signature = base64::decode_config(signature, base64::URL_SAFE_NO_PAD)
+            .map_err(|_| BearerTokenError::Malformed)?;
+
+        // The signature is checked first, so that nothing is parsed out of forged claims.
+        if !signing::verify(&self.secret, &claims, &signature) {
+            return Err(BearerTokenError::InvalidSignature);
+        }
+
+        let claims = String::from_utf8(claims).map_err(|_| BearerTokenError::Malformed)?;
+        let (identifier, expires_at) =
+            claims.rsplit_once('/').ok_or(BearerTokenError::Malformed)?;
+        let expires_at: u64 = expires_at
+            .parse()
+            .map_err(|_| BearerTokenError::Malformed)?;
+
+        if expires_at <= now {
+            return Err(BearerTokenError::Expired);
+        }
+
+        identifier.parse().map_err(|_| BearerTokenError::Malformed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn new_key() -> BearerTokenKey {
+        BearerTokenKey::new(b"secret", Duration::from_secs(60))
+    }
+
+    fn new_identifier() -> Identifier {
+        Identifier::new(Uuid::new_v4(), 3, 1)
+    }
+
+    #[test]
+    fn bearer_token_round_trip() {
+        let key = new_key();
+        let identifier = new_identifier();
+
+        let bearer_token = key.issue_at(identifier, NOW);
+        assert_eq!(bearer_token.expires_at, NOW + 60);
+        assert_eq!(key.verify_at(&bearer_token.token, NOW), Ok(identifier));
+        assert_eq!(key.verify_at(&bearer_token.token, NOW + 59), Ok(identifier));
+        assert_eq!(key.verify(&key.issue(identifier).token), Ok(identifier));
+    }
+
+    #[test]
+    fn bearer_token_expired() {
+        let key = new_key();
+        let bearer_token = key.issue_at(new_identifier(), NOW);
+
+        assert_eq!(
+            key.verify_at(&bearer_token.token, NOW + 60),
+            Err(BearerTokenError::Expired)
+        );
+    }
+
+    #[test]
+    fn bearer_token_invalid_signature() {
+        let key = new_key();
+        let identifier = new_identifier();
+        let bearer_token = key.issue_at(identifier, NOW);
+
+        // Signed with another secret.
+        let other_key = BearerTokenKey::new(b"other secret", Duration::from_secs(60));
+        assert_eq!(
+            other_key.verify_at(&bearer_token.token, NOW),
+            Err(BearerTokenError::InvalidSignature)
+        );
+
+        // Claims tampered with, to act as another player.
+        let (_, signature) = bearer_token.token.split_once('.').unwrap();
+        let forged_claims = base64::encode_config(
+            format!("{}/{}", Identifier::new(Uuid::new_v4(), 0, 1), NOW + 60),
+            base64::URL_SAFE_NO_PAD,
+        );
+        assert_eq!(
+            key.verify_at(&format!("{}.{}", forged_claims, signature), NOW),
+            Err(BearerTokenError::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn bearer_token_malformed() {
+        let key = new_key();
+
+        assert_eq!(
+            key.verify_at("not a token", NOW),
+            Err(BearerTokenError::Malformed)
+        );
+        assert_eq!(
+            key.verify_at("not.base64!", NOW),
+            Err(BearerTokenError::Malformed)
+        );
+        assert_eq!(key.verify_at("", NOW), Err(BearerTokenError::Malformed));
+    }
+
+    #[test]
+    fn bearer_token_key_from_figment() {
+        let figment = Figment::from(rocket::Config::default())
+            .merge(("bearer_token_secret", "secret"))
+            .merge(("bearer_token_ttl", 60));
+        let key = BearerTokenKey::from_figment(&figment);
+        let identifier = new_identifier();
+
+        assert_eq!(key.ttl, Duration::from_secs(60));
+        assert_eq!(
+            new_key().verify_at(&key.issue_at(identifier, NOW).token, NOW),
+            Ok(identifier)
+        );
+
+        // Without configuration, the secret is random.
+        let figment = Figment::from(rocket::Config::default());
+        let key = BearerTokenKey::from_figment(&figment);
+        let other_key = BearerTokenKey::from_figment(&figment);
+
+        assert_eq!(key.ttl, DEFAULT_BEARER_TOKEN_TTL);
+        assert_eq!(key.secret.len(), signing::KEY_LEN);
+        assert_eq!(
+            other_key.verify_at(&key.issue_at(identifier, NOW).token, NOW),
+            Err(BearerTokenError::InvalidSignature)
+        );
+    }
+}
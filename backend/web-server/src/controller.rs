@@ -1,19 +1,75 @@
 //! The middleman between the [`crate::router`] handlers, [`Authenticator`], and [`ticket_to_ride::manager::Manager`].
 
-use crate::authenticator::{Authenticator, AuthenticatorError, Identifier};
+use crate::authenticator::{
+    Authenticator, AuthenticatorError, Identifier, SpectatorAuthenticator, SpectatorIdentifier,
+};
+use crate::bearer_token::BearerTokenKey;
+use crate::game::{Game, GameMode};
+use crate::game_code::assign_game_code;
+use crate::limits::GameLimits;
+use crate::logging::{controller_span, GameTracer};
+use crate::rate_limit::{self, RateLimitScope};
 use crate::request_types::*;
 use crate::response_types::*;
+use crate::snapshot::{Drain, InFlight};
+use crate::webhook::WebhookDispatcher;
 
 use dashmap::{mapref::one::Ref, mapref::one::RefMut, DashMap};
-use rocket::http::{uri::Origin, CookieJar, Status};
+use rocket::http::{
+    uri::{Origin, Path},
+    CookieJar, Status,
+};
 use rocket::request::{FromRequest, Outcome, Request};
-use rocket::State;
+use rocket::tokio::{select, time};
+use rocket::{Shutdown, State};
+use std::cmp::Reverse;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, error, info, Span};
 use uuid::Uuid;
 
-use ticket_to_ride::manager::{GameState, Manager};
+use ticket_to_ride::manager::{GameState, Manager, ManagerActionResult};
 
-/// Maps a game ID to a manager in a shared concurrent hash map.
-pub type GameIdManagerMapping = DashMap<Uuid, Manager>;
+/// Maps a game ID to a game (and its manager) in a shared concurrent hash map.
+///
+/// The map is shared between request handlers, and background tasks (see [`enforce_time_controls`]).
+pub type GameIdManagerMapping = Arc<DashMap<Uuid, Game>>;
+
+/// Name of the query parameter with which a request acts as a given seat.
+///
+/// Only hot-seat games allow acting as another seat than the authenticated one.
+const SEAT_QUERY_PARAMETER: &str = "seat";
+
+/// How often games are checked for players running out of time.
+const TIME_CONTROLS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Background task that plays on behalf of players running out of time, until the server shuts down.
+///
+/// Only the games whose current player ran out of time are locked, one at a time, such that request handlers are not
+/// held up. More details in [`Game::enforce_time_controls`].
+pub(crate) async fn enforce_time_controls(games: GameIdManagerMapping, mut shutdown: Shutdown) {
+    let mut interval = time::interval(TIME_CONTROLS_INTERVAL);
+
+    loop {
+        select! {
+            _ = interval.tick() => {},
+            _ = &mut shutdown => break,
+        };
+
+        let timed_out_game_ids: Vec<Uuid> = games
+            .iter()
+            .filter(|game| game.manager().has_timed_out())
+            .map(|game| *game.key())
+            .collect();
+
+        for game_id in timed_out_game_ids {
+            // The game may have been deleted in the meantime.
+            if let Some(mut game) = games.get_mut(&game_id) {
+                game.enforce_time_controls();
+            }
+        }
+    }
+}
 
 /// Types of error when creating a controller.
 #[derive(Debug)]
@@ -25,6 +81,39 @@ pub enum ControllerGuardError {
     /// guard against the [`GameIdManagerMapping`] state.
     StateNotFound,
     AuthenticatorFailed(AuthenticatorError),
+    /// The `seat` query parameter is not a valid seat number.
+    InvalidSeat,
+    /// The session does not control the requested seat.
+    SeatNotControlled,
+    /// The session was revoked by the host (see [`Game::revoke_sessions`]).
+    SessionRevoked,
+    /// The player took too many actions lately (see [`crate::rate_limit`]).
+    RateLimited,
+    /// The server is shutting down, and no longer accepts player actions (see [`crate::snapshot`]).
+    ShuttingDown,
+}
+
+/// Why a visitor could not get seated in a game.
+#[derive(Debug, PartialEq)]
+pub enum SeatingError {
+    /// The game is private, and the visitor did not provide the right invite code or password.
+    InvitationRequired,
+    /// The game does not let new players in (e.g. it is full, locked, or has started).
+    NoSeat,
+}
+
+/// Returns the player a session acts as, once it is checked that the session was not revoked.
+fn acting_player_id(
+    game: &Game,
+    authenticator: &Authenticator,
+    requested_seat: Option<usize>,
+) -> Result<usize, (Status, ControllerGuardError)> {
+    if game.session(authenticator.player_id()) != Some(authenticator.session()) {
+        return Err((Status::Unauthorized, ControllerGuardError::SessionRevoked));
+    }
+
+    game.acting_seat(authenticator.player_id(), requested_seat)
+        .ok_or((Status::Forbidden, ControllerGuardError::SeatNotControlled))
 }
 
 /// Main entrypoint of read-only requests to the server, after routing.
@@ -38,21 +127,75 @@ pub enum ControllerGuardError {
 /// Implements [`rocket::request::FromRequest`], so it can be used as a request guard.
 /// In fact, as there is no public constructor, it can only be instantiated via a request guard.
 pub struct ReadController<'a> {
-    /// Shared reference to the game ID, and to the [`Manager`] of that game.
-    game_id_and_manager: Ref<'a, Uuid, Manager>,
+    /// Shared reference to the game ID, and to that [`Game`].
+    game_id_and_game: Ref<'a, Uuid, Game>,
     /// The player initiating the read-only request.
     player_id: usize,
 }
 
 impl<'a> ReadController<'a> {
     #[inline]
-    fn manager(&self) -> &Manager {
-        self.game_id_and_manager.value()
+    fn game(&self) -> &Game {
+        self.game_id_and_game.value()
     }
 
     #[inline]
     pub(crate) fn get_game_state(&self) -> GameState {
-        self.manager().get_state(self.player_id)
+        self.game().get_state(self.player_id)
+    }
+
+    /// Issues a bearer token for the player, as an alternative to their cookie.
+    pub(crate) fn issue_bearer_token(
+        &self,
+        bearer_token_key: &BearerTokenKey,
+    ) -> BearerTokenResponse {
+        let game = self.game();
+        let session = game.session(self.player_id).unwrap_or_default();
+        let bearer_token = bearer_token_key.issue(Identifier::new(
+            *self.game_id_and_game.key(),
+            self.player_id,
+            session,
+        ));
+
+        BearerTokenResponse::new(self.player_id, bearer_token)
+    }
+
+    #[inline]
+    pub(crate) fn game_id(&self) -> Uuid {
+        *self.game_id_and_game.key()
+    }
+
+    #[inline]
+    pub(crate) fn get_game_code(&self) -> String {
+        self.game()
+            .code()
+            .expect("Every game hosted by the server has a code")
+            .to_owned()
+    }
+
+    #[inline]
+    pub(crate) fn get_rejoin_token(&self) -> Uuid {
+        self.game()
+            .rejoin_token(self.player_id)
+            .expect("Every seated player has a rejoin token")
+    }
+
+    /// Returns the invite code to share with other players, if the game is protected by one.
+    #[inline]
+    pub(crate) fn get_invite_code(&self) -> Option<String> {
+        self.game().invite_code().map(String::from)
+    }
+
+    /// Lists the games listed publicly, from newest to oldest.
+    pub(crate) fn list_public_games(state: &DashMap<Uuid, Game>) -> Vec<PublicGameResponse> {
+        let mut public_games: Vec<PublicGameResponse> = state
+            .iter()
+            .filter(|game| game.is_public())
+            .map(|game| PublicGameResponse::new(*game.key(), game.value()))
+            .collect();
+
+        public_games.sort_by_key(|public_game| Reverse(public_game.created_at));
+        public_games
     }
 }
 
@@ -70,12 +213,24 @@ impl<'a> Controller<'a> for ReadController<'a> {
     fn controller_from_request_internal(
         game_id_manager_mapping: &'a State<GameIdManagerMapping>,
         authenticator: Authenticator,
+        requested_seat: Option<usize>,
+        route: &str,
     ) -> Outcome<Self, ControllerGuardError> {
         match game_id_manager_mapping.get(authenticator.game_id()) {
-            Some(game_id_and_manager) => Outcome::Success(Self {
-                game_id_and_manager,
-                player_id: authenticator.player_id(),
-            }),
+            Some(game_id_and_game) => {
+                match acting_player_id(&game_id_and_game, &authenticator, requested_seat) {
+                    Ok(player_id) => {
+                        let span = controller_span(route, *game_id_and_game.key(), player_id);
+                        debug!(parent: &span, "Read");
+
+                        Outcome::Success(Self {
+                            game_id_and_game,
+                            player_id,
+                        })
+                    }
+                    Err(e) => Outcome::Failure(e),
+                }
+            }
             None => Outcome::Failure((Status::NotFound, ControllerGuardError::InvalidGameId)),
         }
     }
@@ -92,58 +247,326 @@ impl<'a> Controller<'a> for ReadController<'a> {
 /// Implements [`rocket::request::FromRequest`], so it can be used as a request guard.
 /// In fact, as there is no public constructor, it can only be instantiated via a request guard.
 pub struct WriteController<'a> {
-    /// Mutable reference to the game ID, and to the [`Manager`] of that game.
-    game_id_and_manager: RefMut<'a, Uuid, Manager>,
+    /// Mutable reference to the game ID, and to that [`Game`].
+    game_id_and_game: RefMut<'a, Uuid, Game>,
     /// The player initiating the write request.
     player_id: usize,
+    /// Span of the request (see [`controller_span`]), in which the outcome of actions is logged.
+    span: Span,
+    /// Keeps the server from shutting down until the request is handled (see [`Drain`]).
+    _in_flight: Option<InFlight<'a>>,
 }
 
 impl<'a> WriteController<'a> {
     #[inline]
-    fn manager(&mut self) -> &mut Manager {
-        self.game_id_and_manager.value_mut()
+    fn game(&mut self) -> &mut Game {
+        self.game_id_and_game.value_mut()
+    }
+
+    /// Takes an action on the [`Manager`] as the acting player.
+    ///
+    /// If the action succeeds, spectators get to see its outcome (once the spectator delay elapsed).
+    fn act<F>(&mut self, action: F) -> ActionResponse
+    where
+        F: FnOnce(&mut Manager, usize) -> ManagerActionResult,
+    {
+        let player_id = self.player_id;
+        let game = self.game();
+
+        let result = action(game.manager_mut(), player_id);
+        if result.is_ok() {
+            game.record_spectator_snapshot();
+        }
+
+        self.respond(result)
     }
 
-    pub(crate) fn create_game(state: &DashMap<Uuid, Manager>) -> Uuid {
+    /// Logs the outcome of the action in the span of the request, and responds with it.
+    fn respond(&self, result: ManagerActionResult) -> ActionResponse {
+        match &result {
+            Ok(()) => info!(parent: &self.span, outcome = "success", "Action"),
+            Err(e) => info!(parent: &self.span, outcome = "failure", error = %e, "Action"),
+        }
+
+        ActionResponse::new(result)
+    }
+
+    /// Creates a game, and returns its ID, along with the invite code visitors have to provide to get seated, if it
+    /// is protected by one (see [`Game::invite_code`]).
+    ///
+    /// The game is also assigned a short code, which identifies it alongside its ID (see [`crate::game_code`]).
+    /// Fails if the server already hosts as many games as it can (see [`GameLimits::reserve_capacity`]).
+    pub(crate) fn create_game(
+        state: &DashMap<Uuid, Game>,
+        game_codes: &DashMap<String, Uuid>,
+        limits: &GameLimits,
+        webhook_dispatcher: &WebhookDispatcher,
+        create_game_options: &CreateGameOptions,
+    ) -> Result<(Uuid, Option<String>), String> {
+        let _reservation = limits.reserve_capacity(state)?;
         let game_id = Uuid::new_v4();
 
-        state.insert(game_id, Manager::new());
+        let mut game = Game::new(create_game_options);
+        game.set_code(assign_game_code(game_codes, game_id));
+        let invite_code = game.invite_code().map(String::from);
+        game.enable_webhooks(game_id, webhook_dispatcher);
+        game.manager_mut()
+            .add_observer(Box::new(GameTracer::new(game_id)));
+        info!(%game_id, game_code = game.code(), mode = ?game.mode(), "Game created");
+        state.insert(game_id, game);
 
-        game_id
+        Ok((game_id, invite_code))
     }
 
+    /// Seats the player loading the game, unless they are already authenticated.
+    ///
+    /// In hot-seat games, only the first session to load the game gets seated: it then controls every seat.
+    /// In private games, the player has to provide the invite code or password (see [`Game::admits`]).
     pub(crate) fn load_game(
-        mut manager: RefMut<'a, Uuid, Manager>,
+        mut game: RefMut<'a, Uuid, Game>,
         cookies: &CookieJar,
         origin: &Origin,
+        invitation: Option<&str>,
+    ) -> Result<(), SeatingError> {
+        let game_id = game.key().clone();
+        let game = game.value_mut();
+
+        if let Some(authenticator) = Authenticator::validate(cookies, game_id) {
+            // Players whose session was revoked are treated as new visitors.
+            if game.session(authenticator.player_id()) == Some(authenticator.session()) {
+                game.touch();
+                return Ok(());
+            }
+        }
+
+        let identifier = Self::seat_new_player(game, game_id, invitation)?;
+        Authenticator::authenticate(cookies, &origin.path(), identifier);
+        Ok(())
+    }
+
+    /// Seats the player in the fullest public game with an open seat (see [`Game::has_open_seat`]), the oldest one
+    /// amongst equally full games. If there is none, creates a public game instead, with the default options.
+    ///
+    /// Returns the ID of the game, in which the player gets seated by [`WriteController::load_game`]. `game_uri`
+    /// returns the URI of a game, to which the player's cookie is scoped.
+    ///
+    /// Fails if a game has to be created, but the server already hosts as many games as it can.
+    pub(crate) fn quick_match(
+        state: &'a DashMap<Uuid, Game>,
+        game_codes: &DashMap<String, Uuid>,
+        limits: &GameLimits,
+        webhook_dispatcher: &WebhookDispatcher,
+        cookies: &CookieJar,
+        game_uri: impl Fn(Uuid) -> Origin<'static>,
+    ) -> Result<Uuid, String> {
+        let mut candidates: Vec<(Uuid, usize, u64)> = state
+            .iter()
+            .filter(|game| game.is_public() && !game.is_private() && game.has_open_seat())
+            .map(|game| (*game.key(), game.manager().num_players(), game.created_at()))
+            .collect();
+        candidates.sort_by_key(|(_, num_players, created_at)| (Reverse(*num_players), *created_at));
+
+        // Games may have filled up since they were listed, in which case the next one is tried.
+        for (game_id, _, _) in candidates {
+            if let Some(game) = state.get_mut(&game_id) {
+                if Self::load_game(game, cookies, &game_uri(game_id), None).is_ok() {
+                    return Ok(game_id);
+                }
+            }
+        }
+
+        let (game_id, _) = Self::create_game(
+            state,
+            game_codes,
+            limits,
+            webhook_dispatcher,
+            &CreateGameOptions {
+                public: true,
+                ..Default::default()
+            },
+        )?;
+        Ok(game_id)
+    }
+
+    /// Seats a new player authenticating with a bearer token, rather than a cookie (e.g. a bot).
+    ///
+    /// In private games, the player has to provide the invite code or password (see [`Game::admits`]).
+    pub(crate) fn join_game(
+        mut game: RefMut<'a, Uuid, Game>,
+        bearer_token_key: &BearerTokenKey,
+        invitation: Option<&str>,
+    ) -> Result<BearerTokenResponse, SeatingError> {
+        let game_id = *game.key();
+        let identifier = Self::seat_new_player(game.value_mut(), game_id, invitation)?;
+
+        Ok(BearerTokenResponse::new(
+            identifier.player_id(),
+            bearer_token_key.issue(identifier),
+        ))
+    }
+
+    /// Adds a player to the game, if it lets new players in, and returns how to identify them.
+    ///
+    /// Nobody gets seated without the invitation of a private game, nor while the host keeps the lobby locked.
+    /// In hot-seat games, only the first player gets seated: their session then controls every seat.
+    fn seat_new_player(
+        game: &mut Game,
+        game_id: Uuid,
+        invitation: Option<&str>,
+    ) -> Result<Identifier, SeatingError> {
+        if !game.admits(invitation) {
+            return Err(SeatingError::InvitationRequired);
+        }
+        if game.is_locked() {
+            return Err(SeatingError::NoSeat);
+        }
+        if game.mode() == GameMode::HotSeat && game.manager().num_players() > 0 {
+            return Err(SeatingError::NoSeat);
+        }
+
+        let player_id = game.add_player().ok_or(SeatingError::NoSeat)?;
+        let session = game.session(player_id).unwrap_or_default();
+        info!(%game_id, player_id, "Player seated");
+
+        Ok(Identifier::new(game_id, player_id, session))
+    }
+
+    /// Seats the visitor back at the seat the rejoin token belongs to, whichever device they use.
+    ///
+    /// Returns whether the rejoin token is valid. The cookie is scoped to the whole game (`game_path`).
+    pub(crate) fn rejoin_game(
+        game: RefMut<'a, Uuid, Game>,
+        cookies: &CookieJar,
+        game_path: &Path,
+        rejoin_token: &Uuid,
     ) -> bool {
-        let game_id = manager.key().clone();
-        let manager = manager.value_mut();
+        match game.seat_of_rejoin_token(rejoin_token) {
+            Some((player_id, session)) => {
+                info!(game_id = %game.key(), player_id, session, "Player rejoined");
+                Authenticator::authenticate(
+                    cookies,
+                    game_path,
+                    Identifier::new(*game.key(), player_id, session),
+                );
+                true
+            }
+            None => false,
+        }
+    }
 
-        if Authenticator::validate_and_get_player_id(cookies, game_id).is_some() {
-            return true;
+    /// Lets the visitor spectate the game, unless they already are a spectator.
+    ///
+    /// The spectator cookie is scoped to the whole game (`game_path`), like the players' cookie.
+    pub(crate) fn spectate_game(
+        mut game: RefMut<'a, Uuid, Game>,
+        cookies: &CookieJar,
+        game_path: &Path,
+    ) {
+        let game_id = *game.key();
+
+        if SpectatorAuthenticator::validate_and_get_spectator_id(cookies, game_id).is_some() {
+            return;
         }
 
-        let player_id = match manager.add_player() {
-            Some(player_id) => player_id,
-            None => return false,
-        };
+        let spectator_id = game.value_mut().add_spectator();
 
-        Authenticator::authenticate(cookies, &origin.path(), Identifier::new(game_id, player_id));
-        true
+        SpectatorAuthenticator::authenticate(
+            cookies,
+            game_path,
+            SpectatorIdentifier::new(game_id, spectator_id),
+        );
     }
 
     #[inline]
-    pub(crate) fn change_player_name(
+    pub(crate) fn regenerate_rejoin_token(&mut self, seat_request: SeatRequest) -> ActionResponse {
+        let player_id = self.player_id;
+        let result = self
+            .game()
+            .regenerate_rejoin_token(player_id, seat_request.player_id);
+
+        self.respond(result)
+    }
+
+    #[inline]
+    pub(crate) fn revoke_sessions(&mut self, seat_request: SeatRequest) -> ActionResponse {
+        let player_id = self.player_id;
+        let result = self
+            .game()
+            .revoke_sessions(player_id, seat_request.player_id);
+
+        self.respond(result)
+    }
+
+    #[inline]
+    pub(crate) fn kick_player(&mut self, seat_request: SeatRequest) -> ActionResponse {
+        let player_id = self.player_id;
+        let result = self.game().kick_player(player_id, seat_request.player_id);
+
+        self.respond(result)
+    }
+
+    #[inline]
+    pub(crate) fn leave_game(&mut self) -> ActionResponse {
+        let player_id = self.player_id;
+        let result = self.game().leave(player_id);
+
+        self.respond(result)
+    }
+
+    #[inline]
+    pub(crate) fn lock_lobby(&mut self, lock_lobby_request: LockLobbyRequest) -> ActionResponse {
+        let player_id = self.player_id;
+        let result = self
+            .game()
+            .lock_lobby(player_id, lock_lobby_request.is_locked);
+
+        self.respond(result)
+    }
+
+    #[inline]
+    pub(crate) fn set_seating_order(
         &mut self,
-        change_name_request: ChangeNameRequest,
+        set_seating_order_request: SetSeatingOrderRequest,
     ) -> ActionResponse {
         let player_id = self.player_id;
+        let result = self
+            .game()
+            .set_seating_order(player_id, set_seating_order_request.seating_order);
 
-        ActionResponse::new(
-            self.manager()
-                .change_player_name(player_id, change_name_request.new_name),
-        )
+        self.respond(result)
+    }
+
+    #[inline]
+    pub(crate) fn force_start(&mut self) -> ActionResponse {
+        let player_id = self.player_id;
+        let result = self.game().force_start(player_id);
+
+        self.respond(result)
+    }
+
+    #[inline]
+    pub(crate) fn add_seat(&mut self) -> ActionResponse {
+        let result = self.game().add_seat();
+
+        self.respond(result)
+    }
+
+    #[inline]
+    pub(crate) fn pass_device(&mut self) -> ActionResponse {
+        let player_id = self.player_id;
+        let result = self.game().pass_device(player_id);
+
+        self.respond(result)
+    }
+
+    #[inline]
+    pub(crate) fn change_player_name(
+        &mut self,
+        change_name_request: ChangeNameRequest,
+    ) -> ActionResponse {
+        self.act(|manager, player_id| {
+            manager.change_player_name(player_id, change_name_request.new_name)
+        })
     }
 
     #[inline]
@@ -151,12 +574,9 @@ impl<'a> WriteController<'a> {
         &mut self,
         change_color_request: ChangeColorRequest,
     ) -> ActionResponse {
-        let player_id = self.player_id;
-
-        ActionResponse::new(
-            self.manager()
-                .change_player_color(player_id, change_color_request.new_color),
-        )
+        self.act(|manager, player_id| {
+            manager.change_player_color(player_id, change_color_request.new_color)
+        })
     }
 
     #[inline]
@@ -164,12 +584,9 @@ impl<'a> WriteController<'a> {
         &mut self,
         set_player_ready_request: SetPlayerReadyRequest,
     ) -> ActionResponse {
-        let player_id = self.player_id;
-
-        ActionResponse::new(
-            self.manager()
-                .set_ready(player_id, set_player_ready_request.is_ready),
-        )
+        self.act(|manager, player_id| {
+            manager.set_ready(player_id, set_player_ready_request.is_ready)
+        })
     }
 
     #[inline]
@@ -177,19 +594,17 @@ impl<'a> WriteController<'a> {
         &mut self,
         select_destination_cards_request: SelectDestinationCardsRequest,
     ) -> ActionResponse {
-        let player_id = self.player_id;
-
-        ActionResponse::new(self.manager().select_destination_cards(
-            player_id,
-            select_destination_cards_request.destination_cards_decisions,
-        ))
+        self.act(|manager, player_id| {
+            manager.select_destination_cards(
+                player_id,
+                select_destination_cards_request.destination_cards_decisions,
+            )
+        })
     }
 
     #[inline]
     pub(crate) fn draw_destination_cards(&mut self) -> ActionResponse {
-        let player_id = self.player_id;
-
-        ActionResponse::new(self.manager().draw_destination_cards(player_id))
+        self.act(|manager, player_id| manager.draw_destination_cards(player_id))
     }
 
     #[inline]
@@ -197,31 +612,61 @@ impl<'a> WriteController<'a> {
         &mut self,
         draw_open_train_card_request: DrawOpenTrainCardRequest,
     ) -> ActionResponse {
-        let player_id = self.player_id;
-
-        ActionResponse::new(
-            self.manager()
-                .draw_open_train_card(player_id, draw_open_train_card_request.card_index),
-        )
+        self.act(|manager, player_id| {
+            manager.draw_open_train_card(player_id, draw_open_train_card_request.card_index)
+        })
     }
 
     #[inline]
     pub(crate) fn draw_close_train_card(&mut self) -> ActionResponse {
+        self.act(|manager, player_id| manager.draw_close_train_card(player_id))
+    }
+
+    #[inline]
+    pub(crate) fn claim_route(&mut self, claim_route_request: ClaimRouteRequest) -> ActionResponse {
+        self.act(|manager, player_id| {
+            manager.claim_route(
+                player_id,
+                claim_route_request.route,
+                claim_route_request.parallel_route_index,
+                claim_route_request.cards,
+            )
+        })
+    }
+
+    #[inline]
+    pub(crate) fn register_webhook(
+        &mut self,
+        register_webhook_request: RegisterWebhookRequest,
+    ) -> Result<WebhookResponse, String> {
         let player_id = self.player_id;
 
-        ActionResponse::new(self.manager().draw_close_train_card(player_id))
+        self.game()
+            .register_webhook(player_id, &register_webhook_request.url)
+            .map(|secret| WebhookResponse { secret })
     }
 
     #[inline]
-    pub(crate) fn claim_route(&mut self, claim_route_request: ClaimRouteRequest) -> ActionResponse {
+    pub(crate) fn unregister_webhook(&mut self) -> ActionResponse {
         let player_id = self.player_id;
+        let result = self.game().unregister_webhook(player_id);
 
-        ActionResponse::new(self.manager().claim_route(
-            player_id,
-            claim_route_request.route,
-            claim_route_request.parallel_route_index,
-            claim_route_request.cards,
-        ))
+        self.respond(result)
+    }
+
+    #[inline]
+    pub(crate) fn request_undo(&mut self) -> ActionResponse {
+        self.act(|manager, player_id| manager.request_undo(player_id))
+    }
+
+    #[inline]
+    pub(crate) fn answer_undo_request(
+        &mut self,
+        answer_undo_request: AnswerUndoRequest,
+    ) -> ActionResponse {
+        self.act(|manager, player_id| {
+            manager.answer_undo_request(player_id, answer_undo_request.approve)
+        })
     }
 }
 
@@ -229,9 +674,34 @@ impl<'a> WriteController<'a> {
 impl<'a> FromRequest<'a> for WriteController<'a> {
     type Error = ControllerGuardError;
 
-    #[inline]
+    /// Write requests are rate limited, per player and per IP address, before locking the game. They are refused
+    /// once the server is shutting down.
     async fn from_request(request: &'a Request<'_>) -> Outcome<Self, Self::Error> {
-        Self::controller_from_request(request).await
+        if let Outcome::Failure((status, _)) =
+            rate_limit::limit_request(request, RateLimitScope::Action).await
+        {
+            return Outcome::Failure((status, ControllerGuardError::RateLimited));
+        }
+
+        let in_flight = match request.rocket().state::<Drain>() {
+            Some(drain) => match drain.enter() {
+                Some(in_flight) => Some(in_flight),
+                None => {
+                    return Outcome::Failure((
+                        Status::ServiceUnavailable,
+                        ControllerGuardError::ShuttingDown,
+                    ))
+                }
+            },
+            None => None,
+        };
+
+        Self::controller_from_request(request)
+            .await
+            .map(|write_controller| Self {
+                _in_flight: in_flight,
+                ..write_controller
+            })
     }
 }
 
@@ -239,39 +709,140 @@ impl<'a> Controller<'a> for WriteController<'a> {
     fn controller_from_request_internal(
         game_id_manager_mapping: &'a State<GameIdManagerMapping>,
         authenticator: Authenticator,
+        requested_seat: Option<usize>,
+        route: &str,
     ) -> Outcome<Self, ControllerGuardError> {
         match game_id_manager_mapping.get_mut(authenticator.game_id()) {
-            Some(game_id_and_manager) => Outcome::Success(Self {
-                game_id_and_manager,
-                player_id: authenticator.player_id(),
-            }),
+            Some(game_id_and_game) => {
+                match acting_player_id(&game_id_and_game, &authenticator, requested_seat) {
+                    Ok(player_id) => {
+                        let span = controller_span(route, *game_id_and_game.key(), player_id);
+
+                        Outcome::Success(Self {
+                            game_id_and_game,
+                            player_id,
+                            span,
+                            _in_flight: None,
+                        })
+                    }
+                    Err(e) => Outcome::Failure(e),
+                }
+            }
             None => Outcome::Failure((Status::NotFound, ControllerGuardError::InvalidGameId)),
         }
     }
 }
 
+/// Entrypoint of spectators' requests to the server, after routing.
+///
+/// Spectators are not seated in the game: they only get to see the public game state,
+/// delayed as configured when creating the game (see [`Game::get_spectator_state`]).
+///
+/// Implements [`rocket::request::FromRequest`], so it can be used as a request guard.
+/// In fact, as there is no public constructor, it can only be instantiated via a request guard.
+pub struct SpectatorController<'a> {
+    /// Shared reference to the game ID, and to that [`Game`].
+    game_id_and_game: Ref<'a, Uuid, Game>,
+}
+
+impl<'a> SpectatorController<'a> {
+    #[inline]
+    pub(crate) fn get_spectator_state(&self) -> String {
+        self.game_id_and_game
+            .value()
+            .get_spectator_state()
+            .to_string()
+    }
+
+    /// Releases the game, and returns its ID.
+    ///
+    /// Long-lived requests must not hold onto the game, which would block players' actions.
+    #[inline]
+    pub(crate) fn into_game_id(self) -> Uuid {
+        *self.game_id_and_game.key()
+    }
+
+    /// Retrieves the spectator state of the given game, if it still exists.
+    pub(crate) fn get_spectator_state_of(
+        state: &GameIdManagerMapping,
+        game_id: &Uuid,
+    ) -> Option<String> {
+        state
+            .get(game_id)
+            .map(|game| game.get_spectator_state().to_string())
+    }
+}
+
+#[rocket::async_trait]
+impl<'a> FromRequest<'a> for SpectatorController<'a> {
+    type Error = ControllerGuardError;
+
+    async fn from_request(request: &'a Request<'_>) -> Outcome<Self, Self::Error> {
+        let authenticator = match request.guard::<SpectatorAuthenticator>().await {
+            Outcome::Success(authenticator) => authenticator,
+            Outcome::Failure((status, e)) => {
+                return Outcome::Failure((status, ControllerGuardError::AuthenticatorFailed(e)))
+            }
+            Outcome::Forward(_) => unreachable!("The authenticator should never forward."),
+        };
+
+        match request.guard::<&'a State<GameIdManagerMapping>>().await {
+            Outcome::Success(game_id_manager_mapping) => {
+                match game_id_manager_mapping.get(authenticator.game_id()) {
+                    Some(game_id_and_game) => Outcome::Success(Self { game_id_and_game }),
+                    None => {
+                        Outcome::Failure((Status::NotFound, ControllerGuardError::InvalidGameId))
+                    }
+                }
+            }
+            _ => Outcome::Failure((
+                Status::InternalServerError,
+                ControllerGuardError::StateNotFound,
+            )),
+        }
+    }
+}
+
 #[rocket::async_trait]
 trait Controller<'a>: Sized {
     fn controller_from_request_internal(
         game_id_manager_mapping: &'a State<GameIdManagerMapping>,
         authenticator: Authenticator,
+        requested_seat: Option<usize>,
+        route: &str,
     ) -> Outcome<Self, ControllerGuardError>;
 
     async fn controller_from_request(
         request: &'a Request<'_>,
     ) -> Outcome<Self, ControllerGuardError> {
-        match request.guard::<Authenticator>().await {
+        let requested_seat = match request.query_value::<usize>(SEAT_QUERY_PARAMETER) {
+            Some(Ok(seat)) => Some(seat),
+            Some(Err(_)) => {
+                return Outcome::Failure((Status::BadRequest, ControllerGuardError::InvalidSeat))
+            }
+            None => None,
+        };
+
+        let route = request
+            .route()
+            .and_then(|route| route.name.as_deref())
+            .unwrap_or_default();
+
+        let outcome = match request.guard::<Authenticator>().await {
             Outcome::Success(authenticator) => {
                 match request.guard::<&'a State<GameIdManagerMapping>>().await {
                     Outcome::Success(game_id_manager_mapping) => {
                         Self::controller_from_request_internal(
                             game_id_manager_mapping,
                             authenticator,
+                            requested_seat,
+                            route,
                         )
                     }
                     _ => {
-                        eprintln!(
-                            "No `State<GameIdManagerMapping>` is set for the given handler.
+                        error!(
+                            route,
+                            "No `State<GameIdManagerMapping>` is set for the given handler. \
                             Consider adding `ReadController` or `WriteController` as a request guard."
                         );
 
@@ -286,6 +857,11 @@ trait Controller<'a>: Sized {
                 Outcome::Failure((status, ControllerGuardError::AuthenticatorFailed(e)))
             }
             Outcome::Forward(_) => unreachable!("The authenticator should never forward."),
+        };
+
+        if let Outcome::Failure((status, e)) = &outcome {
+            debug!(route, status = status.code, error = ?e, "Request rejected");
         }
+        outcome
     }
 }
@@ -0,0 +1,284 @@
+//! The frontend app, as served to browsers: its pages (see [`crate::router::root`]), and its static files (see
+//! [`crate::router::static_file`]).
+//!
+//! With the `embed-frontend` feature, the frontend build is embedded into the binary at compile time, such that the
+//! server ships as a single executable. Otherwise, or if `frontend_dir` is configured, the frontend is read from a
+//! directory (see [`Frontend::from_figment`]).
+//!
+//! Files are served with their content type, guessed from their extension, and with an `ETag` header, such that
+//! browsers can revalidate them cheaply (see [`FrontendFile`]). Static files fingerprinted by the frontend build are
+//! cached for good instead (see [`is_fingerprinted`]).
+
+use rocket::figment::Figment;
+use rocket::http::{ContentType, Header, Status};
+use rocket::request::Request;
+use rocket::response::{self, Responder, Response};
+use rocket::tokio::fs;
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::fmt::Write;
+use std::io::{self, Cursor};
+use std::path::{Path, PathBuf};
+
+/// Directory of the frontend build in this repository, served unless configured otherwise.
+#[cfg(any(test, not(feature = "embed-frontend")))]
+pub(crate) const DEFAULT_FRONTEND_DIR: &str =
+    concat!(env!("CARGO_MANIFEST_DIR"), "/../../frontend/build");
+
+/// Directory of the static files, within the frontend build.
+const STATIC_DIR: &str = "static";
+
+/// Pages, and static files which are not fingerprinted, are revalidated on every load, such that a new frontend is
+/// picked up as soon as it is deployed.
+const REVALIDATED_CACHE_CONTROL: &str = "no-cache";
+
+/// Fingerprinted static files never change: a new frontend build refers to new files instead.
+const FINGERPRINTED_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+/// The frontend build, embedded into the binary at compile time.
+#[cfg(feature = "embed-frontend")]
+#[derive(rust_embed::RustEmbed)]
+#[folder = "../../frontend/build"]
+struct EmbeddedFrontend;
+
+/// Where the frontend is served from.
+///
+/// Managed by Rocket.
+#[derive(Debug, PartialEq)]
+pub enum Frontend {
+    /// The frontend build embedded into the binary (see the `embed-frontend` feature).
+    #[cfg(feature = "embed-frontend")]
+    Embedded,
+    /// A frontend build directory.
+    Directory(PathBuf),
+}
+
+impl Frontend {
+    /// Reads where to serve the frontend from in Rocket's configuration: `frontend_dir`, a frontend build directory.
+    ///
+    /// If not configured (or empty), the frontend embedded into the binary is served, if any, and the frontend build
+    /// of this repository otherwise (see [`DEFAULT_FRONTEND_DIR`]).
+    pub fn from_figment(figment: &Figment) -> Self {
+        let frontend_dir = figment
+            .extract_inner::<PathBuf>("frontend_dir")
+            .ok()
+            .filter(|frontend_dir| !frontend_dir.as_os_str().is_empty());
+
+        match frontend_dir {
+            Some(frontend_dir) => Self::Directory(frontend_dir),
+            #[cfg(feature = "embed-frontend")]
+            None => Self::Embedded,
+            #[cfg(not(feature = "embed-frontend"))]
+            None => Self::Directory(PathBuf::from(DEFAULT_FRONTEND_DIR)),
+        }
+    }
+
+    /// Returns the page with the given file name, at the root of the frontend build (e.g. `index.html`).
+    pub(crate) async fn page(&self, name: &str) -> io::Result<FrontendFile> {
+        self.file(Path::new(name), REVALIDATED_CACHE_CONTROL).await
+    }
+
+    /// Returns the static file at the given path, relative to the static files directory.
+    ///
+    /// The path must not escape that directory: Rocket sanitizes paths taken from requests accordingly.
+    pub(crate) async fn static_file(&self, path: &Path) -> io::Result<FrontendFile> {
+        let cache_control = if is_fingerprinted(path) {
+            FINGERPRINTED_CACHE_CONTROL
+        } else {
+            REVALIDATED_CACHE_CONTROL
+        };
+
+        self.file(&Path::new(STATIC_DIR).join(path), cache_control)
+            .await
+    }
+
+    async fn file(&self, path: &Path, cache_control: &'static str) -> io::Result<FrontendFile> {
+        let content_type = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(ContentType::from_extension)
+            .unwrap_or(ContentType::Binary);
+
+        let (data, hash) = match self {
+            #[cfg(feature = "embed-frontend")]
+            Self::Embedded => {
+                // Embedded files are keyed by their path, with forward slashes whatever the platform.
+                let key: Vec<&str> = path
+                    .components()
+                    .filter_map(|component| component.as_os_str().to_str())
+                    .collect();
+                let file = EmbeddedFrontend::get(&key.join("/")).ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::NotFound, "No such embedded file")
+                })?;
+
+                (file.data, file.metadata.sha256_hash())
+            }
+            Self::Directory(frontend_dir) => {
+                let data = fs::read(frontend_dir.join(path)).await?;
+                let hash = Sha256::digest(&data).into();
+
+                (Cow::Owned(data), hash)
+            }
+        };
+
+        Ok(FrontendFile {
+            content_type,
+            data,
+            etag: etag(&hash),
+            cache_control,
+        })
+    }
+}
+
+/// Whether the name of the file holds a hash of its content, as the frontend build names the files it generates
+/// (e.g. `js/main.1a2b3c4d.js`, or `js/787.1a2b3c4d.chunk.js`). Files copied as is (e.g. `favicon.ico`) are not.
+fn is_fingerprinted(path: &Path) -> bool {
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+    let segments: Vec<&str> = name.split('.').collect();
+
+    segments.len() > 2
+        && segments[1..segments.len() - 1]
+            .iter()
+            .any(|segment| segment.len() == 8 && segment.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Returns the entity tag of a file, from its SHA-256 hash.
+fn etag(hash: &[u8; 32]) -> String {
+    // Half the hash is plenty to tell versions of a file apart.
+    let mut etag = String::from("\"");
+    for byte in &hash[..16] {
+        let _ = write!(etag, "{:02x}", byte);
+    }
+    etag.push('"');
+    etag
+}
+
+/// A file of the frontend, as served to browsers.
+///
+/// Responds with `304 Not Modified` if the browser already holds this version of the file (i.e. it sent its entity
+/// tag in the `If-None-Match` header).
+#[derive(Debug)]
+pub struct FrontendFile {
+    content_type: ContentType,
+    data: Cow<'static, [u8]>,
+    etag: String,
+    cache_control: &'static str,
+}
+
+impl<'r> Responder<'r, 'static> for FrontendFile {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        let not_modified = req
+            .headers()
+            .get("If-None-Match")
+            .flat_map(|etags| etags.split(','))
+            .any(|etag| {
+                let etag = etag.trim();
+                etag == "*" || etag.trim_start_matches("W/") == self.etag
+            });
+
+        let mut response = Response::build();
+        response
+            .header(Header::new("ETag", self.etag))
+            .header(Header::new("Cache-Control", self.cache_control));
+
+        if not_modified {
+            response.status(Status::NotModified);
+        } else {
+            response
+                .header(self.content_type)
+                .sized_body(self.data.len(), Cursor::new(self.data));
+        }
+        response.ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn frontend_from_figment() {
+        let figment = Figment::from(rocket::Config::default());
+        #[cfg(feature = "embed-frontend")]
+        assert_eq!(Frontend::from_figment(&figment), Frontend::Embedded);
+        #[cfg(not(feature = "embed-frontend"))]
+        assert_eq!(
+            Frontend::from_figment(&figment),
+            Frontend::Directory(PathBuf::from(DEFAULT_FRONTEND_DIR))
+        );
+
+        let figment = figment.merge(("frontend_dir", "/srv/frontend"));
+        assert_eq!(
+            Frontend::from_figment(&figment),
+            Frontend::Directory(PathBuf::from("/srv/frontend"))
+        );
+    }
+
+    #[test]
+    fn frontend_directory_files() {
+        let frontend_dir = std::env::temp_dir().join(format!("frontend-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(frontend_dir.join(STATIC_DIR)).unwrap();
+        std::fs::write(frontend_dir.join("index.html"), "<html></html>").unwrap();
+        std::fs::write(frontend_dir.join(STATIC_DIR).join("app.js"), "let a;").unwrap();
+        std::fs::write(
+            frontend_dir.join(STATIC_DIR).join("app.0123abcd.js"),
+            "let a;",
+        )
+        .unwrap();
+        let frontend = Frontend::Directory(frontend_dir.clone());
+
+        rocket::async_test(async {
+            let page = frontend.page("index.html").await.unwrap();
+            assert_eq!(page.content_type, ContentType::HTML);
+            assert_eq!(page.data.as_ref(), b"<html></html>");
+            assert_eq!(page.cache_control, REVALIDATED_CACHE_CONTROL);
+            assert_eq!(page.etag.len(), 34);
+
+            let file = frontend.static_file(Path::new("app.js")).await.unwrap();
+            assert_eq!(file.content_type, ContentType::JavaScript);
+            assert_eq!(file.cache_control, REVALIDATED_CACHE_CONTROL);
+            assert_ne!(file.etag, page.etag);
+
+            let file = frontend
+                .static_file(Path::new("app.0123abcd.js"))
+                .await
+                .unwrap();
+            assert_eq!(file.cache_control, FINGERPRINTED_CACHE_CONTROL);
+
+            let err = frontend
+                .static_file(Path::new("nope.js"))
+                .await
+                .unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        });
+
+        std::fs::remove_dir_all(frontend_dir).unwrap();
+    }
+
+    #[test]
+    fn frontend_fingerprinted_files() {
+        for path in [
+            "js/main.1a2b3c4d.js",
+            "js/787.1a2b3c4d.chunk.js",
+            "css/main.0f9e8d7c.css",
+            "media/logo.6ce24c58.svg",
+        ] {
+            assert!(is_fingerprinted(Path::new(path)), "{}", path);
+        }
+
+        for path in [
+            "favicon.ico",
+            "manifest.json",
+            "js/main.js",
+            "deadbeef.png",
+            "js/main.not-hash.js",
+            "js/main.1a2b3c.js",
+        ] {
+            assert!(!is_fingerprinted(Path::new(path)), "{}", path);
+        }
+    }
+}
@@ -0,0 +1,1261 @@
+//! A game hosted by the server: its [`Manager`], along with how players are seated around it,
+//! and what spectators get to see.
+
+use crate::game_code::generate_code;
+use crate::request_types::CreateGameOptions;
+use crate::signing;
+use crate::unix_time::unix_now;
+use crate::webhook::{Webhook, WebhookDispatcher, Webhooks};
+
+use rocket::serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use ticket_to_ride::clock::ClockSnapshot;
+use ticket_to_ride::event::GameEvent;
+use ticket_to_ride::manager::{
+    GamePhase, GameState, Manager, ManagerActionResult, SeatingOrder, MAX_PLAYERS,
+};
+use uuid::Uuid;
+
+/// Upper bound on the delay spectators can be kept behind the game.
+pub const MAX_SPECTATOR_DELAY: Duration = Duration::from_secs(10 * 60);
+
+/// Number of characters in an invite code.
+const INVITE_CODE_LEN: usize = 6;
+
+/// How players connect to a game.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GameMode {
+    /// Every player joins from their own browser, which controls a single seat.
+    Online,
+    /// Players share a single device: the browser that created the game controls every seat.
+    HotSeat,
+}
+
+/// What visitors have to provide to get seated in a game.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(crate = "rocket::serde", rename_all = "snake_case")]
+enum Admission {
+    /// Anyone who knows the game ID can take a seat.
+    Open,
+    /// A short code generated by the server, which players share with those they invite.
+    ///
+    /// Codes are case-insensitive, and stored in upper case.
+    InviteCode(String),
+    /// A password chosen by the player who created the game, of which only a salted hash is kept.
+    Password(PasswordHash),
+}
+
+impl Admission {
+    fn new(options: &CreateGameOptions) -> Self {
+        match (&options.password, options.private) {
+            (Some(password), _) => Self::Password(PasswordHash::new(password)),
+            (None, true) => Self::InviteCode(generate_code(INVITE_CODE_LEN)),
+            (None, false) => Self::Open,
+        }
+    }
+}
+
+/// The salted hash of a game's password, such that the password is neither kept in memory, nor persisted (see
+/// [`GameSnapshot`]), in the clear. Both are base64url-encoded.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(crate = "rocket::serde")]
+struct PasswordHash {
+    salt: String,
+    hash: String,
+}
+
+impl PasswordHash {
+    fn new(password: &str) -> Self {
+        let salt = signing::random_key();
+        let hash = signing::sign(&salt, password.as_bytes());
+
+        Self {
+            salt: base64::encode_config(salt, base64::URL_SAFE_NO_PAD),
+            hash: base64::encode_config(hash, base64::URL_SAFE_NO_PAD),
+        }
+    }
+
+    /// Whether the given password is the one hashed. Hashes are compared in constant time.
+    fn verify(&self, password: &str) -> bool {
+        let salt = base64::decode_config(&self.salt, base64::URL_SAFE_NO_PAD);
+        let hash = base64::decode_config(&self.hash, base64::URL_SAFE_NO_PAD);
+
+        match (salt, hash) {
+            (Ok(salt), Ok(hash)) => signing::verify(&salt, password.as_bytes(), &hash),
+            _ => false,
+        }
+    }
+}
+
+/// The seat which last confirmed holding the device, in a hot-seat game.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct DeviceHolder {
+    seat: usize,
+    /// The turn at which the seat got the device. Once the turn changes, the device has to be passed again.
+    turn: Option<usize>,
+}
+
+/// How a seat is reclaimed, and which of its sessions are still valid.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(crate = "rocket::serde")]
+struct SeatSessions {
+    /// Secret token with which the seat can be rejoined from any device.
+    rejoin_token: Uuid,
+    /// Generation of the seat's sessions: only cookies issued for the current generation are valid.
+    generation: u32,
+}
+
+impl SeatSessions {
+    fn new() -> Self {
+        Self {
+            rejoin_token: Uuid::new_v4(),
+            generation: 0,
+        }
+    }
+}
+
+/// The public game state, as it was at some point in time.
+struct SpectatorSnapshot {
+    recorded_at: Instant,
+    /// The serialized [`GameState`], without any private player state.
+    state: String,
+}
+
+/// Everything needed to restore a game once the server restarted, serializable in JSON (see [`crate::snapshot`]).
+///
+/// The [`Manager`] is replayed from its events (see [`Manager::replay`]), and its clock restored (see
+/// [`Manager::restore_clock`]). Spectators are shown the state they were shown before, until the delay elapses
+/// again, and the players of hot-seat games pass the device again.
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct GameSnapshot {
+    events: Vec<GameEvent>,
+    hot_seat: bool,
+    host: Option<usize>,
+    locked: bool,
+    seat_sessions: HashMap<usize, SeatSessions>,
+    spectator_delay: Duration,
+    /// The serialized public state spectators were shown.
+    spectator_state: String,
+    num_spectators: usize,
+    clock: Option<ClockSnapshot>,
+    webhooks: HashMap<usize, Webhook>,
+    public: bool,
+    admission: Admission,
+    code: Option<String>,
+    created_at: u64,
+}
+
+/// Everything the server keeps track of for a single game.
+pub struct Game {
+    manager: Manager,
+    mode: GameMode,
+    /// Only set in hot-seat games, once a seat confirmed it holds the device.
+    device_holder: Option<DeviceHolder>,
+    /// The first player to join the game, who runs the lobby and can manage the other seats' sessions.
+    host: Option<usize>,
+    /// Whether the host keeps new players out of the lobby.
+    locked: bool,
+    /// Rejoin token and session generation of every seat, by player ID.
+    seat_sessions: HashMap<usize, SeatSessions>,
+    /// How far behind the game spectators are.
+    spectator_delay: Duration,
+    /// Public states recorded after every change to the game, from oldest to newest.
+    ///
+    /// Only the states spectators may still be shown (given the delay) are kept around.
+    spectator_snapshots: VecDeque<SpectatorSnapshot>,
+    /// Number of spectators that loaded the game so far, which is also the next spectator ID.
+    num_spectators: usize,
+    /// Webhooks registered by players, notified when it becomes their turn (see [`Game::enable_webhooks`]).
+    webhooks: Webhooks,
+    /// Whether the game is listed publicly, for anyone to join.
+    public: bool,
+    /// What visitors have to provide to get seated.
+    admission: Admission,
+    /// Short code identifying the game, alongside its ID (see [`crate::game_code`]).
+    code: Option<String>,
+    /// When the game was created, in seconds since the UNIX epoch.
+    created_at: u64,
+    /// When the game last changed, or was last loaded by a player.
+    last_activity: Instant,
+}
+
+impl Game {
+    /// Creates a game, without any players.
+    pub fn new(options: &CreateGameOptions) -> Self {
+        let mut game = Self {
+            manager: Manager::new(),
+            mode: if options.hot_seat {
+                GameMode::HotSeat
+            } else {
+                GameMode::Online
+            },
+            device_holder: None,
+            host: None,
+            locked: false,
+            seat_sessions: HashMap::new(),
+            spectator_delay: options.spectator_delay.min(MAX_SPECTATOR_DELAY),
+            spectator_snapshots: VecDeque::new(),
+            num_spectators: 0,
+            webhooks: Webhooks::default(),
+            public: options.public,
+            admission: Admission::new(options),
+            code: None,
+            created_at: unix_now(),
+            last_activity: Instant::now(),
+        };
+
+        game.manager
+            .set_abandoned_routes(options.abandoned_routes)
+            .expect("A new game is in the lobby");
+        game.manager
+            .set_time_controls(options.time_controls)
+            .expect("A new game is in the lobby");
+        game.record_spectator_snapshot();
+        game
+    }
+
+    /// Restores a game from its snapshot (see [`Game::snapshot`]).
+    ///
+    /// Returns an `Err` if its events cannot be replayed. Otherwise, returns the game, without any observers: as
+    /// when creating a game, they have to be added again (e.g. [`Game::enable_webhooks`]).
+    pub fn restore(snapshot: GameSnapshot) -> Result<Self, String> {
+        let mut manager = Manager::replay(&snapshot.events)?;
+        if let Some(clock) = &snapshot.clock {
+            manager.restore_clock(clock);
+        }
+
+        let mut game = Self {
+            manager,
+            mode: if snapshot.hot_seat {
+                GameMode::HotSeat
+            } else {
+                GameMode::Online
+            },
+            device_holder: None,
+            host: snapshot.host,
+            locked: snapshot.locked,
+            seat_sessions: snapshot.seat_sessions,
+            spectator_delay: snapshot.spectator_delay.min(MAX_SPECTATOR_DELAY),
+            spectator_snapshots: VecDeque::new(),
+            num_spectators: snapshot.num_spectators,
+            webhooks: Webhooks::restore(snapshot.webhooks),
+            public: snapshot.public,
+            admission: snapshot.admission,
+            code: snapshot.code,
+            created_at: snapshot.created_at,
+            last_activity: Instant::now(),
+        };
+
+        // Spectators must not catch up with the game right away.
+        let now = Instant::now();
+        game.spectator_snapshots.push_back(SpectatorSnapshot {
+            recorded_at: now,
+            state: snapshot.spectator_state,
+        });
+        game.record_spectator_snapshot_at(now);
+        Ok(game)
+    }
+
+    /// Returns everything needed to restore the game once the server restarted (see [`Game::restore`]).
+    pub fn snapshot(&self) -> GameSnapshot {
+        GameSnapshot {
+            events: self.manager.events().to_vec(),
+            hot_seat: self.mode == GameMode::HotSeat,
+            host: self.host,
+            locked: self.locked,
+            seat_sessions: self.seat_sessions.clone(),
+            spectator_delay: self.spectator_delay,
+            spectator_state: self.get_spectator_state().to_owned(),
+            num_spectators: self.num_spectators,
+            clock: self.manager.clock_snapshot(),
+            webhooks: self.webhooks.snapshot(),
+            public: self.public,
+            admission: self.admission.clone(),
+            code: self.code.clone(),
+            created_at: self.created_at,
+        }
+    }
+
+    #[inline]
+    pub fn manager(&self) -> &Manager {
+        &self.manager
+    }
+
+    #[inline]
+    pub fn manager_mut(&mut self) -> &mut Manager {
+        &mut self.manager
+    }
+
+    #[inline]
+    pub fn mode(&self) -> GameMode {
+        self.mode
+    }
+
+    /// Whether the game is listed publicly, for anyone to join.
+    #[inline]
+    pub fn is_public(&self) -> bool {
+        self.public
+    }
+
+    /// When the game was created, in seconds since the UNIX epoch.
+    #[inline]
+    pub fn created_at(&self) -> u64 {
+        self.created_at
+    }
+
+    /// When the game last changed, or was last loaded by a player (see [`Game::touch`]).
+    #[inline]
+    pub fn last_activity(&self) -> Instant {
+        self.last_activity
+    }
+
+    /// Records some activity in the game, which keeps it from expiring (see [`crate::limits`]).
+    ///
+    /// Every change to the game counts as activity: changes spectators can see are recorded as such by
+    /// [`Game::record_spectator_snapshot`], and the others (e.g. locking the lobby) touch the game themselves.
+    #[inline]
+    pub fn touch(&mut self) {
+        self.last_activity = Instant::now();
+    }
+
+    /// Returns the short code identifying the game, once it was assigned one.
+    #[inline]
+    pub fn code(&self) -> Option<&str> {
+        self.code.as_deref()
+    }
+
+    /// Sets the short code identifying the game (see [`crate::game_code::assign_game_code`]).
+    #[inline]
+    pub fn set_code(&mut self, code: String) {
+        self.code = Some(code);
+    }
+
+    /// Whether visitors have to provide an invite code or password to get seated.
+    #[inline]
+    pub fn is_private(&self) -> bool {
+        self.admission != Admission::Open
+    }
+
+    /// Returns the invite code players can share, if the game is protected by one.
+    ///
+    /// Passwords are never handed out: players share them however they see fit.
+    pub fn invite_code(&self) -> Option<&str> {
+        match &self.admission {
+            Admission::InviteCode(invite_code) => Some(invite_code),
+            _ => None,
+        }
+    }
+
+    /// Whether a visitor providing the given invite code or password (if any) may get seated.
+    pub fn admits(&self, invitation: Option<&str>) -> bool {
+        match (&self.admission, invitation) {
+            (Admission::Open, _) => true,
+            (Admission::InviteCode(invite_code), Some(invitation)) => {
+                invitation.trim().eq_ignore_ascii_case(invite_code)
+            }
+            (Admission::Password(password_hash), Some(invitation)) => {
+                password_hash.verify(invitation)
+            }
+            (_, None) => false,
+        }
+    }
+
+    /// How far behind the game spectators are.
+    #[inline]
+    pub fn spectator_delay(&self) -> Duration {
+        self.spectator_delay
+    }
+
+    /// Whether anyone loading the game gets a seat of their own: the game is still in the lobby, which is neither
+    /// locked nor full, and players join from their own browser.
+    pub fn has_open_seat(&self) -> bool {
+        self.mode == GameMode::Online
+            && !self.locked
+            && self.manager.phase() == GamePhase::InLobby
+            && self.manager.num_players() < MAX_PLAYERS
+    }
+
+    /// Returns the seat a session acts as, if it is allowed to.
+    ///
+    /// By default, a session acts as the player it authenticated as. A session can ask to act as another
+    /// seat, which is only allowed in hot-seat games, where a single session controls every seat.
+    pub fn acting_seat(
+        &self,
+        session_player_id: usize,
+        requested_seat: Option<usize>,
+    ) -> Option<usize> {
+        match (self.mode, requested_seat) {
+            (_, None) => Some(session_player_id),
+            (GameMode::Online, Some(seat)) if seat == session_player_id => Some(seat),
+            (GameMode::Online, Some(_)) => None,
+            (GameMode::HotSeat, Some(seat)) if self.manager.has_player(seat) => Some(seat),
+            (GameMode::HotSeat, Some(_)) => None,
+        }
+    }
+
+    /// Adds a player to the game, if possible. See [`Manager::add_player`].
+    ///
+    /// The first player to join becomes the host.
+    pub fn add_player(&mut self) -> Option<usize> {
+        let player_id = self.manager.add_player()?;
+
+        self.host.get_or_insert(player_id);
+        self.seat_sessions.insert(player_id, SeatSessions::new());
+        self.record_spectator_snapshot();
+        Some(player_id)
+    }
+
+    /// Whether the given player is the host of the game.
+    #[inline]
+    pub fn is_host(&self, player_id: usize) -> bool {
+        self.host == Some(player_id)
+    }
+
+    /// Whether the host keeps new players out of the lobby.
+    #[inline]
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    /// Locks (or unlocks) the lobby: while it is locked, no new players can join.
+    ///
+    /// Only the host can do so.
+    pub fn lock_lobby(&mut self, host_id: usize, is_locked: bool) -> ManagerActionResult {
+        self.ensure_host(host_id, "lock the lobby")?;
+
+        self.locked = is_locked;
+        self.touch();
+        Ok(())
+    }
+
+    /// Removes a player from the game, whether it has started or not. See [`Game::leave`].
+    ///
+    /// Only the host can do so, and not to themselves.
+    pub fn kick_player(&mut self, host_id: usize, player_id: usize) -> ManagerActionResult {
+        self.ensure_host(host_id, "kick players")?;
+        if host_id == player_id {
+            return Err(String::from("The host cannot kick themselves."));
+        }
+
+        self.leave(player_id)
+    }
+
+    /// Removes the given player from the game, whether it has started or not. See [`Manager::remove_player`].
+    ///
+    /// The player's sessions, rejoin token and webhook stop working. If the host leaves, the player who joined
+    /// the earliest amongst the remaining ones becomes the host.
+    ///
+    /// In hot-seat games, the host's seat cannot leave, as it holds the session controlling every seat.
+    pub fn leave(&mut self, player_id: usize) -> ManagerActionResult {
+        if self.mode == GameMode::HotSeat && self.is_host(player_id) {
+            return Err(String::from(
+                "The seat controlling a hot-seat game cannot leave it.",
+            ));
+        }
+
+        self.manager.remove_player(player_id)?;
+        self.seat_sessions.remove(&player_id);
+        self.webhooks.unregister(player_id);
+        if self
+            .device_holder
+            .is_some_and(|device_holder| device_holder.seat == player_id)
+        {
+            self.device_holder = None;
+        }
+        if self.is_host(player_id) {
+            self.host = self.seat_sessions.keys().min().copied();
+        }
+
+        self.record_spectator_snapshot();
+        Ok(())
+    }
+
+    /// Delivers to the webhooks players register via the given dispatcher, from now on.
+    pub fn enable_webhooks(&mut self, game_id: Uuid, webhook_dispatcher: &WebhookDispatcher) {
+        let notifier = webhook_dispatcher.notifier(game_id, self.webhooks.clone());
+        self.manager.add_observer(Box::new(notifier));
+    }
+
+    /// Registers the URL of the given player's webhook, replacing any previous one. Returns the secret deliveries
+    /// are signed with. See [`crate::webhook`].
+    pub fn register_webhook(&mut self, player_id: usize, url: &str) -> Result<String, String> {
+        let secret = self.webhooks.register(player_id, url)?;
+
+        self.touch();
+        Ok(secret)
+    }
+
+    /// Removes the given player's webhook.
+    pub fn unregister_webhook(&mut self, player_id: usize) -> ManagerActionResult {
+        if self.webhooks.unregister(player_id) {
+            self.touch();
+            Ok(())
+        } else {
+            Err(String::from("You have not registered any webhook."))
+        }
+    }
+
+    /// Whether any player registered a webhook, i.e. the game is played by correspondence (see [`crate::limits`]).
+    pub fn has_webhooks(&self) -> bool {
+        self.webhooks.has_any()
+    }
+
+    /// Changes how the order in which players play is decided. See [`Manager::set_seating_order`].
+    ///
+    /// Only the host can do so.
+    pub fn set_seating_order(
+        &mut self,
+        host_id: usize,
+        seating_order: SeatingOrder,
+    ) -> ManagerActionResult {
+        self.ensure_host(host_id, "choose the seating order")?;
+
+        self.manager.set_seating_order(seating_order)?;
+        self.record_spectator_snapshot();
+        Ok(())
+    }
+
+    /// Starts the game, even if some players are not ready. See [`Manager::force_start`].
+    ///
+    /// Only the host can do so.
+    pub fn force_start(&mut self, host_id: usize) -> ManagerActionResult {
+        self.ensure_host(host_id, "start the game")?;
+
+        self.manager.force_start()?;
+        self.record_spectator_snapshot();
+        Ok(())
+    }
+
+    /// Ends the game, wherever it is at. See [`Manager::force_end`].
+    ///
+    /// This is left to admins (see [`crate::admin`]), rather than to the host.
+    pub fn force_end(&mut self) -> ManagerActionResult {
+        self.manager.force_end()?;
+        self.record_spectator_snapshot();
+        Ok(())
+    }
+
+    fn ensure_host(&self, host_id: usize, action: &str) -> ManagerActionResult {
+        if self.is_host(host_id) {
+            Ok(())
+        } else {
+            Err(format!("Only the host can {}.", action))
+        }
+    }
+
+    /// Returns the current session generation of the given seat, if it exists.
+    pub fn session(&self, player_id: usize) -> Option<u32> {
+        self.seat_sessions
+            .get(&player_id)
+            .map(|seat_sessions| seat_sessions.generation)
+    }
+
+    /// Returns the secret token with which the given seat can be rejoined, if it exists.
+    pub fn rejoin_token(&self, player_id: usize) -> Option<Uuid> {
+        self.seat_sessions
+            .get(&player_id)
+            .map(|seat_sessions| seat_sessions.rejoin_token)
+    }
+
+    /// Returns the seat, and its current session generation, that the rejoin token belongs to.
+    pub fn seat_of_rejoin_token(&self, rejoin_token: &Uuid) -> Option<(usize, u32)> {
+        self.seat_sessions
+            .iter()
+            .find(|(_, seat_sessions)| &seat_sessions.rejoin_token == rejoin_token)
+            .map(|(player_id, seat_sessions)| (*player_id, seat_sessions.generation))
+    }
+
+    /// Replaces the rejoin token of a seat, so that previously shared rejoin links stop working.
+    ///
+    /// Only the host can do so.
+    pub fn regenerate_rejoin_token(
+        &mut self,
+        host_id: usize,
+        player_id: usize,
+    ) -> ManagerActionResult {
+        self.seat_sessions_managed_by_host(host_id, player_id)?
+            .rejoin_token = Uuid::new_v4();
+        self.touch();
+        Ok(())
+    }
+
+    /// Invalidates every session of a seat: all devices currently seated there are logged out,
+    /// and need to use the rejoin link to get back in.
+    ///
+    /// Only the host can do so.
+    pub fn revoke_sessions(&mut self, host_id: usize, player_id: usize) -> ManagerActionResult {
+        self.seat_sessions_managed_by_host(host_id, player_id)?
+            .generation += 1;
+        self.touch();
+        Ok(())
+    }
+
+    fn seat_sessions_managed_by_host(
+        &mut self,
+        host_id: usize,
+        player_id: usize,
+    ) -> Result<&mut SeatSessions, String> {
+        self.ensure_host(host_id, "manage the seats' sessions")?;
+
+        self.seat_sessions
+            .get_mut(&player_id)
+            .ok_or_else(|| format!("There is no player with ID {}.", player_id))
+    }
+
+    /// Adds a seat to a hot-seat game, controlled by the same session as the other seats.
+    pub fn add_seat(&mut self) -> ManagerActionResult {
+        if self.mode != GameMode::HotSeat {
+            return Err(String::from(
+                "Only hot-seat games can have more seats added by a player.",
+            ));
+        }
+
+        match self.add_player() {
+            Some(_) => Ok(()),
+            None => Err(String::from("Cannot add another seat to this game.")),
+        }
+    }
+
+    /// Plays on behalf of the current player if they ran out of time. See [`Manager::enforce_time_controls`].
+    ///
+    /// Returns whether the game changed.
+    pub fn enforce_time_controls(&mut self) -> bool {
+        if !self.manager.enforce_time_controls() {
+            return false;
+        }
+
+        self.record_spectator_snapshot();
+        true
+    }
+
+    /// Registers a new spectator, and returns its ID.
+    pub fn add_spectator(&mut self) -> usize {
+        self.num_spectators += 1;
+        self.num_spectators - 1
+    }
+
+    /// Records the current public state, for spectators to see once the delay elapsed.
+    ///
+    /// Must be called after every change to the game.
+    pub fn record_spectator_snapshot(&mut self) {
+        self.record_spectator_snapshot_at(Instant::now());
+    }
+
+    fn record_spectator_snapshot_at(&mut self, now: Instant) {
+        self.last_activity = self.last_activity.max(now);
+
+        let state = serde_json::to_string(&self.manager.get_public_state())
+            .expect("Game state should never fail serializing as JSON");
+
+        if self
+            .spectator_snapshots
+            .back()
+            .is_none_or(|snapshot| snapshot.state != state)
+        {
+            self.spectator_snapshots.push_back(SpectatorSnapshot {
+                recorded_at: now,
+                state,
+            });
+        }
+
+        // Spectators will never be shown a snapshot older than one which is itself old enough.
+        while self.spectator_snapshots.len() > 1
+            && now.duration_since(self.spectator_snapshots[1].recorded_at) >= self.spectator_delay
+        {
+            self.spectator_snapshots.pop_front();
+        }
+    }
+
+    /// Returns the serialized public game state spectators get to see.
+    ///
+    /// This is the latest state which is at least as old as the spectator delay (or the oldest state
+    /// known, if the game is more recent than the delay).
+    pub fn get_spectator_state(&self) -> &str {
+        self.get_spectator_state_at(Instant::now())
+    }
+
+    fn get_spectator_state_at(&self, now: Instant) -> &str {
+        let snapshot = self
+            .spectator_snapshots
+            .iter()
+            .rev()
+            .find(|snapshot| now.duration_since(snapshot.recorded_at) >= self.spectator_delay)
+            .or_else(|| self.spectator_snapshots.front())
+            .expect("A snapshot is recorded when creating the game");
+
+        &snapshot.state
+    }
+
+    /// In a hot-seat game, confirms the device was passed to the given seat.
+    ///
+    /// Until the turn changes, or the device is passed again, the game state reveals that seat's private state.
+    pub fn pass_device(&mut self, seat: usize) -> ManagerActionResult {
+        if self.mode != GameMode::HotSeat {
+            return Err(String::from(
+                "Only hot-seat games have a device to pass around.",
+            ));
+        }
+
+        self.device_holder = Some(DeviceHolder {
+            seat,
+            turn: self.manager.turn(),
+        });
+        self.touch();
+        Ok(())
+    }
+
+    /// Returns the game state, as seen by the given player.
+    ///
+    /// In hot-seat games, everyone is looking at the same screen: no private state is revealed, except the
+    /// one of the seat holding the device (see [`Game::pass_device`]).
+    pub fn get_state(&self, player_id: usize) -> GameState<'_> {
+        match self.mode {
+            GameMode::Online => self.manager.get_state(player_id),
+            GameMode::HotSeat => match self.device_holder {
+                Some(DeviceHolder { seat, turn })
+                    if seat == player_id && turn == self.manager.turn() =>
+                {
+                    self.manager.get_state(seat)
+                }
+                _ => self.manager.get_public_state(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smallvec::smallvec;
+    use ticket_to_ride::clock::TimeControls;
+
+    fn new_game(hot_seat: bool) -> Game {
+        Game::new(&CreateGameOptions {
+            hot_seat,
+            ..Default::default()
+        })
+    }
+
+    fn spectator_num_players(game: &Game, now: Instant) -> usize {
+        let state: serde_json::Value =
+            serde_json::from_str(game.get_spectator_state_at(now)).unwrap();
+
+        state["players_state"].as_array().unwrap().len()
+    }
+
+    fn num_private_states(game: &Game, player_id: usize) -> usize {
+        game.get_state(player_id)
+            .players_state
+            .iter()
+            .filter(|player_state| player_state.private_player_state.is_some())
+            .count()
+    }
+
+    #[test]
+    fn game_new() {
+        assert_eq!(new_game(false).mode(), GameMode::Online);
+        assert_eq!(new_game(true).mode(), GameMode::HotSeat);
+        assert_eq!(new_game(true).manager().num_players(), 0);
+    }
+
+    #[test]
+    fn game_online_acting_seat() {
+        let mut game = new_game(false);
+        game.add_player();
+        game.add_player();
+
+        assert_eq!(game.acting_seat(1, None), Some(1));
+        assert_eq!(game.acting_seat(1, Some(1)), Some(1));
+        assert_eq!(game.acting_seat(1, Some(0)), None);
+
+        assert!(game.add_seat().is_err());
+        assert!(game.pass_device(1).is_err());
+        assert_eq!(num_private_states(&game, 1), 1);
+    }
+
+    #[test]
+    fn game_hot_seat_acting_seat() {
+        let mut game = new_game(true);
+        game.add_player();
+        assert!(game.add_seat().is_ok());
+        assert_eq!(game.manager().num_players(), 2);
+
+        assert_eq!(game.acting_seat(0, None), Some(0));
+        assert_eq!(game.acting_seat(0, Some(1)), Some(1));
+        assert_eq!(game.acting_seat(0, Some(2)), None);
+    }
+
+    #[test]
+    fn game_hot_seat_add_seat_full() {
+        let mut game = new_game(true);
+
+        for _ in 0..5 {
+            assert!(game.add_seat().is_ok());
+        }
+        assert!(game.add_seat().is_err());
+    }
+
+    #[test]
+    fn game_hot_seat_pass_device() {
+        let mut game = new_game(true);
+        game.add_player();
+        game.add_seat().unwrap();
+
+        for seat in 0..2 {
+            assert!(game.manager_mut().set_ready(seat, true).is_ok());
+        }
+
+        // Until the device is passed, nothing private is revealed.
+        assert_eq!(num_private_states(&game, 0), 0);
+        assert_eq!(num_private_states(&game, 1), 0);
+
+        assert!(game.pass_device(1).is_ok());
+        assert_eq!(num_private_states(&game, 0), 0);
+        assert_eq!(num_private_states(&game, 1), 1);
+
+        assert!(game.pass_device(0).is_ok());
+        assert_eq!(num_private_states(&game, 0), 1);
+        assert_eq!(num_private_states(&game, 1), 0);
+
+        // Once the turn changes, the device must be passed again.
+        for seat in 0..2 {
+            assert!(game
+                .manager_mut()
+                .select_destination_cards(seat, smallvec::smallvec![true, true, true])
+                .is_ok());
+        }
+        assert_eq!(game.manager().turn(), Some(0));
+        assert_eq!(num_private_states(&game, 0), 0);
+    }
+
+    #[test]
+    fn game_spectators() {
+        let mut game = new_game(false);
+
+        assert_eq!(game.add_spectator(), 0);
+        assert_eq!(game.add_spectator(), 1);
+        assert_eq!(spectator_num_players(&game, Instant::now()), 0);
+
+        // Without any delay, spectators see the latest state.
+        game.add_player();
+        assert_eq!(spectator_num_players(&game, Instant::now()), 1);
+        assert_eq!(game.spectator_snapshots.len(), 1);
+    }
+
+    #[test]
+    fn game_spectators_delay() {
+        let delay = Duration::from_secs(30);
+        let mut game = Game::new(&CreateGameOptions {
+            spectator_delay: delay,
+            ..Default::default()
+        });
+        let created_at = game.spectator_snapshots[0].recorded_at;
+
+        // The player joins 10 seconds after the game was created.
+        assert!(game.manager.add_player().is_some());
+        game.record_spectator_snapshot_at(created_at + Duration::from_secs(10));
+        assert_eq!(game.spectator_snapshots.len(), 2);
+
+        // The game is not old enough: spectators see its first state.
+        assert_eq!(spectator_num_players(&game, created_at), 0);
+        assert_eq!(
+            spectator_num_players(&game, created_at + Duration::from_secs(39)),
+            0
+        );
+        assert_eq!(
+            spectator_num_players(&game, created_at + Duration::from_secs(40)),
+            1
+        );
+
+        // Once spectators can no longer be shown the first state, it is dropped.
+        assert!(game.manager.add_player().is_some());
+        game.record_spectator_snapshot_at(created_at + Duration::from_secs(45));
+        assert_eq!(game.spectator_snapshots.len(), 2);
+        assert_eq!(
+            spectator_num_players(&game, created_at + Duration::from_secs(45)),
+            1
+        );
+        assert_eq!(
+            spectator_num_players(&game, created_at + Duration::from_secs(75)),
+            2
+        );
+    }
+
+    #[test]
+    fn game_restore_spectators_delay() {
+        let mut game = Game::new(&CreateGameOptions {
+            spectator_delay: Duration::from_secs(30),
+            ..Default::default()
+        });
+        game.add_player();
+        assert_eq!(spectator_num_players(&game, Instant::now()), 0);
+
+        // Once restored, spectators are still shown the state they were shown, until the delay elapses again.
+        let game = Game::restore(game.snapshot()).unwrap();
+        let restored_at = game.spectator_snapshots[0].recorded_at;
+        assert_eq!(spectator_num_players(&game, restored_at), 0);
+        assert_eq!(
+            spectator_num_players(&game, restored_at + Duration::from_secs(29)),
+            0
+        );
+        assert_eq!(
+            spectator_num_players(&game, restored_at + Duration::from_secs(30)),
+            1
+        );
+    }
+
+    #[test]
+    fn game_spectators_delay_is_bounded() {
+        let game = Game::new(&CreateGameOptions {
+            spectator_delay: Duration::from_secs(24 * 60 * 60),
+            ..Default::default()
+        });
+
+        assert_eq!(game.spectator_delay, MAX_SPECTATOR_DELAY);
+    }
+
+    #[test]
+    fn game_host() {
+        let mut game = new_game(false);
+        assert!(!game.is_host(0));
+
+        game.add_player();
+        game.add_player();
+        assert!(game.is_host(0));
+        assert!(!game.is_host(1));
+    }
+
+    #[test]
+    fn game_rejoin_tokens() {
+        let mut game = new_game(false);
+        game.add_player();
+        game.add_player();
+
+        let rejoin_token = game.rejoin_token(1).unwrap();
+        assert_ne!(game.rejoin_token(0), Some(rejoin_token));
+        assert!(game.rejoin_token(2).is_none());
+        assert_eq!(game.seat_of_rejoin_token(&rejoin_token), Some((1, 0)));
+
+        // Only the host can regenerate tokens.
+        assert!(game.regenerate_rejoin_token(1, 1).is_err());
+        assert!(game.regenerate_rejoin_token(0, 2).is_err());
+        assert!(game.regenerate_rejoin_token(0, 1).is_ok());
+
+        assert!(game.seat_of_rejoin_token(&rejoin_token).is_none());
+        assert_ne!(game.rejoin_token(1), Some(rejoin_token));
+        assert_eq!(
+            game.seat_of_rejoin_token(&game.rejoin_token(1).unwrap()),
+            Some((1, 0))
+        );
+    }
+
+    #[test]
+    fn game_revoke_sessions() {
+        let mut game = new_game(false);
+        game.add_player();
+        game.add_player();
+        assert_eq!(game.session(1), Some(0));
+        assert!(game.session(2).is_none());
+
+        // Only the host can revoke sessions.
+        assert!(game.revoke_sessions(1, 1).is_err());
+        assert!(game.revoke_sessions(0, 2).is_err());
+        assert!(game.revoke_sessions(0, 1).is_ok());
+
+        assert_eq!(game.session(0), Some(0));
+        assert_eq!(game.session(1), Some(1));
+        assert_eq!(
+            game.seat_of_rejoin_token(&game.rejoin_token(1).unwrap()),
+            Some((1, 1))
+        );
+    }
+
+    #[test]
+    fn game_host_actions_are_activity() {
+        let mut game = new_game(false);
+        game.add_player();
+        game.add_player();
+
+        // Host actions spectators cannot see still keep the game from expiring.
+        let actions: [fn(&mut Game) -> ManagerActionResult; 3] = [
+            |game| game.lock_lobby(0, true),
+            |game| game.regenerate_rejoin_token(0, 1),
+            |game| game.revoke_sessions(0, 1),
+        ];
+        for action in actions {
+            let last_activity = game.last_activity();
+            std::thread::sleep(Duration::from_millis(1));
+            assert!(action(&mut game).is_ok());
+            assert!(game.last_activity() > last_activity);
+        }
+
+        // Failed actions are no activity.
+        let last_activity = game.last_activity();
+        std::thread::sleep(Duration::from_millis(1));
+        assert!(game.lock_lobby(1, false).is_err());
+        assert_eq!(game.last_activity(), last_activity);
+    }
+
+    #[test]
+    fn game_webhooks() {
+        let mut game = new_game(false);
+        game.add_player();
+        assert!(!game.has_webhooks());
+
+        let last_activity = game.last_activity();
+        std::thread::sleep(Duration::from_millis(1));
+        assert!(game.register_webhook(0, "https://example.com/hook").is_ok());
+        assert!(game.has_webhooks());
+        assert!(game.last_activity() > last_activity);
+
+        assert!(game.unregister_webhook(0).is_ok());
+        assert!(!game.has_webhooks());
+        assert!(game.unregister_webhook(0).is_err());
+    }
+
+    #[test]
+    fn game_lock_lobby() {
+        let mut game = new_game(false);
+        game.add_player();
+        game.add_player();
+        assert!(!game.is_locked());
+
+        // Only the host can lock the lobby.
+        assert!(game.lock_lobby(1, true).is_err());
+        assert!(game.lock_lobby(0, true).is_ok());
+        assert!(game.is_locked());
+        assert!(game.lock_lobby(0, false).is_ok());
+        assert!(!game.is_locked());
+    }
+
+    #[test]
+    fn game_admission() {
+        let game = new_game(false);
+        assert!(!game.is_private());
+        assert!(game.admits(None));
+        assert!(game.admits(Some("anything")));
+
+        let game = Game::new(&CreateGameOptions {
+            private: true,
+            ..Default::default()
+        });
+        assert!(game.is_private());
+        let invite_code = game.invite_code().unwrap().to_owned();
+        assert_eq!(invite_code.len(), INVITE_CODE_LEN);
+        assert!(!game.admits(None));
+        assert!(!game.admits(Some("")));
+        assert!(game.admits(Some(&invite_code)));
+        // Invite codes are typed by hand.
+        assert!(game.admits(Some(&format!(" {} ", invite_code.to_lowercase()))));
+
+        let game = Game::new(&CreateGameOptions {
+            password: Some(String::from("Hunter2")),
+            ..Default::default()
+        });
+        assert!(game.is_private());
+        assert_eq!(game.invite_code(), None);
+        assert!(!game.admits(None));
+        assert!(!game.admits(Some("hunter2")));
+        assert!(game.admits(Some("Hunter2")));
+
+        // The password is kept, and persisted, as a salted hash only.
+        let snapshot = rocket::serde::json::to_string(&game.snapshot()).unwrap();
+        assert!(!snapshot.contains("Hunter2"));
+        let other_game = Game::new(&CreateGameOptions {
+            password: Some(String::from("Hunter2")),
+            ..Default::default()
+        });
+        assert_ne!(other_game.admission, game.admission);
+        let game = Game::restore(rocket::serde::json::from_str(&snapshot).unwrap()).unwrap();
+        assert!(!game.admits(Some("hunter2")));
+        assert!(game.admits(Some("Hunter2")));
+    }
+
+    #[test]
+    fn game_has_open_seat() {
+        let mut game = new_game(false);
+        assert!(game.has_open_seat());
+
+        game.add_player();
+        assert!(game.lock_lobby(0, true).is_ok());
+        assert!(!game.has_open_seat());
+        assert!(game.lock_lobby(0, false).is_ok());
+
+        for _ in 1..MAX_PLAYERS {
+            assert!(game.has_open_seat());
+            game.add_player();
+        }
+        assert!(!game.has_open_seat());
+
+        assert!(game.leave(4).is_ok());
+        assert!(game.force_start(0).is_ok());
+        assert!(!game.has_open_seat());
+
+        // Hot-seat games are played from the device that created them.
+        assert!(!new_game(true).has_open_seat());
+    }
+
+    #[test]
+    fn game_kick_player() {
+        let mut game = new_game(false);
+        game.add_player();
+        game.add_player();
+        game.add_player();
+        let rejoin_token = game.rejoin_token(1).unwrap();
+
+        // Only the host can kick players, and not themselves.
+        assert!(game.kick_player(2, 1).is_err());
+        assert!(game.kick_player(0, 0).is_err());
+        assert!(game.kick_player(0, 3).is_err());
+        assert!(game.kick_player(0, 1).is_ok());
+
+        assert_eq!(game.manager().num_players(), 2);
+        assert!(!game.manager().has_player(1));
+        assert!(game.session(1).is_none());
+        assert!(game.seat_of_rejoin_token(&rejoin_token).is_none());
+        assert_eq!(spectator_num_players(&game, Instant::now()), 2);
+
+        // The freed seat can be taken by someone else.
+        assert_eq!(game.add_player(), Some(3));
+    }
+
+    #[test]
+    fn game_host_starts_game() {
+        let mut game = new_game(false);
+        game.add_player();
+
+        assert!(game.force_start(0).is_err());
+
+        game.add_player();
+        game.add_player();
+        assert!(game
+            .set_seating_order(1, SeatingOrder::Manual(vec![2, 1, 0]))
+            .is_err());
+        assert!(game
+            .set_seating_order(0, SeatingOrder::Manual(vec![2, 1, 0]))
+            .is_ok());
+
+        assert!(game.force_start(1).is_err());
+        assert!(game.force_start(0).is_ok());
+        assert_eq!(game.manager().phase(), GamePhase::Starting);
+
+        let player_ids: Vec<usize> = game
+            .manager()
+            .get_public_state()
+            .players_state
+            .iter()
+            .map(|player_state| player_state.public_player_state.id)
+            .collect();
+        assert_eq!(player_ids, vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn game_leave() {
+        let mut game = new_game(false);
+        for _ in 0..4 {
+            game.add_player();
+        }
+        assert!(game.force_start(0).is_ok());
+
+        // Players can be kicked, or leave, once the game has started.
+        assert!(game.kick_player(0, 1).is_ok());
+        assert!(game.session(1).is_none());
+        assert!(game.leave(2).is_ok());
+        assert_eq!(game.manager().num_players(), 2);
+        assert_eq!(spectator_num_players(&game, Instant::now()), 2);
+        assert!(game.leave(2).is_err());
+
+        // When the host leaves, the next player becomes the host.
+        assert!(game.leave(0).is_ok());
+        assert!(game.is_host(3));
+        assert_eq!(game.manager().phase(), GamePhase::Done);
+    }
+
+    #[test]
+    fn game_hot_seat_leave() {
+        let mut game = new_game(true);
+        game.add_player();
+        game.add_seat().unwrap();
+        game.add_seat().unwrap();
+        game.pass_device(1).unwrap();
+
+        assert!(game.leave(0).is_err());
+        assert!(game.leave(1).is_ok());
+        assert!(game.device_holder.is_none());
+        assert!(game.kick_player(0, 2).is_ok());
+        assert_eq!(game.manager().num_players(), 1);
+    }
+
+    #[test]
+    fn game_enforce_time_controls() {
+        let mut game = Game::new(&CreateGameOptions {
+            time_controls: TimeControls {
+                time_bank: Some(Duration::ZERO),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        game.add_player();
+        game.add_player();
+        assert!(game.force_start(0).is_ok());
+        assert!(!game.enforce_time_controls());
+
+        for player_id in 0..2 {
+            assert!(game
+                .manager_mut()
+                .select_destination_cards(player_id, smallvec![true, true, true])
+                .is_ok());
+        }
+        assert!(game.manager().get_public_state().clock.is_some());
+
+        // Players have no time at all, so every turn times out right away.
+        let spectator_state = game.get_spectator_state().to_owned();
+        assert!(game.enforce_time_controls());
+        assert_ne!(game.get_spectator_state(), spectator_state);
+        assert!(game.enforce_time_controls());
+
+        // Without time controls, nothing happens.
+        let mut game = new_game(false);
+        game.add_player();
+        game.add_player();
+        assert!(game.force_start(0).is_ok());
+        assert!(!game.enforce_time_controls());
+        assert!(game.manager().get_public_state().clock.is_none());
+    }
+
+    #[test]
+    fn game_restore_clock() {
+        let mut game = Game::new(&CreateGameOptions {
+            time_controls: TimeControls {
+                time_bank: Some(Duration::from_secs(600)),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        game.add_player();
+        game.add_player();
+        assert!(game.force_start(0).is_ok());
+        for player_id in 0..2 {
+            assert!(game
+                .manager_mut()
+                .select_destination_cards(player_id, smallvec![true, true, true])
+                .is_ok());
+        }
+
+        // Time banks are persisted, rather than refilled when the game is restored.
+        let mut snapshot = rocket::serde::json::to_value(game.snapshot()).unwrap();
+        snapshot["clock"]["time_banks"]["1"] = rocket::serde::json::json!({"secs": 42, "nanos": 0});
+        let game = Game::restore(rocket::serde::json::from_value(snapshot).unwrap()).unwrap();
+        let time_banks_left_ms = game
+            .manager()
+            .get_public_state()
+            .clock
+            .unwrap()
+            .time_banks_left_ms;
+        // Either player's turn may be running.
+        assert!(time_banks_left_ms[&0] > 599_000);
+        assert!(time_banks_left_ms[&1] > 41_000 && time_banks_left_ms[&1] <= 42_000);
+    }
+}
@@ -0,0 +1,178 @@
+//! Game codes: short, human-friendly identifiers of games, alongside their [`Uuid`].
+//!
+//! A code is made of [`GAME_CODE_LEN`] characters, which can be read aloud without ambiguity: upper-case letters
+//! and digits, except those easily mistaken for one another. Codes are case-insensitive. Routes under
+//! `/game/<game_id>` accept either the game's ID, or its code (see [`GameKey`]).
+
+use dashmap::{mapref::entry::Entry, DashMap};
+use rand::seq::SliceRandom;
+use rocket::http::uri::fmt::{FromUriParam, Path};
+use rocket::request::FromParam;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Characters codes are made of: upper-case letters and digits, except those easily mistaken for one another
+/// (`0`/`O`, `1`/`I`).
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+
+/// Number of characters in a game code, as long as there are free codes of that length.
+pub const GAME_CODE_LEN: usize = 6;
+
+/// How many codes are drawn, in case they collide with the codes of other games, before drawing longer ones.
+const MAX_ATTEMPTS_PER_LEN: usize = 8;
+
+/// Maps the code of a game to its ID, shared between request handlers like [`crate::controller::GameIdManagerMapping`].
+pub type GameCodeMapping = Arc<DashMap<String, Uuid>>;
+
+/// Generates a random code of the given length, made of characters that are easy to read and type.
+pub(crate) fn generate_code(len: usize) -> String {
+    let mut rng = rand::thread_rng();
+
+    (0..len)
+        .map(|_| *CODE_ALPHABET.choose(&mut rng).unwrap() as char)
+        .collect()
+}
+
+/// Draws a code no other game has, and maps it to the given game.
+///
+/// Codes are drawn at random, until one is free: after too many collisions, longer codes are drawn.
+pub(crate) fn assign_game_code(game_codes: &DashMap<String, Uuid>, game_id: Uuid) -> String {
+    assign_code(game_codes, game_id, GAME_CODE_LEN)
+}
+
+fn assign_code(game_codes: &DashMap<String, Uuid>, game_id: Uuid, min_len: usize) -> String {
+    for len in min_len.. {
+        for _ in 0..MAX_ATTEMPTS_PER_LEN {
+            let code = generate_code(len);
+
+            if let Entry::Vacant(entry) = game_codes.entry(code.clone()) {
+                entry.insert(game_id);
+                return code;
+            }
+        }
+    }
+
+    unreachable!("There are infinitely many codes")
+}
+
+/// Whether the given string is shaped like a game code, once upper-cased.
+fn is_game_code(code: &str) -> bool {
+    code.len() >= GAME_CODE_LEN && code.bytes().all(|byte| CODE_ALPHABET.contains(&byte))
+}
+
+/// How a game is identified in the path of a request: by its ID, or by its code.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameKey {
+    Id(Uuid),
+    /// Upper-cased.
+    Code(String),
+}
+
+impl GameKey {
+    /// Returns the ID of the game, if the key is a code which maps to a game.
+    pub fn resolve(&self, game_codes: &DashMap<String, Uuid>) -> Option<Uuid> {
+        match self {
+            Self::Id(game_id) => Some(*game_id),
+            Self::Code(code) => game_codes.get(code).map(|game_id| *game_id),
+        }
+    }
+}
+
+impl<'a> FromParam<'a> for GameKey {
+    type Error = &'a str;
+
+    fn from_param(param: &'a str) -> Result<Self, Self::Error> {
+        if let Ok(game_id) = Uuid::parse_str(param) {
+            return Ok(Self::Id(game_id));
+        }
+
+        let code = param.to_ascii_uppercase();
+        if is_game_code(&code) {
+            Ok(Self::Code(code))
+        } else {
+            Err(param)
+        }
+    }
+}
+
+/// Lets [`uri!`] build paths to routes taking a [`GameKey`], from a game ID.
+impl FromUriParam<Path, Uuid> for GameKey {
+    type Target = Uuid;
+
+    #[inline]
+    fn from_uri_param(game_id: Uuid) -> Uuid {
+        game_id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generate_code_alphabet() {
+        let code = generate_code(GAME_CODE_LEN);
+
+        assert_eq!(code.len(), GAME_CODE_LEN);
+        assert!(is_game_code(&code));
+        assert!(!code.contains(['0', 'O', '1', 'I']));
+    }
+
+    #[test]
+    fn assign_game_code_collisions() {
+        let game_codes = DashMap::new();
+        let game_id = Uuid::new_v4();
+
+        // Once every single-character code is taken, longer ones are drawn.
+        for byte in CODE_ALPHABET {
+            game_codes.insert(String::from(*byte as char), game_id);
+        }
+
+        let other_game_id = Uuid::new_v4();
+        let code = assign_code(&game_codes, other_game_id, 1);
+        assert_eq!(code.len(), 2);
+        assert_eq!(
+            GameKey::Code(code).resolve(&game_codes),
+            Some(other_game_id)
+        );
+
+        let code = assign_game_code(&game_codes, game_id);
+        assert_eq!(code.len(), GAME_CODE_LEN);
+        assert_eq!(game_codes.get(&code).map(|game_id| *game_id), Some(game_id));
+    }
+
+    #[test]
+    fn game_key_from_param() {
+        let game_id = Uuid::new_v4();
+
+        assert_eq!(
+            GameKey::from_param(&game_id.to_string()),
+            Ok(GameKey::Id(game_id))
+        );
+        assert_eq!(
+            GameKey::from_param("k7xq2m"),
+            Ok(GameKey::Code(String::from("K7XQ2M")))
+        );
+        // Confusable characters are never part of a code.
+        assert!(GameKey::from_param("K0XQ1M").is_err());
+        assert!(GameKey::from_param("K7XQ2").is_err());
+        assert!(GameKey::from_param("not-a-game").is_err());
+    }
+
+    #[test]
+    fn game_key_resolve() {
+        let game_codes = DashMap::new();
+        let game_id = Uuid::new_v4();
+        game_codes.insert(String::from("K7XQ2M"), game_id);
+
+        assert_eq!(GameKey::Id(game_id).resolve(&game_codes), Some(game_id));
+        assert_eq!(
+            GameKey::Code(String::from("K7XQ2M")).resolve(&game_codes),
+            Some(game_id)
+        );
+        assert_eq!(
+            GameKey::Code(String::from("K7XQ2N")).resolve(&game_codes),
+            None
+        );
+    }
+}
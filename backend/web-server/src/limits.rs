@@ -0,0 +1,462 @@
+//! Limits on the games the server hosts: how many at once, and for how long they are kept around once idle.
+//!
+//! Games are evicted by a background task (see [`sweep_expired_games`]), once nothing happened in them for a while:
+//! lobbies nobody joined, games players abandoned, and finished games. If an archive directory is configured, the
+//! events of evicted games are written there first (see [`GameArchive`]), such that they can be replayed.
+
+use crate::controller::GameIdManagerMapping;
+use crate::game::Game;
+use crate::game_code::GameCodeMapping;
+use crate::unix_time::unix_now;
+
+use dashmap::DashMap;
+use rocket::figment::Figment;
+use rocket::serde::Serialize;
+use rocket::tokio::{fs, select, time};
+use rocket::Shutdown;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use ticket_to_ride::event::GameEvent;
+use ticket_to_ride::manager::GamePhase;
+use tracing::warn;
+use uuid::Uuid;
+
+/// How many games the server hosts at most, unless configured otherwise.
+pub(crate) const DEFAULT_MAX_GAMES: usize = 10_000;
+
+/// How long lobbies are kept without any activity, unless configured otherwise.
+pub(crate) const DEFAULT_IDLE_LOBBY_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// How long games in progress are kept without any activity, unless configured otherwise.
+pub(crate) const DEFAULT_IDLE_GAME_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How long games in progress played by correspondence (where players registered webhooks, see [`crate::webhook`])
+/// are kept without any activity, unless configured otherwise. Players may take days to come back to those.
+pub(crate) const DEFAULT_IDLE_CORRESPONDENCE_GAME_TTL: Duration =
+    Duration::from_secs(14 * 24 * 60 * 60);
+
+/// How long finished games are kept, unless configured otherwise.
+pub(crate) const DEFAULT_FINISHED_GAME_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// How often games are checked for expiry.
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Serializes the creation of games, such that concurrent requests cannot go over [`GameLimits::max_games`].
+///
+/// Clones share the same lock.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct CreationLock(Arc<Mutex<()>>);
+
+impl PartialEq for CreationLock {
+    /// The lock is no part of the limits: limits configured the same way are equal.
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+/// Room for a new game, reserved by [`GameLimits::reserve_capacity`] until dropped.
+///
+/// Hold on to it until the game is inserted: it is released either way once dropped, e.g. if creating the game
+/// fails in between.
+#[must_use]
+pub(crate) struct CapacityReservation<'a> {
+    _guard: MutexGuard<'a, ()>,
+}
+
+/// Limits on the games the server hosts, read from Rocket's configuration (see [`GameLimits::from_figment`]).
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameLimits {
+    /// How many games the server hosts at most: creating more fails.
+    pub max_games: usize,
+    /// How long games in the lobby are kept without any activity.
+    pub idle_lobby_ttl: Duration,
+    /// How long games in progress are kept without any activity.
+    pub idle_game_ttl: Duration,
+    /// How long games in progress played by correspondence are kept without any activity.
+    pub idle_correspondence_game_ttl: Duration,
+    /// How long finished games are kept, after their last activity.
+    pub finished_game_ttl: Duration,
+    /// Directory evicted games are archived in, if any.
+    pub archive_dir: Option<PathBuf>,
+    /// Taken to create a game (see [`GameLimits::reserve_capacity`]).
+    pub(crate) creation_lock: CreationLock,
+}
+
+impl Default for GameLimits {
+    fn default() -> Self {
+        Self {
+            max_games: DEFAULT_MAX_GAMES,
+            idle_lobby_ttl: DEFAULT_IDLE_LOBBY_TTL,
+            idle_game_ttl: DEFAULT_IDLE_GAME_TTL,
+            idle_correspondence_game_ttl: DEFAULT_IDLE_CORRESPONDENCE_GAME_TTL,
+            finished_game_ttl: DEFAULT_FINISHED_GAME_TTL,
+            archive_dir: None,
+            creation_lock: CreationLock::default(),
+        }
+    }
+}
+
+impl GameLimits {
+    /// Reads the limits from Rocket's configuration:
+    ///   * `max_games`: how many games the server hosts at most (see [`DEFAULT_MAX_GAMES`]).
+    ///   * `idle_lobby_ttl`, `idle_game_ttl`, `idle_correspondence_game_ttl` and `finished_game_ttl`: how long games
+    ///     are kept without any activity, in seconds, depending on their phase (see [`GameLimits::ttl`]).
+    ///   * `archive_dir`: the directory evicted games are archived in. If not configured, they are not archived.
+    pub fn from_figment(figment: &Figment) -> Self {
+        let defaults = Self::default();
+        let ttl = |key: &str, default: Duration| {
+            figment
+                .extract_inner::<u64>(key)
+                .map(Duration::from_secs)
+                .unwrap_or(default)
+        };
+
+        Self {
+            max_games: figment
+                .extract_inner::<usize>("max_games")
+                .unwrap_or(defaults.max_games),
+            idle_lobby_ttl: ttl("idle_lobby_ttl", defaults.idle_lobby_ttl),
+            idle_game_ttl: ttl("idle_game_ttl", defaults.idle_game_ttl),
+            idle_correspondence_game_ttl: ttl(
+                "idle_correspondence_game_ttl",
+                defaults.idle_correspondence_game_ttl,
+            ),
+            finished_game_ttl: ttl("finished_game_ttl", defaults.finished_game_ttl),
+            archive_dir: figment
+                .extract_inner::<PathBuf>("archive_dir")
+                .ok()
+                .filter(|archive_dir| !archive_dir.as_os_str().is_empty()),
+            creation_lock: CreationLock::default(),
+        }
+    }
+
+    /// Returns how long a game in the given phase is kept without any activity, depending on whether it is played
+    /// by correspondence (see [`Game::has_webhooks`]).
+    pub fn ttl(&self, phase: GamePhase, is_correspondence: bool) -> Duration {
+        match phase {
+            GamePhase::InLobby => self.idle_lobby_ttl,
+            GamePhase::Done => self.finished_game_ttl,
+            _ if is_correspondence => self.idle_correspondence_game_ttl,
+            _ => self.idle_game_ttl,
+        }
+    }
+
+    /// Whether the game expired, as of `now`.
+    pub fn is_expired(&self, game: &Game, now: Instant) -> bool {
+        let ttl = self.ttl(game.manager().phase(), game.has_webhooks());
+
+        now.saturating_duration_since(game.last_activity()) >= ttl
+    }
+
+    /// Reserves room for a new game, until the returned reservation is dropped. Fails if the server already hosts as
+    /// many games as it can.
+    ///
+    /// Reservations are taken one at a time, and games are only ever removed concurrently: as long as the game is
+    /// inserted before its reservation is dropped, concurrent requests cannot go over the limit.
+    pub(crate) fn reserve_capacity(
+        &self,
+        games: &DashMap<Uuid, Game>,
+    ) -> Result<CapacityReservation<'_>, String> {
+        // The lock guards no data, which a panicking holder could have left half-updated.
+        let guard = self
+            .creation_lock
+            .0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        self.ensure_capacity(games)?;
+
+        Ok(CapacityReservation { _guard: guard })
+    }
+
+    /// Fails if the server already hosts as many games as it can.
+    ///
+    /// This only tells whether there is room right now: creating a game takes a reservation instead (see
+    /// [`GameLimits::reserve_capacity`]).
+    fn ensure_capacity(&self, games: &DashMap<Uuid, Game>) -> Result<(), String> {
+        if games.len() >= self.max_games {
+            Err(String::from(
+                "The server is hosting too many games already: try again later.",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// What is written down for an evicted game, serializable in JSON.
+///
+/// The game can be rebuilt from its events (see [`ticket_to_ride::manager::Manager::replay`]).
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct GameArchive<'a> {
+    pub game_id: Uuid,
+    pub game_code: Option<&'a str>,
+    /// When the game was created, in seconds since the UNIX epoch.
+    pub created_at: u64,
+    /// When the game was archived, in seconds since the UNIX epoch.
+    pub archived_at: u64,
+    pub events: &'a [GameEvent],
+}
+
+/// Background task that evicts expired games (see [`GameLimits::is_expired`]), until the server shuts down.
+pub(crate) async fn sweep_expired_games(
+    games: GameIdManagerMapping,
+    game_codes: GameCodeMapping,
+    limits: GameLimits,
+    mut shutdown: Shutdown,
+) {
+    let mut interval = time::interval(SWEEP_INTERVAL);
+
+    loop {
+        select! {
+            _ = interval.tick() => {},
+            _ = &mut shutdown => break,
+        };
+
+        for (game_id, game) in evict_expired_games(&games, &game_codes, &limits, Instant::now()) {
+            if let Some(archive_dir) = &limits.archive_dir {
+                archive_game(archive_dir, game_id, &game).await;
+            }
+        }
+    }
+}
+
+/// Removes the games which expired as of `now`, along with their codes, and returns them.
+fn evict_expired_games(
+    games: &DashMap<Uuid, Game>,
+    game_codes: &DashMap<String, Uuid>,
+    limits: &GameLimits,
+    now: Instant,
+) -> Vec<(Uuid, Game)> {
+    let expired_game_ids: Vec<Uuid> = games
+        .iter()
+        .filter(|game| limits.is_expired(game.value(), now))
+        .map(|game| *game.key())
+        .collect();
+
+    expired_game_ids
+        .into_iter()
+        // The game may have seen some activity since it was found to be expired.
+        .filter_map(|game_id| games.remove_if(&game_id, |_, game| limits.is_expired(game, now)))
+        .inspect(|(_, game)| {
+            if let Some(game_code) = game.code() {
+                game_codes.remove(game_code);
+            }
+        })
+        .collect()
+}
+
+/// Writes the archive of the game to `<archive_dir>/<game_id>.json`.
+async fn archive_game(archive_dir: &PathBuf, game_id: Uuid, game: &Game) {
+    let archive = GameArchive {
+        game_id,
+        game_code: game.code(),
+        created_at: game.created_at(),
+        archived_at: unix_now(),
+        events: game.manager().events(),
+    };
+    let archive =
+        serde_json::to_vec(&archive).expect("Game archives should never fail serializing as JSON");
+
+    let path = archive_dir.join(format!("{}.json", game_id));
+    let written = match fs::create_dir_all(archive_dir).await {
+        Ok(()) => fs::write(&path, archive).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = written {
+        warn!(%game_id, error = %e, "Could not archive game");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request_types::CreateGameOptions;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    fn new_game() -> Game {
+        Game::new(&CreateGameOptions::default())
+    }
+
+    #[test]
+    fn game_limits_from_figment() {
+        let figment = Figment::from(rocket::Config::default())
+            .merge(("max_games", 3))
+            .merge(("finished_game_ttl", 5))
+            .merge(("archive_dir", "archive"));
+
+        assert_eq!(
+            GameLimits::from_figment(&figment),
+            GameLimits {
+                max_games: 3,
+                finished_game_ttl: 5 * SECOND,
+                archive_dir: Some(PathBuf::from("archive")),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            GameLimits::from_figment(&Figment::from(rocket::Config::default())),
+            GameLimits::default()
+        );
+    }
+
+    #[test]
+    fn game_limits_ttl() {
+        let limits = GameLimits::default();
+
+        assert_eq!(
+            limits.ttl(GamePhase::InLobby, false),
+            DEFAULT_IDLE_LOBBY_TTL
+        );
+        assert_eq!(limits.ttl(GamePhase::Playing, false), DEFAULT_IDLE_GAME_TTL);
+        assert_eq!(
+            limits.ttl(GamePhase::LastTurn, false),
+            DEFAULT_IDLE_GAME_TTL
+        );
+        assert_eq!(
+            limits.ttl(GamePhase::Done, false),
+            DEFAULT_FINISHED_GAME_TTL
+        );
+
+        // Only games in progress are kept longer when played by correspondence.
+        assert_eq!(limits.ttl(GamePhase::InLobby, true), DEFAULT_IDLE_LOBBY_TTL);
+        assert_eq!(
+            limits.ttl(GamePhase::Playing, true),
+            DEFAULT_IDLE_CORRESPONDENCE_GAME_TTL
+        );
+        assert_eq!(
+            limits.ttl(GamePhase::LastTurn, true),
+            DEFAULT_IDLE_CORRESPONDENCE_GAME_TTL
+        );
+        assert_eq!(limits.ttl(GamePhase::Done, true), DEFAULT_FINISHED_GAME_TTL);
+    }
+
+    #[test]
+    fn game_limits_ensure_capacity() {
+        let limits = GameLimits {
+            max_games: 2,
+            ..Default::default()
+        };
+        let games = DashMap::new();
+
+        for _ in 0..2 {
+            assert!(limits.ensure_capacity(&games).is_ok());
+            games.insert(Uuid::new_v4(), new_game());
+        }
+        assert!(limits.ensure_capacity(&games).is_err());
+    }
+
+    #[test]
+    fn game_limits_reserve_capacity() {
+        let limits = GameLimits {
+            max_games: 8,
+            ..Default::default()
+        };
+        let games = DashMap::new();
+
+        // Concurrent creations never go over the limit.
+        std::thread::scope(|scope| {
+            for _ in 0..32 {
+                scope.spawn(|| {
+                    if let Ok(_reservation) = limits.reserve_capacity(&games) {
+                        std::thread::yield_now();
+                        games.insert(Uuid::new_v4(), new_game());
+                    }
+                });
+            }
+        });
+        assert_eq!(games.len(), 8);
+
+        // Dropping a reservation without inserting a game frees the room.
+        let game_id = *games.iter().next().unwrap().key();
+        games.remove(&game_id);
+        drop(limits.reserve_capacity(&games).unwrap());
+        assert!(limits.reserve_capacity(&games).is_ok());
+        assert!(limits.clone().reserve_capacity(&games).is_ok());
+    }
+
+    #[test]
+    fn evict_expired_games_by_phase() {
+        let limits = GameLimits {
+            idle_lobby_ttl: 60 * SECOND,
+            idle_game_ttl: 120 * SECOND,
+            ..Default::default()
+        };
+        let games = DashMap::new();
+        let game_codes = DashMap::new();
+
+        let lobby_id = Uuid::new_v4();
+        let mut lobby = new_game();
+        lobby.set_code(String::from("LOBBY2"));
+        game_codes.insert(String::from("LOBBY2"), lobby_id);
+        games.insert(lobby_id, lobby);
+
+        let started_game_id = Uuid::new_v4();
+        let mut started_game = new_game();
+        started_game.add_player();
+        started_game.add_player();
+        assert!(started_game.force_start(0).is_ok());
+        games.insert(started_game_id, started_game);
+
+        let now = games.get(&started_game_id).unwrap().last_activity();
+        assert!(evict_expired_games(&games, &game_codes, &limits, now + 30 * SECOND).is_empty());
+
+        let evicted = evict_expired_games(&games, &game_codes, &limits, now + 90 * SECOND);
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(evicted[0].0, lobby_id);
+        assert!(!games.contains_key(&lobby_id));
+        assert!(game_codes.is_empty());
+
+        let evicted = evict_expired_games(&games, &game_codes, &limits, now + 150 * SECOND);
+        assert_eq!(evicted.len(), 1);
+        assert!(games.is_empty());
+    }
+
+    #[test]
+    fn evict_expired_correspondence_games() {
+        let limits = GameLimits {
+            idle_game_ttl: 120 * SECOND,
+            idle_correspondence_game_ttl: 600 * SECOND,
+            ..Default::default()
+        };
+        let games = DashMap::new();
+        let game_codes = DashMap::new();
+
+        let game_id = Uuid::new_v4();
+        let mut game = new_game();
+        game.add_player();
+        game.add_player();
+        assert!(game.force_start(0).is_ok());
+        assert!(game.register_webhook(1, "https://example.com/hook").is_ok());
+        games.insert(game_id, game);
+
+        let now = games.get(&game_id).unwrap().last_activity();
+        assert!(evict_expired_games(&games, &game_codes, &limits, now + 150 * SECOND).is_empty());
+
+        let evicted = evict_expired_games(&games, &game_codes, &limits, now + 630 * SECOND);
+        assert_eq!(evicted.len(), 1);
+        assert!(games.is_empty());
+    }
+
+    #[test]
+    fn archive_game_events() {
+        let archive_dir = std::env::temp_dir().join(format!("archive-{}", Uuid::new_v4()));
+        let game_id = Uuid::new_v4();
+        let mut game = new_game();
+        game.add_player();
+
+        rocket::async_test(archive_game(&archive_dir, game_id, &game));
+
+        let archive: serde_json::Value = serde_json::from_str(
+            &std::fs::read_to_string(archive_dir.join(format!("{}.json", game_id))).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(archive["game_id"], game_id.to_string());
+        let events: Vec<GameEvent> = serde_json::from_value(archive["events"].clone()).unwrap();
+        assert_eq!(events, game.manager().events());
+
+        std::fs::remove_dir_all(archive_dir).unwrap();
+    }
+}
@@ -0,0 +1,155 @@
+//! Structured logging, via [`tracing`]: what happened in which game, and to whom.
+//!
+//! Every controller call runs in a span carrying the route, the game ID and the player ID (see
+//! [`controller_span`]), in which its outcome is logged. The events of every game are logged as well (see
+//! [`GameTracer`]), such that what happened in a game can be reconstructed from the logs alone.
+//!
+//! Logs are written to the standard output, in plain text or in JSON (see [`attach`]). In JSON, Rocket's own logs
+//! are replaced by the [`RequestLogger`], such that every line is JSON.
+
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::figment::Figment;
+use rocket::serde::Deserialize;
+use rocket::{Build, Orbit, Request, Response, Rocket};
+use ticket_to_ride::event::GameEvent;
+use ticket_to_ride::manager::{GamePhase, GameState};
+use ticket_to_ride::observer::GameObserver;
+use tracing::{info, info_span, Span};
+use tracing_subscriber::EnvFilter;
+use uuid::Uuid;
+
+/// Filter applied to logs, unless the `RUST_LOG` environment variable sets one (see [`EnvFilter`]).
+const DEFAULT_LOG_FILTER: &str = "info";
+
+/// How logs are formatted.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(crate = "rocket::serde", rename_all = "snake_case")]
+pub enum LogFormat {
+    /// Human-readable lines, with the fields of the event and its spans.
+    #[default]
+    Text,
+    /// One JSON object per line, with the fields of the event and its spans.
+    Json,
+}
+
+impl LogFormat {
+    /// Reads the format from Rocket's configuration: `log_format`, either `text` (by default) or `json`.
+    pub fn from_figment(figment: &Figment) -> Self {
+        figment.extract_inner("log_format").unwrap_or_default()
+    }
+}
+
+/// Installs the global logger, in the format read from Rocket's configuration (see [`LogFormat::from_figment`]).
+///
+/// In JSON, Rocket's own logs are turned off, and requests are logged by the [`RequestLogger`] instead.
+pub fn attach(rocket: Rocket<Build>) -> Rocket<Build> {
+    let log_format = LogFormat::from_figment(rocket.figment());
+    init(log_format);
+
+    match log_format {
+        LogFormat::Text => rocket,
+        LogFormat::Json => {
+            let figment = rocket.figment().clone().merge(("log_level", "off"));
+            rocket.configure(figment).attach(RequestLogger)
+        }
+    }
+}
+
+/// Installs the global logger, writing to the standard output in the given format.
+///
+/// Logs are filtered by the `RUST_LOG` environment variable, if set, e.g. `RUST_LOG=web_server=debug`. This does
+/// nothing if a logger was installed already.
+fn init(log_format: LogFormat) {
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_LOG_FILTER));
+    let subscriber = tracing_subscriber::fmt().with_env_filter(filter);
+    // Lets the test harness capture logs.
+    #[cfg(test)]
+    let subscriber = subscriber.with_test_writer();
+
+    let _ = match log_format {
+        LogFormat::Text => subscriber.try_init(),
+        LogFormat::Json => subscriber.json().flatten_event(true).try_init(),
+    };
+}
+
+/// Fairing which logs the launch of the server, and every response, in place of Rocket's own logs.
+pub struct RequestLogger;
+
+#[rocket::async_trait]
+impl Fairing for RequestLogger {
+    fn info(&self) -> Info {
+        Info {
+            name: "Request logger",
+            kind: Kind::Liftoff | Kind::Response,
+        }
+    }
+
+    async fn on_liftoff(&self, rocket: &Rocket<Orbit>) {
+        let config = rocket.config();
+
+        info!(address = %config.address, port = config.port, "Server launched");
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        let route = req.route().and_then(|route| route.name.as_deref());
+
+        info!(
+            method = %req.method(),
+            uri = %req.uri(),
+            route,
+            status = res.status().code,
+            "Request"
+        );
+    }
+}
+
+/// Returns the span of a controller call: the route it was made to, by which player, in which game.
+pub(crate) fn controller_span(route: &str, game_id: Uuid, player_id: usize) -> Span {
+    info_span!("controller", route, %game_id, player_id)
+}
+
+/// Logs everything that happens in a game, with its ID.
+///
+/// Events are logged in JSON, as they are archived (see [`crate::limits::GameArchive`]): the game can be replayed
+/// from them.
+pub struct GameTracer {
+    game_id: Uuid,
+}
+
+impl GameTracer {
+    /// Constructs a [`GameTracer`], for the game with the given ID.
+    pub fn new(game_id: Uuid) -> Self {
+        Self { game_id }
+    }
+}
+
+impl GameObserver for GameTracer {
+    fn on_action_applied(&mut self, event: &GameEvent, _state: &GameState) {
+        let event = serde_json::to_string(event).unwrap_or_default();
+
+        info!(game_id = %self.game_id, %event, "Game event");
+    }
+
+    fn on_phase_changed(&mut self, phase: GamePhase) {
+        info!(game_id = %self.game_id, ?phase, "Game phase changed");
+    }
+
+    fn on_game_over(&mut self, state: &GameState) {
+        info!(game_id = %self.game_id, turn = state.turn, "Game over");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_format_from_figment() {
+        let figment = Figment::from(rocket::Config::default());
+        assert_eq!(LogFormat::from_figment(&figment), LogFormat::Text);
+
+        let figment = figment.merge(("log_format", "json"));
+        assert_eq!(LogFormat::from_figment(&figment), LogFormat::Json);
+    }
+}
@@ -3,47 +3,180 @@
 #[macro_use]
 extern crate rocket;
 
+mod admin;
 mod authenticator;
+mod bearer_token;
 mod controller;
+mod frontend;
+mod game;
+mod game_code;
+mod limits;
+mod logging;
+mod metrics;
+mod openapi;
+mod rate_limit;
 mod request_types;
 mod response_types;
 mod router;
+mod signing;
+mod snapshot;
+mod unix_time;
+mod webhook;
 
 #[cfg(test)]
 mod router_tests;
 
 use crate::router::*;
 
+use admin::{AdminSecret, MaintenanceNotice, Readiness};
+use bearer_token::BearerTokenKey;
 use controller::GameIdManagerMapping;
-use rocket::fs::FileServer;
-
-/// Path to static files.
-const STATIC_FILES_PATH: &str = "../../frontend/build/static";
+use frontend::Frontend;
+use game_code::GameCodeMapping;
+use limits::GameLimits;
+use metrics::{Metrics, RequestMetrics};
+use openapi::ApiDocument;
+use rate_limit::RateLimiter;
+use rocket::fairing::AdHoc;
+use rocket::{Build, Rocket};
+use snapshot::{Drain, GracefulShutdown};
+use webhook::WebhookDispatcher;
 
 #[launch]
 /// Launches the web server.
 fn rocket() -> _ {
-    let game_id_manager_mapping = GameIdManagerMapping::new();
-    rocket::build()
+    server(rocket::build())
+}
+
+/// Sets up the web server, configured by the given Rocket instance: routes, managed state, and background tasks.
+fn server(rocket: Rocket<Build>) -> Rocket<Build> {
+    let game_id_manager_mapping = GameIdManagerMapping::default();
+    let rocket = logging::attach(rocket);
+    let bearer_token_key = BearerTokenKey::from_figment(rocket.figment());
+    let webhook_dispatcher = WebhookDispatcher::from_figment(rocket.figment());
+    let game_limits = GameLimits::from_figment(rocket.figment());
+    let rate_limiter = RateLimiter::from_figment(rocket.figment());
+    let admin_secret = AdminSecret::from_figment(rocket.figment());
+    let frontend = Frontend::from_figment(rocket.figment());
+    let game_codes = GameCodeMapping::default();
+    let snapshot_file = snapshot::snapshot_file_from_figment(rocket.figment());
+
+    if let Some(snapshot_file) = &snapshot_file {
+        snapshot::restore_games(
+            snapshot_file,
+            &game_id_manager_mapping,
+            &game_codes,
+            &webhook_dispatcher,
+        );
+    }
+
+    #[cfg(debug_assertions)]
+    let rocket = rocket.mount("/", routes![receive_webhook]);
+
+    rocket
         .mount(
             "/",
             routes![
-                change_player_color,
-                change_player_name,
-                claim_route,
                 create_game,
-                draw_close_train_card,
-                draw_destination_cards,
-                draw_open_train_card,
-                get_game_state,
+                get_metrics,
+                healthz,
                 index,
                 load_game,
+                quick_match,
+                readyz,
+                rejoin_game,
                 robots,
                 root,
-                select_destination_cards,
-                set_player_ready,
+                spectate_game,
+                static_file,
             ],
         )
-        .mount("/static", FileServer::from(STATIC_FILES_PATH))
+        .mount("/", frontend_routes())
+        .mount(API_BASE, api_routes())
+        .mount(API_BASE, routes![get_openapi])
+        .register("/", catchers![too_many_requests])
         .manage(game_id_manager_mapping)
+        .manage(game_codes)
+        .manage(game_limits)
+        .manage(rate_limiter)
+        .manage(Metrics::default())
+        .manage(bearer_token_key)
+        .manage(webhook_dispatcher)
+        .manage(admin_secret)
+        .manage(frontend)
+        .manage(MaintenanceNotice::default())
+        .manage(Readiness::default())
+        .manage(Drain::default())
+        .manage(ApiDocument::default())
+        .attach(RequestMetrics)
+        .attach(GracefulShutdown::new(snapshot_file))
+        .attach(AdHoc::on_liftoff("Readiness", |rocket| {
+            Box::pin(async move {
+                rocket
+                    .state::<Readiness>()
+                    .expect("Readiness is managed by the server")
+                    .track(rocket.shutdown());
+            })
+        }))
+        .attach(AdHoc::on_liftoff("Time controls", |rocket| {
+            Box::pin(async move {
+                let games = rocket
+                    .state::<GameIdManagerMapping>()
+                    .expect("Games are managed by the server")
+                    .clone();
+
+                rocket::tokio::spawn(controller::enforce_time_controls(games, rocket.shutdown()));
+            })
+        }))
+        .attach(AdHoc::on_liftoff("Game expiry", |rocket| {
+            Box::pin(async move {
+                let games = rocket
+                    .state::<GameIdManagerMapping>()
+                    .expect("Games are managed by the server")
+                    .clone();
+                let game_codes = rocket
+                    .state::<GameCodeMapping>()
+                    .expect("Game codes are managed by the server")
+                    .clone();
+                let limits = rocket
+                    .state::<GameLimits>()
+                    .expect("Game limits are managed by the server")
+                    .clone();
+
+                rocket::tokio::spawn(limits::sweep_expired_games(
+                    games,
+                    game_codes,
+                    limits,
+                    rocket.shutdown(),
+                ));
+            })
+        }))
+        .attach(AdHoc::on_liftoff("Rate limits", |rocket| {
+            Box::pin(async move {
+                let rate_limiter = rocket
+                    .state::<RateLimiter>()
+                    .expect("Rate limits are managed by the server")
+                    .clone();
+
+                rocket::tokio::spawn(rate_limit::sweep_full_buckets(
+                    rate_limiter,
+                    rocket.shutdown(),
+                ));
+            })
+        }))
+        .attach(AdHoc::on_liftoff("Webhooks", |rocket| {
+            Box::pin(async move {
+                let pending_deliveries = rocket
+                    .state::<WebhookDispatcher>()
+                    .expect("Webhooks are managed by the server")
+                    .take_pending_deliveries();
+
+                if let Some(pending_deliveries) = pending_deliveries {
+                    rocket::tokio::spawn(webhook::deliver_webhooks(
+                        pending_deliveries,
+                        rocket.shutdown(),
+                    ));
+                }
+            })
+        }))
 }
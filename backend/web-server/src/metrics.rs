@@ -0,0 +1,367 @@
+//! Metrics about the server, exposed at `/metrics` in the Prometheus text format (see [`crate::router::get_metrics`]).
+//!
+//! Requests are measured by the [`RequestMetrics`] fairing, around the router: how long they took, per route, and
+//! how player actions turned out (see [`ActionResponse`]). Games and players are counted when metrics are scraped.
+
+use crate::game::Game;
+use crate::response_types::ActionResponse;
+
+use dashmap::DashMap;
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::{ContentType, Method};
+use rocket::{Request, Response};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::io::Cursor;
+use std::time::{Duration, Instant};
+use ticket_to_ride::manager::GamePhase;
+use ticket_to_ride::map::Map;
+use uuid::Uuid;
+
+/// Prefix of every metric exposed by the server.
+const PREFIX: &str = "ticket_to_ride";
+
+/// Upper bounds of the buckets of request latencies, in seconds.
+const LATENCY_BUCKETS: [f64; 11] = [
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1., 2.5,
+];
+
+/// Phases games are counted by, such that phases with no games are reported as well.
+const PHASES: [GamePhase; 5] = [
+    GamePhase::InLobby,
+    GamePhase::Starting,
+    GamePhase::Playing,
+    GamePhase::LastTurn,
+    GamePhase::Done,
+];
+
+/// Label of requests which did not match any route.
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// Latencies of the requests to a route.
+#[derive(Debug, Default)]
+struct Latencies {
+    /// How many requests took at most the corresponding bound of [`LATENCY_BUCKETS`], but more than the previous one.
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: Duration,
+}
+
+impl Latencies {
+    fn observe(&mut self, latency: Duration) {
+        let seconds = latency.as_secs_f64();
+
+        if let Some(bucket) = LATENCY_BUCKETS.iter().position(|bound| seconds <= *bound) {
+            self.buckets[bucket] += 1;
+        }
+        self.count += 1;
+        self.sum += latency;
+    }
+}
+
+/// Metrics recorded as requests are handled, shared between request handlers.
+///
+/// Managed by Rocket, and recorded by the [`RequestMetrics`] fairing.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    /// Latencies of requests, per route name.
+    latencies: DashMap<String, Latencies>,
+    /// Responses, per route name and status code.
+    responses: DashMap<(String, u16), u64>,
+    /// Player actions, per route name and whether they succeeded.
+    actions: DashMap<(String, bool), u64>,
+}
+
+impl Metrics {
+    fn record_request(&self, route: &str, status: u16, latency: Duration) {
+        self.latencies
+            .entry(route.to_owned())
+            .or_default()
+            .observe(latency);
+        *self
+            .responses
+            .entry((route.to_owned(), status))
+            .or_default() += 1;
+    }
+
+    fn record_action(&self, route: &str, success: bool) {
+        *self.actions.entry((route.to_owned(), success)).or_default() += 1;
+    }
+
+    /// Renders the metrics in the Prometheus text format, counting the given games and their players.
+    pub fn render(&self, games: &DashMap<Uuid, Game>) -> String {
+        let mut games_per_phase: BTreeMap<&'static str, (usize, usize)> = PHASES
+            .iter()
+            .map(|phase| (phase_label(*phase), (0, 0)))
+            .collect();
+        for game in games.iter() {
+            let manager = game.manager();
+            let (num_games, num_players) = games_per_phase
+                .entry(phase_label(manager.phase()))
+                .or_default();
+
+            *num_games += 1;
+            *num_players += manager.num_players();
+        }
+
+        let mut out = String::new();
+
+        header(
+            &mut out,
+            "games",
+            "gauge",
+            "Games hosted by the server, per phase.",
+        );
+        for (phase, (num_games, _)) in &games_per_phase {
+            sample(&mut out, "games", &[("phase", phase)], num_games);
+        }
+
+        header(
+            &mut out,
+            "players",
+            "gauge",
+            "Players seated in the games hosted by the server, per phase.",
+        );
+        for (phase, (_, num_players)) in &games_per_phase {
+            sample(&mut out, "players", &[("phase", phase)], num_players);
+        }
+
+        header(
+            &mut out,
+            "actions_total",
+            "counter",
+            "Player actions, per route and outcome.",
+        );
+        for ((route, success), count) in sorted(&self.actions) {
+            let outcome = if success { "success" } else { "failure" };
+            sample(
+                &mut out,
+                "actions_total",
+                &[("route", &route), ("outcome", outcome)],
+                count,
+            );
+        }
+
+        header(
+            &mut out,
+            "http_responses_total",
+            "counter",
+            "HTTP responses, per route and status code.",
+        );
+        for ((route, status), count) in sorted(&self.responses) {
+            sample(
+                &mut out,
+                "http_responses_total",
+                &[("route", &route), ("status", &status.to_string())],
+                count,
+            );
+        }
+
+        header(
+            &mut out,
+            "http_request_duration_seconds",
+            "histogram",
+            "Time spent handling HTTP requests, per route.",
+        );
+        let mut latencies: Vec<_> = self.latencies.iter().collect();
+        latencies.sort_by(|a, b| a.key().cmp(b.key()));
+        for route_latencies in latencies {
+            let (route, latencies) = route_latencies.pair();
+            let mut cumulative_count = 0;
+
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(latencies.buckets) {
+                cumulative_count += count;
+                sample(
+                    &mut out,
+                    "http_request_duration_seconds_bucket",
+                    &[("route", route), ("le", &bound.to_string())],
+                    cumulative_count,
+                );
+            }
+            sample(
+                &mut out,
+                "http_request_duration_seconds_bucket",
+                &[("route", route), ("le", "+Inf")],
+                latencies.count,
+            );
+            sample(
+                &mut out,
+                "http_request_duration_seconds_sum",
+                &[("route", route)],
+                latencies.sum.as_secs_f64(),
+            );
+            sample(
+                &mut out,
+                "http_request_duration_seconds_count",
+                &[("route", route)],
+                latencies.count,
+            );
+        }
+
+        let longest_route_timings = Map::longest_route_timings();
+        header(
+            &mut out,
+            "longest_route_seconds",
+            "summary",
+            "Time spent computing players' longest routes, once games are over.",
+        );
+        sample(
+            &mut out,
+            "longest_route_seconds_sum",
+            &[],
+            longest_route_timings.total.as_secs_f64(),
+        );
+        sample(
+            &mut out,
+            "longest_route_seconds_count",
+            &[],
+            longest_route_timings.computations,
+        );
+
+        out
+    }
+}
+
+/// Returns the label of the phase, as it is serialized in JSON.
+fn phase_label(phase: GamePhase) -> &'static str {
+    match phase {
+        GamePhase::InLobby => "in_lobby",
+        GamePhase::Starting => "starting",
+        GamePhase::Playing => "playing",
+        GamePhase::LastTurn => "last_turn",
+        GamePhase::Done => "done",
+    }
+}
+
+/// Returns the counters, sorted by their labels such that the output is stable.
+fn sorted<K: Clone + Ord + std::hash::Hash>(counters: &DashMap<K, u64>) -> Vec<(K, u64)> {
+    let mut counters: Vec<(K, u64)> = counters
+        .iter()
+        .map(|counter| (counter.key().clone(), *counter.value()))
+        .collect();
+
+    counters.sort();
+    counters
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {}_{} {}", PREFIX, name, help);
+    let _ = writeln!(out, "# TYPE {}_{} {}", PREFIX, name, kind);
+}
+
+fn sample(out: &mut String, name: &str, labels: &[(&str, &str)], value: impl std::fmt::Display) {
+    let _ = write!(out, "{}_{}", PREFIX, name);
+
+    if !labels.is_empty() {
+        let labels: Vec<String> = labels
+            .iter()
+            .map(|(label, value)| format!("{}=\"{}\"", label, escape_label_value(value)))
+            .collect();
+        let _ = write!(out, "{{{}}}", labels.join(","));
+    }
+
+    let _ = writeln!(out, " {}", value);
+}
+
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// When the request started being handled, cached in the request by [`RequestMetrics`].
+struct RequestStart(Instant);
+
+/// Fairing which records metrics about every request in the [`Metrics`] managed by Rocket.
+///
+/// Responses to write requests are inspected: if they are an [`ActionResponse`], whether the action succeeded is
+/// recorded.
+pub struct RequestMetrics;
+
+#[rocket::async_trait]
+impl Fairing for RequestMetrics {
+    fn info(&self) -> Info {
+        Info {
+            name: "Request metrics",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, req: &mut Request<'_>, _: &mut rocket::Data<'_>) {
+        req.local_cache(|| RequestStart(Instant::now()));
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        let metrics = match req.rocket().state::<Metrics>() {
+            Some(metrics) => metrics,
+            None => return,
+        };
+        let route = req
+            .route()
+            .and_then(|route| route.name.as_deref())
+            .unwrap_or(UNMATCHED_ROUTE);
+
+        if req.method() != Method::Get && res.content_type() == Some(ContentType::JSON) {
+            // Action responses are tiny: buffering them is cheap.
+            if let Ok(body) = res.body_mut().to_bytes().await {
+                if let Ok(action_response) = serde_json::from_slice::<ActionResponse>(&body) {
+                    metrics.record_action(route, action_response.success);
+                }
+                res.set_sized_body(body.len(), Cursor::new(body));
+            }
+        }
+
+        let started_at = req.local_cache(|| RequestStart(Instant::now())).0;
+        metrics.record_request(route, res.status().code, started_at.elapsed());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request_types::CreateGameOptions;
+
+    #[test]
+    fn metrics_render() {
+        let metrics = Metrics::default();
+        metrics.record_request("claim_route", 200, Duration::from_millis(3));
+        metrics.record_request("claim_route", 200, Duration::from_secs(5));
+        metrics.record_action("claim_route", true);
+        metrics.record_action("claim_route", false);
+        metrics.record_action("claim_route", false);
+
+        let games = DashMap::new();
+        let mut game = Game::new(&CreateGameOptions::default());
+        game.add_player();
+        game.add_player();
+        games.insert(Uuid::new_v4(), game);
+
+        let rendered = metrics.render(&games);
+        let lines: Vec<&str> = rendered.lines().collect();
+
+        for line in [
+            "# TYPE ticket_to_ride_games gauge",
+            "ticket_to_ride_games{phase=\"in_lobby\"} 1",
+            "ticket_to_ride_games{phase=\"playing\"} 0",
+            "ticket_to_ride_players{phase=\"in_lobby\"} 2",
+            "ticket_to_ride_actions_total{route=\"claim_route\",outcome=\"failure\"} 2",
+            "ticket_to_ride_actions_total{route=\"claim_route\",outcome=\"success\"} 1",
+            "ticket_to_ride_http_responses_total{route=\"claim_route\",status=\"200\"} 2",
+            "ticket_to_ride_http_request_duration_seconds_bucket{route=\"claim_route\",le=\"0.001\"} 0",
+            "ticket_to_ride_http_request_duration_seconds_bucket{route=\"claim_route\",le=\"0.005\"} 1",
+            "ticket_to_ride_http_request_duration_seconds_bucket{route=\"claim_route\",le=\"2.5\"} 1",
+            "ticket_to_ride_http_request_duration_seconds_bucket{route=\"claim_route\",le=\"+Inf\"} 2",
+            "ticket_to_ride_http_request_duration_seconds_sum{route=\"claim_route\"} 5.003",
+            "ticket_to_ride_http_request_duration_seconds_count{route=\"claim_route\"} 2",
+            "# TYPE ticket_to_ride_longest_route_seconds summary",
+        ] {
+            assert!(lines.contains(&line), "Missing `{}` in:\n{}", line, rendered);
+        }
+    }
+
+    #[test]
+    fn escape_label_values() {
+        assert_eq!(escape_label_value("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
@@ -0,0 +1,683 @@
+//! The OpenAPI document of the JSON API, served at `/api/v1/openapi.json` (see [`crate::router::get_openapi`]).
+//!
+//! Schemas are generated from the request and response types, and from the game state (see
+//! [`ticket_to_ride::manager::GameState`]). Operations are listed by hand in [`operations`], by the name of the
+//! route serving them: tests check that they match the routes mounted under [`API_BASE`].
+
+use crate::request_types::*;
+use crate::response_types::*;
+use crate::router::API_BASE;
+
+use rocket::http::{Method, Status};
+use schemars::gen::{SchemaGenerator, SchemaSettings};
+use schemars::schema::{Schema, SchemaObject, SingleOrVec, SubschemaValidation};
+use schemars::visit::{self, Visitor};
+use schemars::JsonSchema;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use ticket_to_ride::manager::GameState;
+
+/// The OpenAPI document, generated once.
+///
+/// Managed by Rocket.
+pub struct ApiDocument(pub(crate) String);
+
+impl Default for ApiDocument {
+    fn default() -> Self {
+        Self(document().to_string())
+    }
+}
+
+/// Who can call an operation, and how they authenticate.
+#[derive(Clone, Copy, PartialEq)]
+enum Auth {
+    /// Anyone, without authenticating.
+    Public,
+    /// Players of the game, with a bearer token (see [`crate::bearer_token`]).
+    Player,
+    /// Admins, with the admin secret (see [`crate::admin`]).
+    Admin,
+}
+
+/// A response of an operation: its status, and what its body holds, if anything.
+struct Response {
+    status: Status,
+    description: &'static str,
+    /// The media type of the body, and its schema.
+    content: Option<(&'static str, Schema)>,
+}
+
+/// An operation of the JSON API: a route, what it takes, and what it responds with.
+struct Operation {
+    method: Method,
+    /// Path of the operation, relative to [`API_BASE`], with parameters in braces (e.g. `/game/{game_id}/state`).
+    path: &'static str,
+    /// Name of the route serving the operation, which is also the ID of the operation.
+    route: &'static str,
+    summary: &'static str,
+    auth: Auth,
+    /// Optional query parameters, by name, with their description.
+    query: Vec<(&'static str, &'static str)>,
+    /// Schema of the JSON body of the request, if any.
+    request: Option<Schema>,
+    responses: Vec<Response>,
+}
+
+impl Operation {
+    fn new(method: Method, path: &'static str, route: &'static str, summary: &'static str) -> Self {
+        Self {
+            method,
+            path,
+            route,
+            summary,
+            auth: Auth::Public,
+            query: Vec::new(),
+            request: None,
+            responses: Vec::new(),
+        }
+    }
+
+    fn auth(mut self, auth: Auth) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    fn query(mut self, name: &'static str, description: &'static str) -> Self {
+        self.query.push((name, description));
+        self
+    }
+
+    /// Takes a JSON body.
+    fn request<T: JsonSchema>(mut self, gen: &mut SchemaGenerator) -> Self {
+        self.request = Some(schema_for::<T>(gen));
+        self
+    }
+
+    /// Responds with a JSON body.
+    fn json<T: JsonSchema>(self, gen: &mut SchemaGenerator, description: &'static str) -> Self {
+        let schema = schema_for::<T>(gen);
+        self.respond(Status::Ok, description, Some(("application/json", schema)))
+    }
+
+    /// Responds with whether the player action succeeded (see [`ActionResponse`]).
+    fn action(self, gen: &mut SchemaGenerator) -> Self {
+        self.json::<ActionResponse>(gen, "Whether the action succeeded, and why not otherwise.")
+    }
+
+    /// Responds without a body.
+    fn status(self, status: Status, description: &'static str) -> Self {
+        self.respond(status, description, None)
+    }
+
+    fn respond(
+        mut self,
+        status: Status,
+        description: &'static str,
+        content: Option<(&'static str, Schema)>,
+    ) -> Self {
+        self.responses.push(Response {
+            status,
+            description,
+            content,
+        });
+        self
+    }
+
+    /// Returns the operation as an OpenAPI operation object.
+    fn to_json(&self) -> Value {
+        let mut parameters: Vec<Value> = path_parameters(self.path)
+            .map(|name| {
+                json!({
+                    "name": name,
+                    "in": "path",
+                    "required": true,
+                    "description": "ID of the game, or its code.",
+                    "schema": {"type": "string"},
+                })
+            })
+            .collect();
+        let mut query = self.query.clone();
+        if self.auth == Auth::Player {
+            query.push(("seat", "In hot-seat games, the seat to act as."));
+        }
+        parameters.extend(query.into_iter().map(|(name, description)| {
+            json!({
+                "name": name,
+                "in": "query",
+                "required": false,
+                "description": description,
+                "schema": {"type": "string"},
+            })
+        }));
+
+        let mut responses = BTreeMap::new();
+        for response in &self.responses {
+            let mut object = json!({"description": response.description});
+            if let Some((media_type, schema)) = &response.content {
+                object["content"] = json!({media_type.to_string(): {"schema": schema}});
+            }
+            responses.insert(response.status.code.to_string(), object);
+        }
+        // Failures common to every operation authenticating the same way, unless documented already.
+        let failures: &[(Status, &str)] = match self.auth {
+            Auth::Public => &[],
+            Auth::Player => &[
+                (Status::Unauthorized, "The player is not authenticated."),
+                (Status::NotFound, "No games are found for that ID."),
+            ],
+            Auth::Admin => &[
+                (
+                    Status::Unauthorized,
+                    "The admin secret is missing or wrong.",
+                ),
+                (Status::NotFound, "The admin API is disabled."),
+            ],
+        };
+        for (status, description) in failures {
+            responses
+                .entry(status.code.to_string())
+                .or_insert_with(|| json!({ "description": description }));
+        }
+
+        let mut operation = json!({
+            "operationId": self.route,
+            "summary": self.summary,
+            "parameters": parameters,
+            "responses": responses,
+        });
+        if let Some(request) = &self.request {
+            operation["requestBody"] = json!({
+                "required": true,
+                "content": {"application/json": {"schema": request}},
+            });
+        }
+        let security = match self.auth {
+            Auth::Public => None,
+            Auth::Player => Some("player"),
+            Auth::Admin => Some("admin"),
+        };
+        if let Some(security) = security {
+            operation["security"] = json!([{ security: [] }]);
+        }
+        operation
+    }
+}
+
+/// Returns the names of the parameters of the path (e.g. `game_id` in `/game/{game_id}/state`).
+fn path_parameters(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter_map(|segment| {
+        segment
+            .strip_prefix('{')
+            .and_then(|segment| segment.strip_suffix('}'))
+    })
+}
+
+/// Returns the schema of `T`, referring to the definitions of named types (which end up in the document's
+/// components).
+fn schema_for<T: JsonSchema>(gen: &mut SchemaGenerator) -> Schema {
+    let mut schema = gen.subschema_for::<T>();
+    for visitor in gen.visitors_mut() {
+        visitor.visit_schema(&mut schema);
+    }
+    schema
+}
+
+/// OpenAPI 3.0 has no tuples: their items are described as any of the types of the tuple instead (e.g. routes,
+/// which are pairs of cities, are arrays of cities).
+#[derive(Clone, Debug)]
+struct TupleItems;
+
+impl Visitor for TupleItems {
+    fn visit_schema_object(&mut self, schema: &mut SchemaObject) {
+        if let Some(array) = &mut schema.array {
+            if let Some(SingleOrVec::Vec(items)) = &array.items {
+                let mut items = items.clone();
+                items.dedup();
+                let item = if items.len() == 1 {
+                    items.remove(0)
+                } else {
+                    Schema::Object(SchemaObject {
+                        subschemas: Some(Box::new(SubschemaValidation {
+                            any_of: Some(items),
+                            ..Default::default()
+                        })),
+                        ..Default::default()
+                    })
+                };
+                array.items = Some(SingleOrVec::Single(Box::new(item)));
+            }
+        }
+
+        visit::visit_schema_object(self, schema);
+    }
+}
+
+/// Lists every operation of the JSON API, in the order of [`crate::router`].
+fn operations(gen: &mut SchemaGenerator) -> Vec<Operation> {
+    vec![
+        Operation::new(
+            Method::Get,
+            "/games",
+            "list_public_games",
+            "Lists the public games, from newest to oldest.",
+        )
+        .json::<Vec<PublicGameResponse>>(gen, "The public games."),
+        Operation::new(
+            Method::Post,
+            "/game/{game_id}/join",
+            "join_game",
+            "Seats a new player, who authenticates with the bearer token in the response.",
+        )
+        .query("code", "The invite code or password, in private games.")
+        .json::<BearerTokenResponse>(gen, "The bearer token of the new player.")
+        .status(
+            Status::Unauthorized,
+            "The game is private, and the invite code or password is missing or wrong.",
+        )
+        .status(Status::Forbidden, "The game does not let new players in.")
+        .status(Status::NotFound, "No games are found for that ID.")
+        .status(Status::TooManyRequests, "The client joined too many games lately."),
+        Operation::new(
+            Method::Get,
+            "/game/{game_id}/player/bearer_token",
+            "get_bearer_token",
+            "Issues a new bearer token for the player.",
+        )
+        .auth(Auth::Player)
+        .json::<BearerTokenResponse>(gen, "The bearer token."),
+        Operation::new(
+            Method::Get,
+            "/game/{game_id}/player/rejoin_link",
+            "get_rejoin_link",
+            "Retrieves the player's secret rejoin link, which seats whoever loads it back at the player's seat.",
+        )
+        .auth(Auth::Player)
+        .json::<RejoinLinkResponse>(gen, "The rejoin link."),
+        Operation::new(
+            Method::Get,
+            "/game/{game_id}/player/invite_link",
+            "get_invite_link",
+            "Retrieves a link which seats whoever loads it in the game, and the code of the game.",
+        )
+        .auth(Auth::Player)
+        .json::<InviteLinkResponse>(gen, "The invite link."),
+        Operation::new(
+            Method::Post,
+            "/game/{game_id}/host/regenerate_rejoin_token",
+            "regenerate_rejoin_token",
+            "Allows the host to replace the rejoin token of a seat.",
+        )
+        .auth(Auth::Player)
+        .request::<SeatRequest>(gen)
+        .action(gen),
+        Operation::new(
+            Method::Post,
+            "/game/{game_id}/host/revoke_sessions",
+            "revoke_sessions",
+            "Allows the host to log out every device currently seated at a seat.",
+        )
+        .auth(Auth::Player)
+        .request::<SeatRequest>(gen)
+        .action(gen),
+        Operation::new(
+            Method::Post,
+            "/game/{game_id}/host/kick_player",
+            "kick_player",
+            "Allows the host to remove a player from the game.",
+        )
+        .auth(Auth::Player)
+        .request::<SeatRequest>(gen)
+        .action(gen),
+        Operation::new(
+            Method::Post,
+            "/game/{game_id}/player/leave",
+            "leave_game",
+            "Allows the player to leave the game, even once it has started.",
+        )
+        .auth(Auth::Player)
+        .action(gen),
+        Operation::new(
+            Method::Post,
+            "/game/{game_id}/host/lock_lobby",
+            "lock_lobby",
+            "Allows the host to keep new players out of the lobby, or to let them in again.",
+        )
+        .auth(Auth::Player)
+        .request::<LockLobbyRequest>(gen)
+        .action(gen),
+        Operation::new(
+            Method::Post,
+            "/game/{game_id}/host/seating_order",
+            "set_seating_order",
+            "Allows the host to choose between a random and a manual seating order.",
+        )
+        .auth(Auth::Player)
+        .request::<SetSeatingOrderRequest>(gen)
+        .action(gen),
+        Operation::new(
+            Method::Post,
+            "/game/{game_id}/host/force_start",
+            "force_start",
+            "Allows the host to start the game, even if some players are not ready.",
+        )
+        .auth(Auth::Player)
+        .action(gen),
+        Operation::new(
+            Method::Post,
+            "/game/{game_id}/player/seat",
+            "add_seat",
+            "Adds a seat to a hot-seat game.",
+        )
+        .auth(Auth::Player)
+        .action(gen),
+        Operation::new(
+            Method::Post,
+            "/game/{game_id}/player/pass_device",
+            "pass_device",
+            "Confirms that the device of a hot-seat game was passed to the seat given with `seat`.",
+        )
+        .auth(Auth::Player)
+        .action(gen),
+        Operation::new(
+            Method::Put,
+            "/game/{game_id}/player/name",
+            "change_player_name",
+            "Changes the player's name, in the lobby.",
+        )
+        .auth(Auth::Player)
+        .request::<ChangeNameRequest>(gen)
+        .action(gen),
+        Operation::new(
+            Method::Put,
+            "/game/{game_id}/player/color",
+            "change_player_color",
+            "Changes the player's color, in the lobby.",
+        )
+        .auth(Auth::Player)
+        .request::<ChangeColorRequest>(gen)
+        .action(gen),
+        Operation::new(
+            Method::Put,
+            "/game/{game_id}/player/is_ready",
+            "set_player_ready",
+            "Marks the player as ready to start the game, or not.",
+        )
+        .auth(Auth::Player)
+        .request::<SetPlayerReadyRequest>(gen)
+        .action(gen),
+        Operation::new(
+            Method::Put,
+            "/game/{game_id}/player/select_destination_cards",
+            "select_destination_cards",
+            "Selects which of the pending destination cards the player keeps.",
+        )
+        .auth(Auth::Player)
+        .request::<SelectDestinationCardsRequest>(gen)
+        .action(gen),
+        Operation::new(
+            Method::Post,
+            "/game/{game_id}/player/draw_destination_cards",
+            "draw_destination_cards",
+            "Draws destination cards, which the player then selects from.",
+        )
+        .auth(Auth::Player)
+        .action(gen),
+        Operation::new(
+            Method::Post,
+            "/game/{game_id}/player/draw_open_train_card",
+            "draw_open_train_card",
+            "Draws one of the open train cards.",
+        )
+        .auth(Auth::Player)
+        .request::<DrawOpenTrainCardRequest>(gen)
+        .action(gen),
+        Operation::new(
+            Method::Post,
+            "/game/{game_id}/player/draw_close_train_card",
+            "draw_close_train_card",
+            "Draws a train card from the close deck.",
+        )
+        .auth(Auth::Player)
+        .action(gen),
+        Operation::new(
+            Method::Post,
+            "/game/{game_id}/player/claim_route",
+            "claim_route",
+            "Claims a route, with the given train cards.",
+        )
+        .auth(Auth::Player)
+        .request::<ClaimRouteRequest>(gen)
+        .action(gen),
+        Operation::new(
+            Method::Post,
+            "/game/{game_id}/player/webhook",
+            "register_webhook",
+            "Registers the player's webhook, notified of what happens in the game.",
+        )
+        .auth(Auth::Player)
+        .request::<RegisterWebhookRequest>(gen)
+        .json::<WebhookResponse>(gen, "The secret deliveries are signed with.")
+        .status(Status::BadRequest, "The URL is not a valid HTTP(S) URL."),
+        Operation::new(
+            Method::Delete,
+            "/game/{game_id}/player/webhook",
+            "unregister_webhook",
+            "Removes the player's webhook.",
+        )
+        .auth(Auth::Player)
+        .action(gen),
+        Operation::new(
+            Method::Post,
+            "/game/{game_id}/player/undo",
+            "request_undo",
+            "Asks for the last action to be undone.",
+        )
+        .auth(Auth::Player)
+        .action(gen),
+        Operation::new(
+            Method::Post,
+            "/game/{game_id}/player/undo/answer",
+            "answer_undo_request",
+            "Agrees, or refuses, to undo the last action.",
+        )
+        .auth(Auth::Player)
+        .request::<AnswerUndoRequest>(gen)
+        .action(gen),
+        Operation::new(
+            Method::Get,
+            "/game/{game_id}/state",
+            "get_game_state",
+            "Retrieves the game state, with the private state of the player.",
+        )
+        .auth(Auth::Player)
+        .json::<GameState<'static>>(gen, "The game state."),
+        Operation::new(
+            Method::Get,
+            "/notice",
+            "get_notice",
+            "Retrieves the maintenance notice posted by admins, if any.",
+        )
+        .json::<Option<NoticeResponse>>(gen, "The maintenance notice, or `null`."),
+        Operation::new(
+            Method::Get,
+            "/admin/games",
+            "admin_list_games",
+            "Lists every game hosted by the server, from newest to oldest.",
+        )
+        .auth(Auth::Admin)
+        .json::<Vec<AdminGameResponse>>(gen, "The games."),
+        Operation::new(
+            Method::Get,
+            "/admin/game/{game_id}/state",
+            "admin_get_game_state",
+            "Retrieves the full state of any game, with the private state of every player.",
+        )
+        .auth(Auth::Admin)
+        .json::<GameState<'static>>(gen, "The game state.")
+        .status(
+            Status::NotFound,
+            "The admin API is disabled, or no games are found for that ID.",
+        ),
+        Operation::new(
+            Method::Post,
+            "/admin/game/{game_id}/end",
+            "admin_end_game",
+            "Ends any game which has started, wherever it is at.",
+        )
+        .auth(Auth::Admin)
+        .action(gen)
+        .status(
+            Status::NotFound,
+            "The admin API is disabled, or no games are found for that ID.",
+        ),
+        Operation::new(
+            Method::Delete,
+            "/admin/game/{game_id}",
+            "admin_delete_game",
+            "Deletes any game, whatever its phase.",
+        )
+        .auth(Auth::Admin)
+        .status(Status::NoContent, "The game was deleted.")
+        .status(
+            Status::NotFound,
+            "The admin API is disabled, or no games are found for that ID.",
+        ),
+        Operation::new(
+            Method::Put,
+            "/admin/notice",
+            "admin_set_notice",
+            "Posts a maintenance notice, replacing the previous one.",
+        )
+        .auth(Auth::Admin)
+        .request::<NoticeRequest>(gen)
+        .status(Status::NoContent, "The notice was posted."),
+        Operation::new(
+            Method::Delete,
+            "/admin/notice",
+            "admin_clear_notice",
+            "Removes the maintenance notice.",
+        )
+        .auth(Auth::Admin)
+        .status(Status::NoContent, "The notice was removed."),
+        Operation::new(
+            Method::Get,
+            "/openapi.json",
+            "get_openapi",
+            "Retrieves this document.",
+        )
+        .json::<Value>(gen, "The OpenAPI document."),
+    ]
+}
+
+/// Generates the OpenAPI document of the JSON API.
+pub(crate) fn document() -> Value {
+    let mut gen = SchemaSettings::openapi3()
+        .with_visitor(TupleItems)
+        .into_generator();
+
+    let mut paths: BTreeMap<&str, Map<String, Value>> = BTreeMap::new();
+    for operation in operations(&mut gen) {
+        paths.entry(operation.path).or_default().insert(
+            operation.method.as_str().to_lowercase(),
+            operation.to_json(),
+        );
+    }
+
+    let mut schemas = gen.take_definitions();
+    for visitor in gen.visitors_mut() {
+        for schema in schemas.values_mut() {
+            visitor.visit_schema(schema);
+        }
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Ticket To Ride",
+            "description": "JSON API of the Ticket To Ride server. Players authenticate with a bearer token, \
+                returned when they join a game.",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "servers": [{"url": API_BASE}],
+        "paths": paths,
+        "components": {
+            "schemas": schemas,
+            "securitySchemes": {
+                "player": {
+                    "type": "http",
+                    "scheme": "bearer",
+                    "description": "Bearer token of a player (see `join_game` and `get_bearer_token`).",
+                },
+                "admin": {
+                    "type": "http",
+                    "scheme": "bearer",
+                    "description": "The admin secret, configured with `admin_secret`.",
+                },
+            },
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns every `$ref` in the value.
+    fn references(value: &Value) -> Vec<&str> {
+        match value {
+            Value::Object(object) => object
+                .iter()
+                .flat_map(|(key, value)| match (key.as_str(), value) {
+                    ("$ref", Value::String(reference)) => vec![reference.as_str()],
+                    _ => references(value),
+                })
+                .collect(),
+            Value::Array(array) => array.iter().flat_map(references).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    #[test]
+    fn document_references_resolve() {
+        let document = document();
+        let schemas = document["components"]["schemas"].as_object().unwrap();
+
+        for name in [
+            "GameState",
+            "PublicPlayerState",
+            "City",
+            "ClaimRouteRequest",
+        ] {
+            assert!(schemas.contains_key(name), "Missing schema `{}`", name);
+        }
+        for reference in references(&document) {
+            let name = reference.strip_prefix("#/components/schemas/").unwrap();
+            assert!(schemas.contains_key(name), "Dangling `{}`", reference);
+        }
+    }
+
+    #[test]
+    fn document_has_no_tuples() {
+        let document = document();
+        let route = &document["components"]["schemas"]["ClaimRouteRequest"]["properties"]["route"];
+
+        assert_eq!(route["type"], "array");
+        assert_eq!(route["items"]["$ref"], "#/components/schemas/City");
+    }
+
+    #[test]
+    fn operation_parameters() {
+        let document = document();
+        let claim_route = &document["paths"]["/game/{game_id}/player/claim_route"]["post"];
+        let parameters: Vec<&str> = claim_route["parameters"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|parameter| parameter["name"].as_str().unwrap())
+            .collect();
+
+        assert_eq!(parameters, ["game_id", "seat"]);
+        assert_eq!(claim_route["security"], json!([{"player": []}]));
+        assert!(claim_route["responses"]["401"].is_object());
+    }
+}
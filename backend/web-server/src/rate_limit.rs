@@ -0,0 +1,523 @@
+//! Rate limiting of requests that allocate or change server state: creating games, joining them, and acting in them.
+//!
+//! Every client gets a token bucket per kind of request (see [`Bucket`]): each request takes a token, and tokens
+//! are refilled at a constant rate, up to the bucket's capacity. Once a bucket is empty, requests are rejected with
+//! `429 Too Many Requests`, and a `Retry-After` header telling when the next token will be available (see
+//! [`crate::router::too_many_requests`]).
+//!
+//! Clients are identified by their IP address and, once authenticated, by their seat in the game. The address is the
+//! one of the connection (see [`Request::remote`]), as headers can be set by clients at will: behind a proxy, the
+//! header in which the proxy passes the client's address has to be configured (see [`RateLimits::ip_header`]).
+//! Requests whose IP address is unknown are only limited by seat.
+
+use crate::authenticator::Authenticator;
+
+use dashmap::DashMap;
+use rocket::figment::Figment;
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::tokio::{select, time};
+use rocket::Shutdown;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// How many games a client may create in a burst, unless configured otherwise.
+const DEFAULT_CREATE_BURST: u32 = 5;
+/// How many games a client may create per minute, once their burst is spent, unless configured otherwise.
+const DEFAULT_CREATE_PER_MINUTE: u32 = 10;
+
+/// How many games a client may join in a burst, unless configured otherwise.
+const DEFAULT_JOIN_BURST: u32 = 20;
+/// How many games a client may join per minute, once their burst is spent, unless configured otherwise.
+const DEFAULT_JOIN_PER_MINUTE: u32 = 60;
+
+/// How many actions a player may take in a burst, unless configured otherwise.
+const DEFAULT_PLAYER_ACTIONS_BURST: u32 = 60;
+/// How many actions a player may take per minute, once their burst is spent, unless configured otherwise.
+const DEFAULT_PLAYER_ACTIONS_PER_MINUTE: u32 = 600;
+
+/// How many actions may come from the same IP address in a burst, unless configured otherwise.
+///
+/// This is looser than the limit per player, as players of the same household or venue share their address.
+const DEFAULT_IP_ACTIONS_BURST: u32 = 120;
+/// How many actions may come from the same IP address per minute, once their burst is spent, unless configured
+/// otherwise.
+const DEFAULT_IP_ACTIONS_PER_MINUTE: u32 = 1200;
+
+/// How often buckets which were refilled to capacity are forgotten.
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Limit on the rate of a kind of request, as a token bucket.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateLimit {
+    /// How many requests can be made in a burst: the capacity of the bucket.
+    pub burst: u32,
+    /// How many tokens are refilled per minute.
+    pub per_minute: u32,
+}
+
+impl RateLimit {
+    /// Whether the limit is disabled, which is the case if either its burst or its rate is zero.
+    pub fn is_disabled(&self) -> bool {
+        self.burst == 0 || self.per_minute == 0
+    }
+
+    /// How many tokens are refilled per second.
+    fn per_second(&self) -> f64 {
+        f64::from(self.per_minute) / 60.
+    }
+}
+
+/// Limits on the rate of requests, read from Rocket's configuration (see [`RateLimits::from_figment`]).
+#[derive(Clone, Debug, PartialEq)]
+pub struct RateLimits {
+    /// Limit on the games created per IP address (see [`crate::router::create_game`]).
+    pub create: RateLimit,
+    /// Limit on the games joined per IP address (see [`crate::router::load_game`]).
+    pub join: RateLimit,
+    /// Limit on the actions taken per player (see [`crate::controller::WriteController`]).
+    pub player_actions: RateLimit,
+    /// Limit on the actions taken per IP address (see [`crate::controller::WriteController`]).
+    pub ip_actions: RateLimit,
+    /// Header holding the client's IP address, set by a trusted proxy in front of the server (e.g. `X-Real-IP`).
+    /// Without it, the address of the connection is used.
+    pub ip_header: Option<String>,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        Self {
+            create: RateLimit {
+                burst: DEFAULT_CREATE_BURST,
+                per_minute: DEFAULT_CREATE_PER_MINUTE,
+            },
+            join: RateLimit {
+                burst: DEFAULT_JOIN_BURST,
+                per_minute: DEFAULT_JOIN_PER_MINUTE,
+            },
+            player_actions: RateLimit {
+                burst: DEFAULT_PLAYER_ACTIONS_BURST,
+                per_minute: DEFAULT_PLAYER_ACTIONS_PER_MINUTE,
+            },
+            ip_actions: RateLimit {
+                burst: DEFAULT_IP_ACTIONS_BURST,
+                per_minute: DEFAULT_IP_ACTIONS_PER_MINUTE,
+            },
+            ip_header: None,
+        }
+    }
+}
+
+impl RateLimits {
+    /// Reads the limits from Rocket's configuration, under the `rate_limits` table: `create`, `join`,
+    /// `player_actions` and `ip_actions` each have a `burst` and a `per_minute` key, and `ip_header` names the header
+    /// of a trusted proxy (see [`RateLimits::ip_header`]).
+    ///
+    /// Missing keys keep their default. Setting either key of a limit to 0 disables it.
+    pub fn from_figment(figment: &Figment) -> Self {
+        let defaults = Self::default();
+        let limit = |name: &str, default: RateLimit| {
+            let key = |key: &str| format!("rate_limits.{}.{}", name, key);
+
+            RateLimit {
+                burst: figment
+                    .extract_inner(&key("burst"))
+                    .unwrap_or(default.burst),
+                per_minute: figment
+                    .extract_inner(&key("per_minute"))
+                    .unwrap_or(default.per_minute),
+            }
+        };
+
+        Self {
+            create: limit("create", defaults.create),
+            join: limit("join", defaults.join),
+            player_actions: limit("player_actions", defaults.player_actions),
+            ip_actions: limit("ip_actions", defaults.ip_actions),
+            ip_header: figment.extract_inner("rate_limits.ip_header").ok(),
+        }
+    }
+}
+
+/// A client's bucket for a kind of request.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum Bucket {
+    Create(IpAddr),
+    Join(IpAddr),
+    PlayerActions { game_id: Uuid, player_id: usize },
+    IpActions(IpAddr),
+}
+
+impl Bucket {
+    fn limit(self, limits: &RateLimits) -> RateLimit {
+        match self {
+            Self::Create(_) => limits.create,
+            Self::Join(_) => limits.join,
+            Self::PlayerActions { .. } => limits.player_actions,
+            Self::IpActions(_) => limits.ip_actions,
+        }
+    }
+}
+
+/// How many tokens are left in a bucket, as of when it was last updated.
+#[derive(Clone, Copy, Debug)]
+struct Tokens {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl Tokens {
+    fn full(limit: RateLimit, now: Instant) -> Self {
+        Self {
+            tokens: f64::from(limit.burst),
+            updated_at: now,
+        }
+    }
+
+    /// Refills the bucket for the time elapsed since it was last updated.
+    fn refill(&mut self, limit: RateLimit, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated_at).as_secs_f64();
+
+        self.tokens = (self.tokens + elapsed * limit.per_second()).min(f64::from(limit.burst));
+        self.updated_at = self.updated_at.max(now);
+    }
+
+    /// Takes a token from the bucket, or returns how long to wait until one is available.
+    fn take(&mut self, limit: RateLimit, now: Instant) -> Result<(), Duration> {
+        self.refill(limit, now);
+
+        if self.tokens >= 1. {
+            self.tokens -= 1.;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (1. - self.tokens) * 60. / f64::from(limit.per_minute),
+            ))
+        }
+    }
+}
+
+/// The kinds of request which are rate limited.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RateLimitScope {
+    /// Creating a game, per IP address.
+    Create,
+    /// Joining a game, per IP address.
+    Join,
+    /// Acting in a game, per player and per IP address.
+    Action,
+}
+
+/// Keeps track of the buckets of every client, shared between request handlers.
+///
+/// Managed by Rocket, and used through request guards (see [`CreateRateLimit`] and [`JoinRateLimit`]), or
+/// [`RateLimiter::limit_request`].
+#[derive(Clone, Debug, Default)]
+pub struct RateLimiter {
+    limits: RateLimits,
+    buckets: Arc<DashMap<Bucket, Tokens>>,
+}
+
+/// How long the client has to wait before retrying a rejected request, cached in the request for
+/// [`crate::router::too_many_requests`] to set the `Retry-After` header.
+#[derive(Debug, Default)]
+pub(crate) struct RetryAfter(pub Option<Duration>);
+
+impl RateLimiter {
+    /// Constructs a [`RateLimiter`], applying the given limits.
+    pub fn new(limits: RateLimits) -> Self {
+        Self {
+            limits,
+            buckets: Default::default(),
+        }
+    }
+
+    /// Constructs a [`RateLimiter`], applying the limits read from Rocket's configuration
+    /// (see [`RateLimits::from_figment`]).
+    pub fn from_figment(figment: &Figment) -> Self {
+        Self::new(RateLimits::from_figment(figment))
+    }
+
+    /// Takes a token from every given bucket, or returns how long to wait until a token is available in all of them.
+    ///
+    /// Tokens are only taken if they are available in every bucket, such that a rejected request costs nothing.
+    fn take(&self, buckets: &[Bucket], now: Instant) -> Result<(), Duration> {
+        let buckets: Vec<(Bucket, RateLimit)> = buckets
+            .iter()
+            .map(|bucket| (*bucket, bucket.limit(&self.limits)))
+            .filter(|(_, limit)| !limit.is_disabled())
+            .collect();
+
+        let retry_after = buckets
+            .iter()
+            .filter_map(|(bucket, limit)| {
+                let mut tokens = *self.buckets.get(bucket)?;
+                tokens.take(*limit, now).err()
+            })
+            .max();
+        if let Some(retry_after) = retry_after {
+            return Err(retry_after);
+        }
+
+        for (bucket, limit) in buckets {
+            // A concurrent request may have taken the last token in the meantime: let it slide.
+            let _ = self
+                .buckets
+                .entry(bucket)
+                .or_insert_with(|| Tokens::full(limit, now))
+                .take(limit, now);
+        }
+
+        Ok(())
+    }
+
+    /// Takes a token for the request from the client's buckets for the given kind of request.
+    ///
+    /// If the client made too many such requests, fails with `429 Too Many Requests`, and caches how long the client
+    /// has to wait in the request (see [`RetryAfter`]).
+    pub(crate) async fn limit_request(
+        &self,
+        req: &Request<'_>,
+        scope: RateLimitScope,
+    ) -> Outcome<(), Duration> {
+        let client_ip = self.client_ip(req);
+        let mut buckets = Vec::with_capacity(2);
+
+        match scope {
+            RateLimitScope::Create => buckets.extend(client_ip.map(Bucket::Create)),
+            RateLimitScope::Join => buckets.extend(client_ip.map(Bucket::Join)),
+            RateLimitScope::Action => {
+                buckets.extend(client_ip.map(Bucket::IpActions));
+                // Unauthenticated requests are rejected by the controller anyway.
+                if let Outcome::Success(authenticator) = req.guard::<Authenticator>().await {
+                    buckets.push(Bucket::PlayerActions {
+                        game_id: *authenticator.game_id(),
+                        player_id: authenticator.player_id(),
+                    });
+                }
+            }
+        }
+
+        match self.take(&buckets, Instant::now()) {
+            Ok(()) => Outcome::Success(()),
+            Err(retry_after) => {
+                req.local_cache(|| RetryAfter(Some(retry_after)));
+                Outcome::Failure((Status::TooManyRequests, retry_after))
+            }
+        }
+    }
+
+    /// Returns the IP address of the client: the one of the connection, unless a trusted proxy passes it in a header
+    /// (see [`RateLimits::ip_header`]).
+    fn client_ip(&self, req: &Request<'_>) -> Option<IpAddr> {
+        self.limits
+            .ip_header
+            .as_deref()
+            .and_then(|ip_header| req.headers().get_one(ip_header))
+            .and_then(|ip| ip.trim().parse().ok())
+            .or_else(|| req.remote().map(|remote| remote.ip()))
+    }
+
+    /// Forgets the buckets which were refilled to capacity by now, as they are no different from new buckets.
+    fn forget_full_buckets(&self, now: Instant) {
+        self.buckets.retain(|bucket, tokens| {
+            let limit = bucket.limit(&self.limits);
+            tokens.refill(limit, now);
+
+            tokens.tokens < f64::from(limit.burst)
+        });
+    }
+}
+
+/// Background task that forgets the buckets which were refilled to capacity, until the server shuts down.
+pub(crate) async fn sweep_full_buckets(rate_limiter: RateLimiter, mut shutdown: Shutdown) {
+    let mut interval = time::interval(SWEEP_INTERVAL);
+
+    loop {
+        select! {
+            _ = interval.tick() => {},
+            _ = &mut shutdown => break,
+        };
+
+        rate_limiter.forget_full_buckets(Instant::now());
+    }
+}
+
+/// Limits the rate at which clients create games (see [`RateLimits::create`]).
+///
+/// Implements [`rocket::request::FromRequest`], so it can be used as a request guard.
+pub struct CreateRateLimit;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for CreateRateLimit {
+    type Error = Duration;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        limit_request(req, RateLimitScope::Create)
+            .await
+            .map(|()| CreateRateLimit)
+    }
+}
+
+/// Limits the rate at which clients join games (see [`RateLimits::join`]).
+///
+/// Implements [`rocket::request::FromRequest`], so it can be used as a request guard.
+pub struct JoinRateLimit;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for JoinRateLimit {
+    type Error = Duration;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        limit_request(req, RateLimitScope::Join)
+            .await
+            .map(|()| JoinRateLimit)
+    }
+}
+
+/// Limits the request with the [`RateLimiter`] managed by Rocket, if any.
+pub(crate) async fn limit_request(
+    req: &Request<'_>,
+    scope: RateLimitScope,
+) -> Outcome<(), Duration> {
+    match req.rocket().state::<RateLimiter>() {
+        Some(rate_limiter) => rate_limiter.limit_request(req, scope).await,
+        None => Outcome::Success(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IP: IpAddr = IpAddr::V4(std::net::Ipv4Addr::LOCALHOST);
+
+    /// Returns how many seconds to wait, rounded to get rid of floating-point errors.
+    fn retry_after_secs(result: Result<(), Duration>) -> u64 {
+        result.unwrap_err().as_secs_f64().round() as u64
+    }
+
+    #[test]
+    fn rate_limits_from_figment() {
+        let figment = Figment::from(rocket::Config::default())
+            .merge(("rate_limits.create.burst", 1))
+            .merge(("rate_limits.ip_actions.per_minute", 0));
+
+        let rate_limits = RateLimits::from_figment(&figment);
+        assert_eq!(
+            rate_limits.create,
+            RateLimit {
+                burst: 1,
+                per_minute: DEFAULT_CREATE_PER_MINUTE,
+            }
+        );
+        assert!(rate_limits.ip_actions.is_disabled());
+        assert_eq!(rate_limits.join, RateLimits::default().join);
+        assert_eq!(rate_limits.ip_header, None);
+
+        let figment =
+            Figment::from(rocket::Config::default()).merge(("rate_limits.ip_header", "X-Real-IP"));
+        assert_eq!(
+            RateLimits::from_figment(&figment).ip_header.as_deref(),
+            Some("X-Real-IP")
+        );
+
+        assert_eq!(
+            RateLimits::from_figment(&Figment::from(rocket::Config::default())),
+            RateLimits::default()
+        );
+    }
+
+    #[test]
+    fn rate_limiter_take() {
+        let rate_limiter = RateLimiter::new(RateLimits {
+            create: RateLimit {
+                burst: 2,
+                per_minute: 6,
+            },
+            ..Default::default()
+        });
+        let now = Instant::now();
+
+        assert!(rate_limiter.take(&[Bucket::Create(IP)], now).is_ok());
+        assert!(rate_limiter.take(&[Bucket::Create(IP)], now).is_ok());
+        assert_eq!(
+            retry_after_secs(rate_limiter.take(&[Bucket::Create(IP)], now)),
+            10
+        );
+        // Other clients and other kinds of request have their own buckets.
+        let other_ip = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 1));
+        assert!(rate_limiter.take(&[Bucket::Create(other_ip)], now).is_ok());
+        assert!(rate_limiter.take(&[Bucket::Join(IP)], now).is_ok());
+
+        let later = now + Duration::from_secs(4);
+        assert_eq!(
+            retry_after_secs(rate_limiter.take(&[Bucket::Create(IP)], later)),
+            6
+        );
+        let later = now + Duration::from_secs(10);
+        assert!(rate_limiter.take(&[Bucket::Create(IP)], later).is_ok());
+        assert!(rate_limiter.take(&[Bucket::Create(IP)], later).is_err());
+    }
+
+    #[test]
+    fn rate_limiter_take_from_every_bucket() {
+        let rate_limiter = RateLimiter::new(RateLimits {
+            player_actions: RateLimit {
+                burst: 1,
+                per_minute: 60,
+            },
+            ..Default::default()
+        });
+        let now = Instant::now();
+        let player = Bucket::PlayerActions {
+            game_id: Uuid::new_v4(),
+            player_id: 0,
+        };
+
+        assert!(rate_limiter
+            .take(&[Bucket::IpActions(IP), player], now)
+            .is_ok());
+        assert!(rate_limiter
+            .take(&[Bucket::IpActions(IP), player], now)
+            .is_err());
+
+        // The rejected request did not cost a token to the address.
+        let ip_tokens = rate_limiter.buckets.get(&Bucket::IpActions(IP)).unwrap();
+        assert_eq!(ip_tokens.tokens, f64::from(DEFAULT_IP_ACTIONS_BURST - 1));
+    }
+
+    #[test]
+    fn rate_limiter_disabled() {
+        let rate_limiter = RateLimiter::new(RateLimits {
+            create: RateLimit {
+                burst: 0,
+                per_minute: 0,
+            },
+            ..Default::default()
+        });
+        let now = Instant::now();
+
+        for _ in 0..2 * DEFAULT_CREATE_BURST {
+            assert!(rate_limiter.take(&[Bucket::Create(IP)], now).is_ok());
+        }
+        assert!(rate_limiter.buckets.is_empty());
+    }
+
+    #[test]
+    fn rate_limiter_forget_full_buckets() {
+        let rate_limiter = RateLimiter::default();
+        let now = Instant::now();
+
+        assert!(rate_limiter
+            .take(&[Bucket::Create(IP), Bucket::Join(IP)], now)
+            .is_ok());
+        // A join token is refilled within a second, but a creation token takes six.
+        rate_limiter.forget_full_buckets(now + Duration::from_secs(1));
+
+        assert!(rate_limiter.buckets.contains_key(&Bucket::Create(IP)));
+        assert!(!rate_limiter.buckets.contains_key(&Bucket::Join(IP)));
+    }
+}
@@ -1,17 +1,66 @@
-//! All the types of JSON requests the server supports.
+//! All the types of requests the server supports.
 //!
-//! These requests should be valid JSON (otherwise the server will refuse them),
-//! part of the request body.
+//! Most requests should be valid JSON (otherwise the server will refuse them),
+//! part of the request body. Options when creating a game are part of the query string instead.
 
 use rocket::serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
 use smallvec::SmallVec;
+use std::time::Duration;
 use ticket_to_ride::{
     card::{TrainColor, NUM_DRAWN_DESTINATION_CARDS},
     city::CityToCity,
+    clock::TimeControls,
+    manager::{AbandonedRoutes, SeatingOrder},
     player::PlayerColor,
 };
 
-#[derive(Debug, Deserialize, PartialEq, Serialize)]
+#[derive(Debug, Default, PartialEq)]
+/// Query options when calling [`crate::router::create_game()`].
+pub struct CreateGameOptions {
+    /// Whether the game is played on a single shared device (see [`crate::game::GameMode::HotSeat`]).
+    pub hot_seat: bool,
+    /// How far behind the game spectators are, so that they cannot relay information to players live.
+    ///
+    /// Bounded by [`crate::game::MAX_SPECTATOR_DELAY`].
+    pub spectator_delay: Duration,
+    /// What happens to the routes of players leaving mid-game.
+    pub abandoned_routes: AbandonedRoutes,
+    /// Time limits of the turn-based game, if any.
+    pub time_controls: TimeControls,
+    /// Whether the game is listed publicly, for anyone to join (see [`crate::router::list_public_games()`]).
+    pub public: bool,
+    /// Whether visitors need an invite code, generated by the server, to get seated.
+    pub private: bool,
+    /// The password visitors need to get seated, if any. This makes the game private, instead of an invite code.
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+#[serde(crate = "rocket::serde")]
+/// Expected request when the host manages another seat, e.g. [`crate::router::revoke_sessions()`].
+pub struct SeatRequest {
+    /// The player ID of the seat.
+    pub player_id: usize,
+}
+
+#[derive(Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+#[serde(crate = "rocket::serde")]
+/// Expected request when calling [`crate::router::lock_lobby()`].
+pub struct LockLobbyRequest {
+    /// Whether new players are kept out of the lobby, or not.
+    pub is_locked: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+#[serde(crate = "rocket::serde")]
+/// Expected request when calling [`crate::router::set_seating_order()`].
+pub struct SetSeatingOrderRequest {
+    /// How the order in which players play is decided.
+    pub seating_order: SeatingOrder,
+}
+
+#[derive(Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
 #[serde(crate = "rocket::serde")]
 /// Expected request when calling [`crate::router::change_player_name()`].
 pub struct ChangeNameRequest {
@@ -19,7 +68,7 @@ pub struct ChangeNameRequest {
     pub new_name: String,
 }
 
-#[derive(Debug, Deserialize, PartialEq, Serialize)]
+#[derive(Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
 #[serde(crate = "rocket::serde")]
 /// Expected request when calling [`crate::router::change_player_color()`].
 pub struct ChangeColorRequest {
@@ -27,7 +76,7 @@ pub struct ChangeColorRequest {
     pub new_color: PlayerColor,
 }
 
-#[derive(Debug, Deserialize, PartialEq, Serialize)]
+#[derive(Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
 #[serde(crate = "rocket::serde")]
 /// Expected request when calling [`crate::router::set_player_ready()`].
 pub struct SetPlayerReadyRequest {
@@ -35,7 +84,7 @@ pub struct SetPlayerReadyRequest {
     pub is_ready: bool,
 }
 
-#[derive(Debug, Deserialize, PartialEq, Serialize)]
+#[derive(Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
 #[serde(crate = "rocket::serde")]
 /// Expected request when calling [`crate::router::select_destination_cards()`].
 pub struct SelectDestinationCardsRequest {
@@ -45,7 +94,7 @@ pub struct SelectDestinationCardsRequest {
     pub destination_cards_decisions: SmallVec<[bool; NUM_DRAWN_DESTINATION_CARDS]>,
 }
 
-#[derive(Debug, Deserialize, PartialEq, Serialize)]
+#[derive(Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
 #[serde(crate = "rocket::serde")]
 /// Expected request when calling [`crate::router::draw_open_train_card()`].
 pub struct DrawOpenTrainCardRequest {
@@ -53,7 +102,7 @@ pub struct DrawOpenTrainCardRequest {
     pub card_index: usize,
 }
 
-#[derive(Debug, Deserialize, PartialEq, Serialize)]
+#[derive(Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
 #[serde(crate = "rocket::serde")]
 /// Expected request when calling [`crate::router::claim_route()`].
 pub struct ClaimRouteRequest {
@@ -66,12 +115,62 @@ pub struct ClaimRouteRequest {
     pub cards: Vec<TrainColor>,
 }
 
+#[derive(Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+#[serde(crate = "rocket::serde")]
+/// Expected request when calling [`crate::router::register_webhook()`].
+pub struct RegisterWebhookRequest {
+    /// The HTTP(S) URL to notify.
+    pub url: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+#[serde(crate = "rocket::serde")]
+/// Expected request when calling [`crate::router::answer_undo_request()`].
+pub struct AnswerUndoRequest {
+    /// Whether the player agrees to undo the last action, or not.
+    pub approve: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+#[serde(crate = "rocket::serde")]
+/// Expected request when an admin posts a maintenance notice, i.e. [`crate::router::admin_set_notice()`].
+pub struct NoticeRequest {
+    /// The message of the notice, shown as is.
+    pub message: String,
+}
+
 #[cfg(test)]
 mod tests {
     use smallvec::smallvec;
 
     use super::*;
 
+    #[test]
+    fn json_to_seat_request() -> serde_json::Result<()> {
+        let request = SeatRequest { player_id: 3 };
+        assert_eq!(
+            serde_json::from_str::<SeatRequest>(r#"{ "player_id": 3 }"#)?,
+            request
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn json_to_set_seating_order_request() -> serde_json::Result<()> {
+        let request = SetSeatingOrderRequest {
+            seating_order: SeatingOrder::Manual(vec![2, 0, 1]),
+        };
+        assert_eq!(
+            serde_json::from_str::<SetSeatingOrderRequest>(
+                r#"{ "seating_order": { "manual": [2, 0, 1] } }"#
+            )?,
+            request
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn json_to_change_name_request() -> serde_json::Result<()> {
         let request = ChangeNameRequest {
@@ -140,4 +239,30 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn json_to_register_webhook_request() -> serde_json::Result<()> {
+        let request = RegisterWebhookRequest {
+            url: String::from("https://example.com/hook"),
+        };
+        assert_eq!(
+            serde_json::from_str::<RegisterWebhookRequest>(
+                r#"{ "url": "https://example.com/hook" }"#
+            )?,
+            request
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn json_to_answer_undo_request() -> serde_json::Result<()> {
+        let request = AnswerUndoRequest { approve: false };
+        assert_eq!(
+            serde_json::from_str::<AnswerUndoRequest>(r#"{ "approve": false }"#)?,
+            request
+        );
+
+        Ok(())
+    }
 }
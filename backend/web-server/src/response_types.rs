@@ -1,8 +1,16 @@
 //! All the custom responses the server supports.
 
+use crate::bearer_token::BearerToken;
+use crate::game::Game;
+use rocket::http::Header;
 use rocket::response::Redirect;
 use rocket::serde::{Deserialize, Serialize};
-use ticket_to_ride::manager::ManagerActionResult;
+use schemars::JsonSchema;
+
+use std::time::{Duration, Instant};
+use ticket_to_ride::clock::TimeoutPolicy;
+use ticket_to_ride::manager::{AbandonedRoutes, GamePhase, ManagerActionResult, MAX_PLAYERS};
+use uuid::Uuid;
 
 /// Types of error when loading a game.
 #[derive(Responder)]
@@ -10,10 +18,41 @@ pub enum LoadGameError {
     NoFile(std::io::Error),
     NoGame(Redirect),
     Unauthorized(Redirect),
+    /// The game was loaded by its code: redirects to the same page with its ID, to which cookies are scoped.
+    GameCode(Redirect),
+    /// The player was seated with an invite code or password: redirects to the same page without it, as the frontend
+    /// builds the URLs of its requests from the page's.
+    Invitation(Redirect),
+    /// The game is private, and the visitor did not provide the right invite code or password.
+    #[response(status = 401)]
+    InvitationRequired(&'static str),
+}
+
+/// The response to clients making too many requests (see [`crate::rate_limit`]).
+#[derive(Responder)]
+#[response(status = 429)]
+pub struct TooManyRequestsResponse {
+    message: &'static str,
+    /// When to retry, in seconds (rounded up).
+    retry_after: Header<'static>,
+}
+
+impl TooManyRequestsResponse {
+    /// Constructs a [`TooManyRequestsResponse`], telling the client to retry after the given duration, if known.
+    pub(crate) fn new(retry_after: Option<Duration>) -> Self {
+        let retry_after = retry_after.map_or(1, |retry_after| {
+            (retry_after.as_secs_f64().ceil() as u64).max(1)
+        });
+
+        Self {
+            message: "Too many requests: slow down, and try again later.",
+            retry_after: Header::new("Retry-After", retry_after.to_string()),
+        }
+    }
 }
 
 /// The general response to player actions, serializable in JSON.
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, JsonSchema, Serialize)]
 #[serde(crate = "rocket::serde")]
 pub struct ActionResponse {
     /// Whether the action succeeded.
@@ -39,3 +78,193 @@ impl ActionResponse {
         }
     }
 }
+
+/// The response when asking for a rejoin link, serializable in JSON.
+#[derive(Deserialize, JsonSchema, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct RejoinLinkResponse {
+    /// Path which seats whoever loads it back at the player's seat, on any device.
+    ///
+    /// It must be kept secret, as it is as good as the player's cookie.
+    pub rejoin_path: String,
+}
+
+/// The response when asking for an invite link, serializable in JSON.
+#[derive(Deserialize, JsonSchema, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct InviteLinkResponse {
+    /// Path which seats whoever loads it in the game, while it has open seats.
+    ///
+    /// In games protected by an invite code, it carries the code. In games protected by a password, it does not:
+    /// the password has to be shared separately.
+    pub invite_path: String,
+    /// The short code of the game, easier to share aloud than its ID (see [`crate::game_code`]).
+    pub game_code: String,
+}
+
+/// The response when registering a webhook, serializable in JSON.
+#[derive(Deserialize, JsonSchema, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct WebhookResponse {
+    /// The secret deliveries are signed with. See [`crate::webhook`].
+    pub secret: String,
+}
+
+/// The response when issuing a bearer token, serializable in JSON.
+#[derive(Deserialize, JsonSchema, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct BearerTokenResponse {
+    /// The player the token authenticates.
+    pub player_id: usize,
+    /// The token, to send in the `Authorization: Bearer <token>` header.
+    pub token: String,
+    /// When the token expires, in seconds since the UNIX epoch.
+    pub expires_at: u64,
+}
+
+impl BearerTokenResponse {
+    /// Constructs a [`BearerTokenResponse`] for the given player.
+    pub(crate) fn new(player_id: usize, bearer_token: BearerToken) -> Self {
+        Self {
+            player_id,
+            token: bearer_token.token,
+            expires_at: bearer_token.expires_at,
+        }
+    }
+}
+
+/// A game listed publicly, serializable in JSON. See [`crate::router::list_public_games()`].
+#[derive(Deserialize, JsonSchema, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct PublicGameResponse {
+    /// The ID of the game, to load it with [`crate::router::load_game()`].
+    pub game_id: Uuid,
+    /// The short code of the game, which routes accept instead of its ID (see [`crate::game_code`]).
+    pub game_code: String,
+    /// The phase of the game.
+    pub phase: GamePhase,
+    /// How many seats are taken.
+    pub num_players: usize,
+    /// How many seats the game has at most.
+    pub max_players: usize,
+    /// Whether players need an invite code or password to get seated.
+    pub private: bool,
+    /// The options the game was created with.
+    pub rules: PublicGameRules,
+    /// When the game was created, in seconds since the UNIX epoch.
+    pub created_at: u64,
+}
+
+impl PublicGameResponse {
+    /// Constructs a [`PublicGameResponse`] for the given game.
+    pub(crate) fn new(game_id: Uuid, game: &Game) -> Self {
+        let manager = game.manager();
+        let time_controls = manager.time_controls();
+
+        Self {
+            game_id,
+            game_code: game
+                .code()
+                .expect("Every game hosted by the server has a code")
+                .to_owned(),
+            phase: manager.phase(),
+            num_players: manager.num_players(),
+            max_players: MAX_PLAYERS,
+            private: game.is_private(),
+            rules: PublicGameRules {
+                spectator_delay: game.spectator_delay().as_secs(),
+                abandoned_routes: manager.abandoned_routes(),
+                turn_time_limit: time_controls.turn_time_limit.map(|limit| limit.as_secs()),
+                time_bank: time_controls.time_bank.map(|time_bank| time_bank.as_secs()),
+                timeout_policy: time_controls.timeout_policy,
+            },
+            created_at: game.created_at(),
+        }
+    }
+}
+
+/// The options a game listed publicly was created with, as passed to [`crate::router::create_game()`].
+#[derive(Deserialize, JsonSchema, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct PublicGameRules {
+    /// How far behind the game spectators are, in seconds.
+    pub spectator_delay: u64,
+    /// What happens to the routes of players leaving mid-game.
+    pub abandoned_routes: AbandonedRoutes,
+    /// How long a single turn can last, in seconds, if it is limited.
+    pub turn_time_limit: Option<u64>,
+    /// How much time each player has for all their turns combined, in seconds, if it is limited.
+    pub time_bank: Option<u64>,
+    /// What happens once a player runs out of time.
+    pub timeout_policy: TimeoutPolicy,
+}
+
+/// A game hosted by the server, as listed to admins, serializable in JSON. See
+/// [`crate::router::admin_list_games()`].
+#[derive(Deserialize, JsonSchema, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct AdminGameResponse {
+    pub game_id: Uuid,
+    /// The short code of the game, if it has one (see [`crate::game_code`]).
+    pub game_code: Option<String>,
+    /// The phase of the game.
+    pub phase: GamePhase,
+    /// The players seated in the game.
+    pub players: Vec<AdminPlayerResponse>,
+    /// Whether the game is listed publicly.
+    pub public: bool,
+    /// Whether players need an invite code or password to get seated.
+    pub private: bool,
+    /// When the game was created, in seconds since the UNIX epoch.
+    pub created_at: u64,
+    /// How long ago the game last changed, or was last loaded by a player, in seconds.
+    pub idle_for: u64,
+}
+
+impl AdminGameResponse {
+    /// Constructs an [`AdminGameResponse`] for the given game, as of `now`.
+    pub(crate) fn new(game_id: Uuid, game: &Game, now: Instant) -> Self {
+        let manager = game.manager();
+
+        Self {
+            game_id,
+            game_code: game.code().map(String::from),
+            phase: manager.phase(),
+            players: manager
+                .get_public_state()
+                .players_state
+                .iter()
+                .map(|player_state| AdminPlayerResponse {
+                    player_id: player_state.public_player_state.id,
+                    name: player_state.public_player_state.name.clone(),
+                    is_host: game.is_host(player_state.public_player_state.id),
+                })
+                .collect(),
+            public: game.is_public(),
+            private: game.is_private(),
+            created_at: game.created_at(),
+            idle_for: now
+                .saturating_duration_since(game.last_activity())
+                .as_secs(),
+        }
+    }
+}
+
+/// A player seated in a game, as listed to admins, serializable in JSON.
+#[derive(Deserialize, JsonSchema, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct AdminPlayerResponse {
+    pub player_id: usize,
+    pub name: String,
+    /// Whether the player runs the lobby.
+    pub is_host: bool,
+}
+
+/// The maintenance notice posted by admins, serializable in JSON. See [`crate::admin::MaintenanceNotice`].
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct NoticeResponse {
+    pub message: String,
+    /// When the notice was posted, in seconds since the UNIX epoch.
+    pub posted_at: u64,
+}
@@ -4,24 +4,128 @@
 //! which themselves delegate to the [`crate::authenticator::Authenticator`] and to the
 //! [`ticket_to_ride::manager::Manager`].
 
-use crate::controller::{GameIdManagerMapping, ReadController, WriteController};
+use crate::admin::{self, AdminAuthenticator, MaintenanceNotice, Readiness};
+use crate::bearer_token::BearerTokenKey;
+use crate::controller::{
+    GameIdManagerMapping, ReadController, SeatingError, SpectatorController, WriteController,
+};
+use crate::frontend::{Frontend, FrontendFile};
+use crate::game_code::{GameCodeMapping, GameKey};
+use crate::limits::GameLimits;
+use crate::metrics::Metrics;
+use crate::openapi::ApiDocument;
+use crate::rate_limit::{CreateRateLimit, JoinRateLimit, RetryAfter};
 use crate::request_types::*;
 use crate::response_types::*;
+use crate::snapshot::Drain;
+use crate::webhook::WebhookDispatcher;
+#[cfg(debug_assertions)]
+use crate::webhook::{WebhookPayload, WebhookSignature};
 
 use rocket::response::content::RawJson;
+use rocket::response::status::{BadRequest, Custom};
+use rocket::response::stream::{Event, EventStream};
 use rocket::serde::json::Json;
+use rocket::tokio::{select, time};
 use rocket::{
-    fs::NamedFile,
-    http::{uri::Origin, CookieJar},
+    http::{uri::Origin, ContentType, CookieJar, Status},
+    request::Request,
     response::Redirect,
     serde::uuid::Uuid,
-    State,
+    Route, Shutdown, State,
+};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use ticket_to_ride::{
+    clock::{TimeControls, TimeoutPolicy},
+    manager::AbandonedRoutes,
 };
-use std::path::Path;
 
-/// Path to the frontend build directory.
-/// This directory contains the frontend app that needs to be served to clients.
-pub(crate) const BUILD_FILES_PATH: &str = "../../frontend/build";
+/// Base path of the JSON API, which the version is part of (see [`api_routes()`]).
+pub const API_BASE: &str = "/api/v1";
+
+/// How often the spectator state is checked for changes, when streaming it to spectators.
+const SPECTATOR_EVENTS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Returns the routes of the JSON API, as opposed to the frontend pages, to mount under [`API_BASE`].
+///
+/// Every route of the JSON API is documented in [`crate::openapi`]. Spectators authenticate with the cookie set by
+/// their page, which browsers do not send under [`API_BASE`]: their routes are only served to the frontend (see
+/// [`frontend_routes()`]).
+pub fn api_routes() -> Vec<Route> {
+    routes![
+        add_seat,
+        admin_clear_notice,
+        admin_delete_game,
+        admin_end_game,
+        admin_get_game_state,
+        admin_list_games,
+        admin_set_notice,
+        answer_undo_request,
+        change_player_color,
+        change_player_name,
+        claim_route,
+        draw_close_train_card,
+        draw_destination_cards,
+        draw_open_train_card,
+        force_start,
+        get_bearer_token,
+        get_game_state,
+        get_invite_link,
+        get_notice,
+        get_rejoin_link,
+        join_game,
+        kick_player,
+        leave_game,
+        list_public_games,
+        lock_lobby,
+        pass_device,
+        regenerate_rejoin_token,
+        register_webhook,
+        request_undo,
+        revoke_sessions,
+        select_destination_cards,
+        set_player_ready,
+        set_seating_order,
+        unregister_webhook,
+    ]
+}
+
+/// Returns the routes the frontend calls from a game's page, to mount at the root, without the version: browsers
+/// only send the cookies of players and spectators to the game's page, and below (see
+/// [`crate::authenticator::Authenticator::authenticate`]).
+pub fn frontend_routes() -> Vec<Route> {
+    routes![
+        add_seat,
+        answer_undo_request,
+        change_player_color,
+        change_player_name,
+        claim_route,
+        draw_close_train_card,
+        draw_destination_cards,
+        draw_open_train_card,
+        force_start,
+        get_bearer_token,
+        get_game_state,
+        get_invite_link,
+        get_rejoin_link,
+        get_spectator_state,
+        kick_player,
+        leave_game,
+        lock_lobby,
+        pass_device,
+        regenerate_rejoin_token,
+        register_webhook,
+        request_undo,
+        revoke_sessions,
+        select_destination_cards,
+        set_player_ready,
+        set_seating_order,
+        spectator_events,
+        unregister_webhook,
+    ]
+}
 
 #[inline]
 /// Helper to redirect to [`root()`].
@@ -29,10 +133,25 @@ fn redirect_to_root() -> Redirect {
     Redirect::to(uri!(root()))
 }
 
+// The arguments of the helpers below are not named after the parameters of the routes: `uri!` rebinds arguments
+// named like them to themselves, which Clippy flags as redundant.
+
+#[inline]
+/// Helper to build the URI of [`load_game()`], with the invite code or password to get seated with, if any.
+pub(crate) fn game_uri(id: Uuid, invitation: Option<&str>) -> Origin<'static> {
+    uri!(load_game(id, invitation))
+}
+
+#[inline]
+/// Helper to build the URI of [`spectate_game()`].
+pub(crate) fn spectator_uri(id: Uuid) -> Origin<'static> {
+    uri!(spectate_game(id))
+}
+
 /// Serves the frontend app.
 #[get("/")]
-pub async fn root() -> std::io::Result<NamedFile> {
-    NamedFile::open(Path::new(BUILD_FILES_PATH).join("index.html")).await
+pub async fn root(frontend: &State<Frontend>) -> Option<FrontendFile> {
+    frontend.page("index.html").await.ok()
 }
 
 /// Redirects permanently to [`root()`].
@@ -43,16 +162,137 @@ pub fn index() -> Redirect {
 
 /// Serves the robots file, for crawlers.
 #[get("/robots.txt")]
-pub async fn robots() -> std::io::Result<NamedFile> {
-    NamedFile::open(Path::new(BUILD_FILES_PATH).join("robots.txt")).await
+pub async fn robots(frontend: &State<Frontend>) -> Option<FrontendFile> {
+    frontend.page("robots.txt").await.ok()
+}
+
+/// Serves the static files of the frontend app (e.g. its icons).
+#[get("/static/<path..>")]
+pub async fn static_file(path: PathBuf, frontend: &State<Frontend>) -> Option<FrontendFile> {
+    frontend.static_file(&path).await.ok()
 }
 
 /// Creates a game, and redirects to [`load_game()`].
-#[post("/create")]
-pub fn create_game(state: &State<GameIdManagerMapping>) -> Redirect {
-    let game_id = WriteController::create_game(state);
+///
+/// By default, every player joins from their own browser. With `?hot_seat=true`, the game is played on a
+/// single shared device instead: the browser creating the game controls every seat (see [`add_seat()`]).
+///
+/// With `?spectator_delay=<seconds>`, spectators (see [`spectate_game()`]) are kept behind the game.
+///
+/// With `?keep_abandoned_routes=true`, the routes of players leaving mid-game stay claimed, instead of being freed
+/// (see [`leave_game()`]).
+///
+/// With `?turn_time_limit=<seconds>` and/or `?time_bank=<seconds>`, turns are timed: a player running out of time
+/// draws two train cards from the close deck, or simply passes with `?auto_pass=true`.
+///
+/// With `?public=true`, the game is listed publicly (see [`list_public_games()`]), and players looking for a game
+/// can be seated in it (see [`quick_match()`]).
+///
+/// With `?private=true`, visitors need an invite code to get seated (see [`get_invite_link()`]), which the
+/// redirection carries. With `?password=<password>`, they need that password instead: the player creating the game
+/// is seated right away, and redirected to the game without it, such that it ends up neither in their history nor
+/// in logs. They share it however they see fit.
+///
+/// Responds with 503 if the server already hosts as many games as it can (see [`crate::limits`]), or if it is shutting
+/// down (see [`crate::snapshot`]), and with 429 if the client created too many games lately (see
+/// [`crate::rate_limit`]).
+#[post(
+    "/create?<hot_seat>&<spectator_delay>&<keep_abandoned_routes>&<turn_time_limit>&<time_bank>&<auto_pass>&<public>&<private>&<password>"
+)]
+#[allow(clippy::too_many_arguments)]
+pub fn create_game(
+    hot_seat: Option<bool>,
+    spectator_delay: Option<u64>,
+    keep_abandoned_routes: Option<bool>,
+    turn_time_limit: Option<u64>,
+    time_bank: Option<u64>,
+    auto_pass: Option<bool>,
+    public: Option<bool>,
+    private: Option<bool>,
+    password: Option<String>,
+    _rate_limit: CreateRateLimit,
+    cookies: &CookieJar<'_>,
+    state: &State<GameIdManagerMapping>,
+    game_codes: &State<GameCodeMapping>,
+    limits: &State<GameLimits>,
+    webhook_dispatcher: &State<WebhookDispatcher>,
+    drain: &State<Drain>,
+) -> Result<Redirect, Custom<String>> {
+    drain
+        .ensure_accepting_games()
+        .map_err(|e| Custom(Status::ServiceUnavailable, e))?;
+    let options = CreateGameOptions {
+        hot_seat: hot_seat.unwrap_or(false),
+        spectator_delay: Duration::from_secs(spectator_delay.unwrap_or(0)),
+        abandoned_routes: if keep_abandoned_routes.unwrap_or(false) {
+            AbandonedRoutes::Kept
+        } else {
+            AbandonedRoutes::Freed
+        },
+        time_controls: TimeControls {
+            turn_time_limit: turn_time_limit.map(Duration::from_secs),
+            time_bank: time_bank.map(Duration::from_secs),
+            timeout_policy: if auto_pass.unwrap_or(false) {
+                TimeoutPolicy::Pass
+            } else {
+                TimeoutPolicy::DrawTrainCards
+            },
+        },
+        public: public.unwrap_or(false),
+        private: private.unwrap_or(false),
+        password: password.filter(|password| !password.is_empty()),
+    };
+    let (game_id, invite_code) =
+        WriteController::create_game(state, game_codes, limits, webhook_dispatcher, &options)
+            .map_err(|e| Custom(Status::ServiceUnavailable, e))?;
+
+    if let (Some(password), Some(game)) = (&options.password, state.get_mut(&game_id)) {
+        // The player creating the game provided its password already. Should seating them fail, the game page asks.
+        let _ = WriteController::load_game(game, cookies, &game_uri(game_id, None), Some(password));
+    }
+
+    Ok(Redirect::to(game_uri(game_id, invite_code.as_deref())))
+}
+
+/// Lists the games created with `?public=true` (see [`create_game()`]), from newest to oldest, whatever their phase.
+#[get("/games")]
+pub fn list_public_games(state: &State<GameIdManagerMapping>) -> Json<Vec<PublicGameResponse>> {
+    Json(ReadController::list_public_games(state))
+}
+
+/// Seats the player in the fullest public game still in the lobby, and redirects to [`load_game()`].
+///
+/// Amongst equally full games, the oldest one is picked, and private games are skipped. If no public game has an
+/// open seat, a public game is created (with the default options of [`create_game()`]), unless the server already
+/// hosts as many games as it can, or is shutting down, in which case this responds with 503.
+///
+/// Quick matches count against the limit on creating games (see [`crate::rate_limit`]).
+///
+/// More details in [`WriteController::quick_match`].
+#[post("/quick_match")]
+pub fn quick_match(
+    _rate_limit: CreateRateLimit,
+    cookies: &CookieJar<'_>,
+    state: &State<GameIdManagerMapping>,
+    game_codes: &State<GameCodeMapping>,
+    limits: &State<GameLimits>,
+    webhook_dispatcher: &State<WebhookDispatcher>,
+    drain: &State<Drain>,
+) -> Result<Redirect, Custom<String>> {
+    drain
+        .ensure_accepting_games()
+        .map_err(|e| Custom(Status::ServiceUnavailable, e))?;
+    let game_id = WriteController::quick_match(
+        state,
+        game_codes,
+        limits,
+        webhook_dispatcher,
+        cookies,
+        |game_id| game_uri(game_id, None),
+    )
+    .map_err(|e| Custom(Status::ServiceUnavailable, e))?;
 
-    Redirect::to(uri!(load_game(game_id)))
+    Ok(Redirect::to(game_uri(game_id, None)))
 }
 
 /// Authenticates the player, and serves the frontend app.
@@ -63,21 +303,303 @@ pub fn create_game(state: &State<GameIdManagerMapping>) -> Redirect {
 ///   * If they are, then simply serves the frontend app.
 ///   * If they are not, but we can add a player (see [`ticket_to_ride::manager::Manager::add_player`]),
 ///     then we add the player to the game, store a cookie, and serve the frontend app.
-///   * If they are not and we can't add a player, redirects to [`root()`].
-#[get("/game/<game_id>")]
+///   * If they are not and we can't add a player, redirects to [`spectate_game()`].
+///     In hot-seat games, this is the case as soon as a first player was added.
+///
+/// In private games (see [`create_game()`]), new players have to provide the invite code or password with
+/// `?code=<code>`: otherwise, responds with 401. Once seated, they are redirected to the same page without it.
+///
+/// The game may be loaded by its code instead of its ID (see [`crate::game_code`]), in which case this redirects to
+/// the same page with its ID.
+///
+/// Responds with 429 if the client loaded too many games lately (see [`crate::rate_limit`]).
+#[get("/game/<game_id>?<code>")]
+#[allow(clippy::too_many_arguments)]
 pub async fn load_game(
-    game_id: Uuid,
+    game_id: GameKey,
+    code: Option<&str>,
+    _rate_limit: JoinRateLimit,
     cookies: &CookieJar<'_>,
     origin: &Origin<'_>,
     state: &State<GameIdManagerMapping>,
-) -> Result<NamedFile, LoadGameError> {
+    game_codes: &State<GameCodeMapping>,
+    frontend: &State<Frontend>,
+) -> Result<FrontendFile, LoadGameError> {
+    let game_id = match game_id {
+        GameKey::Id(game_id) => game_id,
+        game_key => {
+            return Err(match game_key.resolve(game_codes) {
+                Some(game_id) => LoadGameError::GameCode(Redirect::to(game_uri(game_id, code))),
+                None => LoadGameError::NoGame(redirect_to_root()),
+            })
+        }
+    };
+
     match state.get_mut(&game_id) {
         Some(game_id_and_state) => {
-            if !WriteController::load_game(game_id_and_state, cookies, origin) {
-                return Err(LoadGameError::Unauthorized(redirect_to_root()));
+            match WriteController::load_game(game_id_and_state, cookies, origin, code) {
+                Ok(()) if code.is_some() => {
+                    return Err(LoadGameError::Invitation(Redirect::to(game_uri(
+                        game_id, None,
+                    ))))
+                }
+                Ok(()) => {}
+                Err(SeatingError::InvitationRequired) => {
+                    return Err(LoadGameError::InvitationRequired(
+                        "This game is private: an invite code or password is required.",
+                    ))
+                }
+                Err(SeatingError::NoSeat) => {
+                    return Err(LoadGameError::Unauthorized(Redirect::to(spectator_uri(
+                        game_id,
+                    ))))
+                }
+            }
+
+            match frontend.page("index.html").await {
+                Ok(file) => Ok(file),
+                Err(e) => Err(LoadGameError::NoFile(e)),
             }
+        }
+        None => Err(LoadGameError::NoGame(redirect_to_root())),
+    }
+}
+
+/// Seats a new player, for clients authenticating with a bearer token rather than a cookie (e.g. bots).
+///
+/// Responds with the bearer token, to send in the `Authorization: Bearer <token>` header of subsequent requests.
+/// Responds with 404 if no games are found for that ID, and with 403 if the game does not let new players in.
+/// In private games, the invite code or password has to be provided with `?code=<code>`: otherwise, responds
+/// with 401. Responds with 429 if the client joined too many games lately (see [`crate::rate_limit`]).
+#[post("/game/<game_id>/join?<code>")]
+pub fn join_game(
+    game_id: GameKey,
+    code: Option<&str>,
+    _rate_limit: JoinRateLimit,
+    state: &State<GameIdManagerMapping>,
+    game_codes: &State<GameCodeMapping>,
+    bearer_token_key: &State<BearerTokenKey>,
+) -> Result<Json<BearerTokenResponse>, Status> {
+    let game_id = game_id.resolve(game_codes).ok_or(Status::NotFound)?;
+
+    match state.get_mut(&game_id) {
+        Some(game_id_and_state) => {
+            WriteController::join_game(game_id_and_state, bearer_token_key, code)
+                .map(Json)
+                .map_err(|e| match e {
+                    SeatingError::InvitationRequired => Status::Unauthorized,
+                    SeatingError::NoSeat => Status::Forbidden,
+                })
+        }
+        None => Err(Status::NotFound),
+    }
+}
+
+/// Issues a bearer token for the player, e.g. to hand their seat over to a script.
+/// The player must be authenticated to do so.
+#[get("/game/<_>/player/bearer_token")]
+pub fn get_bearer_token(
+    read_controller: ReadController,
+    bearer_token_key: &State<BearerTokenKey>,
+) -> Json<BearerTokenResponse> {
+    Json(read_controller.issue_bearer_token(bearer_token_key))
+}
+
+/// Seats the visitor back at the seat the rejoin token belongs to, and redirects to [`load_game()`].
+///
+/// This works on any device, even after the player's cookie was lost.
+/// If no games are found for that ID, or the token is invalid, redirects to [`root()`].
+/// Rejoining counts against the limit on joining games (see [`crate::rate_limit`]).
+#[get("/game/<game_id>/rejoin/<rejoin_token>")]
+pub fn rejoin_game(
+    game_id: GameKey,
+    rejoin_token: Uuid,
+    _rate_limit: JoinRateLimit,
+    cookies: &CookieJar<'_>,
+    state: &State<GameIdManagerMapping>,
+    game_codes: &State<GameCodeMapping>,
+) -> Redirect {
+    let game_id = match game_id.resolve(game_codes) {
+        Some(game_id) => game_id,
+        None => return redirect_to_root(),
+    };
+
+    match state.get_mut(&game_id) {
+        Some(game_id_and_state) => {
+            let game_uri = game_uri(game_id, None);
+
+            if WriteController::rejoin_game(
+                game_id_and_state,
+                cookies,
+                &game_uri.path(),
+                &rejoin_token,
+            ) {
+                Redirect::to(game_uri)
+            } else {
+                redirect_to_root()
+            }
+        }
+        None => redirect_to_root(),
+    }
+}
+
+/// Retrieves the player's secret rejoin link. The player must be authenticated to do so.
+///
+/// More details in [`rejoin_game()`].
+#[get("/game/<_>/player/rejoin_link")]
+pub fn get_rejoin_link(read_controller: ReadController) -> Json<RejoinLinkResponse> {
+    Json(RejoinLinkResponse {
+        rejoin_path: uri!(rejoin_game(
+            read_controller.game_id(),
+            read_controller.get_rejoin_token()
+        ))
+        .to_string(),
+    })
+}
+
+/// Retrieves a link which seats whoever loads it in the game, to invite other players.
+/// The player must be authenticated to do so.
+///
+/// In games protected by an invite code, the link carries it. More details in [`create_game()`].
+/// The response also holds the code of the game, to share it aloud (see [`crate::game_code`]).
+#[get("/game/<_>/player/invite_link")]
+pub fn get_invite_link(read_controller: ReadController) -> Json<InviteLinkResponse> {
+    Json(InviteLinkResponse {
+        invite_path: uri!(load_game(
+            read_controller.game_id(),
+            read_controller.get_invite_code()
+        ))
+        .to_string(),
+        game_code: read_controller.get_game_code(),
+    })
+}
+
+/// Allows the host to replace the rejoin token of a seat, so that previously shared rejoin links stop working.
+///
+/// More details in [`crate::game::Game::regenerate_rejoin_token`].
+#[post(
+    "/game/<_>/host/regenerate_rejoin_token",
+    format = "json",
+    data = "<seat_request>"
+)]
+pub fn regenerate_rejoin_token(
+    mut write_controller: WriteController,
+    seat_request: Json<SeatRequest>,
+) -> Json<ActionResponse> {
+    Json(write_controller.regenerate_rejoin_token(seat_request.into_inner()))
+}
+
+/// Allows the host to log out every device currently seated at a seat.
+///
+/// More details in [`crate::game::Game::revoke_sessions`].
+#[post(
+    "/game/<_>/host/revoke_sessions",
+    format = "json",
+    data = "<seat_request>"
+)]
+pub fn revoke_sessions(
+    mut write_controller: WriteController,
+    seat_request: Json<SeatRequest>,
+) -> Json<ActionResponse> {
+    Json(write_controller.revoke_sessions(seat_request.into_inner()))
+}
+
+/// Allows the host to remove a player from the game, e.g. if they stopped playing.
+///
+/// In the lobby, this frees their seat and color. Once the game has started, this is the same as the player
+/// leaving (see [`leave_game()`]).
+///
+/// More details in [`crate::game::Game::kick_player`].
+#[post("/game/<_>/host/kick_player", format = "json", data = "<seat_request>")]
+pub fn kick_player(
+    mut write_controller: WriteController,
+    seat_request: Json<SeatRequest>,
+) -> Json<ActionResponse> {
+    Json(write_controller.kick_player(seat_request.into_inner()))
+}
+
+/// Allows the player to leave the game, even once it has started (i.e. to forfeit).
+///
+/// Their cards are handed back, their routes are freed or kept (see [`create_game()`]), and the other players
+/// keep playing. If the host leaves, the next player to have joined becomes the host.
+///
+/// More details in [`crate::game::Game::leave`].
+#[post("/game/<_>/player/leave")]
+pub fn leave_game(mut write_controller: WriteController) -> Json<ActionResponse> {
+    Json(write_controller.leave_game())
+}
+
+/// Allows the host to keep new players out of the lobby (or to let them in again).
+///
+/// More details in [`crate::game::Game::lock_lobby`].
+#[post(
+    "/game/<_>/host/lock_lobby",
+    format = "json",
+    data = "<lock_lobby_request>"
+)]
+pub fn lock_lobby(
+    mut write_controller: WriteController,
+    lock_lobby_request: Json<LockLobbyRequest>,
+) -> Json<ActionResponse> {
+    Json(write_controller.lock_lobby(lock_lobby_request.into_inner()))
+}
+
+/// Allows the host to choose between a random and a manual seating order.
+///
+/// More details in [`ticket_to_ride::manager::Manager::set_seating_order`].
+#[post(
+    "/game/<_>/host/seating_order",
+    format = "json",
+    data = "<set_seating_order_request>"
+)]
+pub fn set_seating_order(
+    mut write_controller: WriteController,
+    set_seating_order_request: Json<SetSeatingOrderRequest>,
+) -> Json<ActionResponse> {
+    Json(write_controller.set_seating_order(set_seating_order_request.into_inner()))
+}
+
+/// Allows the host to start the game, even if some players are not ready.
+///
+/// More details in [`ticket_to_ride::manager::Manager::force_start`].
+#[post("/game/<_>/host/force_start")]
+pub fn force_start(mut write_controller: WriteController) -> Json<ActionResponse> {
+    Json(write_controller.force_start())
+}
+
+/// Lets the visitor watch the game, without being seated, and serves the frontend app.
+///
+/// If no games are found for that ID, redirects to [`root()`].
+/// Otherwise, the visitor is given a spectator cookie, unless they already have one.
+///
+/// Like [`load_game()`], this redirects to the same page with the game's ID, if it is loaded by its code.
+#[get("/game/<game_id>/spectate")]
+pub async fn spectate_game(
+    game_id: GameKey,
+    cookies: &CookieJar<'_>,
+    state: &State<GameIdManagerMapping>,
+    game_codes: &State<GameCodeMapping>,
+    frontend: &State<Frontend>,
+) -> Result<FrontendFile, LoadGameError> {
+    let game_id = match game_id {
+        GameKey::Id(game_id) => game_id,
+        game_key => {
+            return Err(match game_key.resolve(game_codes) {
+                Some(game_id) => LoadGameError::GameCode(Redirect::to(spectator_uri(game_id))),
+                None => LoadGameError::NoGame(redirect_to_root()),
+            })
+        }
+    };
+
+    match state.get_mut(&game_id) {
+        Some(game_id_and_state) => {
+            WriteController::spectate_game(
+                game_id_and_state,
+                cookies,
+                &game_uri(game_id, None).path(),
+            );
 
-            match NamedFile::open(Path::new(BUILD_FILES_PATH).join("index.html")).await {
+            match frontend.page("index.html").await {
                 Ok(file) => Ok(file),
                 Err(e) => Err(LoadGameError::NoFile(e)),
             }
@@ -86,6 +608,67 @@ pub async fn load_game(
     }
 }
 
+/// Retrieves the game state, as seen by spectators. The spectator must be authenticated to do so.
+///
+/// No private player state is included, and the state may be delayed (see [`create_game()`]).
+#[get("/game/<_>/spectate/state")]
+pub fn get_spectator_state(spectator_controller: SpectatorController) -> RawJson<String> {
+    RawJson(spectator_controller.get_spectator_state())
+}
+
+/// Streams the game state, as seen by spectators, as server-sent events.
+/// The spectator must be authenticated to do so.
+///
+/// An event is sent with the current state, and then every time it changes.
+#[get("/game/<_>/spectate/events")]
+pub fn spectator_events<'a>(
+    spectator_controller: SpectatorController,
+    state: &'a State<GameIdManagerMapping>,
+    mut shutdown: Shutdown,
+) -> EventStream![Event + 'a] {
+    let game_id = spectator_controller.into_game_id();
+
+    EventStream! {
+        let mut interval = time::interval(SPECTATOR_EVENTS_INTERVAL);
+        let mut last_spectator_state = None;
+
+        loop {
+            select! {
+                _ = interval.tick() => {},
+                _ = &mut shutdown => break,
+            };
+
+            let spectator_state = match SpectatorController::get_spectator_state_of(state, &game_id) {
+                Some(spectator_state) => spectator_state,
+                None => break,
+            };
+
+            if last_spectator_state.as_ref() != Some(&spectator_state) {
+                yield Event::data(spectator_state.clone());
+                last_spectator_state = Some(spectator_state);
+            }
+        }
+    }
+}
+
+/// Adds a seat to a hot-seat game. The player must be authenticated to do so.
+///
+/// All seats are controlled by the same session: every player route acts as a given seat with `?seat=<id>`.
+/// More details in [`crate::game::Game::add_seat`].
+#[post("/game/<_>/player/seat")]
+pub fn add_seat(mut write_controller: WriteController) -> Json<ActionResponse> {
+    Json(write_controller.add_seat())
+}
+
+/// Confirms that the device of a hot-seat game was passed to the seat given with `?seat=<id>`.
+/// The player must be authenticated to do so.
+///
+/// More details in [`crate::game::Game::pass_device`].
+#[post("/game/<_>/player/pass_device")]
+pub fn pass_device(mut write_controller: WriteController) -> Json<ActionResponse> {
+    Json(write_controller.pass_device())
+}
+
 /// Tries to change the player's name. The player must be authenticated to do so.
 ///
 /// More details in [`ticket_to_ride::manager::Manager::change_player_name`].
@@ -197,9 +780,79 @@ pub fn claim_route(
     Json(write_controller.claim_route(claim_route_request.into_inner()))
 }
 
+/// Registers a webhook, notified when it becomes the player's turn, and when the game is over. Registering again
+/// replaces the previous webhook. The player must be authenticated to do so.
+///
+/// Responds with the secret deliveries are signed with (see [`crate::webhook`]), or with 400 if the URL is invalid.
+#[post(
+    "/game/<_>/player/webhook",
+    format = "json",
+    data = "<register_webhook_request>"
+)]
+pub fn register_webhook(
+    mut write_controller: WriteController,
+    register_webhook_request: Json<RegisterWebhookRequest>,
+) -> Result<Json<WebhookResponse>, BadRequest<String>> {
+    write_controller
+        .register_webhook(register_webhook_request.into_inner())
+        .map(Json)
+        .map_err(|e| BadRequest(Some(e)))
+}
+
+/// Removes the player's webhook. The player must be authenticated to do so.
+#[delete("/game/<_>/player/webhook")]
+pub fn unregister_webhook(mut write_controller: WriteController) -> Json<ActionResponse> {
+    Json(write_controller.unregister_webhook())
+}
+
+/// Receives deliveries of webhooks, and logs them, to try out webhooks during development
+/// (e.g. by registering `http://localhost:8000/dev/webhook`). Only served by debug builds.
+#[cfg(debug_assertions)]
+#[post("/dev/webhook", data = "<body>")]
+pub fn receive_webhook(signature: WebhookSignature, body: String) -> Status {
+    match rocket::serde::json::from_str::<WebhookPayload>(&body) {
+        Ok(payload) => tracing::info!(
+            game_id = %payload.game_id,
+            player_id = payload.player_id,
+            event = ?payload.event,
+            turn = ?payload.turn,
+            signature = %signature.0,
+            "Received webhook delivery"
+        ),
+        Err(_) => tracing::info!(signature = %signature.0, %body, "Received webhook delivery"),
+    }
+    Status::NoContent
+}
+
+/// Allows a player to ask for the last action to be undone.
+/// The player must be authenticated to do so.
+///
+/// More details in [`ticket_to_ride::manager::Manager::request_undo`].
+#[post("/game/<_>/player/undo")]
+pub fn request_undo(mut write_controller: WriteController) -> Json<ActionResponse> {
+    Json(write_controller.request_undo())
+}
+
+/// Allows a player to agree, or refuse, to undo the last action.
+/// The player must be authenticated to do so.
+///
+/// More details in [`ticket_to_ride::manager::Manager::answer_undo_request`].
+#[post(
+    "/game/<_>/player/undo/answer",
+    format = "json",
+    data = "<answer_undo_request>"
+)]
+pub fn answer_undo_request(
+    mut write_controller: WriteController,
+    answer_undo_request: Json<AnswerUndoRequest>,
+) -> Json<ActionResponse> {
+    Json(write_controller.answer_undo_request(answer_undo_request.into_inner()))
+}
+
 /// Retrieves the game state. The player must be authenticated to do so.
 ///
-/// More details in [`ticket_to_ride::manager::Manager::get_state`].
+/// More details in [`ticket_to_ride::manager::Manager::get_state`], and in [`crate::game::Game::get_state`]
+/// for hot-seat games.
 #[get("/game/<_>/state")]
 pub fn get_game_state(read_controller: ReadController) -> RawJson<String> {
     RawJson(
@@ -207,3 +860,132 @@ pub fn get_game_state(read_controller: ReadController) -> RawJson<String> {
             .expect("Game state should never fail serializing as JSON"),
     )
 }
+
+/// Exposes metrics about the server and the games it hosts, in the Prometheus text format (see [`crate::metrics`]).
+#[get("/metrics")]
+pub fn get_metrics(
+    state: &State<GameIdManagerMapping>,
+    metrics: &State<Metrics>,
+) -> (ContentType, String) {
+    (ContentType::Plain, metrics.render(state))
+}
+
+/// Tells whether the server is up, e.g. to a liveness probe.
+#[get("/healthz")]
+pub fn healthz() -> &'static str {
+    "ok"
+}
+
+/// Tells whether the server is ready to serve requests, e.g. to a readiness probe.
+///
+/// Responds with 503 while the server is starting or shutting down. More details in [`crate::admin::is_ready`].
+#[get("/readyz")]
+pub fn readyz(readiness: &State<Readiness>) -> Result<&'static str, Custom<String>> {
+    admin::is_ready(readiness)
+        .map(|()| "ready")
+        .map_err(|e| Custom(Status::ServiceUnavailable, e))
+}
+
+/// Retrieves the maintenance notice posted by admins (see [`admin_set_notice()`]), or `null` if there is none.
+#[get("/notice")]
+pub fn get_notice(notice: &State<MaintenanceNotice>) -> Json<Option<NoticeResponse>> {
+    Json(notice.get())
+}
+
+/// Lists every game hosted by the server, from newest to oldest, with its players and how long it has been idle.
+/// The admin must be authenticated to do so (see [`crate::admin`]).
+#[get("/admin/games")]
+pub fn admin_list_games(
+    _admin: AdminAuthenticator,
+    state: &State<GameIdManagerMapping>,
+) -> Json<Vec<AdminGameResponse>> {
+    Json(admin::list_games(state))
+}
+
+/// Retrieves the full state of any game, including the private state of every player.
+/// The admin must be authenticated to do so (see [`crate::admin`]).
+///
+/// Responds with 404 if no games are found for that ID. More details in
+/// [`ticket_to_ride::manager::Manager::get_full_state`].
+#[get("/admin/game/<game_id>/state")]
+pub fn admin_get_game_state(
+    _admin: AdminAuthenticator,
+    game_id: GameKey,
+    state: &State<GameIdManagerMapping>,
+    game_codes: &State<GameCodeMapping>,
+) -> Result<RawJson<String>, Status> {
+    let game_id = game_id.resolve(game_codes).ok_or(Status::NotFound)?;
+    let game = state.get(&game_id).ok_or(Status::NotFound)?;
+    let full_state = serde_json::to_string(&game.manager().get_full_state())
+        .expect("Game state should never fail serializing as JSON");
+
+    Ok(RawJson(full_state))
+}
+
+/// Ends any game which has started, wherever it is at. The admin must be authenticated to do so
+/// (see [`crate::admin`]).
+///
+/// Responds with 404 if no games are found for that ID. More details in
+/// [`ticket_to_ride::manager::Manager::force_end`].
+#[post("/admin/game/<game_id>/end")]
+pub fn admin_end_game(
+    _admin: AdminAuthenticator,
+    game_id: GameKey,
+    state: &State<GameIdManagerMapping>,
+    game_codes: &State<GameCodeMapping>,
+) -> Result<Json<ActionResponse>, Status> {
+    let game_id = game_id.resolve(game_codes).ok_or(Status::NotFound)?;
+
+    admin::end_game(state, game_id)
+        .map(|result| Json(ActionResponse::new(result)))
+        .ok_or(Status::NotFound)
+}
+
+/// Deletes any game, whatever its phase: its players and spectators are left with a game that no longer exists.
+/// The admin must be authenticated to do so (see [`crate::admin`]).
+///
+/// Responds with 204 once deleted, and with 404 if no games are found for that ID.
+#[delete("/admin/game/<game_id>")]
+pub fn admin_delete_game(
+    _admin: AdminAuthenticator,
+    game_id: GameKey,
+    state: &State<GameIdManagerMapping>,
+    game_codes: &State<GameCodeMapping>,
+) -> Status {
+    match game_id.resolve(game_codes) {
+        Some(game_id) if admin::delete_game(state, game_codes, game_id) => Status::NoContent,
+        _ => Status::NotFound,
+    }
+}
+
+/// Posts a maintenance notice, replacing the previous one, for everyone to see (see [`get_notice()`]).
+/// The admin must be authenticated to do so (see [`crate::admin`]).
+#[put("/admin/notice", format = "json", data = "<notice_request>")]
+pub fn admin_set_notice(
+    _admin: AdminAuthenticator,
+    notice_request: Json<NoticeRequest>,
+    notice: &State<MaintenanceNotice>,
+) -> Status {
+    notice.set(notice_request.into_inner().message);
+    Status::NoContent
+}
+
+/// Removes the maintenance notice. The admin must be authenticated to do so (see [`crate::admin`]).
+#[delete("/admin/notice")]
+pub fn admin_clear_notice(_admin: AdminAuthenticator, notice: &State<MaintenanceNotice>) -> Status {
+    notice.clear();
+    Status::NoContent
+}
+
+/// Serves the OpenAPI document of the JSON API (see [`crate::openapi`]). Only mounted under [`API_BASE`].
+#[get("/openapi.json")]
+pub fn get_openapi(document: &State<ApiDocument>) -> RawJson<&str> {
+    RawJson(&document.0)
+}
+
+/// Responds to requests rejected by the [`crate::rate_limit::RateLimiter`], telling the client when to retry in the
+/// `Retry-After` header.
+#[catch(429)]
+pub fn too_many_requests(req: &Request) -> TooManyRequestsResponse {
+    TooManyRequestsResponse::new(req.local_cache(RetryAfter::default).0)
+}
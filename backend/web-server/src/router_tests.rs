@@ -1,23 +1,33 @@
 //! Integration tests for the _Ticket To Ride_ web server.
 
 use crate::authenticator::Identifier;
-use crate::authenticator::COOKIE_IDENTIFIER_NAME;
+use crate::authenticator::{COOKIE_IDENTIFIER_NAME, COOKIE_SPECTATOR_IDENTIFIER_NAME};
 use crate::controller::GameIdManagerMapping;
+use crate::frontend::DEFAULT_FRONTEND_DIR;
+use crate::game_code::{GameCodeMapping, GameKey, GAME_CODE_LEN};
+use crate::openapi;
 use crate::request_types::*;
-use crate::response_types::ActionResponse;
-use crate::rocket;
+use crate::response_types::{
+    ActionResponse, AdminGameResponse, BearerTokenResponse, InviteLinkResponse, NoticeResponse,
+    PublicGameResponse, RejoinLinkResponse, WebhookResponse,
+};
 use crate::router::*;
-use crate::STATIC_FILES_PATH;
+use crate::snapshot;
+use crate::webhook::{self, SIGNATURE_HEADER};
+use crate::{rocket, server};
 
 use pretty_assertions::{assert_eq, assert_ne, assert_str_eq};
 use regex::Regex;
 use rocket::{
-    http::{ContentType, Cookie, Status},
+    http::{uri::Origin, ContentType, Cookie, Header, MediaType, Status},
     local::blocking::{Client, LocalResponse},
 };
 use smallvec::smallvec;
 use std::{
+    collections::HashMap,
     fs::{read, read_to_string},
+    io::Read,
+    net::SocketAddr,
     path::Path,
 };
 use strum::IntoEnumIterator;
@@ -26,7 +36,7 @@ use ticket_to_ride::{
         TrainColor, NUM_DRAWN_INITIAL_TRAIN_CARDS, NUM_OPEN_TRAIN_CARDS, TOTAL_NUM_TRAIN_CARDS,
     },
     city::City,
-    manager::{GamePhase, Manager},
+    manager::{AbandonedRoutes, GamePhase, Manager, SeatingOrder, MAX_PLAYERS},
     player::PlayerColor,
 };
 use uuid::Uuid;
@@ -46,7 +56,7 @@ fn router_root() {
     assert!(res_str.is_some());
     let res_str = res_str.unwrap();
 
-    let expected_res_str = read_to_string(Path::new(BUILD_FILES_PATH).join("index.html"));
+    let expected_res_str = read_to_string(Path::new(DEFAULT_FRONTEND_DIR).join("index.html"));
     assert!(expected_res_str.is_ok());
     let expected_res_str = expected_res_str.unwrap();
 
@@ -76,7 +86,7 @@ fn router_robots() {
     assert!(res_str.is_some());
     let res_str = res_str.unwrap();
 
-    let expected_res_str = read_to_string(Path::new(BUILD_FILES_PATH).join("robots.txt"));
+    let expected_res_str = read_to_string(Path::new(DEFAULT_FRONTEND_DIR).join("robots.txt"));
     assert!(expected_res_str.is_ok());
     let expected_res_str = expected_res_str.unwrap();
 
@@ -96,13 +106,84 @@ fn router_static() {
     assert!(res_str.is_some());
     let res_str = res_str.unwrap();
 
-    let expected_res_str = read(Path::new(STATIC_FILES_PATH).join("favicon.ico"));
+    let expected_res_str = read(Path::new(DEFAULT_FRONTEND_DIR).join("static/favicon.ico"));
     assert!(expected_res_str.is_ok());
     let expected_res_str = expected_res_str.unwrap();
 
     assert_eq!(res_str, expected_res_str);
 }
 
+#[test]
+fn router_frontend_dir() {
+    let frontend_dir = std::env::temp_dir().join(format!("frontend-{}", Uuid::new_v4()));
+    std::fs::create_dir_all(frontend_dir.join("static/js")).unwrap();
+    std::fs::write(frontend_dir.join("index.html"), "<html>app</html>").unwrap();
+    std::fs::write(frontend_dir.join("static/js/main.js"), "let a;").unwrap();
+    std::fs::write(frontend_dir.join("static/js/main.1a2b3c4d.js"), "let b;").unwrap();
+    std::fs::write(std::env::temp_dir().join("secret.txt"), "secret").unwrap();
+    let figment = rocket::Config::figment().merge(("frontend_dir", &frontend_dir));
+    let client = Client::tracked(server(rocket::custom(figment))).expect("valid rocket");
+
+    let res = client.get(uri!(root())).dispatch();
+    assert_eq!(res.status(), Status::Ok);
+    assert_eq!(res.content_type(), Some(ContentType::HTML));
+    assert_eq!(res.headers().get_one("Cache-Control"), Some("no-cache"));
+    let etag = res.headers().get_one("ETag").unwrap().to_owned();
+    assert_eq!(res.into_string().unwrap(), "<html>app</html>");
+
+    // Browsers holding the current version of a file revalidate it without downloading it again.
+    let res = client
+        .get(uri!(root()))
+        .header(Header::new("If-None-Match", etag.clone()))
+        .dispatch();
+    assert_eq!(res.status(), Status::NotModified);
+    assert_eq!(res.headers().get_one("ETag"), Some(etag.as_str()));
+    assert_eq!(res.into_bytes(), None);
+
+    std::fs::write(frontend_dir.join("index.html"), "<html>new app</html>").unwrap();
+    let res = client
+        .get(uri!(root()))
+        .header(Header::new("If-None-Match", etag.clone()))
+        .dispatch();
+    assert_eq!(res.status(), Status::Ok);
+    assert_ne!(res.headers().get_one("ETag"), Some(etag.as_str()));
+    assert_eq!(res.into_string().unwrap(), "<html>new app</html>");
+
+    let res = client.get("/static/js/main.js").dispatch();
+    assert_eq!(res.status(), Status::Ok);
+    assert_eq!(res.content_type(), Some(ContentType::JavaScript));
+    assert_eq!(res.headers().get_one("Cache-Control"), Some("no-cache"));
+    assert_eq!(res.into_string().unwrap(), "let a;");
+
+    // Files fingerprinted by the frontend build are cached for good.
+    let res = client.get("/static/js/main.1a2b3c4d.js").dispatch();
+    assert_eq!(res.status(), Status::Ok);
+    assert_eq!(
+        res.headers().get_one("Cache-Control"),
+        Some("public, max-age=31536000, immutable")
+    );
+    assert_eq!(res.into_string().unwrap(), "let b;");
+
+    let res = client.get(uri!(robots())).dispatch();
+    assert_eq!(res.status(), Status::NotFound);
+
+    // Files outside the frontend directory are out of reach.
+    for path in [
+        "/static/../../secret.txt",
+        "/static/%2E%2E/%2E%2E/secret.txt",
+    ] {
+        let res = client.get(path).dispatch();
+        assert_eq!(res.status(), Status::NotFound);
+    }
+
+    std::fs::remove_dir_all(frontend_dir).unwrap();
+}
+
+/// The path to join the game through the API, with the given invite code or password, if any (see [`game_uri`]).
+fn join_path(id: Uuid, invitation: Option<&str>) -> String {
+    uri!("/api/v1", join_game(id, invitation)).to_string()
+}
+
 fn validate_state_num_of_players(
     state: &GameIdManagerMapping,
     game_id: &Uuid,
@@ -147,11 +228,11 @@ fn validate_state_if<F>(state: &GameIdManagerMapping, game_id: &Uuid, predicate:
 where
     F: FnOnce(&Manager),
 {
-    let game_manager = state.get(game_id);
-    assert!(game_manager.is_some());
-    let game_manager = game_manager.unwrap();
+    let game = state.get(game_id);
+    assert!(game.is_some());
+    let game = game.unwrap();
 
-    predicate(&*game_manager);
+    predicate(game.manager());
 }
 
 fn reorder_cookies<'a>(
@@ -159,9 +240,10 @@ fn reorder_cookies<'a>(
     game_id: &Uuid,
     cookies: Vec<Cookie<'a>>,
 ) -> Vec<Cookie<'a>> {
-    let game_manager = state.get(game_id);
-    assert!(game_manager.is_some());
-    let game_manager = game_manager.unwrap();
+    let game = state.get(game_id);
+    assert!(game.is_some());
+    let game_manager = game.unwrap();
+    let game_manager = game_manager.manager();
 
     let mut cookies: Vec<Option<Cookie>> = cookies.into_iter().map(|cookie| Some(cookie)).collect();
     let mut reordered_cookies = Vec::new();
@@ -205,7 +287,9 @@ fn expect_invalid_action_response(res: LocalResponse) {
 #[test]
 fn router_create_and_load_game() {
     let client = Client::untracked(rocket()).expect("valid rocket");
-    let res = client.post(uri!(create_game())).dispatch();
+    let res = client
+        .post(uri!(create_game(_, _, _, _, _, _, _, _, _)))
+        .dispatch();
 
     assert_eq!(res.status(), Status::SeeOther);
     assert_eq!(res.cookies().iter().count(), 0);
@@ -230,7 +314,7 @@ fn router_create_and_load_game() {
     let state = client.rocket().state::<GameIdManagerMapping>().unwrap();
     validate_state_num_of_players(state, &game_id, 0);
 
-    let res = client.get(uri!(load_game(game_id))).dispatch();
+    let res = client.get(game_uri(game_id, None)).dispatch();
     assert_eq!(res.status(), Status::Ok);
     assert_eq!(res.content_type(), Some(ContentType::HTML));
     assert_eq!(res.cookies().iter().count(), 1);
@@ -238,14 +322,14 @@ fn router_create_and_load_game() {
     let cookie = res.cookies().get_private(COOKIE_IDENTIFIER_NAME);
     assert!(cookie.is_some());
     let cookie = cookie.unwrap();
-    assert_eq!(cookie.value(), format!("{}/0", game_id));
+    assert_eq!(cookie.value(), format!("{}/0/0", game_id));
     assert_eq!(cookie.path(), Some(game_path_str));
 
     let res_str = res.into_string();
     assert!(res_str.is_some());
     let res_str = res_str.unwrap();
 
-    let expected_res_str = read_to_string(Path::new(BUILD_FILES_PATH).join("index.html"));
+    let expected_res_str = read_to_string(Path::new(DEFAULT_FRONTEND_DIR).join("index.html"));
     assert!(expected_res_str.is_ok());
     let expected_res_str = expected_res_str.unwrap();
 
@@ -256,7 +340,7 @@ fn router_create_and_load_game() {
     // Issue an idempotent request, which should not update the state.
 
     let res = client
-        .get(uri!(load_game(game_id)))
+        .get(game_uri(game_id, None))
         .private_cookie(cookie)
         .dispatch();
 
@@ -271,7 +355,7 @@ fn router_create_and_load_game() {
 
     // Now, let's have a second client loading the game.
 
-    let res = client.get(uri!(load_game(game_id))).dispatch();
+    let res = client.get(game_uri(game_id, None)).dispatch();
 
     assert_eq!(res.status(), Status::Ok);
     assert_eq!(res.content_type(), Some(ContentType::HTML));
@@ -280,7 +364,7 @@ fn router_create_and_load_game() {
     let cookie = res.cookies().get_private(COOKIE_IDENTIFIER_NAME);
     assert!(cookie.is_some());
     let cookie = cookie.unwrap();
-    assert_eq!(cookie.value(), format!("{}/1", game_id));
+    assert_eq!(cookie.value(), format!("{}/1/0", game_id));
     assert_eq!(cookie.path(), Some(game_path_str));
 
     validate_state_num_of_players(state, &game_id, 2);
@@ -290,7 +374,7 @@ fn router_create_and_load_game() {
 fn router_load_game_not_found() {
     let client = Client::untracked(rocket()).expect("valid rocket");
     let game_id = Uuid::new_v4();
-    let res = client.get(uri!(load_game(game_id))).dispatch();
+    let res = client.get(game_uri(game_id, None)).dispatch();
 
     assert_eq!(res.status(), Status::SeeOther);
     assert_eq!(res.cookies().iter().count(), 0);
@@ -299,7 +383,9 @@ fn router_load_game_not_found() {
 #[test]
 fn router_load_game_too_many_players() {
     let client = Client::untracked(rocket()).expect("valid rocket");
-    let res = client.post(uri!(create_game())).dispatch();
+    let res = client
+        .post(uri!(create_game(_, _, _, _, _, _, _, _, _)))
+        .dispatch();
 
     assert_eq!(res.status(), Status::SeeOther);
     assert_eq!(res.cookies().iter().count(), 0);
@@ -325,7 +411,7 @@ fn router_load_game_too_many_players() {
     // Load five unique players.
 
     for i in 1..=5 {
-        let res = client.get(uri!(load_game(game_id))).dispatch();
+        let res = client.get(game_uri(game_id, None)).dispatch();
         assert_eq!(res.status(), Status::Ok);
         assert_eq!(res.content_type(), Some(ContentType::HTML));
         assert_eq!(res.cookies().iter().count(), 1);
@@ -333,20 +419,22 @@ fn router_load_game_too_many_players() {
         let cookie = res.cookies().get_private(COOKIE_IDENTIFIER_NAME);
         assert!(cookie.is_some());
         let cookie = cookie.unwrap();
-        assert_eq!(cookie.value(), format!("{}/{}", game_id, i - 1));
+        assert_eq!(cookie.value(), format!("{}/{}/0", game_id, i - 1));
         assert_eq!(cookie.path(), Some(game_path_str));
 
         validate_state_num_of_players(state, &game_id, i);
     }
 
     // The 6th player to join should fail.
-    let res = client.get(uri!(load_game(game_id))).dispatch();
+    let res = client.get(game_uri(game_id, None)).dispatch();
     assert_eq!(res.status(), Status::SeeOther);
     assert_eq!(res.cookies().iter().count(), 0);
 }
 
 fn create_game(client: &Client) -> Uuid {
-    let res = client.post(uri!(create_game())).dispatch();
+    let res = client
+        .post(uri!(create_game(_, _, _, _, _, _, _, _, _)))
+        .dispatch();
 
     assert_eq!(res.status(), Status::SeeOther);
     assert_eq!(res.cookies().iter().count(), 0);
@@ -377,7 +465,7 @@ fn router_change_player_name() {
 
     let cookies: Vec<_> = (1..=5)
         .map(|_| {
-            let res = client.get(uri!(load_game(game_id))).dispatch();
+            let res = client.get(game_uri(game_id, None)).dispatch();
             assert_eq!(res.status(), Status::Ok);
 
             let cookie = res.cookies().get_private(COOKIE_IDENTIFIER_NAME);
@@ -475,14 +563,14 @@ fn router_change_player_name_unauthorized() {
     let game_id = create_game(&client);
     let wrong_game_id = Uuid::new_v4();
     let player_id = 0;
-    let identifier = Identifier::new(wrong_game_id, player_id);
+    let identifier = Identifier::new(wrong_game_id, player_id, 0);
     let cookie = Cookie::new(COOKIE_IDENTIFIER_NAME, identifier.to_string());
 
     let state = client.rocket().state::<GameIdManagerMapping>().unwrap();
     validate_state_num_of_players(state, &game_id, 0);
 
     // Load one player into the game. This player has the same ID as `player_id`.
-    let res = client.get(uri!(load_game(game_id))).dispatch();
+    let res = client.get(game_uri(game_id, None)).dispatch();
     assert_eq!(res.status(), Status::Ok);
 
     // Change the name, but cookie authorizes for a different game ID.
@@ -503,7 +591,7 @@ fn router_change_player_name_game_not_found() {
     let client = Client::untracked(rocket()).expect("valid rocket");
     let game_id = Uuid::new_v4();
     let player_id = 0;
-    let identifier = Identifier::new(game_id, player_id);
+    let identifier = Identifier::new(game_id, player_id, 0);
     let cookie = Cookie::new(COOKIE_IDENTIFIER_NAME, identifier.to_string());
 
     // Change the name with a valid cookie, but no such game exists.
@@ -528,7 +616,7 @@ fn router_change_player_color() {
 
     let cookies: Vec<_> = (1..=5)
         .map(|_| {
-            let res = client.get(uri!(load_game(game_id))).dispatch();
+            let res = client.get(game_uri(game_id, None)).dispatch();
             assert_eq!(res.status(), Status::Ok);
 
             let cookie = res.cookies().get_private(COOKIE_IDENTIFIER_NAME);
@@ -606,7 +694,7 @@ fn router_set_player_ready() {
 
     let cookies: Vec<_> = (1..=5)
         .map(|_| {
-            let res = client.get(uri!(load_game(game_id))).dispatch();
+            let res = client.get(game_uri(game_id, None)).dispatch();
             assert_eq!(res.status(), Status::Ok);
 
             let cookie = res.cookies().get_private(COOKIE_IDENTIFIER_NAME);
@@ -691,7 +779,7 @@ fn router_draw_and_select_destination_cards() {
 
     let cookies: Vec<_> = (1..=5)
         .map(|_| {
-            let res = client.get(uri!(load_game(game_id))).dispatch();
+            let res = client.get(game_uri(game_id, None)).dispatch();
             assert_eq!(res.status(), Status::Ok);
 
             let cookie = res.cookies().get_private(COOKIE_IDENTIFIER_NAME);
@@ -787,7 +875,7 @@ fn router_draw_open_train_card() {
 
     let cookies: Vec<_> = (1..=5)
         .map(|_| {
-            let res = client.get(uri!(load_game(game_id))).dispatch();
+            let res = client.get(game_uri(game_id, None)).dispatch();
             assert_eq!(res.status(), Status::Ok);
 
             let cookie = res.cookies().get_private(COOKIE_IDENTIFIER_NAME);
@@ -885,7 +973,7 @@ fn router_draw_close_train_card() {
     let num_players = 5;
     let cookies: Vec<_> = (1..=num_players)
         .map(|_| {
-            let res = client.get(uri!(load_game(game_id))).dispatch();
+            let res = client.get(game_uri(game_id, None)).dispatch();
             assert_eq!(res.status(), Status::Ok);
 
             let cookie = res.cookies().get_private(COOKIE_IDENTIFIER_NAME);
@@ -968,7 +1056,7 @@ fn router_claim_route() {
     let num_players = 5;
     let cookies: Vec<_> = (1..=num_players)
         .map(|_| {
-            let res = client.get(uri!(load_game(game_id))).dispatch();
+            let res = client.get(game_uri(game_id, None)).dispatch();
             assert_eq!(res.status(), Status::Ok);
 
             let cookie = res.cookies().get_private(COOKIE_IDENTIFIER_NAME);
@@ -1080,14 +1168,14 @@ fn router_get_game_state_unauthorized() {
     let game_id = create_game(&client);
     let wrong_game_id = Uuid::new_v4();
     let player_id = 0;
-    let identifier = Identifier::new(wrong_game_id, player_id);
+    let identifier = Identifier::new(wrong_game_id, player_id, 0);
     let cookie = Cookie::new(COOKIE_IDENTIFIER_NAME, identifier.to_string());
 
     let state = client.rocket().state::<GameIdManagerMapping>().unwrap();
     validate_state_num_of_players(state, &game_id, 0);
 
     // Load one player into the game. This player has the same ID as `player_id`.
-    let res = client.get(uri!(load_game(game_id))).dispatch();
+    let res = client.get(game_uri(game_id, None)).dispatch();
     assert_eq!(res.status(), Status::Ok);
 
     // Get game state, but cookie authorizes for a different game ID.
@@ -1104,7 +1192,7 @@ fn router_get_game_state_game_not_found() {
     let client = Client::untracked(rocket()).expect("valid rocket");
     let game_id = Uuid::new_v4();
     let player_id = 0;
-    let identifier = Identifier::new(game_id, player_id);
+    let identifier = Identifier::new(game_id, player_id, 0);
     let cookie = Cookie::new(COOKIE_IDENTIFIER_NAME, identifier.to_string());
 
     // Get game state, but no such game exists.
@@ -1122,19 +1210,19 @@ fn router_get_game_state_success() {
     let game_id = create_game(&client);
     let player_id = 0;
     let other_player_id = 1;
-    let identifier = Identifier::new(game_id, player_id);
-    let other_identifier = Identifier::new(game_id, other_player_id);
+    let identifier = Identifier::new(game_id, player_id, 0);
+    let other_identifier = Identifier::new(game_id, other_player_id, 0);
     let cookie = Cookie::new(COOKIE_IDENTIFIER_NAME, identifier.to_string());
     let other_cookie = Cookie::new(COOKIE_IDENTIFIER_NAME, other_identifier.to_string());
     let state = client.rocket().state::<GameIdManagerMapping>().unwrap();
     validate_state_num_of_players(state, &game_id, 0);
 
     // Load one player into the game. This player has the same ID as `player_id`.
-    let res = client.get(uri!(load_game(game_id))).dispatch();
+    let res = client.get(game_uri(game_id, None)).dispatch();
     assert_eq!(res.status(), Status::Ok);
 
     // Load a second player into the game. This player has the same ID as `other_player_id`.
-    let res = client.get(uri!(load_game(game_id))).dispatch();
+    let res = client.get(game_uri(game_id, None)).dispatch();
     assert_eq!(res.status(), Status::Ok);
 
     validate_state_num_of_players(state, &game_id, 2);
@@ -1188,3 +1276,1883 @@ fn router_get_game_state_success() {
         assert_str_eq!(other_res_body_str, other_state_json_str);
     });
 }
+
+// Tests for hot-seat games.
+
+fn create_hot_seat_game(client: &Client) -> Uuid {
+    let res = client
+        .post(uri!(create_game(Some(true), _, _, _, _, _, _, _, _)))
+        .dispatch();
+
+    assert_eq!(res.status(), Status::SeeOther);
+    assert_eq!(res.cookies().iter().count(), 0);
+
+    let game_path_str = res.headers().get_one("location");
+    assert!(game_path_str.is_some());
+
+    let captures = Regex::new(r"^/game/([0-9a-f-]+)$")
+        .unwrap()
+        .captures(game_path_str.unwrap());
+    assert!(captures.is_some());
+
+    let game_id = Uuid::parse_str(captures.unwrap().get(1).unwrap().as_str());
+    assert!(game_id.is_ok());
+
+    game_id.unwrap()
+}
+
+/// Appends the `seat` query parameter to the given route.
+fn as_seat(route: Origin, seat: impl std::fmt::Display) -> String {
+    format!("{}?seat={}", route, seat)
+}
+
+fn seats_with_private_state(res: LocalResponse) -> Vec<usize> {
+    assert_eq!(res.status(), Status::Ok);
+
+    let res_json = res.into_json::<serde_json::Value>();
+    assert!(res_json.is_some());
+
+    res_json.unwrap()["players_state"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|player| !player["private_player_state"].is_null())
+        .map(|player| player["public_player_state"]["id"].as_u64().unwrap() as usize)
+        .collect()
+}
+
+#[test]
+fn router_hot_seat_game() {
+    let client = Client::untracked(rocket()).expect("valid rocket");
+    let game_id = create_hot_seat_game(&client);
+
+    // The first browser to load the game gets the first seat.
+    let res = client.get(game_uri(game_id, None)).dispatch();
+    assert_eq!(res.status(), Status::Ok);
+
+    let cookie = res.cookies().get_private(COOKIE_IDENTIFIER_NAME);
+    assert!(cookie.is_some());
+    let cookie = cookie.unwrap();
+    assert_eq!(cookie.value(), format!("{}/0/0", game_id));
+
+    // Another browser cannot join a hot-seat game, but can watch it.
+    let res = client.get(game_uri(game_id, None)).dispatch();
+    assert_eq!(res.status(), Status::SeeOther);
+    assert_eq!(res.cookies().iter().count(), 0);
+    assert_eq!(
+        res.headers().get_one("location"),
+        Some(spectator_uri(game_id).to_string().as_str())
+    );
+
+    let state = client.rocket().state::<GameIdManagerMapping>().unwrap();
+    validate_state_num_of_players(state, &game_id, 1);
+
+    // Adding seats requires to be authenticated.
+    let res = client.post(uri!(add_seat(game_id))).dispatch();
+    assert_eq!(res.status(), Status::Unauthorized);
+
+    for _ in 0..2 {
+        let res = client
+            .post(uri!(add_seat(game_id)))
+            .private_cookie(cookie.clone())
+            .dispatch();
+        expect_valid_action_response(res);
+    }
+    validate_state_num_of_players(state, &game_id, 3);
+
+    // The same session acts as every seat.
+    for seat in 0..3 {
+        let change_name_request = ChangeNameRequest {
+            new_name: format!("seat {}", seat),
+        };
+        let res = client
+            .put(as_seat(uri!(change_player_name(game_id)), seat))
+            .private_cookie(cookie.clone())
+            .json(&change_name_request)
+            .dispatch();
+        expect_valid_action_response(res);
+
+        let set_player_ready_request = SetPlayerReadyRequest { is_ready: true };
+        let res = client
+            .put(as_seat(uri!(set_player_ready(game_id)), seat))
+            .private_cookie(cookie.clone())
+            .json(&set_player_ready_request)
+            .dispatch();
+        expect_valid_action_response(res);
+    }
+    validate_state_phase(state, &game_id, GamePhase::Starting);
+    validate_state_if(state, &game_id, |game_manager| {
+        for player in game_manager.get_public_state().players_state {
+            let player_id = player.public_player_state.id;
+            assert_eq!(
+                player.public_player_state.name,
+                format!("seat {}", player_id)
+            );
+        }
+    });
+
+    // Seats that do not exist, or are not numbers, are refused.
+    let res = client
+        .post(as_seat(uri!(pass_device(game_id)), 3))
+        .private_cookie(cookie.clone())
+        .dispatch();
+    assert_eq!(res.status(), Status::Forbidden);
+
+    let res = client
+        .post(as_seat(uri!(pass_device(game_id)), "first"))
+        .private_cookie(cookie.clone())
+        .dispatch();
+    assert_eq!(res.status(), Status::BadRequest);
+
+    // Until the device is passed, no private state is revealed.
+    let res = client
+        .get(uri!(get_game_state(game_id)))
+        .private_cookie(cookie.clone())
+        .dispatch();
+    assert!(seats_with_private_state(res).is_empty());
+
+    let res = client
+        .post(as_seat(uri!(pass_device(game_id)), 1))
+        .private_cookie(cookie.clone())
+        .dispatch();
+    expect_valid_action_response(res);
+
+    // Only the seat holding the device has its private state revealed.
+    let res = client
+        .get(as_seat(uri!(get_game_state(game_id)), 1))
+        .private_cookie(cookie.clone())
+        .dispatch();
+    assert_eq!(seats_with_private_state(res), vec![1]);
+
+    let res = client
+        .get(as_seat(uri!(get_game_state(game_id)), 2))
+        .private_cookie(cookie.clone())
+        .dispatch();
+    assert!(seats_with_private_state(res).is_empty());
+
+    let select_destination_cards_request = SelectDestinationCardsRequest {
+        destination_cards_decisions: smallvec![true, true, true],
+    };
+    let res = client
+        .put(as_seat(uri!(select_destination_cards(game_id)), 1))
+        .private_cookie(cookie.clone())
+        .json(&select_destination_cards_request)
+        .dispatch();
+    expect_valid_action_response(res);
+}
+
+#[test]
+fn router_online_game_other_seat() {
+    let client = Client::untracked(rocket()).expect("valid rocket");
+    let game_id = create_game(&client);
+
+    let cookies: Vec<_> = (1..=2)
+        .map(|_| {
+            let res = client.get(game_uri(game_id, None)).dispatch();
+            assert_eq!(res.status(), Status::Ok);
+
+            let cookie = res.cookies().get_private(COOKIE_IDENTIFIER_NAME);
+            assert!(cookie.is_some());
+            cookie.unwrap()
+        })
+        .collect();
+
+    // A player can only act as their own seat.
+    let res = client
+        .get(as_seat(uri!(get_game_state(game_id)), 0))
+        .private_cookie(cookies[0].clone())
+        .dispatch();
+    assert_eq!(seats_with_private_state(res), vec![0]);
+
+    let res = client
+        .get(as_seat(uri!(get_game_state(game_id)), 1))
+        .private_cookie(cookies[0].clone())
+        .dispatch();
+    assert_eq!(res.status(), Status::Forbidden);
+
+    let set_player_ready_request = SetPlayerReadyRequest { is_ready: true };
+    let res = client
+        .put(as_seat(uri!(set_player_ready(game_id)), 0))
+        .private_cookie(cookies[1].clone())
+        .json(&set_player_ready_request)
+        .dispatch();
+    assert_eq!(res.status(), Status::Forbidden);
+
+    // Seats and devices are only a thing in hot-seat games.
+    let res = client
+        .post(uri!(add_seat(game_id)))
+        .private_cookie(cookies[0].clone())
+        .dispatch();
+    expect_invalid_action_response(res);
+
+    let res = client
+        .post(uri!(pass_device(game_id)))
+        .private_cookie(cookies[0].clone())
+        .dispatch();
+    expect_invalid_action_response(res);
+
+    let state = client.rocket().state::<GameIdManagerMapping>().unwrap();
+    validate_state_num_of_players(state, &game_id, 2);
+}
+
+// Tests for spectators.
+
+fn load_players(client: &Client, game_id: Uuid, num_players: usize) -> Vec<Cookie<'static>> {
+    (0..num_players)
+        .map(|_| {
+            let res = client.get(game_uri(game_id, None)).dispatch();
+            assert_eq!(res.status(), Status::Ok);
+
+            let cookie = res.cookies().get_private(COOKIE_IDENTIFIER_NAME);
+            assert!(cookie.is_some());
+            cookie.unwrap()
+        })
+        .collect()
+}
+
+fn spectate(client: &Client, game_id: Uuid) -> Cookie<'static> {
+    let res = client.get(spectator_uri(game_id)).dispatch();
+    assert_eq!(res.status(), Status::Ok);
+    assert_eq!(res.content_type(), Some(ContentType::HTML));
+
+    let cookie = res.cookies().get_private(COOKIE_SPECTATOR_IDENTIFIER_NAME);
+    assert!(cookie.is_some());
+    let cookie = cookie.unwrap();
+    assert_eq!(cookie.path(), Some(game_uri(game_id, None).path().as_str()));
+
+    cookie
+}
+
+fn spectator_num_players(res: LocalResponse) -> usize {
+    assert_eq!(res.status(), Status::Ok);
+
+    let res_json = res.into_json::<serde_json::Value>();
+    assert!(res_json.is_some());
+
+    res_json.unwrap()["players_state"].as_array().unwrap().len()
+}
+
+#[test]
+fn router_spectate_game() {
+    let client = Client::untracked(rocket()).expect("valid rocket");
+    let game_id = create_game(&client);
+    let cookies = load_players(&client, game_id, 2);
+
+    cookies.iter().for_each(|cookie| {
+        let set_player_ready_request = SetPlayerReadyRequest { is_ready: true };
+        let res = client
+            .put(uri!(set_player_ready(game_id)))
+            .private_cookie(cookie.clone())
+            .json(&set_player_ready_request)
+            .dispatch();
+        expect_valid_action_response(res);
+    });
+
+    // Once out of the lobby, visitors are sent to spectate the game.
+    let res = client.get(game_uri(game_id, None)).dispatch();
+    assert_eq!(res.status(), Status::SeeOther);
+    assert_eq!(res.cookies().iter().count(), 0);
+    assert_eq!(
+        res.headers().get_one("location"),
+        Some(spectator_uri(game_id).to_string().as_str())
+    );
+
+    let spectator_cookie = spectate(&client, game_id);
+
+    // Spectating again does not create a new spectator.
+    let res = client
+        .get(spectator_uri(game_id))
+        .private_cookie(spectator_cookie.clone())
+        .dispatch();
+    assert_eq!(res.status(), Status::Ok);
+    assert_eq!(res.cookies().iter().count(), 0);
+
+    // Spectators see every player, without any private state.
+    let res = client
+        .get(uri!(get_spectator_state(game_id)))
+        .private_cookie(spectator_cookie.clone())
+        .dispatch();
+    assert_eq!(res.status(), Status::Ok);
+    let res_body_str = res.into_string().unwrap();
+
+    let state = client.rocket().state::<GameIdManagerMapping>().unwrap();
+    validate_state_if(state, &game_id, |game_manager| {
+        assert_eq!(game_manager.get_public_state().phase, GamePhase::Starting);
+        assert_str_eq!(
+            res_body_str,
+            serde_json::to_string(&game_manager.get_public_state()).unwrap()
+        );
+    });
+
+    // Browsers only send the spectator cookie to the game's page, and below: spectators are not served under the
+    // versioned API.
+    let res = client
+        .get(uri!("/api/v1", get_spectator_state(game_id)))
+        .private_cookie(spectator_cookie.clone())
+        .dispatch();
+    assert_eq!(res.status(), Status::NotFound);
+
+    // Spectators are not players, and players are not spectators.
+    let res = client
+        .get(uri!(get_game_state(game_id)))
+        .private_cookie(spectator_cookie.clone())
+        .dispatch();
+    assert_eq!(res.status(), Status::Unauthorized);
+
+    let res = client
+        .post(uri!(draw_close_train_card(game_id)))
+        .private_cookie(spectator_cookie)
+        .dispatch();
+    assert_eq!(res.status(), Status::Unauthorized);
+
+    let res = client
+        .get(uri!(get_spectator_state(game_id)))
+        .private_cookie(cookies[0].clone())
+        .dispatch();
+    assert_eq!(res.status(), Status::Unauthorized);
+}
+
+#[test]
+fn router_spectate_game_not_found() {
+    let client = Client::untracked(rocket()).expect("valid rocket");
+    let game_id = Uuid::new_v4();
+
+    let res = client.get(spectator_uri(game_id)).dispatch();
+    assert_eq!(res.status(), Status::SeeOther);
+    assert_eq!(res.cookies().iter().count(), 0);
+}
+
+#[test]
+fn router_spectate_game_with_delay() {
+    let client = Client::untracked(rocket()).expect("valid rocket");
+    let res = client
+        .post(uri!(create_game(_, Some(60), _, _, _, _, _, _, _)))
+        .dispatch();
+    assert_eq!(res.status(), Status::SeeOther);
+
+    let game_path_str = res.headers().get_one("location").unwrap();
+    let game_id = Uuid::parse_str(game_path_str.trim_start_matches("/game/")).unwrap();
+
+    let spectator_cookie = spectate(&client, game_id);
+    load_players(&client, game_id, 2);
+
+    // Spectators still see the game as it was a minute ago (or rather, when it was created).
+    let res = client
+        .get(uri!(get_spectator_state(game_id)))
+        .private_cookie(spectator_cookie)
+        .dispatch();
+    assert_eq!(spectator_num_players(res), 0);
+
+    let state = client.rocket().state::<GameIdManagerMapping>().unwrap();
+    validate_state_num_of_players(state, &game_id, 2);
+}
+
+#[test]
+fn router_spectator_events() {
+    let client = Client::untracked(rocket()).expect("valid rocket");
+    let game_id = create_game(&client);
+    load_players(&client, game_id, 3);
+    let spectator_cookie = spectate(&client, game_id);
+
+    let res = client.get(uri!(spectator_events(game_id))).dispatch();
+    assert_eq!(res.status(), Status::Unauthorized);
+
+    let mut res = client
+        .get(uri!(spectator_events(game_id)))
+        .private_cookie(spectator_cookie)
+        .dispatch();
+    assert_eq!(res.status(), Status::Ok);
+    assert_eq!(res.content_type(), Some(ContentType::EventStream));
+
+    // The first event is the current spectator state.
+    let mut event = Vec::new();
+    let mut buffer = [0; 1024];
+    while !event.ends_with(b"\n\n") {
+        let num_bytes = res.read(&mut buffer).unwrap();
+        assert!(num_bytes > 0);
+        event.extend_from_slice(&buffer[..num_bytes]);
+    }
+
+    let event = String::from_utf8(event).unwrap();
+    // Lines starting with `:` are comments, e.g. heartbeats.
+    let data = event.lines().find_map(|line| line.strip_prefix("data:"));
+    assert!(data.is_some());
+
+    let data: serde_json::Value = serde_json::from_str(data.unwrap()).unwrap();
+    assert_eq!(data["players_state"].as_array().unwrap().len(), 3);
+}
+
+// Tests for rejoin links.
+
+fn get_rejoin_path(client: &Client, game_id: Uuid, cookie: &Cookie<'static>) -> String {
+    let res = client
+        .get(uri!(get_rejoin_link(game_id)))
+        .private_cookie(cookie.clone())
+        .dispatch();
+    assert_eq!(res.status(), Status::Ok);
+
+    let res_json = res.into_json::<RejoinLinkResponse>();
+    assert!(res_json.is_some());
+    let rejoin_path = res_json.unwrap().rejoin_path;
+    assert!(rejoin_path.starts_with(&format!("/game/{}/rejoin/", game_id)));
+
+    rejoin_path
+}
+
+/// Follows the rejoin link from a new device, and returns the cookie it gets, if any.
+fn rejoin(client: &Client, game_id: Uuid, rejoin_path: &str) -> Option<Cookie<'static>> {
+    let res = client.get(rejoin_path.to_string()).dispatch();
+    assert_eq!(res.status(), Status::SeeOther);
+
+    let cookie = res.cookies().get_private(COOKIE_IDENTIFIER_NAME);
+    match &cookie {
+        Some(_) => assert_eq!(
+            res.headers().get_one("location"),
+            Some(game_uri(game_id, None).to_string().as_str())
+        ),
+        None => assert_eq!(res.headers().get_one("location"), Some("/")),
+    }
+
+    cookie
+}
+
+#[test]
+fn router_rejoin_game() {
+    let client = Client::untracked(rocket()).expect("valid rocket");
+    let game_id = create_game(&client);
+    let cookies = load_players(&client, game_id, 2);
+
+    cookies.iter().for_each(|cookie| {
+        let set_player_ready_request = SetPlayerReadyRequest { is_ready: true };
+        let res = client
+            .put(uri!(set_player_ready(game_id)))
+            .private_cookie(cookie.clone())
+            .json(&set_player_ready_request)
+            .dispatch();
+        expect_valid_action_response(res);
+    });
+    let state = client.rocket().state::<GameIdManagerMapping>().unwrap();
+    validate_state_phase(state, &game_id, GamePhase::Starting);
+
+    // Rejoin links require to be authenticated.
+    let res = client.get(uri!(get_rejoin_link(game_id))).dispatch();
+    assert_eq!(res.status(), Status::Unauthorized);
+
+    // Even though the game started, the second player can rejoin from another device.
+    let rejoin_path = get_rejoin_path(&client, game_id, &cookies[1]);
+    assert_ne!(rejoin_path, get_rejoin_path(&client, game_id, &cookies[0]));
+
+    let rejoined_cookie = rejoin(&client, game_id, &rejoin_path);
+    assert!(rejoined_cookie.is_some());
+    let rejoined_cookie = rejoined_cookie.unwrap();
+    assert_eq!(rejoined_cookie.value(), format!("{}/1/0", game_id));
+
+    let res = client
+        .get(uri!(get_game_state(game_id)))
+        .private_cookie(rejoined_cookie.clone())
+        .dispatch();
+    assert_eq!(seats_with_private_state(res), vec![1]);
+
+    assert!(rejoin(
+        &client,
+        game_id,
+        &format!("/game/{}/rejoin/{}", game_id, Uuid::new_v4())
+    )
+    .is_none());
+    assert!(rejoin(
+        &client,
+        game_id,
+        &format!("/game/{}/rejoin/{}", Uuid::new_v4(), Uuid::new_v4())
+    )
+    .is_none());
+
+    // Only the host manages the seats' sessions.
+    let seat_request = SeatRequest { player_id: 1 };
+    let res = client
+        .post(uri!(revoke_sessions(game_id)))
+        .private_cookie(cookies[1].clone())
+        .json(&seat_request)
+        .dispatch();
+    expect_invalid_action_response(res);
+
+    let res = client
+        .post(uri!(revoke_sessions(game_id)))
+        .private_cookie(cookies[0].clone())
+        .json(&seat_request)
+        .dispatch();
+    expect_valid_action_response(res);
+
+    // Every previous session of the seat is logged out.
+    for cookie in [&cookies[1], &rejoined_cookie] {
+        let res = client
+            .get(uri!(get_game_state(game_id)))
+            .private_cookie(cookie.clone())
+            .dispatch();
+        assert_eq!(res.status(), Status::Unauthorized);
+
+        let res = client
+            .get(game_uri(game_id, None))
+            .private_cookie(cookie.clone())
+            .dispatch();
+        assert_eq!(res.status(), Status::SeeOther);
+        assert_eq!(
+            res.headers().get_one("location"),
+            Some(spectator_uri(game_id).to_string().as_str())
+        );
+    }
+
+    // The rejoin link still works, with the new session.
+    let rejoined_cookie = rejoin(&client, game_id, &rejoin_path);
+    assert!(rejoined_cookie.is_some());
+    let rejoined_cookie = rejoined_cookie.unwrap();
+    assert_eq!(rejoined_cookie.value(), format!("{}/1/1", game_id));
+
+    let res = client
+        .get(uri!(get_game_state(game_id)))
+        .private_cookie(rejoined_cookie.clone())
+        .dispatch();
+    assert_eq!(res.status(), Status::Ok);
+
+    // Until the host regenerates it.
+    let res = client
+        .post(uri!(regenerate_rejoin_token(game_id)))
+        .private_cookie(rejoined_cookie.clone())
+        .json(&seat_request)
+        .dispatch();
+    expect_invalid_action_response(res);
+
+    let res = client
+        .post(uri!(regenerate_rejoin_token(game_id)))
+        .private_cookie(cookies[0].clone())
+        .json(&seat_request)
+        .dispatch();
+    expect_valid_action_response(res);
+
+    assert!(rejoin(&client, game_id, &rejoin_path).is_none());
+
+    let new_rejoin_path = get_rejoin_path(&client, game_id, &rejoined_cookie);
+    assert_ne!(new_rejoin_path, rejoin_path);
+    assert!(rejoin(&client, game_id, &new_rejoin_path).is_some());
+}
+
+// Tests for bearer tokens.
+
+fn bearer(token: &str) -> Header<'static> {
+    Header::new("Authorization", format!("Bearer {}", token))
+}
+
+#[test]
+fn router_join_game_with_bearer_token() {
+    let client = Client::untracked(rocket()).expect("valid rocket");
+    let game_id = create_game(&client);
+
+    let res = client
+        .post(uri!("/api/v1", join_game(Uuid::new_v4(), _)))
+        .dispatch();
+    assert_eq!(res.status(), Status::NotFound);
+
+    // Bots join without any cookie.
+    let bearer_tokens: Vec<_> = (0..2)
+        .map(|i| {
+            let res = client.post(join_path(game_id, None)).dispatch();
+            assert_eq!(res.status(), Status::Ok);
+            assert_eq!(res.cookies().iter().count(), 0);
+
+            let res_json = res.into_json::<BearerTokenResponse>();
+            assert!(res_json.is_some());
+            let res_json = res_json.unwrap();
+            assert_eq!(res_json.player_id, i);
+
+            res_json.token
+        })
+        .collect();
+
+    let state = client.rocket().state::<GameIdManagerMapping>().unwrap();
+    validate_state_num_of_players(state, &game_id, 2);
+
+    // The token authenticates the same way as the cookie.
+    let res = client
+        .get(uri!(get_game_state(game_id)))
+        .header(bearer(&bearer_tokens[1]))
+        .dispatch();
+    assert_eq!(seats_with_private_state(res), vec![1]);
+
+    let change_name_request = ChangeNameRequest {
+        new_name: String::from("bot"),
+    };
+    let res = client
+        .put(uri!(change_player_name(game_id)))
+        .header(bearer(&bearer_tokens[0]))
+        .json(&change_name_request)
+        .dispatch();
+    expect_valid_action_response(res);
+
+    let res = client
+        .get(uri!(get_game_state(game_id)))
+        .header(bearer("forged.token"))
+        .dispatch();
+    assert_eq!(res.status(), Status::Unauthorized);
+
+    // A token is only valid for its game.
+    let other_game_id = create_game(&client);
+    let res = client
+        .get(uri!(get_game_state(other_game_id)))
+        .header(bearer(&bearer_tokens[0]))
+        .dispatch();
+    assert_eq!(res.status(), Status::Unauthorized);
+
+    // Once the game started, bots cannot join anymore.
+    for bearer_token in &bearer_tokens {
+        let set_player_ready_request = SetPlayerReadyRequest { is_ready: true };
+        let res = client
+            .put(uri!(set_player_ready(game_id)))
+            .header(bearer(bearer_token))
+            .json(&set_player_ready_request)
+            .dispatch();
+        expect_valid_action_response(res);
+    }
+
+    let res = client.post(join_path(game_id, None)).dispatch();
+    assert_eq!(res.status(), Status::Forbidden);
+}
+
+#[test]
+fn router_get_bearer_token() {
+    let client = Client::untracked(rocket()).expect("valid rocket");
+    let game_id = create_game(&client);
+    let cookies = load_players(&client, game_id, 2);
+
+    let res = client.get(uri!(get_bearer_token(game_id))).dispatch();
+    assert_eq!(res.status(), Status::Unauthorized);
+
+    let res = client
+        .get(uri!(get_bearer_token(game_id)))
+        .private_cookie(cookies[1].clone())
+        .dispatch();
+    assert_eq!(res.status(), Status::Ok);
+
+    let res_json = res.into_json::<BearerTokenResponse>();
+    assert!(res_json.is_some());
+    let res_json = res_json.unwrap();
+    assert_eq!(res_json.player_id, 1);
+
+    let res = client
+        .get(uri!(get_game_state(game_id)))
+        .header(bearer(&res_json.token))
+        .dispatch();
+    assert_eq!(seats_with_private_state(res), vec![1]);
+
+    // Revoking the player's sessions also revokes their tokens.
+    let res = client
+        .post(uri!(revoke_sessions(game_id)))
+        .private_cookie(cookies[0].clone())
+        .json(&SeatRequest { player_id: 1 })
+        .dispatch();
+    expect_valid_action_response(res);
+
+    let res = client
+        .get(uri!(get_game_state(game_id)))
+        .header(bearer(&res_json.token))
+        .dispatch();
+    assert_eq!(res.status(), Status::Unauthorized);
+}
+
+// Tests for the host's lobby controls.
+
+#[test]
+fn router_host_lobby_controls() {
+    let client = Client::untracked(rocket()).expect("valid rocket");
+    let game_id = create_game(&client);
+    let cookies = load_players(&client, game_id, 3);
+    let state = client.rocket().state::<GameIdManagerMapping>().unwrap();
+
+    // Only the host can lock the lobby.
+    let lock_lobby_request = LockLobbyRequest { is_locked: true };
+    let res = client
+        .post(uri!(lock_lobby(game_id)))
+        .private_cookie(cookies[1].clone())
+        .json(&lock_lobby_request)
+        .dispatch();
+    expect_invalid_action_response(res);
+
+    let res = client
+        .post(uri!(lock_lobby(game_id)))
+        .private_cookie(cookies[0].clone())
+        .json(&lock_lobby_request)
+        .dispatch();
+    expect_valid_action_response(res);
+
+    // Newcomers can only spectate, whichever way they try to join.
+    let res = client.get(game_uri(game_id, None)).dispatch();
+    assert_eq!(res.status(), Status::SeeOther);
+    assert_eq!(
+        res.headers().get_one("location"),
+        Some(spectator_uri(game_id).to_string().as_str())
+    );
+    let res = client.post(join_path(game_id, None)).dispatch();
+    assert_eq!(res.status(), Status::Forbidden);
+    validate_state_num_of_players(state, &game_id, 3);
+
+    // Only the host can kick players.
+    let seat_request = SeatRequest { player_id: 2 };
+    let res = client
+        .post(uri!(kick_player(game_id)))
+        .private_cookie(cookies[1].clone())
+        .json(&seat_request)
+        .dispatch();
+    expect_invalid_action_response(res);
+
+    let res = client
+        .post(uri!(kick_player(game_id)))
+        .private_cookie(cookies[0].clone())
+        .json(&seat_request)
+        .dispatch();
+    expect_valid_action_response(res);
+    validate_state_num_of_players(state, &game_id, 2);
+
+    let res = client
+        .get(uri!(get_game_state(game_id)))
+        .private_cookie(cookies[2].clone())
+        .dispatch();
+    assert_eq!(res.status(), Status::Unauthorized);
+
+    // The host picks who plays first, and starts the game without waiting for everyone to be ready.
+    let set_seating_order_request = SetSeatingOrderRequest {
+        seating_order: SeatingOrder::Manual(vec![1, 0]),
+    };
+    let res = client
+        .post(uri!(set_seating_order(game_id)))
+        .private_cookie(cookies[0].clone())
+        .json(&set_seating_order_request)
+        .dispatch();
+    expect_valid_action_response(res);
+
+    let res = client
+        .post(uri!(force_start(game_id)))
+        .private_cookie(cookies[1].clone())
+        .dispatch();
+    expect_invalid_action_response(res);
+    validate_state_phase(state, &game_id, GamePhase::InLobby);
+
+    let res = client
+        .post(uri!(force_start(game_id)))
+        .private_cookie(cookies[0].clone())
+        .dispatch();
+    expect_valid_action_response(res);
+    validate_state_phase(state, &game_id, GamePhase::Starting);
+
+    validate_state_if(state, &game_id, |game_manager| {
+        let player_ids: Vec<usize> = game_manager
+            .get_public_state()
+            .players_state
+            .iter()
+            .map(|player_state| player_state.public_player_state.id)
+            .collect();
+        assert_eq!(player_ids, vec![1, 0]);
+    });
+}
+
+#[test]
+fn router_leave_game() {
+    let client = Client::untracked(rocket()).expect("valid rocket");
+    let res = client
+        .post(uri!(create_game(_, _, Some(true), _, _, _, _, _, _)))
+        .dispatch();
+    assert_eq!(res.status(), Status::SeeOther);
+    let game_path = res.headers().get_one("location").unwrap();
+    let game_id = Uuid::parse_str(game_path.trim_start_matches("/game/")).unwrap();
+
+    let cookies = load_players(&client, game_id, 3);
+    let state = client.rocket().state::<GameIdManagerMapping>().unwrap();
+
+    let res = client
+        .post(uri!(force_start(game_id)))
+        .private_cookie(cookies[0].clone())
+        .dispatch();
+    expect_valid_action_response(res);
+
+    // A player forfeits: they can no longer play, and the others keep going.
+    let res = client
+        .post(uri!(leave_game(game_id)))
+        .private_cookie(cookies[2].clone())
+        .dispatch();
+    expect_valid_action_response(res);
+    validate_state_num_of_players(state, &game_id, 2);
+    validate_state_phase(state, &game_id, GamePhase::Starting);
+
+    let res = client
+        .post(uri!(leave_game(game_id)))
+        .private_cookie(cookies[2].clone())
+        .dispatch();
+    assert_eq!(res.status(), Status::Unauthorized);
+
+    // Once the host removes the only other player, the game is over.
+    let res = client
+        .post(uri!(kick_player(game_id)))
+        .private_cookie(cookies[0].clone())
+        .json(&SeatRequest { player_id: 1 })
+        .dispatch();
+    expect_valid_action_response(res);
+    validate_state_num_of_players(state, &game_id, 1);
+    validate_state_phase(state, &game_id, GamePhase::Done);
+}
+
+#[test]
+fn router_time_controls() {
+    let client = Client::untracked(rocket()).expect("valid rocket");
+    let res = client
+        .post(uri!(create_game(
+            _,
+            _,
+            _,
+            Some(30),
+            Some(600),
+            Some(true),
+            _,
+            _,
+            _
+        )))
+        .dispatch();
+    assert_eq!(res.status(), Status::SeeOther);
+    let game_path = res.headers().get_one("location").unwrap();
+    let game_id = Uuid::parse_str(game_path.trim_start_matches("/game/")).unwrap();
+
+    let cookies = load_players(&client, game_id, 2);
+    let state = client.rocket().state::<GameIdManagerMapping>().unwrap();
+
+    let res = client
+        .post(uri!(force_start(game_id)))
+        .private_cookie(cookies[0].clone())
+        .dispatch();
+    expect_valid_action_response(res);
+
+    for cookie in &cookies {
+        let select_destination_cards_request = SelectDestinationCardsRequest {
+            destination_cards_decisions: smallvec![true, true, true],
+        };
+        let res = client
+            .put(uri!(select_destination_cards(game_id)))
+            .private_cookie(cookie.clone())
+            .json(&select_destination_cards_request)
+            .dispatch();
+        expect_valid_action_response(res);
+    }
+    validate_state_phase(state, &game_id, GamePhase::Playing);
+
+    // The clock is part of the game state, once the turn-based game has started.
+    let res = client
+        .get(uri!(get_game_state(game_id)))
+        .private_cookie(cookies[0].clone())
+        .dispatch();
+    assert_eq!(res.status(), Status::Ok);
+
+    let game_state: serde_json::Value = serde_json::from_str(&res.into_string().unwrap()).unwrap();
+    let turn_time_left_ms = game_state["clock"]["turn_time_left_ms"].as_u64().unwrap();
+    assert!(turn_time_left_ms > 29_000 && turn_time_left_ms <= 30_000);
+    assert_eq!(
+        game_state["clock"]["time_banks_left_ms"]
+            .as_object()
+            .unwrap()
+            .len(),
+        2
+    );
+}
+
+#[test]
+fn router_undo() {
+    let client = Client::untracked(rocket()).expect("valid rocket");
+    let res = client
+        .post(uri!(create_game(_, _, _, _, _, _, _, _, _)))
+        .dispatch();
+    assert_eq!(res.status(), Status::SeeOther);
+    let game_path = res.headers().get_one("location").unwrap();
+    let game_id = Uuid::parse_str(game_path.trim_start_matches("/game/")).unwrap();
+
+    let cookies = load_players(&client, game_id, 2);
+    let state = client.rocket().state::<GameIdManagerMapping>().unwrap();
+
+    let res = client
+        .post(uri!(force_start(game_id)))
+        .private_cookie(cookies[0].clone())
+        .dispatch();
+    expect_valid_action_response(res);
+
+    for cookie in &cookies {
+        let select_destination_cards_request = SelectDestinationCardsRequest {
+            destination_cards_decisions: smallvec![true, true, true],
+        };
+        let res = client
+            .put(uri!(select_destination_cards(game_id)))
+            .private_cookie(cookie.clone())
+            .json(&select_destination_cards_request)
+            .dispatch();
+        expect_valid_action_response(res);
+    }
+    validate_state_phase(state, &game_id, GamePhase::Playing);
+    let cookies = reorder_cookies(state, &game_id, cookies);
+
+    let res = client
+        .post(uri!(request_undo(game_id)))
+        .private_cookie(cookies[1].clone())
+        .dispatch();
+    expect_invalid_action_response(res);
+
+    // The route is of length 1, with no required colour: any of the player's train cards claims it.
+    let claimed = TrainColor::iter().any(|train_card| {
+        let res = client
+            .post(uri!(claim_route(game_id)))
+            .private_cookie(cookies[0].clone())
+            .json(&ClaimRouteRequest {
+                route: (City::Vancouver, City::Seattle),
+                parallel_route_index: 0,
+                cards: vec![train_card],
+            })
+            .dispatch();
+        res.into_json::<ActionResponse>().unwrap().success
+    });
+    assert!(claimed);
+    validate_state_turn(state, &game_id, Some(1));
+
+    let res = client
+        .post(uri!(request_undo(game_id)))
+        .private_cookie(cookies[0].clone())
+        .dispatch();
+    expect_valid_action_response(res);
+
+    let res = client
+        .get(uri!(get_game_state(game_id)))
+        .private_cookie(cookies[1].clone())
+        .dispatch();
+    let game_state: serde_json::Value = serde_json::from_str(&res.into_string().unwrap()).unwrap();
+    assert_eq!(game_state["undo"]["action"], "CLAIMED_ROUTE");
+
+    let res = client
+        .post(uri!(answer_undo_request(game_id)))
+        .private_cookie(cookies[1].clone())
+        .json(&AnswerUndoRequest { approve: true })
+        .dispatch();
+    expect_valid_action_response(res);
+    validate_state_turn(state, &game_id, Some(0));
+    validate_state_if(state, &game_id, |game_manager| {
+        let game_state = game_manager.get_state(0);
+        assert!(game_state.undo.is_none());
+        assert_eq!(
+            game_state.players_state[0]
+                .public_player_state
+                .turn_actions
+                .turn,
+            None
+        );
+    });
+}
+
+#[test]
+fn router_webhooks() {
+    let client = Client::untracked(rocket()).expect("valid rocket");
+    let res = client
+        .post(uri!(create_game(_, _, _, _, _, _, _, _, _)))
+        .dispatch();
+    assert_eq!(res.status(), Status::SeeOther);
+    let game_path = res.headers().get_one("location").unwrap();
+    let game_id = Uuid::parse_str(game_path.trim_start_matches("/game/")).unwrap();
+
+    let cookies = load_players(&client, game_id, 2);
+
+    let res = client
+        .post(uri!(register_webhook(game_id)))
+        .private_cookie(cookies[0].clone())
+        .json(&RegisterWebhookRequest {
+            url: String::from("not a url"),
+        })
+        .dispatch();
+    assert_eq!(res.status(), Status::BadRequest);
+
+    let res = client
+        .post(uri!(register_webhook(game_id)))
+        .private_cookie(cookies[0].clone())
+        .json(&RegisterWebhookRequest {
+            url: String::from("http://localhost:8000/dev/webhook"),
+        })
+        .dispatch();
+    assert_eq!(res.status(), Status::Ok);
+    let res_json: WebhookResponse = res.into_json().unwrap();
+    assert!(!res_json.secret.is_empty());
+
+    let res = client
+        .delete(uri!(unregister_webhook(game_id)))
+        .private_cookie(cookies[0].clone())
+        .dispatch();
+    expect_valid_action_response(res);
+
+    let res = client
+        .delete(uri!(unregister_webhook(game_id)))
+        .private_cookie(cookies[0].clone())
+        .dispatch();
+    expect_invalid_action_response(res);
+
+    // Only authenticated players can register a webhook.
+    let res = client
+        .post(uri!(register_webhook(game_id)))
+        .json(&RegisterWebhookRequest {
+            url: String::from("http://localhost:8000/dev/webhook"),
+        })
+        .dispatch();
+    assert_eq!(res.status(), Status::Unauthorized);
+}
+
+#[test]
+fn router_receive_webhook() {
+    let client = Client::untracked(rocket()).expect("valid rocket");
+
+    let res = client.post(uri!(receive_webhook)).body("{}").dispatch();
+    assert_eq!(res.status(), Status::BadRequest);
+
+    let res = client
+        .post(uri!(receive_webhook))
+        .header(Header::new(SIGNATURE_HEADER, webhook::sign("secret", "{}")))
+        .body("{}")
+        .dispatch();
+    assert_eq!(res.status(), Status::NoContent);
+}
+
+fn create_public_game(client: &Client, num_players: usize) -> Uuid {
+    let res = client
+        .post(uri!(create_game(
+            _,
+            _,
+            Some(true),
+            Some(30),
+            _,
+            _,
+            Some(true),
+            _,
+            _
+        )))
+        .dispatch();
+    assert_eq!(res.status(), Status::SeeOther);
+    let game_path = res.headers().get_one("location").unwrap();
+    let game_id = Uuid::parse_str(game_path.trim_start_matches("/game/")).unwrap();
+
+    load_players(client, game_id, num_players);
+    game_id
+}
+
+#[test]
+fn router_list_public_games() {
+    let client = Client::untracked(rocket()).expect("valid rocket");
+
+    let res = client.get(uri!("/api/v1", list_public_games)).dispatch();
+    assert_eq!(res.status(), Status::Ok);
+    let public_games: Vec<PublicGameResponse> = res.into_json().unwrap();
+    assert!(public_games.is_empty());
+
+    // Games are private by default.
+    let private_game_id = create_game(&client);
+    let public_game_id = create_public_game(&client, 2);
+
+    let res = client.get(uri!("/api/v1", list_public_games)).dispatch();
+    assert_eq!(res.status(), Status::Ok);
+    let public_games: Vec<PublicGameResponse> = res.into_json().unwrap();
+    assert_eq!(public_games.len(), 1);
+
+    let public_game = &public_games[0];
+    assert_eq!(public_game.game_id, public_game_id);
+    assert_eq!(public_game.game_code.len(), GAME_CODE_LEN);
+    assert_ne!(public_game.game_id, private_game_id);
+    assert_eq!(public_game.phase, GamePhase::InLobby);
+    assert_eq!(public_game.num_players, 2);
+    assert_eq!(public_game.max_players, MAX_PLAYERS);
+    assert_eq!(public_game.rules.abandoned_routes, AbandonedRoutes::Kept);
+    assert_eq!(public_game.rules.turn_time_limit, Some(30));
+    assert_eq!(public_game.rules.time_bank, None);
+    assert!(public_game.created_at > 0);
+
+    // Games stay listed once they have started.
+    create_public_game(&client, 0);
+    let state = client.rocket().state::<GameIdManagerMapping>().unwrap();
+    assert!(state
+        .get_mut(&public_game_id)
+        .unwrap()
+        .force_start(0)
+        .is_ok());
+
+    let res = client.get(uri!("/api/v1", list_public_games)).dispatch();
+    let public_games: Vec<PublicGameResponse> = res.into_json().unwrap();
+    assert_eq!(public_games.len(), 2);
+    assert!(public_games.iter().any(|public_game| {
+        public_game.game_id == public_game_id && public_game.phase == GamePhase::Starting
+    }));
+}
+
+#[test]
+fn router_quick_match() {
+    let client = Client::untracked(rocket()).expect("valid rocket");
+    let state = client.rocket().state::<GameIdManagerMapping>().unwrap();
+
+    let quick_match = || {
+        let res = client.post(uri!(quick_match)).dispatch();
+        assert_eq!(res.status(), Status::SeeOther);
+        let game_path = res.headers().get_one("location").unwrap();
+        let game_id = Uuid::parse_str(game_path.trim_start_matches("/game/")).unwrap();
+
+        (game_id, res.cookies().get_private(COOKIE_IDENTIFIER_NAME))
+    };
+
+    // Without any public game, one is created, in which the player gets seated once they load it.
+    let (new_game_id, cookie) = quick_match();
+    assert!(cookie.is_none());
+    assert!(state.get(&new_game_id).unwrap().is_public());
+    validate_state_num_of_players(state, &new_game_id, 0);
+    load_players(&client, new_game_id, 1);
+
+    let fullest_game_id = create_public_game(&client, 3);
+    // Private games are never picked, however full they are.
+    load_players(&client, create_game(&client), 4);
+
+    // Players are seated in the fullest public game, until it is full.
+    for player_id in 3..MAX_PLAYERS {
+        let (game_id, cookie) = quick_match();
+        assert_eq!(game_id, fullest_game_id);
+        let cookie = cookie.unwrap();
+        assert_eq!(
+            cookie.value(),
+            format!("{}/{}/0", fullest_game_id, player_id)
+        );
+
+        // Loading the game keeps the player at the seat they got.
+        let res = client
+            .get(game_uri(game_id, None))
+            .private_cookie(cookie)
+            .dispatch();
+        assert_eq!(res.status(), Status::Ok);
+        assert_eq!(res.cookies().iter().count(), 0);
+    }
+    validate_state_num_of_players(state, &fullest_game_id, MAX_PLAYERS);
+
+    let (game_id, cookie) = quick_match();
+    assert_eq!(game_id, new_game_id);
+    assert!(cookie.is_some());
+    validate_state_num_of_players(state, &new_game_id, 2);
+
+    // Locked lobbies are skipped.
+    assert!(state
+        .get_mut(&new_game_id)
+        .unwrap()
+        .lock_lobby(0, true)
+        .is_ok());
+    let (game_id, _) = quick_match();
+    assert_ne!(game_id, new_game_id);
+    assert_ne!(game_id, fullest_game_id);
+    assert!(state.get(&game_id).unwrap().is_public());
+}
+
+#[test]
+fn router_private_game() {
+    let client = Client::untracked(rocket()).expect("valid rocket");
+    let res = client
+        .post(uri!(create_game(_, _, _, _, _, _, _, Some(true), _)))
+        .dispatch();
+    assert_eq!(res.status(), Status::SeeOther);
+
+    // The player creating the game is redirected with the invite code.
+    let game_path = res.headers().get_one("location").unwrap();
+    let captures = Regex::new(r"^/game/([0-9a-f-]+)\?code=([A-Z0-9]{6})$")
+        .unwrap()
+        .captures(game_path)
+        .unwrap();
+    let game_id = Uuid::parse_str(captures.get(1).unwrap().as_str()).unwrap();
+    let invite_code = captures.get(2).unwrap().as_str().to_owned();
+
+    // Once seated, players are redirected to the game without the invite code, as the frontend appends the paths of
+    // its requests to the page's.
+    let res = client.get(game_path).dispatch();
+    assert_eq!(res.status(), Status::SeeOther);
+    let cookie = res.cookies().get_private(COOKIE_IDENTIFIER_NAME).unwrap();
+    let page_path = res.headers().get_one("location").unwrap();
+    assert_eq!(page_path, format!("/game/{}", game_id));
+    assert_eq!(cookie.path(), Some(page_path));
+
+    let res = client
+        .get(page_path)
+        .private_cookie(cookie.clone())
+        .dispatch();
+    assert_eq!(res.status(), Status::Ok);
+    let res = client
+        .get(format!("{}/state", page_path))
+        .private_cookie(cookie.clone())
+        .dispatch();
+    assert_eq!(seats_with_private_state(res), vec![0]);
+
+    // Nobody gets seated without the invite code.
+    let state = client.rocket().state::<GameIdManagerMapping>().unwrap();
+    for code in [None, Some("WRONG1")] {
+        let res = client.get(game_uri(game_id, code)).dispatch();
+        assert_eq!(res.status(), Status::Unauthorized);
+        assert_eq!(res.cookies().iter().count(), 0);
+
+        let res = client.post(join_path(game_id, code)).dispatch();
+        assert_eq!(res.status(), Status::Unauthorized);
+    }
+    validate_state_num_of_players(state, &game_id, 1);
+
+    // Players share the invite link, which carries the invite code.
+    let res = client
+        .get(uri!(get_invite_link(game_id)))
+        .private_cookie(cookie.clone())
+        .dispatch();
+    assert_eq!(res.status(), Status::Ok);
+    let invite_path = res.into_json::<InviteLinkResponse>().unwrap().invite_path;
+    assert_eq!(invite_path, game_path);
+
+    // Invite codes are case-insensitive.
+    let res = client
+        .get(game_uri(game_id, Some(&invite_code.to_lowercase())))
+        .dispatch();
+    assert_eq!(res.status(), Status::SeeOther);
+    let res = client
+        .post(join_path(game_id, Some(&invite_code)))
+        .dispatch();
+    assert_eq!(res.status(), Status::Ok);
+    validate_state_num_of_players(state, &game_id, 3);
+
+    // Seated players do not need the invite code anymore.
+    let res = client
+        .get(game_uri(game_id, None))
+        .private_cookie(cookie)
+        .dispatch();
+    assert_eq!(res.status(), Status::Ok);
+    validate_state_num_of_players(state, &game_id, 3);
+}
+
+#[test]
+fn router_password_protected_game() {
+    let client = Client::untracked(rocket()).expect("valid rocket");
+    let res = client
+        .post(uri!(create_game(
+            _,
+            _,
+            _,
+            _,
+            _,
+            _,
+            Some(true),
+            _,
+            Some("open sesame")
+        )))
+        .dispatch();
+    assert_eq!(res.status(), Status::SeeOther);
+
+    // The player creating the game is seated right away, and the redirection does not carry the password.
+    let game_path = res.headers().get_one("location").unwrap().to_owned();
+    assert!(!game_path.contains('?'));
+    let game_id = Uuid::parse_str(game_path.trim_start_matches("/game/")).unwrap();
+    let cookie = res.cookies().get_private(COOKIE_IDENTIFIER_NAME).unwrap();
+    assert_eq!(cookie.path(), Some(game_path.as_str()));
+
+    let res = client
+        .get(game_path.as_str())
+        .private_cookie(cookie.clone())
+        .dispatch();
+    assert_eq!(res.status(), Status::Ok);
+    let state = client.rocket().state::<GameIdManagerMapping>().unwrap();
+    validate_state_num_of_players(state, &game_id, 1);
+
+    // Others need the password.
+    for code in [None, Some("Open sesame")] {
+        let res = client.get(game_uri(game_id, code)).dispatch();
+        assert_eq!(res.status(), Status::Unauthorized);
+    }
+    let res = client
+        .get(game_uri(game_id, Some("open sesame")))
+        .dispatch();
+    assert_eq!(res.status(), Status::SeeOther);
+    assert_eq!(res.headers().get_one("location"), Some(game_path.as_str()));
+    validate_state_num_of_players(state, &game_id, 2);
+
+    // Passwords are never handed out.
+    let res = client
+        .get(uri!(get_invite_link(game_id)))
+        .private_cookie(cookie)
+        .dispatch();
+    assert_eq!(res.status(), Status::Ok);
+    let invite_path = res.into_json::<InviteLinkResponse>().unwrap().invite_path;
+    assert_eq!(invite_path, game_uri(game_id, None).to_string());
+
+    // Public games may be protected by a password, in which case players are never quick-matched into them.
+    let res = client.get(uri!("/api/v1", list_public_games)).dispatch();
+    let public_games: Vec<PublicGameResponse> = res.into_json().unwrap();
+    assert_eq!(public_games.len(), 1);
+    assert!(public_games[0].private);
+
+    let res = client.post(uri!(quick_match)).dispatch();
+    assert_eq!(res.status(), Status::SeeOther);
+    assert_ne!(
+        res.headers().get_one("location"),
+        Some(game_uri(game_id, None).to_string().as_str())
+    );
+}
+
+#[test]
+fn router_game_code() {
+    let client = Client::untracked(rocket()).expect("valid rocket");
+    let game_id = create_game(&client);
+    let cookies = load_players(&client, game_id, 1);
+
+    let res = client
+        .get(uri!(get_invite_link(game_id)))
+        .private_cookie(cookies[0].clone())
+        .dispatch();
+    assert_eq!(res.status(), Status::Ok);
+    let game_code = res.into_json::<InviteLinkResponse>().unwrap().game_code;
+    let game_codes = client.rocket().state::<GameCodeMapping>().unwrap();
+    assert_eq!(
+        GameKey::Code(game_code.clone()).resolve(game_codes),
+        Some(game_id)
+    );
+
+    // Pages loaded by the game code redirect to the game ID, to which cookies are scoped.
+    let game_uri = game_uri(game_id, None).to_string();
+    let res = client
+        .get(format!("/game/{}", game_code.to_lowercase()))
+        .dispatch();
+    assert_eq!(res.status(), Status::SeeOther);
+    assert_eq!(res.cookies().iter().count(), 0);
+    assert_eq!(res.headers().get_one("location"), Some(game_uri.as_str()));
+
+    let res = client
+        .get(format!("/game/{}/spectate", game_code))
+        .dispatch();
+    assert_eq!(res.status(), Status::SeeOther);
+    assert_eq!(
+        res.headers().get_one("location"),
+        Some(spectator_uri(game_id).to_string().as_str())
+    );
+
+    // Unknown codes are treated like unknown game IDs.
+    let unknown_code = if game_code == "ABCDEF" {
+        "ABCDEG"
+    } else {
+        "ABCDEF"
+    };
+    let res = client.get(format!("/game/{}", unknown_code)).dispatch();
+    assert_eq!(res.status(), Status::SeeOther);
+    assert_eq!(res.headers().get_one("location"), Some("/"));
+    let res = client
+        .post(format!("{}/game/{}/join", API_BASE, unknown_code))
+        .dispatch();
+    assert_eq!(res.status(), Status::NotFound);
+
+    // Routes behind the game path accept the code as well.
+    let res = client
+        .post(format!("{}/game/{}/join", API_BASE, game_code))
+        .dispatch();
+    assert_eq!(res.status(), Status::Ok);
+    let bearer_token = res.into_json::<BearerTokenResponse>().unwrap().token;
+
+    let res = client
+        .get(format!("/game/{}/state", game_code))
+        .header(bearer(&bearer_token))
+        .dispatch();
+    assert_eq!(seats_with_private_state(res), vec![1]);
+    validate_state_num_of_players(
+        client.rocket().state::<GameIdManagerMapping>().unwrap(),
+        &game_id,
+        2,
+    );
+}
+
+fn retry_after(res: &LocalResponse) -> Option<u64> {
+    res.headers()
+        .get_one("Retry-After")
+        .and_then(|retry_after| retry_after.parse().ok())
+}
+
+#[test]
+fn router_rate_limits() {
+    let client = Client::untracked(rocket()).expect("valid rocket");
+    let remote: SocketAddr = "203.0.113.1:4000".parse().unwrap();
+    let other_remote: SocketAddr = "203.0.113.2:4000".parse().unwrap();
+
+    // Games created from the same address are limited, with the default of 5 in a burst, then 10 per minute.
+    for _ in 0..5 {
+        let res = client
+            .post(uri!(create_game(_, _, _, _, _, _, _, _, _)))
+            .remote(remote)
+            .dispatch();
+        assert_eq!(res.status(), Status::SeeOther);
+    }
+    let res = client
+        .post(uri!(create_game(_, _, _, _, _, _, _, _, _)))
+        .remote(remote)
+        .dispatch();
+    assert_eq!(res.status(), Status::TooManyRequests);
+    assert_eq!(retry_after(&res), Some(6));
+    let res = client.post(uri!(quick_match)).remote(remote).dispatch();
+    assert_eq!(res.status(), Status::TooManyRequests);
+
+    // Clients cannot pass for someone else by setting a header.
+    let res = client
+        .post(uri!(create_game(_, _, _, _, _, _, _, _, _)))
+        .remote(remote)
+        .header(Header::new("X-Real-IP", "198.51.100.7"))
+        .dispatch();
+    assert_eq!(res.status(), Status::TooManyRequests);
+
+    let res = client
+        .post(uri!(create_game(_, _, _, _, _, _, _, _, _)))
+        .remote(other_remote)
+        .dispatch();
+    assert_eq!(res.status(), Status::SeeOther);
+
+    // Actions are limited per player, with the default of 60 in a burst.
+    let game_id = create_game(&client);
+    let cookies = load_players(&client, game_id, 2);
+    for _ in 0..60 {
+        let res = client
+            .post(uri!(request_undo(game_id)))
+            .private_cookie(cookies[0].clone())
+            .remote(remote)
+            .dispatch();
+        expect_invalid_action_response(res);
+    }
+    let res = client
+        .post(uri!(request_undo(game_id)))
+        .private_cookie(cookies[0].clone())
+        .remote(remote)
+        .dispatch();
+    assert_eq!(res.status(), Status::TooManyRequests);
+    assert_eq!(retry_after(&res), Some(1));
+
+    // Read-only requests are not limited, nor are other players.
+    let res = client
+        .get(uri!(get_game_state(game_id)))
+        .private_cookie(cookies[0].clone())
+        .remote(remote)
+        .dispatch();
+    assert_eq!(res.status(), Status::Ok);
+    let res = client
+        .post(uri!(request_undo(game_id)))
+        .private_cookie(cookies[1].clone())
+        .remote(remote)
+        .dispatch();
+    expect_invalid_action_response(res);
+}
+
+#[test]
+fn router_rate_limits_behind_proxy() {
+    let client = Client::untracked(server(rocket::custom(
+        rocket::Config::figment()
+            .merge(("rate_limits.create.burst", 1))
+            .merge(("rate_limits.ip_header", "X-Real-IP")),
+    )))
+    .expect("valid rocket");
+    let proxy: SocketAddr = "192.0.2.1:4000".parse().unwrap();
+
+    // Behind a trusted proxy, clients are identified by the header it sets.
+    for (ip, status) in [
+        ("203.0.113.1", Status::SeeOther),
+        ("203.0.113.1", Status::TooManyRequests),
+        ("203.0.113.2", Status::SeeOther),
+    ] {
+        let res = client
+            .post(uri!(create_game(_, _, _, _, _, _, _, _, _)))
+            .remote(proxy)
+            .header(Header::new("X-Real-IP", ip))
+            .dispatch();
+        assert_eq!(res.status(), status);
+    }
+}
+
+#[test]
+fn router_metrics() {
+    let client = Client::untracked(rocket()).expect("valid rocket");
+    let game_id = create_game(&client);
+    let cookies = load_players(&client, game_id, 2);
+
+    // Nothing can be undone in the lobby.
+    let res = client
+        .post(uri!(request_undo(game_id)))
+        .private_cookie(cookies[0].clone())
+        .dispatch();
+    expect_invalid_action_response(res);
+    let res = client
+        .put(uri!(set_player_ready(game_id)))
+        .private_cookie(cookies[0].clone())
+        .json(&SetPlayerReadyRequest { is_ready: true })
+        .dispatch();
+    expect_valid_action_response(res);
+
+    let res = client.get(uri!(get_metrics)).dispatch();
+    assert_eq!(res.status(), Status::Ok);
+    assert_eq!(res.content_type(), Some(ContentType::Plain));
+    let metrics = res.into_string().unwrap();
+    let lines: Vec<&str> = metrics.lines().collect();
+
+    for line in [
+        "ticket_to_ride_games{phase=\"in_lobby\"} 1",
+        "ticket_to_ride_players{phase=\"in_lobby\"} 2",
+        "ticket_to_ride_actions_total{route=\"request_undo\",outcome=\"failure\"} 1",
+        "ticket_to_ride_actions_total{route=\"set_player_ready\",outcome=\"success\"} 1",
+        "ticket_to_ride_http_responses_total{route=\"create_game\",status=\"303\"} 1",
+        "ticket_to_ride_http_responses_total{route=\"load_game\",status=\"200\"} 2",
+        "ticket_to_ride_http_request_duration_seconds_count{route=\"create_game\"} 1",
+    ] {
+        assert!(lines.contains(&line), "Missing `{}` in:\n{}", line, metrics);
+    }
+}
+
+#[test]
+fn router_health_probes() {
+    let client = Client::untracked(rocket()).expect("valid rocket");
+
+    let res = client.get(uri!(healthz)).dispatch();
+    assert_eq!(res.status(), Status::Ok);
+    assert_eq!(res.into_string().as_deref(), Some("ok"));
+
+    let res = client.get(uri!(readyz)).dispatch();
+    assert_eq!(res.status(), Status::Ok);
+    assert_eq!(res.into_string().as_deref(), Some("ready"));
+
+    // Still ready once the server hosts as many games as it can, for the games it hosts to stay reachable: creating
+    // more games fails instead.
+    let client = Client::untracked(server(rocket::custom(
+        rocket::Config::figment().merge(("max_games", 1)),
+    )))
+    .expect("valid rocket");
+    create_game(&client);
+    let res = client
+        .post(uri!(create_game(_, _, _, _, _, _, _, _, _)))
+        .dispatch();
+    assert_eq!(res.status(), Status::ServiceUnavailable);
+    let res = client.get(uri!(readyz)).dispatch();
+    assert_eq!(res.status(), Status::Ok);
+    let res = client.get(uri!(healthz)).dispatch();
+    assert_eq!(res.status(), Status::Ok);
+}
+
+const ADMIN_SECRET: &str = "admin-s3cret";
+
+#[test]
+fn router_admin_disabled() {
+    let client = Client::untracked(rocket()).expect("valid rocket");
+
+    let res = client
+        .get(uri!("/api/v1", admin_list_games))
+        .header(bearer(ADMIN_SECRET))
+        .dispatch();
+    assert_eq!(res.status(), Status::NotFound);
+}
+
+#[test]
+fn router_admin() {
+    let client = Client::untracked(server(rocket::custom(
+        rocket::Config::figment().merge(("admin_secret", ADMIN_SECRET)),
+    )))
+    .expect("valid rocket");
+    let game_id = create_game(&client);
+    let cookies = load_players(&client, game_id, 2);
+    let lobby_id = create_game(&client);
+
+    for res in [
+        client.get(uri!("/api/v1", admin_list_games)).dispatch(),
+        client
+            .get(uri!("/api/v1", admin_list_games))
+            .header(bearer("wrong"))
+            .dispatch(),
+        client
+            .get(uri!("/api/v1", admin_list_games))
+            .private_cookie(cookies[0].clone())
+            .dispatch(),
+    ] {
+        assert_eq!(res.status(), Status::Unauthorized);
+    }
+
+    let res = client
+        .get(uri!("/api/v1", admin_list_games))
+        .header(bearer(ADMIN_SECRET))
+        .dispatch();
+    assert_eq!(res.status(), Status::Ok);
+    let games = res.into_json::<Vec<AdminGameResponse>>().unwrap();
+    assert_eq!(games.len(), 2);
+    assert!(games.iter().any(|game| game.game_id == lobby_id));
+    let game = games.iter().find(|game| game.game_id == game_id).unwrap();
+    assert_eq!(game.phase, GamePhase::InLobby);
+    assert_eq!(game.players.len(), 2);
+    assert!(game.players[0].is_host);
+
+    // The admin API is only served under its version, unlike routes called from the frontend.
+    let res = client
+        .get(uri!(admin_list_games))
+        .header(bearer(ADMIN_SECRET))
+        .dispatch();
+    assert_eq!(res.status(), Status::NotFound);
+
+    for cookie in &cookies {
+        let res = client
+            .put(uri!(set_player_ready(game_id)))
+            .private_cookie(cookie.clone())
+            .json(&SetPlayerReadyRequest { is_ready: true })
+            .dispatch();
+        expect_valid_action_response(res);
+    }
+
+    // Admins see every player's private state.
+    let res = client
+        .get(format!("{}/admin/game/{}/state", API_BASE, game_id))
+        .header(bearer(ADMIN_SECRET))
+        .dispatch();
+    let mut seats = seats_with_private_state(res);
+    seats.sort_unstable();
+    assert_eq!(seats, vec![0, 1]);
+    let res = client
+        .get(uri!(get_game_state(game_id)))
+        .private_cookie(cookies[0].clone())
+        .dispatch();
+    assert_eq!(seats_with_private_state(res), vec![0]);
+
+    let res = client
+        .post(format!("{}/admin/game/{}/end", API_BASE, game_id))
+        .header(bearer(ADMIN_SECRET))
+        .dispatch();
+    expect_valid_action_response(res);
+    let state = client.rocket().state::<GameIdManagerMapping>().unwrap();
+    validate_state_phase(state, &game_id, GamePhase::Done);
+    let res = client
+        .post(format!("{}/admin/game/{}/end", API_BASE, game_id))
+        .header(bearer(ADMIN_SECRET))
+        .dispatch();
+    expect_invalid_action_response(res);
+    let res = client
+        .post(uri!("/api/v1", admin_end_game(lobby_id)))
+        .header(bearer(ADMIN_SECRET))
+        .dispatch();
+    expect_invalid_action_response(res);
+
+    let res = client
+        .delete(format!("{}/admin/game/{}", API_BASE, game_id))
+        .header(bearer(ADMIN_SECRET))
+        .dispatch();
+    assert_eq!(res.status(), Status::NoContent);
+    assert!(!state.contains_key(&game_id));
+    for res in [
+        client
+            .delete(format!("{}/admin/game/{}", API_BASE, game_id))
+            .header(bearer(ADMIN_SECRET))
+            .dispatch(),
+        client
+            .get(format!("{}/admin/game/{}/state", API_BASE, game_id))
+            .header(bearer(ADMIN_SECRET))
+            .dispatch(),
+        client
+            .post(format!("{}/admin/game/{}/end", API_BASE, game_id))
+            .header(bearer(ADMIN_SECRET))
+            .dispatch(),
+    ] {
+        assert_eq!(res.status(), Status::NotFound);
+    }
+}
+
+#[test]
+fn router_maintenance_notice() {
+    let client = Client::untracked(server(rocket::custom(
+        rocket::Config::figment().merge(("admin_secret", ADMIN_SECRET)),
+    )))
+    .expect("valid rocket");
+
+    let res = client.get(uri!("/api/v1", get_notice)).dispatch();
+    assert_eq!(res.into_json::<Option<NoticeResponse>>(), Some(None));
+
+    let notice_request = NoticeRequest {
+        message: String::from("The server restarts in 10 minutes."),
+    };
+    let res = client
+        .put(uri!("/api/v1", admin_set_notice))
+        .json(&notice_request)
+        .dispatch();
+    assert_eq!(res.status(), Status::Unauthorized);
+    let res = client
+        .put(uri!("/api/v1", admin_set_notice))
+        .header(bearer(ADMIN_SECRET))
+        .json(&notice_request)
+        .dispatch();
+    assert_eq!(res.status(), Status::NoContent);
+
+    let res = client.get(uri!("/api/v1", get_notice)).dispatch();
+    let notice = res.into_json::<Option<NoticeResponse>>().unwrap().unwrap();
+    assert_eq!(notice.message, notice_request.message);
+
+    let res = client
+        .delete(uri!("/api/v1", admin_clear_notice))
+        .header(bearer(ADMIN_SECRET))
+        .dispatch();
+    assert_eq!(res.status(), Status::NoContent);
+    let res = client.get(uri!("/api/v1", get_notice)).dispatch();
+    assert_eq!(res.into_json::<Option<NoticeResponse>>(), Some(None));
+}
+
+#[test]
+fn router_restore_games_from_snapshot() {
+    let snapshot_file = std::env::temp_dir().join(format!("snapshot-{}.json", Uuid::new_v4()));
+    // Cookies only survive a restart with the same secret key.
+    let figment = rocket::Config::figment()
+        .merge(("secret_key", "hPRYyVRiMyxpw5sBB1XeCMN1kFsDCqKvBi2QJxBVHQk="))
+        .merge(("snapshot_file", &snapshot_file));
+
+    let client = Client::untracked(server(rocket::custom(figment.clone()))).expect("valid rocket");
+    let game_id = create_game(&client);
+    let cookies = load_players(&client, game_id, 2);
+    for cookie in &cookies {
+        let res = client
+            .put(uri!(set_player_ready(game_id)))
+            .private_cookie(cookie.clone())
+            .json(&SetPlayerReadyRequest { is_ready: true })
+            .dispatch();
+        expect_valid_action_response(res);
+    }
+    let state = client.rocket().state::<GameIdManagerMapping>().unwrap();
+    rocket::async_test(snapshot::save_games(&snapshot_file, state));
+    let game_code = state.get(&game_id).unwrap().code().unwrap().to_owned();
+
+    // Players pick up where they left off, after the server restarted.
+    let client = Client::untracked(server(rocket::custom(figment))).expect("valid rocket");
+    let state = client.rocket().state::<GameIdManagerMapping>().unwrap();
+    validate_state_phase(state, &game_id, GamePhase::Starting);
+    let res = client
+        .get(uri!(get_game_state(game_code.as_str())))
+        .private_cookie(cookies[1].clone())
+        .dispatch();
+    assert_eq!(seats_with_private_state(res), vec![1]);
+    let res = client
+        .put(uri!(select_destination_cards(game_id)))
+        .private_cookie(cookies[0].clone())
+        .json(&SelectDestinationCardsRequest {
+            destination_cards_decisions: smallvec![true, true, true],
+        })
+        .dispatch();
+    expect_valid_action_response(res);
+    assert!(!snapshot_file.exists());
+}
+
+// Tests for the versioned JSON API, and its OpenAPI document.
+
+#[test]
+fn router_openapi() {
+    let client = Client::tracked(rocket()).expect("valid rocket");
+    let res = client.get(uri!("/api/v1", get_openapi())).dispatch();
+    assert_eq!(res.status(), Status::Ok);
+    assert_eq!(res.content_type(), Some(ContentType::JSON));
+    let document = res.into_json::<serde_json::Value>().unwrap();
+    assert_eq!(document, openapi::document());
+
+    let operations: HashMap<&str, (&str, &str, &serde_json::Value)> = document["paths"]
+        .as_object()
+        .unwrap()
+        .iter()
+        .flat_map(|(path, path_item)| {
+            path_item
+                .as_object()
+                .unwrap()
+                .iter()
+                .map(move |(method, operation)| {
+                    let operation_id = operation["operationId"].as_str().unwrap();
+                    (operation_id, (method.as_str(), path.as_str(), operation))
+                })
+        })
+        .collect();
+
+    // Every route of the API is documented, at its path, and nothing else is.
+    // Routes may ignore the game's segment (e.g. `<_>`), leaving it to their guards: names are not compared.
+    let route_segment = Regex::new(r"<[^>]+>").unwrap();
+    let document_segment = Regex::new(r"\{[^}]+\}").unwrap();
+    let api_routes: Vec<_> = client
+        .rocket()
+        .routes()
+        .filter(|route| route.uri.base() == API_BASE)
+        .collect();
+    assert_eq!(api_routes.len(), operations.len());
+    for route in api_routes {
+        let name = route.name.as_deref().unwrap();
+        let (method, path, operation) = operations
+            .get(name)
+            .unwrap_or_else(|| panic!("{} is not documented", name));
+        assert_eq!(method.to_uppercase(), route.method.as_str());
+        let route_path = route.uri.path().to_string();
+        let route_path = route_path.strip_prefix(API_BASE).unwrap();
+        assert_eq!(
+            document_segment.replace_all(path, "{}"),
+            route_segment.replace_all(route_path, "{}")
+        );
+
+        // Documented requests have the body, and the query parameters, that the route takes.
+        assert_eq!(
+            operation.get("requestBody").is_some(),
+            route.format.as_ref() == Some(&MediaType::JSON),
+            "{}",
+            name
+        );
+        let query_parameters: Vec<&str> = operation["parameters"]
+            .as_array()
+            .map(|parameters| {
+                parameters
+                    .iter()
+                    .filter(|parameter| parameter["in"] == "query")
+                    .map(|parameter| parameter["name"].as_str().unwrap())
+                    .collect()
+            })
+            .unwrap_or_default();
+        for query in route
+            .uri
+            .query()
+            .into_iter()
+            .flat_map(|query| query.split('&'))
+        {
+            let query = query.trim_start_matches('<').trim_end_matches('>');
+            assert!(query_parameters.contains(&query), "{}: {}", name, query);
+        }
+    }
+}
+
+#[test]
+fn router_api_v1() {
+    let client = Client::tracked(rocket()).expect("valid rocket");
+    let game_id = create_game(&client);
+
+    // API clients authenticate with bearer tokens, as cookies are scoped to the game's page.
+    let tokens: Vec<String> = (0..2)
+        .map(|_| {
+            let res = client.post(join_path(game_id, None)).dispatch();
+            assert_eq!(res.status(), Status::Ok);
+            res.into_json::<BearerTokenResponse>().unwrap().token
+        })
+        .collect();
+    for token in &tokens {
+        let res = client
+            .put(uri!("/api/v1", set_player_ready(game_id)))
+            .header(bearer(token))
+            .json(&SetPlayerReadyRequest { is_ready: true })
+            .dispatch();
+        expect_valid_action_response(res);
+    }
+
+    let res = client
+        .get(uri!("/api/v1", get_game_state(game_id)))
+        .dispatch();
+    assert_eq!(res.status(), Status::Unauthorized);
+
+    let res = client
+        .get(uri!("/api/v1", get_game_state(game_id)))
+        .header(bearer(&tokens[1]))
+        .dispatch();
+    assert_eq!(res.status(), Status::Ok);
+    let state = res.into_json::<serde_json::Value>().unwrap();
+    assert_eq!(state["phase"], "starting");
+
+    // The game state is as documented.
+    let document = openapi::document();
+    let properties = document["components"]["schemas"]["GameState"]["properties"]
+        .as_object()
+        .unwrap();
+    for key in state.as_object().unwrap().keys() {
+        assert!(properties.contains_key(key), "{} is not documented", key);
+    }
+}
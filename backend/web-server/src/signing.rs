@@ -0,0 +1,57 @@
+//! HMAC-SHA256 signatures, shared by everything the server signs: bearer tokens (see [`crate::bearer_token`]),
+//! webhook deliveries (see [`crate::webhook`]), and digests of the secrets it does not keep in the clear (the admin
+//! secret, see [`crate::admin::AdminSecret`], and the passwords of games, see [`crate::game::Game::admits`]).
+
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::Sha256;
+
+/// Length of randomly generated keys.
+pub(crate) const KEY_LEN: usize = 32;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Generates a random key, of [`KEY_LEN`] bytes.
+pub(crate) fn random_key() -> Vec<u8> {
+    let mut key = vec![0; KEY_LEN];
+    rand::thread_rng().fill_bytes(&mut key);
+    key
+}
+
+/// Returns the signature of the message: its HMAC-SHA256, with the given key.
+pub(crate) fn sign(key: &[u8], message: &[u8]) -> Vec<u8> {
+    mac(key, message).finalize().into_bytes().to_vec()
+}
+
+/// Whether the signature is the one of the message, with the given key. Signatures are compared in constant time.
+pub(crate) fn verify(key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+    mac(key, message).verify_slice(signature).is_ok()
+}
+
+fn mac(key: &[u8], message: &[u8]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(message);
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sign_and_verify() {
+        let key = random_key();
+        assert_eq!(key.len(), KEY_LEN);
+        assert_ne!(key, random_key());
+
+        let signature = sign(&key, b"message");
+        assert_eq!(signature.len(), 32);
+        assert_eq!(signature, sign(&key, b"message"));
+        assert!(verify(&key, b"message", &signature));
+
+        assert!(!verify(&key, b"other message", &signature));
+        assert!(!verify(&random_key(), b"message", &signature));
+        assert!(!verify(&key, b"message", &signature[1..]));
+        assert!(!verify(&key, b"message", b""));
+    }
+}
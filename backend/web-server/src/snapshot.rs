@@ -0,0 +1,408 @@
+//! Graceful shutdown: games survive a restart of the server.
+//!
+//! When the server shuts down (e.g. on `SIGTERM`), the [`GracefulShutdown`] fairing stops accepting new games and
+//! player actions, waits for the actions in flight to finish (see [`Drain`]), and writes every game to the snapshot
+//! file. On the next launch, games are restored from that file (see [`restore_games`]), which is then removed, or set
+//! aside if some games could not be restored.
+
+use crate::controller::GameIdManagerMapping;
+use crate::game::{Game, GameSnapshot};
+use crate::logging::GameTracer;
+use crate::unix_time::unix_now;
+use crate::webhook::WebhookDispatcher;
+
+use dashmap::DashMap;
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::figment::Figment;
+use rocket::serde::{Deserialize, Serialize};
+use rocket::tokio::{fs, time};
+use rocket::{Orbit, Rocket};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// How often the actions in flight are checked, while draining them.
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Keeps track of the player actions in flight, so that the server can wait for them before shutting down.
+///
+/// Managed by Rocket: every [`crate::controller::WriteController`] enters it (see [`Drain::enter`]).
+#[derive(Debug, Default)]
+pub struct Drain {
+    /// Whether the server is shutting down, and refuses new games and player actions.
+    draining: AtomicBool,
+    in_flight: AtomicUsize,
+}
+
+impl Drain {
+    /// Registers an action in flight, until the returned [`InFlight`] is dropped.
+    ///
+    /// Returns `None` if the server is shutting down: the action must then be refused.
+    pub(crate) fn enter(&self) -> Option<InFlight<'_>> {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        let in_flight = InFlight(self);
+
+        // Checked after entering, so that the action is either refused, or waited for.
+        if self.draining.load(Ordering::SeqCst) {
+            None
+        } else {
+            Some(in_flight)
+        }
+    }
+
+    /// Fails if the server is shutting down, and no longer accepts new games.
+    pub(crate) fn ensure_accepting_games(&self) -> Result<(), String> {
+        if self.draining.load(Ordering::SeqCst) {
+            Err(String::from(
+                "The server is shutting down: try again in a moment.",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Refuses new games and player actions from now on, and waits for the actions in flight to finish, for at
+    /// most `timeout`.
+    ///
+    /// Returns whether every action finished in time.
+    async fn drain(&self, timeout: Duration) -> bool {
+        self.draining.store(true, Ordering::SeqCst);
+
+        let drained = async {
+            while self.in_flight.load(Ordering::SeqCst) > 0 {
+                time::sleep(DRAIN_POLL_INTERVAL).await;
+            }
+        };
+        time::timeout(timeout, drained).await.is_ok()
+    }
+}
+
+/// A player action in flight, which the server waits for before shutting down. See [`Drain::enter`].
+pub(crate) struct InFlight<'a>(&'a Drain);
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.0.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// What is written to the snapshot file, serializable in JSON.
+#[derive(Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+struct Snapshot {
+    /// When the snapshot was taken, in seconds since the UNIX epoch.
+    saved_at: u64,
+    games: Vec<SavedGame>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+struct SavedGame {
+    game_id: Uuid,
+    game: GameSnapshot,
+}
+
+/// Reads the path of the snapshot file from Rocket's configuration: `snapshot_file`. If not configured (or empty),
+/// games are not snapshotted.
+pub fn snapshot_file_from_figment(figment: &Figment) -> Option<PathBuf> {
+    figment
+        .extract_inner::<PathBuf>("snapshot_file")
+        .ok()
+        .filter(|snapshot_file| !snapshot_file.as_os_str().is_empty())
+}
+
+/// Restores the games written to the snapshot file when the server last shut down, along with their codes, and
+/// removes the file. Returns how many games were restored.
+///
+/// Games are observed as they are when created (see [`crate::controller::WriteController::create_game`]). Games
+/// which cannot be restored are skipped, in which case the file is renamed to `<snapshot_file>.failed` instead of
+/// being removed, such that they can still be looked into (the next shutdown overwrites the snapshot file).
+pub(crate) fn restore_games(
+    snapshot_file: &Path,
+    games: &DashMap<Uuid, Game>,
+    game_codes: &DashMap<String, Uuid>,
+    webhook_dispatcher: &WebhookDispatcher,
+) -> usize {
+    let snapshot = match std::fs::read(snapshot_file) {
+        Ok(snapshot) => snapshot,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return 0,
+        Err(e) => {
+            warn!(path = %snapshot_file.display(), error = %e, "Could not read the snapshot");
+            return 0;
+        }
+    };
+    let snapshot: Snapshot = match serde_json::from_slice(&snapshot) {
+        Ok(snapshot) => snapshot,
+        Err(e) => {
+            warn!(path = %snapshot_file.display(), error = %e, "Could not parse the snapshot");
+            set_aside(snapshot_file);
+            return 0;
+        }
+    };
+
+    let mut num_games = 0;
+    let mut num_failed_games = 0;
+    for SavedGame { game_id, game } in snapshot.games {
+        let mut game = match Game::restore(game) {
+            Ok(game) => game,
+            Err(e) => {
+                warn!(%game_id, error = %e, "Could not restore game");
+                num_failed_games += 1;
+                continue;
+            }
+        };
+
+        game.enable_webhooks(game_id, webhook_dispatcher);
+        game.manager_mut()
+            .add_observer(Box::new(GameTracer::new(game_id)));
+        if let Some(game_code) = game.code() {
+            game_codes.insert(game_code.to_owned(), game_id);
+        }
+        games.insert(game_id, game);
+        num_games += 1;
+    }
+
+    // Games live on in memory from now on: a stale snapshot must not be restored again.
+    if num_failed_games > 0 {
+        set_aside(snapshot_file);
+    } else if let Err(e) = std::fs::remove_file(snapshot_file) {
+        warn!(path = %snapshot_file.display(), error = %e, "Could not remove the snapshot");
+    }
+    info!(
+        num_games,
+        num_failed_games,
+        saved_at = snapshot.saved_at,
+        "Games restored"
+    );
+    num_games
+}
+
+/// Returns where a snapshot file which could not be fully restored is set aside.
+fn failed_snapshot_file(snapshot_file: &Path) -> PathBuf {
+    let mut failed_snapshot_file = snapshot_file.as_os_str().to_owned();
+    failed_snapshot_file.push(".failed");
+    PathBuf::from(failed_snapshot_file)
+}
+
+/// Renames a snapshot file which could not be fully restored, such that it is neither restored again, nor overwritten.
+fn set_aside(snapshot_file: &Path) {
+    let failed_snapshot_file = failed_snapshot_file(snapshot_file);
+
+    match std::fs::rename(snapshot_file, &failed_snapshot_file) {
+        Ok(()) => warn!(path = %failed_snapshot_file.display(), "Snapshot set aside"),
+        Err(e) => {
+            warn!(path = %snapshot_file.display(), error = %e, "Could not set the snapshot aside")
+        }
+    }
+}
+
+/// Writes every game to the snapshot file, replacing it at once, such that an interrupted write leaves any previous
+/// snapshot untouched.
+pub(crate) async fn save_games(snapshot_file: &Path, games: &DashMap<Uuid, Game>) {
+    let snapshot = Snapshot {
+        saved_at: unix_now(),
+        games: games
+            .iter()
+            .map(|game| SavedGame {
+                game_id: *game.key(),
+                game: game.snapshot(),
+            })
+            .collect(),
+    };
+    let num_games = snapshot.games.len();
+    let snapshot =
+        serde_json::to_vec(&snapshot).expect("Snapshots should never fail serializing as JSON");
+
+    let mut partial_snapshot_file = snapshot_file.as_os_str().to_owned();
+    partial_snapshot_file.push(".partial");
+    let written = match fs::write(&partial_snapshot_file, snapshot).await {
+        Ok(()) => fs::rename(&partial_snapshot_file, snapshot_file).await,
+        Err(e) => Err(e),
+    };
+
+    match written {
+        Ok(()) => info!(num_games, path = %snapshot_file.display(), "Games snapshotted"),
+        Err(e) => warn!(path = %snapshot_file.display(), error = %e, "Could not snapshot games"),
+    }
+}
+
+/// Fairing which, once the server is asked to shut down, waits for the player actions in flight (see [`Drain`]), and
+/// snapshots every game, if a snapshot file is configured (see [`snapshot_file_from_figment`]).
+///
+/// Actions are waited for during Rocket's grace period (`shutdown.grace`) at most.
+pub struct GracefulShutdown {
+    snapshot_file: Option<PathBuf>,
+}
+
+impl GracefulShutdown {
+    /// Constructs a [`GracefulShutdown`], snapshotting games to the given file, if any.
+    pub fn new(snapshot_file: Option<PathBuf>) -> Self {
+        Self { snapshot_file }
+    }
+}
+
+#[rocket::async_trait]
+impl Fairing for GracefulShutdown {
+    fn info(&self) -> Info {
+        Info {
+            name: "Graceful shutdown",
+            kind: Kind::Shutdown,
+        }
+    }
+
+    async fn on_shutdown(&self, rocket: &Rocket<Orbit>) {
+        let grace = Duration::from_secs(rocket.config().shutdown.grace as u64);
+        if let Some(drain) = rocket.state::<Drain>() {
+            if !drain.drain(grace).await {
+                warn!(
+                    in_flight = drain.in_flight.load(Ordering::SeqCst),
+                    "Player actions still in flight after the grace period"
+                );
+            }
+        }
+
+        let games = rocket.state::<GameIdManagerMapping>();
+        if let (Some(snapshot_file), Some(games)) = (&self.snapshot_file, games) {
+            save_games(snapshot_file, games).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request_types::CreateGameOptions;
+
+    #[test]
+    fn drain_in_flight_actions() {
+        let drain = Drain::default();
+        let in_flight = drain.enter();
+        assert!(in_flight.is_some());
+        assert!(drain.ensure_accepting_games().is_ok());
+
+        rocket::async_test(async {
+            assert!(!drain.drain(Duration::from_millis(50)).await);
+            assert!(drain.enter().is_none());
+            assert!(drain.ensure_accepting_games().is_err());
+
+            drop(in_flight);
+            assert!(drain.drain(Duration::from_millis(50)).await);
+        });
+    }
+
+    #[test]
+    fn save_and_restore_games() {
+        let snapshot_file = std::env::temp_dir().join(format!("snapshot-{}.json", Uuid::new_v4()));
+        let webhook_dispatcher = WebhookDispatcher::new("http://localhost:8000");
+        let games = DashMap::new();
+        let game_id = Uuid::new_v4();
+        let mut game = Game::new(&CreateGameOptions {
+            private: true,
+            ..Default::default()
+        });
+        game.set_code(String::from("ABCDEF"));
+        game.add_player();
+        game.add_player();
+        assert!(game.force_start(0).is_ok());
+        assert!(game.register_webhook(1, "https://example.com/hook").is_ok());
+        games.insert(game_id, game);
+
+        rocket::async_test(save_games(&snapshot_file, &games));
+
+        let restored_games = DashMap::new();
+        let game_codes = DashMap::new();
+        assert_eq!(
+            restore_games(
+                &snapshot_file,
+                &restored_games,
+                &game_codes,
+                &webhook_dispatcher
+            ),
+            1
+        );
+        assert!(!snapshot_file.exists());
+        assert_eq!(game_codes.get("ABCDEF").as_deref(), Some(&game_id));
+
+        let game = games.get(&game_id).unwrap();
+        let restored_game = restored_games.get(&game_id).unwrap();
+        assert_eq!(restored_game.snapshot(), game.snapshot());
+        assert_eq!(
+            serde_json::to_value(restored_game.manager().get_full_state()).unwrap(),
+            serde_json::to_value(game.manager().get_full_state()).unwrap()
+        );
+
+        // Nothing is left to restore.
+        assert_eq!(
+            restore_games(
+                &snapshot_file,
+                &restored_games,
+                &game_codes,
+                &webhook_dispatcher
+            ),
+            0
+        );
+    }
+
+    #[test]
+    fn restore_games_set_aside_on_failure() {
+        let snapshot_file = std::env::temp_dir().join(format!("snapshot-{}.json", Uuid::new_v4()));
+        let failed_snapshot_file = failed_snapshot_file(&snapshot_file);
+        let webhook_dispatcher = WebhookDispatcher::new("http://localhost:8000");
+        let games = DashMap::new();
+        let mut game = Game::new(&CreateGameOptions::default());
+        game.add_player();
+        games.insert(Uuid::new_v4(), game);
+        rocket::async_test(save_games(&snapshot_file, &games));
+
+        // A hand-edited game, in which a player who is not in the game draws a card.
+        let mut snapshot: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&snapshot_file).unwrap()).unwrap();
+        let mut bad_game = snapshot["games"][0].clone();
+        bad_game["game_id"] = serde_json::json!(Uuid::new_v4());
+        bad_game["game"]["events"]
+            .as_array_mut()
+            .unwrap()
+            .push(serde_json::json!({"type": "close_train_card_drawn", "player_id": 9}));
+        snapshot["games"].as_array_mut().unwrap().push(bad_game);
+        std::fs::write(&snapshot_file, snapshot.to_string()).unwrap();
+
+        let restored_games = DashMap::new();
+        let game_codes = DashMap::new();
+        assert_eq!(
+            restore_games(
+                &snapshot_file,
+                &restored_games,
+                &game_codes,
+                &webhook_dispatcher
+            ),
+            1
+        );
+        assert_eq!(restored_games.len(), 1);
+        assert!(!snapshot_file.exists());
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(
+                &std::fs::read(&failed_snapshot_file).unwrap()
+            )
+            .unwrap(),
+            snapshot
+        );
+
+        // Snapshots which cannot be parsed are set aside as well.
+        std::fs::write(&snapshot_file, "{").unwrap();
+        assert_eq!(
+            restore_games(
+                &snapshot_file,
+                &restored_games,
+                &game_codes,
+                &webhook_dispatcher
+            ),
+            0
+        );
+        assert!(!snapshot_file.exists());
+        assert_eq!(std::fs::read(&failed_snapshot_file).unwrap(), b"{");
+
+        std::fs::remove_file(failed_snapshot_file).unwrap();
+    }
+}
@@ -0,0 +1,11 @@
+//! Wall-clock time, as found in responses, snapshots and archives: seconds since the UNIX epoch.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Seconds since the UNIX epoch.
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("The clock should be set after the UNIX epoch")
+        .as_secs()
+}
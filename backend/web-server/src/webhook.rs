@@ -0,0 +1,631 @@
+//! Webhooks: a nudge sent to players when it becomes their turn, or when the game is over.
+//!
+//! Each player can register a URL, to which the server POSTs a JSON [`WebhookPayload`]. Every delivery is signed:
+//! the `X-Webhook-Signature` header holds `sha256=<signature>`, the base64url-encoded HMAC-SHA256 of the body, with
+//! the secret handed to the player when they registered the webhook. Failed deliveries are retried, with an
+//! exponential backoff.
+//!
+//! Outside debug builds, webhooks can only point to public addresses, so that they cannot be used to reach services
+//! on the server's own network (e.g. a cloud metadata endpoint): see [`is_public`].
+
+use crate::signing;
+use crate::unix_time::unix_now;
+
+use rocket::figment::Figment;
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::serde::{Deserialize, Serialize};
+use rocket::tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use rocket::tokio::{select, time};
+use rocket::Shutdown;
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use ticket_to_ride::manager::GameState;
+use ticket_to_ride::observer::GameObserver;
+use tracing::warn;
+use uuid::Uuid;
+
+/// Header carrying the signature of a delivery.
+pub(crate) const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+
+/// How many times a delivery is attempted, before giving up.
+const MAX_DELIVERY_ATTEMPTS: u32 = 5;
+
+/// How long to wait before retrying a failed delivery for the first time. The delay doubles with every attempt.
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// How long a receiver has to respond to a delivery.
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Whether webhooks may point to loopback, private, or link-local addresses. Only debug builds allow it, to try out
+/// webhooks locally (see [`crate::router::receive_webhook()`]).
+const ALLOW_PRIVATE_ADDRESSES: bool = cfg!(debug_assertions);
+
+/// What a webhook is notified of.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(crate = "rocket::serde", rename_all = "snake_case")]
+pub enum WebhookEvent {
+    /// It became the player's turn.
+    TurnStarted,
+    /// The game is over.
+    GameOver,
+}
+
+/// The body of a delivery, serialized in JSON.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct WebhookPayload {
+    pub event: WebhookEvent,
+    pub game_id: Uuid,
+    /// The player the webhook belongs to.
+    pub player_id: usize,
+    /// The current turn, if the turn-based game has started.
+    pub turn: Option<usize>,
+    /// Where to play the game.
+    pub link: String,
+    /// When the event happened, in seconds since the UNIX epoch.
+    pub sent_at: u64,
+}
+
+/// A URL registered by a player, and the secret deliveries to it are signed with.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub(crate) struct Webhook {
+    url: String,
+    secret: String,
+}
+
+/// A payload, on its way to a webhook.
+#[derive(Debug)]
+pub(crate) struct Delivery {
+    webhook: Webhook,
+    payload: WebhookPayload,
+}
+
+impl Delivery {
+    /// Serializes the payload, and signs it. Returns the body, and the value of the [`SIGNATURE_HEADER`].
+    fn sign(&self) -> (String, String) {
+        let body =
+            rocket::serde::json::to_string(&self.payload).expect("Payloads serialize as JSON");
+        let signature = sign(&self.webhook.secret, &body);
+
+        (body, signature)
+    }
+}
+
+/// Signs the body with the given secret, as found in the [`SIGNATURE_HEADER`].
+pub(crate) fn sign(secret: &str, body: &str) -> String {
+    let signature = signing::sign(secret.as_bytes(), body.as_bytes());
+
+    format!(
+        "sha256={}",
+        base64::encode_config(signature, base64::URL_SAFE_NO_PAD)
+    )
+}
+
+/// The webhooks registered in a game, by player ID.
+///
+/// They are shared between the [`crate::game::Game`], where players register them, and the [`WebhookNotifier`].
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Webhooks(Arc<Mutex<HashMap<usize, Webhook>>>);
+
+impl Webhooks {
+    /// Registers the URL of the given player's webhook, replacing any previous one.
+    ///
+    /// Returns an `Err` if the URL is not a valid HTTP(S) URL, or if it points to a non-public address (unless
+    /// [`ALLOW_PRIVATE_ADDRESSES`]).
+    ///
+    /// Otherwise, returns the newly generated secret that deliveries will be signed with.
+    pub(crate) fn register(&self, player_id: usize, url: &str) -> Result<String, String> {
+        match reqwest::Url::parse(url) {
+            Ok(parsed_url) if ["http", "https"].contains(&parsed_url.scheme()) => {
+                if !ALLOW_PRIVATE_ADDRESSES {
+                    ensure_public_host(&parsed_url)?;
+                }
+            }
+            _ => return Err(format!("`{}` is not a valid HTTP(S) URL.", url)),
+        }
+
+        let secret = base64::encode_config(signing::random_key(), base64::URL_SAFE_NO_PAD);
+
+        self.0.lock().unwrap().insert(
+            player_id,
+            Webhook {
+                url: String::from(url),
+                secret: secret.clone(),
+            },
+        );
+        Ok(secret)
+    }
+
+    /// Removes the given player's webhook. Returns whether they had one.
+    pub(crate) fn unregister(&self, player_id: usize) -> bool {
+        self.0.lock().unwrap().remove(&player_id).is_some()
+    }
+
+    /// Returns every webhook, by player ID, to restore them once the server restarted (see [`Webhooks::restore`]).
+    pub(crate) fn snapshot(&self) -> HashMap<usize, Webhook> {
+        self.0.lock().unwrap().clone()
+    }
+
+    /// Restores the webhooks of a game, as they were snapshotted (see [`Webhooks::snapshot`]).
+    pub(crate) fn restore(webhooks: HashMap<usize, Webhook>) -> Self {
+        Self(Arc::new(Mutex::new(webhooks)))
+    }
+
+    /// Whether any player registered a webhook.
+    pub(crate) fn has_any(&self) -> bool {
+        !self.0.lock().unwrap().is_empty()
+    }
+
+    fn get(&self, player_id: usize) -> Option<Webhook> {
+        self.0.lock().unwrap().get(&player_id).cloned()
+    }
+
+    fn all(&self) -> Vec<(usize, Webhook)> {
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(player_id, webhook)| (*player_id, webhook.clone()))
+            .collect()
+    }
+}
+
+/// Returns an `Err` if the host of the URL is obviously not public: `localhost`, or a non-public IP address.
+///
+/// Domain names are not resolved here, as games are locked while webhooks get registered: they are resolved by
+/// [`delivery_client`] instead, which also catches names resolving to other addresses later on.
+fn ensure_public_host(url: &reqwest::Url) -> Result<(), String> {
+    // IPv6 addresses are enclosed in brackets.
+    let host = url
+        .host_str()
+        .unwrap_or_default()
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+
+    let is_public_host = match host.parse::<IpAddr>() {
+        Ok(ip) => is_public(ip),
+        Err(_) => {
+            let domain = host.trim_end_matches('.').to_ascii_lowercase();
+            !domain.is_empty() && domain != "localhost" && !domain.ends_with(".localhost")
+        }
+    };
+
+    if is_public_host {
+        Ok(())
+    } else {
+        Err(format!("`{}` does not point to a public address.", url))
+    }
+}
+
+/// Whether the address can be reached from the internet, i.e. is not a loopback, private, link-local (where cloud
+/// metadata endpoints live), shared, or unspecified address.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+            let is_shared = first == 100 && (64..128).contains(&second);
+
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || is_shared)
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => {
+                let first_segment = ip.segments()[0];
+                let is_unique_local = first_segment & 0xfe00 == 0xfc00;
+                let is_link_local = first_segment & 0xffc0 == 0xfe80;
+
+                !(ip.is_loopback() || ip.is_unspecified() || is_unique_local || is_link_local)
+            }
+        },
+    }
+}
+
+/// Resolves the domain to its public addresses (see [`is_public`]). Returns an `Err` if it has none.
+async fn public_addresses(domain: &str) -> Result<Vec<SocketAddr>, String> {
+    let addrs: Vec<SocketAddr> = rocket::tokio::net::lookup_host((domain, 0))
+        .await
+        .map_err(|e| e.to_string())?
+        .filter(|addr| is_public(addr.ip()))
+        .collect();
+
+    if addrs.is_empty() {
+        Err(format!(
+            "`{}` does not resolve to a public address.",
+            domain
+        ))
+    } else {
+        Ok(addrs)
+    }
+}
+
+/// Queues deliveries to the webhooks of a game, as it gets notified by the [`ticket_to_ride::manager::Manager`].
+pub(crate) struct WebhookNotifier {
+    game_id: Uuid,
+    link: String,
+    webhooks: Webhooks,
+    deliveries: UnboundedSender<Delivery>,
+}
+
+impl WebhookNotifier {
+    fn queue(&self, event: WebhookEvent, player_id: usize, webhook: Webhook, turn: Option<usize>) {
+        let payload = WebhookPayload {
+            event,
+            game_id: self.game_id,
+            player_id,
+            turn,
+            link: self.link.clone(),
+            sent_at: unix_now(),
+        };
+
+        // Deliveries are dropped if the server is shutting down.
+        let _ = self.deliveries.send(Delivery { webhook, payload });
+    }
+}
+
+impl GameObserver for WebhookNotifier {
+    fn on_turn_started(&mut self, turn: usize, player_id: usize) {
+        if let Some(webhook) = self.webhooks.get(player_id) {
+            self.queue(WebhookEvent::TurnStarted, player_id, webhook, Some(turn));
+        }
+    }
+
+    fn on_game_over(&mut self, state: &GameState) {
+        for (player_id, webhook) in self.webhooks.all() {
+            self.queue(WebhookEvent::GameOver, player_id, webhook, state.turn);
+        }
+    }
+}
+
+/// Hands out [`WebhookNotifier`]s to games, whose deliveries are sent by [`deliver_webhooks`].
+///
+/// It is managed by Rocket, so that games get notifiers when created.
+pub struct WebhookDispatcher {
+    /// Base URL of the server, as players reach it (e.g. `https://example.com`).
+    public_url: String,
+    deliveries: UnboundedSender<Delivery>,
+    /// Taken by [`deliver_webhooks`], once the server is launched.
+    pending_deliveries: Mutex<Option<UnboundedReceiver<Delivery>>>,
+}
+
+impl WebhookDispatcher {
+    /// Creates a dispatcher, for a server reachable at the given URL.
+    pub fn new(public_url: &str) -> Self {
+        let (deliveries, pending_deliveries) = mpsc::unbounded_channel();
+
+        Self {
+            public_url: String::from(public_url.trim_end_matches('/')),
+            deliveries,
+            pending_deliveries: Mutex::new(Some(pending_deliveries)),
+        }
+    }
+
+    /// Creates a dispatcher from Rocket's configuration:
+    ///   * `public_url`: the base URL of the server, as players reach it, which links in payloads point to.
+    ///     If not configured, it is derived from the address and port the server listens on.
+    pub fn from_figment(figment: &Figment) -> Self {
+        let public_url = figment
+            .extract_inner::<String>("public_url")
+            .unwrap_or_else(|_| {
+                let config = rocket::Config::from(figment);
+                format!("http://{}:{}", config.address, config.port)
+            });
+
+        Self::new(&public_url)
+    }
+
+    /// Creates the notifier of the given game, which delivers to its webhooks.
+    pub(crate) fn notifier(&self, game_id: Uuid, webhooks: Webhooks) -> WebhookNotifier {
+        WebhookNotifier {
+            game_id,
+            link: format!("{}/game/{}", self.public_url, game_id),
+            webhooks,
+            deliveries: self.deliveries.clone(),
+        }
+    }
+
+    /// Returns the deliveries queued by notifiers, to send via [`deliver_webhooks`]. This can only be done once.
+    pub(crate) fn take_pending_deliveries(&self) -> Option<UnboundedReceiver<Delivery>> {
+        self.pending_deliveries.lock().unwrap().take()
+    }
+}
+
+/// Sends queued deliveries, until the server shuts down. Each delivery is retried on its own, without holding up
+/// the others.
+pub(crate) async fn deliver_webhooks(
+    mut pending_deliveries: UnboundedReceiver<Delivery>,
+    mut shutdown: Shutdown,
+) {
+    loop {
+        select! {
+            delivery = pending_deliveries.recv() => match delivery {
+                Some(delivery) => {
+                    rocket::tokio::spawn(deliver(delivery));
+                }
+                None => break,
+            },
+            _ = &mut shutdown => break,
+        }
+    }
+}
+
+/// Builds the client sending deliveries to the given URL.
+///
+/// Unless [`ALLOW_PRIVATE_ADDRESSES`], its host must be public: domain names are resolved, and the client is pinned
+/// to their public addresses, so that they cannot point to private ones once the webhook has been registered.
+async fn delivery_client(url: &str) -> Result<reqwest::Client, String> {
+    // Redirects are not followed, as they could point anywhere.
+    let client = reqwest::Client::builder()
+        .timeout(DELIVERY_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::none());
+
+    let client = if ALLOW_PRIVATE_ADDRESSES {
+        client
+    } else {
+        let url = reqwest::Url::parse(url).map_err(|e| e.to_string())?;
+        ensure_public_host(&url)?;
+
+        match url.domain() {
+            Some(domain) => client.resolve_to_addrs(domain, &public_addresses(domain).await?),
+            None => client,
+        }
+    };
+
+    client.build().map_err(|e| e.to_string())
+}
+
+async fn deliver(delivery: Delivery) {
+    let (body, signature) = delivery.sign();
+    let url = delivery.webhook.url;
+
+    let delivered = retry(MAX_DELIVERY_ATTEMPTS, INITIAL_RETRY_DELAY, || {
+        let (url, signature, body) = (url.clone(), signature.clone(), body.clone());
+
+        async move {
+            let client = match delivery_client(&url).await {
+                Ok(client) => client,
+                Err(e) => {
+                    warn!(%url, error = %e, "Cannot deliver to webhook");
+                    return false;
+                }
+            };
+
+            client
+                .post(&url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(SIGNATURE_HEADER, signature)
+                .body(body)
+                .send()
+                .await
+                .is_ok_and(|response| response.status().is_success())
+        }
+    })
+    .await;
+
+    if !delivered {
+        warn!(
+            %url,
+            attempts = MAX_DELIVERY_ATTEMPTS,
+            "Giving up on delivering to webhook"
+        );
+    }
+}
+
+/// Attempts something up to `max_attempts` times, until it succeeds, waiting `initial_delay` before the first
+/// retry, and twice as long before every subsequent one. Returns whether it eventually succeeded.
+async fn retry<F, Fut>(max_attempts: u32, initial_delay: Duration, mut attempt: F) -> bool
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = bool>,
+{
+    let mut delay = initial_delay;
+
+    for attempt_number in 1..=max_attempts {
+        if attempt().await {
+            return true;
+        }
+
+        if attempt_number < max_attempts {
+            time::sleep(delay).await;
+            delay *= 2;
+        }
+    }
+
+    false
+}
+
+/// The [`SIGNATURE_HEADER`] of a delivery, as received by [`crate::router::receive_webhook()`].
+pub struct WebhookSignature(pub String);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for WebhookSignature {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        match request.headers().get_one(SIGNATURE_HEADER) {
+            Some(signature) => Outcome::Success(Self(String::from(signature))),
+            None => Outcome::Failure((Status::BadRequest, ())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn new_notifier(webhooks: &Webhooks) -> (WebhookNotifier, UnboundedReceiver<Delivery>) {
+        let dispatcher = WebhookDispatcher::new("https://example.com/");
+        let pending_deliveries = dispatcher.take_pending_deliveries().unwrap();
+
+        (
+            dispatcher.notifier(Uuid::nil(), webhooks.clone()),
+            pending_deliveries,
+        )
+    }
+
+    #[test]
+    fn webhooks_register() {
+        let webhooks = Webhooks::default();
+
+        assert!(webhooks.register(0, "ftp://example.com").is_err());
+        assert!(webhooks.register(0, "not a url").is_err());
+
+        let secret = webhooks.register(0, "https://example.com/hook").unwrap();
+        assert_eq!(
+            webhooks.get(0),
+            Some(Webhook {
+                url: String::from("https://example.com/hook"),
+                secret: secret.clone(),
+            })
+        );
+
+        // Registering again replaces the webhook, and its secret.
+        assert_ne!(
+            webhooks.register(0, "http://localhost:8000/hook").unwrap(),
+            secret
+        );
+        assert!(webhooks.unregister(0));
+        assert!(!webhooks.unregister(0));
+        assert!(webhooks.get(0).is_none());
+    }
+
+    #[test]
+    fn webhooks_public_host() {
+        let ensure_public_host = |url| ensure_public_host(&reqwest::Url::parse(url).unwrap());
+
+        assert!(ensure_public_host("https://example.com/hook").is_ok());
+        assert!(ensure_public_host("http://93.184.216.34/hook").is_ok());
+        assert!(ensure_public_host("http://[2606:2800:220:1::]/hook").is_ok());
+
+        for url in [
+            "http://localhost:8000/dev/webhook",
+            "http://LOCALHOST./hook",
+            "http://api.localhost/hook",
+            "http://127.0.0.1/hook",
+            "http://127.1.2.3/hook",
+            "http://0.0.0.0/hook",
+            "http://10.0.0.1/hook",
+            "http://172.16.0.1/hook",
+            "http://192.168.1.1/hook",
+            "http://169.254.169.254/latest/meta-data",
+            "http://100.64.0.1/hook",
+            "http://[::1]/hook",
+            "http://[::]/hook",
+            "http://[fd00::1]/hook",
+            "http://[fe80::1]/hook",
+            "http://[::ffff:127.0.0.1]/hook",
+        ] {
+            assert!(ensure_public_host(url).is_err(), "{}", url);
+        }
+    }
+
+    #[rocket::async_test]
+    async fn webhooks_public_addresses() {
+        // `localhost` only resolves to loopback addresses.
+        assert!(public_addresses("localhost").await.is_err());
+    }
+
+    #[test]
+    fn webhook_notifier_turn_started() {
+        let webhooks = Webhooks::default();
+        let secret = webhooks.register(1, "https://example.com/hook").unwrap();
+        let (mut notifier, mut pending_deliveries) = new_notifier(&webhooks);
+
+        // Only players with a webhook get notified.
+        notifier.on_turn_started(4, 0);
+        assert!(pending_deliveries.try_recv().is_err());
+
+        notifier.on_turn_started(5, 1);
+        let delivery = pending_deliveries.try_recv().unwrap();
+        assert_eq!(delivery.webhook.secret, secret);
+        assert_eq!(delivery.payload.event, WebhookEvent::TurnStarted);
+        assert_eq!(delivery.payload.player_id, 1);
+        assert_eq!(delivery.payload.turn, Some(5));
+        assert_eq!(
+            delivery.payload.link,
+            format!("https://example.com/game/{}", Uuid::nil())
+        );
+    }
+
+    #[test]
+    fn webhook_notifier_game_over() {
+        let webhooks = Webhooks::default();
+        webhooks.register(0, "https://example.com/0").unwrap();
+        webhooks.register(2, "https://example.com/2").unwrap();
+        let (mut notifier, mut pending_deliveries) = new_notifier(&webhooks);
+
+        let manager = ticket_to_ride::manager::Manager::new();
+        notifier.on_game_over(&manager.get_public_state());
+
+        let mut player_ids = vec![
+            pending_deliveries.try_recv().unwrap().payload.player_id,
+            pending_deliveries.try_recv().unwrap().payload.player_id,
+        ];
+        player_ids.sort();
+        assert_eq!(player_ids, vec![0, 2]);
+        assert!(pending_deliveries.try_recv().is_err());
+    }
+
+    #[test]
+    fn delivery_sign() {
+        let delivery = Delivery {
+            webhook: Webhook {
+                url: String::from("https://example.com/hook"),
+                secret: String::from("secret"),
+            },
+            payload: WebhookPayload {
+                event: WebhookEvent::GameOver,
+                game_id: Uuid::nil(),
+                player_id: 3,
+                turn: Some(42),
+                link: String::from("https://example.com/game"),
+                sent_at: 1_700_000_000,
+            },
+        };
+
+        let (body, signature) = delivery.sign();
+        assert_eq!(
+            body,
+            r#"{"event":"game_over","game_id":"00000000-0000-0000-0000-000000000000","player_id":3,"turn":42,"link":"https://example.com/game","sent_at":1700000000}"#
+        );
+        assert_eq!(signature, sign("secret", &body));
+        assert_ne!(signature, sign("other secret", &body));
+        assert!(signature.starts_with("sha256="));
+    }
+
+    #[rocket::async_test]
+    async fn retry_until_success() {
+        let num_attempts = AtomicU32::new(0);
+        let succeeded = retry(5, Duration::from_millis(1), || {
+            let attempt_number = num_attempts.fetch_add(1, Ordering::SeqCst) + 1;
+            async move { attempt_number == 3 }
+        })
+        .await;
+
+        assert!(succeeded);
+        assert_eq!(num_attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[rocket::async_test]
+    async fn retry_gives_up() {
+        let num_attempts = AtomicU32::new(0);
+        let succeeded = retry(3, Duration::from_millis(1), || {
+            num_attempts.fetch_add(1, Ordering::SeqCst);
+            async { false }
+        })
+        .await;
+
+        assert!(!succeeded);
+        assert_eq!(num_attempts.load(Ordering::SeqCst), 3);
+    }
+}
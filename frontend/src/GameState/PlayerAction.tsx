@@ -5,4 +5,6 @@ export enum PlayerAction {
   DrewCloseTrainCard,
   DrewDestinationCards,
   SelectedDestinationCards,
+  RanOutOfTime,
+  Passed,
 }